@@ -4,15 +4,79 @@
 // SPDX-FileCopyrightText: 2023-2024 Christina Sørensen, eza contributors
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
+use std::fmt::Write;
+
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("logger", |b| {
         b.iter(|| {
-            eza::logger::configure(black_box(std::env::var_os(eza::options::vars::EZA_DEBUG)));
+            wls::logger::configure(black_box(std::env::var_os(wls::options::vars::EZA_DEBUG)));
+        });
+    });
+}
+
+/// Parsing a manifest the size of a large monorepo’s, which is the part of
+/// a cold listing that grows with the repository rather than the directory.
+pub fn manifest_benchmark(c: &mut Criterion) {
+    let mut manifest = String::from("{");
+    for area in 0..100 {
+        for zone in 0..100 {
+            if area > 0 || zone > 0 {
+                manifest.push(',');
+            }
+            write!(
+                manifest,
+                r#""//areas/a{area}/z{zone}": {{"type": "library", "deps": ["//areas/a0/z0"], "tags": ["tier1"]}}"#
+            )
+            .unwrap();
+        }
+    }
+    manifest.push('}');
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let path = file.path();
+    std::fs::write(path, manifest).unwrap();
+
+    c.bench_function("parse_manifest", |b| {
+        b.iter(|| wls::fs::parse_manifest(black_box(path), false, None));
+    });
+
+    c.bench_function("parse_manifest_beneath", |b| {
+        b.iter(|| wls::fs::parse_manifest(black_box(path), false, Some("//areas/a50")));
+    });
+}
+
+/// Reading a directory with tens of thousands of entries, with the batched
+/// reader a listing uses and with `std::fs::read_dir` to compare it to.
+pub fn read_dir_benchmark(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    for i in 0..20_000 {
+        std::fs::write(dir.path().join(format!("file-{i}")), "").unwrap();
+    }
+
+    c.bench_function("read_dir_entries", |b| {
+        b.iter(|| {
+            wls::fs::entries::read(black_box(dir.path()))
+                .unwrap()
+                .collect::<std::io::Result<Vec<_>>>()
+        });
+    });
+
+    c.bench_function("read_dir_std", |b| {
+        b.iter(|| {
+            std::fs::read_dir(black_box(dir.path()))
+                .unwrap()
+                .map(|entry| entry?.file_type())
+                .collect::<std::io::Result<Vec<_>>>()
         });
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    manifest_benchmark,
+    read_dir_benchmark
+);
 criterion_main!(benches);
@@ -41,7 +41,7 @@ fn main() -> io::Result<()> {
             "{}\nv{} [{}] built on {} \\1;31m(pre-release!)\\0m\n\\1;4;34m{}\\0m",
             tagline,
             version_string(),
-            git_hash(),
+            git_hash().unwrap_or_default(),
             build_date(),
             url
         )
@@ -58,6 +58,12 @@ fn main() -> io::Result<()> {
         File::create(path).unwrap_or_else(|_| panic!("{}", path.to_string_lossy().to_string()));
     writeln!(f, "{}", strip_codes(&ver))?;
 
+    // The hash on its own, for `--version --json`
+    println!(
+        "cargo:rustc-env=WLS_GIT_HASH={}",
+        git_hash().unwrap_or_default()
+    );
+
     // Generate compiled LS_COLORS from dircolors source
     let ls_colors = compile_ls_colors("LS_COLORS")?;
     let ls_colors_path = &out.join("ls_colors.txt");
@@ -152,19 +158,17 @@ fn strip_codes(input: &str) -> String {
         .replace("\\1;4;34m", "")
 }
 
-/// Retrieve the project’s current Git hash, as a string.
-fn git_hash() -> String {
+/// Retrieve the project’s current Git hash, or `None` when not building
+/// from a Git checkout, such as from a source tarball.
+fn git_hash() -> Option<String> {
     use std::process::Command;
 
-    String::from_utf8_lossy(
-        &Command::new("git")
-            .args(["rev-parse", "--short", "HEAD"])
-            .output()
-            .unwrap()
-            .stdout,
-    )
-    .trim()
-    .to_string()
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
 /// Whether we should show pre-release info in the version string.
@@ -0,0 +1,381 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Keeping parsed manifests warm in a background process, with `--daemon`.
+//!
+//! In a large monorepo, reading and parsing the manifest is most of what a
+//! listing inside the src root costs before it can print anything, and an
+//! interactive shell pays that again on every invocation. `wls --daemon`
+//! stays running and parses each manifest once, and again whenever it
+//! changes: the directory each manifest is in gets watched, so that a zone
+//! refactor rewriting it gets parsed straight away rather than by whichever
+//! listing comes next. The modification time is checked on every request as
+//! well, in case the watching missed something. Every other invocation asks
+//! it for the manifest over a Unix socket first, and reads the file itself
+//! if nothing answers, so a listing never depends on the daemon being there.
+//!
+//! Only the manifest is cached: sync states and the sparse-checkout patterns
+//! are small enough to keep reading each time, and Git statuses and
+//! directory sizes change with every edit in the working tree, so each
+//! invocation still works those out for itself.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use log::*;
+
+use crate::fs::ParsedManifest;
+
+/// The socket to ask for manifests, once it’s been configured.
+static SOCKET: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the socket to ask for manifests, based on the value of an
+/// environment variable. Without one, it’s `wls.sock` in the user’s runtime
+/// directory, or a per-user name in the temporary directory.
+pub fn configure<T: AsRef<OsStr>>(ev: Option<T>) {
+    let path = match ev {
+        Some(ev) if !ev.as_ref().is_empty() => PathBuf::from(ev.as_ref()),
+        _ => default_socket_path(),
+    };
+
+    if SOCKET.set(path).is_err() {
+        warn!("Daemon socket was configured twice");
+    }
+}
+
+/// The socket being used to talk to the daemon, if it’s been configured.
+pub fn socket_path() -> Option<&'static Path> {
+    SOCKET.get().map(PathBuf::as_path)
+}
+
+fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("wls.sock"),
+        _ => std::env::temp_dir().join(format!("wls-{}.sock", user_id())),
+    }
+}
+
+#[cfg(unix)]
+fn user_id() -> u32 {
+    unsafe { libc::getuid() }
+}
+
+#[cfg(not(unix))]
+fn user_id() -> u32 {
+    0
+}
+
+/// Asks the daemon for the parsed manifest at the given path, returning
+/// `None` if there’s no daemon listening or it couldn’t give one back.
+pub fn fetch_manifest(manifest_path: &Path) -> Option<ParsedManifest> {
+    let socket = socket_path()?;
+    if !socket.exists() {
+        return None;
+    }
+
+    unix::fetch_from(socket, manifest_path)
+}
+
+/// Listens on the configured socket until the process is killed, answering
+/// each request with the manifest it names. Every field of each entry is
+/// kept, since the daemon can’t know which invocations will want them.
+pub fn serve() -> std::io::Result<()> {
+    let Some(socket) = socket_path() else {
+        return Err(std::io::Error::other("no socket has been configured"));
+    };
+
+    unix::serve(socket)
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    use log::*;
+    use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+    use crate::fs::{parse_manifest, ParsedManifest};
+
+    /// How long to wait for the daemon to answer before reading the manifest
+    /// instead. Parsing a manifest it hasn’t seen yet can take a while, but
+    /// no longer than it would have taken to parse it here.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn fetch_from(socket: &Path, manifest_path: &Path) -> Option<ParsedManifest> {
+        let mut stream = match UnixStream::connect(socket) {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!("No daemon listening on {socket:?}: {e}");
+                return None;
+            }
+        };
+
+        stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+        writeln!(stream, "{}", manifest_path.display()).ok()?;
+
+        match serde_json::from_reader(BufReader::new(stream)) {
+            Ok(parsed) => Some(parsed),
+            Err(e) => {
+                debug!("Daemon had no manifest at {manifest_path:?}: {e}");
+                None
+            }
+        }
+    }
+
+    /// The cached answer for each manifest, with the modification time it
+    /// was parsed at.
+    pub(super) type Cache = Mutex<HashMap<PathBuf, (SystemTime, Vec<u8>)>>;
+
+    pub fn serve(socket: &Path) -> io::Result<()> {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(io::Error::new(
+                ErrorKind::AddrInUse,
+                format!("a daemon is already listening on {}", socket.display()),
+            ));
+        }
+
+        // Whatever is left at the path is a socket from a daemon that
+        // didn’t get to clean up after itself.
+        let _ = fs::remove_file(socket);
+        let listener = UnixListener::bind(socket)?;
+        fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+        info!("Listening on {socket:?}");
+
+        let cache = Arc::new(Cache::default());
+        let reloading = Arc::clone(&cache);
+        let watcher = match notify::recommended_watcher(move |event| reload(&reloading, event)) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                warn!("Failed to watch manifests for changes: {e}");
+                None
+            }
+        };
+        let watcher = Arc::new(Mutex::new(watcher));
+
+        // Each connection gets its own thread, so a client that stops
+        // halfway through a request only holds up itself
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let cache = Arc::clone(&cache);
+                    let watcher = Arc::clone(&watcher);
+                    let _ = thread::spawn(move || {
+                        if let Err(e) = answer(&stream, &cache, &watcher) {
+                            debug!("Failed to answer request: {e}");
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept connection: {e}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the manifest path from the stream and writes back the parsed
+    /// manifest. If it can’t be parsed, the connection is closed without an
+    /// answer, and the client reads it itself and gets to see why. The
+    /// first time a manifest is asked for, its directory starts being
+    /// watched. A client gets as long to send its request and read the
+    /// answer as it waits for the answer itself.
+    fn answer(
+        stream: &UnixStream,
+        cache: &Cache,
+        watcher: &Mutex<Option<RecommendedWatcher>>,
+    ) -> io::Result<()> {
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut line = String::new();
+        let _ = BufReader::new(stream).read_line(&mut line)?;
+        let manifest_path = PathBuf::from(line.trim_end_matches('\n'));
+
+        let seen = cache.lock().unwrap().contains_key(&manifest_path);
+        let Some(answer) = refresh(cache, &manifest_path)? else {
+            return Ok(());
+        };
+
+        // Manifests tend to be replaced rather than written to, so it’s the
+        // directory they’re in that gets watched
+        if let (false, Some(dir)) = (seen, manifest_path.parent()) {
+            if let Some(watcher) = watcher.lock().unwrap().as_mut() {
+                if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                    warn!("Failed to watch {dir:?}: {e}");
+                }
+            }
+        }
+
+        let mut stream = stream;
+        stream.write_all(&answer)
+    }
+
+    /// The answer for the manifest at the given path, parsing it again if
+    /// it’s changed since it was last parsed, or `None` if it can’t be.
+    pub(super) fn refresh(cache: &Cache, manifest_path: &Path) -> io::Result<Option<Vec<u8>>> {
+        let modified = fs::metadata(manifest_path)?.modified()?;
+        if let Some((time, answer)) = cache.lock().unwrap().get(manifest_path) {
+            if *time == modified {
+                return Ok(Some(answer.clone()));
+            }
+        }
+
+        // The lock isn’t held while parsing, which can take a while
+        let Some(parsed) = parse_manifest(manifest_path, true, None) else {
+            return Ok(None);
+        };
+        debug!("Parsed manifest at {manifest_path:?}");
+        let answer = serde_json::to_vec(&parsed)?;
+        let _ = cache
+            .lock()
+            .unwrap()
+            .insert(manifest_path.to_path_buf(), (modified, answer.clone()));
+        Ok(Some(answer))
+    }
+
+    /// Parses the manifests that an event in a watched directory changed,
+    /// so they’re ready before anything asks for them.
+    pub(super) fn reload(cache: &Cache, event: notify::Result<Event>) {
+        let event = match event {
+            Ok(event) if !event.kind.is_access() => event,
+            Ok(_) => return,
+            Err(e) => {
+                warn!("Error watching manifests for changes: {e}");
+                return;
+            }
+        };
+
+        for path in &event.paths {
+            if cache.lock().unwrap().contains_key(path) {
+                if let Err(e) = refresh(cache, path) {
+                    debug!("Failed to reload manifest at {path:?}: {e}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod unix {
+    use std::io;
+    use std::path::Path;
+
+    use crate::fs::ParsedManifest;
+
+    pub fn fetch_from(_socket: &Path, _manifest_path: &Path) -> Option<ParsedManifest> {
+        None
+    }
+
+    pub fn serve(_socket: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the daemon needs Unix sockets",
+        ))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+    use std::fs;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn manifests_come_back_from_the_daemon() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let manifest_path = dir.join("manifest.json");
+        fs::write(
+            &manifest_path,
+            r#"{"//areas/core": {"type": "library", "tags": ["infra"]}}"#,
+        )
+        .unwrap();
+        let socket = dir.join("wls.sock");
+
+        let serving = socket.clone();
+        let _ = thread::spawn(move || unix::serve(&serving));
+        for _ in 0..100 {
+            if socket.exists() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let fetched = unix::fetch_from(&socket, &manifest_path);
+        assert_eq!(
+            fetched,
+            crate::fs::parse_manifest(&manifest_path, true, None)
+        );
+        assert!(fetched.unwrap().entries.contains("//areas/core"));
+
+        assert_eq!(unix::fetch_from(&socket, &dir.join("missing.json")), None);
+    }
+
+    #[test]
+    fn stalled_clients_only_hold_up_themselves() {
+        use std::os::unix::net::UnixStream;
+        use std::time::Instant;
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let manifest_path = dir.join("manifest.json");
+        fs::write(&manifest_path, r#"{"//areas/core": {}}"#).unwrap();
+        let socket = dir.join("wls.sock");
+
+        let serving = socket.clone();
+        let _ = thread::spawn(move || unix::serve(&serving));
+        for _ in 0..100 {
+            if socket.exists() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // Connects, but never says which manifest it wants
+        let _stalled = UnixStream::connect(&socket).unwrap();
+
+        let start = Instant::now();
+        assert!(unix::fetch_from(&socket, &manifest_path).is_some());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn changed_manifests_are_parsed_again() {
+        use notify::event::{EventKind, ModifyKind};
+        use notify::Event;
+        use std::time::SystemTime;
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let manifest_path = dir.join("manifest.json");
+        fs::write(&manifest_path, r#"{"//areas/core": {}}"#).unwrap();
+
+        let cache = unix::Cache::default();
+        let _ = unix::refresh(&cache, &manifest_path).unwrap();
+
+        fs::write(&manifest_path, r#"{"//areas/tools": {}}"#).unwrap();
+        let file = fs::File::options()
+            .write(true)
+            .open(&manifest_path)
+            .unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1))
+            .unwrap();
+        unix::reload(
+            &cache,
+            Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(manifest_path.clone())),
+        );
+
+        let answer = cache.lock().unwrap()[&manifest_path].1.clone();
+        let parsed: crate::fs::ParsedManifest = serde_json::from_slice(&answer).unwrap();
+        assert!(parsed.entries.contains("//areas/tools"));
+        assert!(!parsed.entries.contains("//areas/core"));
+    }
+}
@@ -5,35 +5,353 @@
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
 use crate::fs::feature::git::GitCache;
-use crate::fs::fields::GitStatus;
-use std::fs;
-use std::fs::DirEntry;
+use crate::fs::fields::{GitStatus, Materialized};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::slice::Iter as SliceIter;
-use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+
+use chrono::{DateTime, NaiveDateTime};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+
+use crate::fs::daemon;
+use crate::fs::entries::{self, DirEntry};
+use crate::fs::filter::ZoneFilter;
+use crate::fs::manifest_cache;
+use crate::fs::repo_config::RepoConfig;
+use crate::fs::sparse::SparseCheckout;
+use crate::fs::timings::{self, Phase, Timings};
+use crate::fs::File;
+use crate::warnings::{self, Kind};
 
-use log::{info, warn, debug};
-use serde::Deserialize;
+/// The fields of a manifest entry that every listing uses, borrowed from
+/// the manifest’s text. Any others are skipped over without being parsed
+/// into anything.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct EntryFields<'a> {
+    #[serde(rename = "type", borrow)]
+    kind: Option<&'a RawValue>,
 
-use crate::fs::File;
+    #[serde(borrow)]
+    deps: Option<&'a RawValue>,
 
-#[derive(Deserialize)]
-struct Manifest {
-    #[serde(flatten)]
-    pub entries: std::collections::HashMap<String, serde_json::Value>,
+    #[serde(borrow)]
+    tags: Option<&'a RawValue>,
+
+    #[serde(borrow)]
+    labels: Option<&'a RawValue>,
+
+    #[serde(borrow)]
+    size: Option<&'a RawValue>,
+
+    #[serde(borrow)]
+    file_count: Option<&'a RawValue>,
+}
+
+impl<'a> EntryFields<'a> {
+    /// The fields of the given entry, or none of them if it isn’t an
+    /// object.
+    fn of(entry: &'a RawValue) -> Self {
+        serde_json::from_str(entry.get()).unwrap_or_default()
+    }
+}
+
+/// Who last materialized a zone, and when, as reported by the sync-state
+/// provider in `sync-state.json` next to the manifest.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SyncState {
+    /// The user who last synced the zone.
+    pub user: String,
+
+    /// When the zone was last synced, in seconds since the Unix epoch.
+    pub time: i64,
+}
+
+impl SyncState {
+    /// The time of the last sync, in UTC.
+    pub fn synced_at(&self) -> Option<NaiveDateTime> {
+        DateTime::from_timestamp(self.time, 0).map(|t| t.naive_utc())
+    }
+}
+
+/// What a zone holds, from the `type` field of its manifest entry.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoneKind {
+    /// Code that other zones depend on.
+    Library,
+
+    /// Something that gets deployed.
+    App,
+
+    /// Something that developers run.
+    Tool,
+}
+
+impl ZoneKind {
+    /// The kind named by a manifest entry, if it names one that’s known.
+    fn from_entry(fields: &EntryFields<'_>) -> Option<Self> {
+        serde_json::from_str(fields.kind?.get()).ok()
+    }
+}
+
+/// How big a zone is, roughly, from the `size` and `file_count` fields of
+/// its manifest entry, so that ghosts have something to show before
+/// they’re materialized.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Estimate {
+    /// About how many bytes the zone takes up.
+    pub bytes: Option<u64>,
+
+    /// About how many files the zone has.
+    pub files: Option<u64>,
+}
+
+impl Estimate {
+    /// The estimate a manifest entry gives, if it gives either number.
+    fn from_entry(fields: &EntryFields<'_>) -> Option<Self> {
+        let number =
+            |field: Option<&RawValue>| field.and_then(|raw| serde_json::from_str(raw.get()).ok());
+        let estimate = Self {
+            bytes: number(fields.size),
+            files: number(fields.file_count),
+        };
+        (estimate != Self::default()).then_some(estimate)
+    }
+}
+
+/// Whether a manifest entry is for a single file, such as a generated
+/// proto, from its `type` being `file`.
+fn is_file_entry(fields: &EntryFields<'_>) -> bool {
+    fields.kind.is_some_and(|kind| {
+        serde_json::from_str::<&str>(kind.get()).is_ok_and(|kind| kind == "file")
+    })
+}
+
+/// The strings in one of a manifest entry’s array fields, leaving out
+/// anything in there that isn’t a string.
+fn strings_from_entry(field: Option<&RawValue>) -> Vec<String> {
+    let Some(array) = field.and_then(|raw| serde_json::from_str::<Vec<&RawValue>>(raw.get()).ok())
+    else {
+        return Vec::new();
+    };
+
+    array
+        .into_iter()
+        .filter_map(|element| serde_json::from_str(element.get()).ok())
+        .collect()
+}
+
+/// The target paths a manifest entry depends on, from its `deps` array.
+fn deps_from_entry(fields: &EntryFields<'_>) -> Vec<String> {
+    strings_from_entry(fields.deps)
+}
+
+/// The tags a manifest entry has, such as `deprecated` or `tier1`, from its
+/// `tags` array along with its `labels` one, which some toolchains use
+/// instead.
+fn tags_from_entry(fields: &EntryFields<'_>) -> Vec<String> {
+    let mut tags = strings_from_entry(fields.tags);
+    for label in strings_from_entry(fields.labels) {
+        if !tags.contains(&label) {
+            tags.push(label);
+        }
+    }
+    tags
+}
+
+/// The fields of a zone’s manifest entry, with every value as a string.
+pub type Metadata = BTreeMap<String, Vec<String>>;
+
+/// Every field of a manifest entry that holds text, a number, or a boolean,
+/// for `--filter-zone` to match against. The fields of nested objects get
+/// dotted names, such as `owner.team`, and an array has a value for each
+/// element that isn’t itself an array or an object.
+fn metadata_from_entry(value: &serde_json::Value) -> Metadata {
+    let mut metadata = Metadata::new();
+    add_metadata(&mut metadata, String::new(), value);
+    metadata
+}
+
+fn add_metadata(metadata: &mut Metadata, key: String, value: &serde_json::Value) {
+    use serde_json::Value;
+
+    let scalar = |value: &Value| match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    };
+
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{key}.{name}")
+                };
+                add_metadata(metadata, key, value);
+            }
+        }
+        Value::Array(values) => {
+            let values: Vec<String> = values.iter().filter_map(scalar).collect();
+            if !values.is_empty() {
+                metadata.entry(key).or_default().extend(values);
+            }
+        }
+        value if !key.is_empty() => {
+            if let Some(value) = scalar(value) {
+                metadata.entry(key).or_default().push(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A child of a target path, as far as the manifest knows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestChild {
+    /// The child’s file name.
+    pub name: String,
+
+    /// Whether the child is a zone itself.
+    pub is_zone: bool,
+
+    /// How many zones there are at or beneath the child.
+    pub zones: usize,
+
+    /// What the child holds, if it’s a zone whose manifest entry says.
+    pub kind: Option<ZoneKind>,
 }
 
 /// Cached manifest information for a src root
+#[derive(Clone)]
 pub struct ManifestInfo {
     pub src_root: PathBuf,
     pub entries: HashSet<String>,
+
+    /// The entries that are single files rather than directories.
+    pub files: HashSet<String>,
+
+    /// What target paths start with in place of a path to the src root,
+    /// which is `//` unless the toolchain uses something else.
+    pub target_prefix: String,
+
+    /// The last sync of each zone, keyed by target path, for the zones the
+    /// sync-state provider knows about.
+    pub sync_states: HashMap<String, SyncState>,
+
+    /// The kind of each zone whose manifest entry has a known `type`.
+    pub zone_kinds: HashMap<String, ZoneKind>,
+
+    /// The target paths each zone depends on, for the zones whose manifest
+    /// entries have any `deps`.
+    pub deps: HashMap<String, Vec<String>>,
+
+    /// The tags each zone has, for the zones whose manifest entries have
+    /// any `tags` or `labels`.
+    pub tags: HashMap<String, Vec<String>>,
+
+    /// Every field of each zone’s manifest entry, for the zones whose
+    /// entries have any.
+    pub metadata: HashMap<String, Metadata>,
+
+    /// How big each zone is, roughly, for the zones whose manifest entries
+    /// have a `size` or `file_count`.
+    pub estimates: HashMap<String, Estimate>,
+
+    /// The sparse-checkout patterns of the repository the src root is in,
+    /// if ghosts should be checked against them.
+    pub sparse_checkout: Option<SparseCheckout>,
+
+    /// The defaults set for everyone listing in the src root.
+    pub repo_config: RepoConfig,
+
+    /// With case folding on, the manifest’s own spelling of each entry and
+    /// each directory on the way to one, keyed by its lowercase form, so
+    /// that `Areas` on a case-insensitive filesystem still finds `//areas`.
+    pub folded: Option<HashMap<String, String>>,
+
+    /// Target path patterns whose ghosts are never listed, copied from
+    /// [`DirOptions::ignored_ghosts`].
+    pub ignored_ghosts: Vec<glob::Pattern>,
+
+    /// The names of what’s directly inside each directory the manifest
+    /// knows of, keyed by the start of their target paths such as
+    /// `//areas/`, worked out the first time they’re needed.
+    pub child_names: OnceLock<HashMap<String, BTreeSet<String>>>,
 }
 
 impl ManifestInfo {
-    /// Check if a target path (relative to `src_root`) is a zone
-    pub fn is_zone(&self, target_path: &str) -> bool {
-        self.entries.contains(target_path)
+    /// Whether the entry with the given target path is a single file.
+    pub fn is_file_target(&self, target_path: &str) -> bool {
+        self.files.contains(target_path)
+    }
+
+    /// Whether the user never wants to see a ghost with the given target
+    /// path, because it or a directory on the way to it matches one of
+    /// their `ignore-ghosts` patterns. Those ghosts are left out of
+    /// listings, out of the counts of unmaterialized zones, and out of
+    /// `--manifest-only`.
+    pub fn ignores_ghost(&self, target: &str) -> bool {
+        if self.ignored_ghosts.is_empty() {
+            return false;
+        }
+
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..glob::MatchOptions::new()
+        };
+        let start = if target.starts_with(self.target_prefix.as_str()) {
+            self.target_prefix.len()
+        } else {
+            0
+        };
+        let ends = target[start..]
+            .match_indices('/')
+            .map(|(i, _)| start + i)
+            .chain([target.len()]);
+        ends.map(|end| &target[..end]).any(|target| {
+            self.ignored_ghosts
+                .iter()
+                .any(|pattern| pattern.matches_with(target, options))
+        })
+    }
+
+    /// The last sync of the zone with the given target path, if known.
+    pub fn sync_state(&self, target_path: &str) -> Option<&SyncState> {
+        self.sync_states.get(target_path)
+    }
+
+    /// Find every entry matching a partial target path, such as `//areas/to`,
+    /// in sorted order. Each component of the query only has to be a prefix
+    /// of the same component of the entry, so `//a/t` matches
+    /// `//areas/tools/dev`.
+    pub fn complete(&self, query: &str) -> Vec<&str> {
+        let prefix = self.target_prefix.as_str();
+        let query = query.strip_prefix(prefix).unwrap_or(query);
+        let wanted: Vec<&str> = query.split('/').collect();
+
+        let mut matches: Vec<&str> = self
+            .entries
+            .iter()
+            .map(String::as_str)
+            .filter(|entry| {
+                let mut have = entry.strip_prefix(prefix).unwrap_or(entry).split('/');
+                wanted
+                    .iter()
+                    .all(|w| have.next().is_some_and(|h| h.starts_with(w)))
+            })
+            .collect();
+
+        matches.sort_unstable();
+        matches
     }
 
     /// Build the target path string for a file given its canonical path
@@ -42,13 +360,255 @@ impl ManifestInfo {
         if rel_path.as_os_str().is_empty() {
             None
         } else {
-            Some(format!("//{}", rel_path.to_string_lossy()))
+            Some(self.spelled_as_in_manifest(format!(
+                "{}{}",
+                self.target_prefix,
+                target_rel_path(rel_path)
+            )))
+        }
+    }
+
+    /// The given target path spelled the way the manifest spells it, if
+    /// case folding is on and it only differs from it in case.
+    fn spelled_as_in_manifest(&self, target: String) -> String {
+        match &self.folded {
+            Some(folded) if !self.entries.contains(&target) => folded
+                .get(&target.to_lowercase())
+                .cloned()
+                .unwrap_or(target),
+            _ => target,
+        }
+    }
+
+    /// Whether the manifest has any entries beneath the directory at the
+    /// given canonical path, which are what it would be listed with.
+    pub fn has_entries_beneath(&self, canonical_path: &Path) -> bool {
+        let Ok(rel_path) = canonical_path.strip_prefix(&self.src_root) else {
+            return false;
+        };
+
+        let prefix = self.children_prefix(rel_path);
+        self.entries.iter().any(|key| key.starts_with(&prefix))
+    }
+
+    /// Whether the directory at the given canonical path is an orphan: in
+    /// the src root, but not a zone, not inside one, and not above any.
+    pub fn is_orphan(&self, canonical_path: &Path) -> bool {
+        self.target_path_for(canonical_path).is_some()
+            && self.enclosing_zone(canonical_path).is_none()
+            && !self.has_entries_beneath(canonical_path)
+    }
+
+    /// The children of a target path as far as the manifest knows, without
+    /// looking at the disk, sorted by name, or `None` if the manifest knows
+    /// nothing about it. The target can be given with or without its prefix,
+    /// and an empty one means the src root.
+    pub fn children(&self, target: &str) -> Option<Vec<ManifestChild>> {
+        let rel_path = target
+            .strip_prefix(self.target_prefix.as_str())
+            .unwrap_or(target);
+        let rel_path = rel_path.trim_matches('/');
+        let rel_path = if rel_path == "." { "" } else { rel_path };
+        let prefix = self.children_prefix(Path::new(rel_path));
+        let is_known =
+            rel_path.is_empty() || self.is_zone(&format!("{}{rel_path}", self.target_prefix));
+
+        let mut children: BTreeMap<&str, ManifestChild> = BTreeMap::new();
+        for rest in self
+            .entries
+            .iter()
+            .filter(|entry| !self.ignores_ghost(entry))
+            .filter_map(|entry| entry.strip_prefix(&prefix))
+        {
+            let (name, is_zone) = match rest.split_once('/') {
+                Some((name, _)) => (name, false),
+                None => (rest, true),
+            };
+            if name.is_empty() {
+                continue;
+            }
+
+            let child = children.entry(name).or_insert_with(|| ManifestChild {
+                name: name.to_string(),
+                is_zone: false,
+                zones: 0,
+                kind: None,
+            });
+            if is_zone {
+                child.is_zone = true;
+                child.kind = self.zone_kinds.get(&format!("{prefix}{name}")).copied();
+            }
+            child.zones += 1;
+        }
+
+        if children.is_empty() && !is_known {
+            return None;
+        }
+
+        Some(children.into_values().collect())
+    }
+
+    /// The target paths that the zone with the given target path depends on,
+    /// in the order its manifest entry lists them.
+    pub fn deps_of(&self, target: &str) -> &[String] {
+        self.deps.get(target).map_or(&[], Vec::as_slice)
+    }
+
+    /// The target paths of the zones that depend on the one with the given
+    /// target path, found by going through every entry, in sorted order.
+    pub fn reverse_deps_of(&self, target: &str) -> Vec<&str> {
+        let mut dependents: Vec<&str> = self
+            .deps
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|dep| dep == target))
+            .map(|(dependent, _)| dependent.as_str())
+            .collect();
+
+        dependents.sort_unstable();
+        dependents
+    }
+
+    /// Whether the zone with the given target path, or any zone beneath
+    /// it, has the given tag.
+    pub fn has_tag_at_or_beneath(&self, target: &str, tag: &str) -> bool {
+        self.zones_at_or_beneath(target).any(|zone| {
+            self.tags
+                .get(zone)
+                .is_some_and(|tags| tags.iter().any(|t| t == tag))
+        })
+    }
+
+    /// Whether the zone with the given target path, or any zone beneath
+    /// it, has manifest metadata that meets every condition of the filter.
+    pub fn has_match_at_or_beneath(&self, target: &str, filter: &ZoneFilter) -> bool {
+        self.zones_at_or_beneath(target)
+            .any(|zone| filter.matches(self.metadata.get(zone)))
+    }
+
+    fn zones_at_or_beneath<'a>(&'a self, target: &'a str) -> impl Iterator<Item = &'a String> + 'a {
+        self.entries.iter().filter(move |zone| {
+            zone.strip_prefix(target)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    /// How many of the zones beneath the one with the given target path
+    /// have a directory on disk, or `None` if there are none beneath it.
+    pub fn materialized(&self, target: &str) -> Option<Materialized> {
+        self.count_materialized(target, Path::is_dir)
+    }
+
+    /// How many of the things directly inside the zone with the given
+    /// target path that the manifest knows of, zones or directories on the
+    /// way to one, aren’t on disk.
+    pub fn unmaterialized_children(&self, target: &str) -> usize {
+        self.count_unmaterialized_children(target, Path::exists)
+    }
+
+    fn count_unmaterialized_children(&self, target: &str, exists: impl Fn(&Path) -> bool) -> usize {
+        let names = self.child_names().get(&format!("{target}/"));
+        let (Some(names), Some(dir)) = (names, self.path_for(target)) else {
+            return 0;
+        };
+
+        names.iter().filter(|name| !exists(&dir.join(name))).count()
+    }
+
+    /// The names of what’s directly inside each directory the manifest
+    /// knows of, leaving out the ghosts the user never wants to see.
+    fn child_names(&self) -> &HashMap<String, BTreeSet<String>> {
+        self.child_names.get_or_init(|| {
+            let mut child_names: HashMap<String, BTreeSet<String>> = HashMap::new();
+            for entry in self
+                .entries
+                .iter()
+                .filter(|entry| !self.ignores_ghost(entry))
+            {
+                let Some(rest) = entry.strip_prefix(self.target_prefix.as_str()) else {
+                    continue;
+                };
+
+                let mut parent_end = entry.len() - rest.len();
+                for name in rest.split('/').filter(|name| !name.is_empty()) {
+                    child_names
+                        .entry(entry[..parent_end].to_string())
+                        .or_default()
+                        .insert(name.to_string());
+                    parent_end += name.len() + 1;
+                }
+            }
+            child_names
+        })
+    }
+
+    /// Where the directory with the given target path would be on disk.
+    pub fn path_for(&self, target: &str) -> Option<PathBuf> {
+        Some(self.src_root.join(path_for_target_rel(
+            target.strip_prefix(self.target_prefix.as_str())?,
+        )))
+    }
+
+    fn count_materialized(
+        &self,
+        target: &str,
+        exists: impl Fn(&Path) -> bool,
+    ) -> Option<Materialized> {
+        let dir = self.path_for(target)?;
+        let prefix = format!("{target}/");
+
+        let mut materialized = Materialized {
+            present: 0,
+            total: 0,
+        };
+        for rest in self
+            .entries
+            .iter()
+            .filter(|entry| !self.ignores_ghost(entry))
+            .filter_map(|entry| entry.strip_prefix(&prefix))
+        {
+            materialized.total += 1;
+            if exists(&dir.join(rest)) {
+                materialized.present += 1;
+            }
+        }
+
+        (materialized.total > 0).then_some(materialized)
+    }
+
+    /// The start shared by the target paths of everything inside the given
+    /// directory, relative to `src_root`, such as `//areas/core/`.
+    pub fn children_prefix(&self, rel_path: &Path) -> String {
+        if rel_path.as_os_str().is_empty() {
+            self.target_prefix.clone()
+        } else {
+            let target = format!("{}{}", self.target_prefix, target_rel_path(rel_path));
+            format!("{}/", self.spelled_as_in_manifest(target))
         }
     }
+
+    /// Whether the manifest has something with the given name inside the
+    /// directory whose children have the given prefix, either as an entry
+    /// itself or on the way to one.
+    pub fn has_child(&self, prefix: &str, name: &str) -> bool {
+        self.entries.iter().any(|entry| {
+            entry
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.split('/').next())
+                .is_some_and(|first| {
+                    first == name
+                        || (self.folded.is_some() && first.to_lowercase() == name.to_lowercase())
+                })
+        })
+    }
 }
 
-/// Find manifest by walking up from the given path looking for src/.meta/manifest.json
-pub fn find_manifest(start_path: &Path) -> Option<ManifestInfo> {
+/// Find manifest by walking up from the given path, looking for it wherever
+/// the `ManifestLocation` says it should be.
+pub fn find_manifest(start_path: &Path, options: &DirOptions) -> Option<ManifestInfo> {
+    if options.ignores_manifest() {
+        return None;
+    }
+
     let canonical_path = match start_path.canonicalize() {
         Ok(p) => p,
         Err(e) => {
@@ -57,13 +617,73 @@ pub fn find_manifest(start_path: &Path) -> Option<ManifestInfo> {
         }
     };
 
-    find_manifest_from_canonical(&canonical_path)
+    find_manifest_from_canonical(&canonical_path, options, false)
+}
+
+/// The manifest file that applies to the given path, if there is one, for
+/// watching it for changes.
+pub fn manifest_path(start_path: &Path, options: &DirOptions) -> Option<PathBuf> {
+    if options.ignores_manifest() {
+        return None;
+    }
+
+    let canonical_path = start_path.canonicalize().ok()?;
+    options
+        .manifest
+        .locate(&canonical_path)
+        .map(|(_, manifest_path)| manifest_path)
+}
+
+/// The defaults set for the src root the given path is in, if it’s in one,
+/// and the file they’re set in, found without reading the manifest itself.
+pub fn find_repo_config(
+    start_path: &Path,
+    manifest: &ManifestLocation,
+) -> Option<(PathBuf, RepoConfig)> {
+    let canonical_path = start_path.canonicalize().ok()?;
+    let (_, manifest_path) = manifest.locate(&canonical_path)?;
+    Some((
+        RepoConfig::path(&manifest_path),
+        RepoConfig::find(&manifest_path),
+    ))
+}
+
+/// The part of a target path after its prefix for the given path relative
+/// to `src_root`. Target paths always separate their components with `/`,
+/// whatever the platform’s own paths use, so that they match the manifest
+/// on Windows checkouts too.
+pub fn target_rel_path(rel_path: &Path) -> String {
+    let names: Vec<_> = rel_path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect();
+
+    names.join("/")
+}
+
+/// The path relative to `src_root` for the part of a target path after its
+/// prefix, the other way round from `target_rel_path`.
+pub fn path_for_target_rel(target_rel: &str) -> PathBuf {
+    target_rel
+        .split('/')
+        .filter(|name| !name.is_empty())
+        .collect()
 }
 
 /// Find manifest for a path that may not exist on disk.
 /// Walks up to find the nearest existing ancestor, canonicalizes that,
 /// then appends the remaining ghost path components.
-pub fn find_manifest_for_ghost(start_path: &Path) -> Option<(ManifestInfo, PathBuf)> {
+pub fn find_manifest_for_ghost(
+    start_path: &Path,
+    options: &DirOptions,
+) -> Option<(ManifestInfo, PathBuf)> {
+    if options.ignores_manifest() {
+        return None;
+    }
+
     // Convert relative paths to absolute by prepending cwd
     let start_path = if start_path.is_relative() {
         match std::env::current_dir() {
@@ -102,174 +722,899 @@ pub fn find_manifest_for_ghost(start_path: &Path) -> Option<(ManifestInfo, PathB
         full_path.push(component);
     }
 
-    let manifest_info = find_manifest_from_canonical(&canonical_ancestor)?;
+    let manifest_info = find_manifest_from_canonical(&canonical_ancestor, options, false)?;
 
     Some((manifest_info, full_path))
 }
 
-fn find_manifest_from_canonical(canonical_path: &Path) -> Option<ManifestInfo> {
-    // Find the src root
-    let mut current = canonical_path;
-    let mut src_root = None;
+/// Where to look for the manifest, and which directory the `//` target
+/// paths in it are relative to.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub enum ManifestLocation {
+    /// `.meta/manifest.json` inside the nearest ancestor directory named
+    /// `src`, which is the root.
+    #[default]
+    Default,
 
-    loop {
-        if current.file_name().is_some_and(|n| n == "src") {
-            let manifest_path = current.join(".meta/manifest.json");
-            if manifest_path.exists() {
-                src_root = Some(current.to_path_buf());
-                break;
-            }
-        }
-        match current.parent() {
-            Some(p) => current = p,
-            None => break,
+    /// A path such as `tools/zones.json`, looked for in every ancestor of the
+    /// listed directory. The ancestor it’s found in is the root.
+    Relative(PathBuf),
+
+    /// One particular manifest file. The root is the parent of the directory
+    /// containing it, so `/repo/tools/zones.json` has the root `/repo`.
+    Absolute(PathBuf),
+
+    /// Nowhere: the manifest is never looked for, so nothing is a zone or a
+    /// ghost, and listings work the same as they would outside a src root.
+    Ignored,
+}
+
+impl From<PathBuf> for ManifestLocation {
+    fn from(path: PathBuf) -> Self {
+        if path.is_absolute() {
+            Self::Absolute(path)
+        } else {
+            Self::Relative(path)
         }
     }
+}
 
-    let src_root = src_root?;
+impl ManifestLocation {
+    /// Find the root and the manifest file for the given canonical path.
+    fn locate(&self, canonical_path: &Path) -> Option<(PathBuf, PathBuf)> {
+        match self {
+            Self::Default => canonical_path
+                .ancestors()
+                .filter(|dir| dir.file_name().is_some_and(|n| n == "src"))
+                .map(|dir| (dir.to_path_buf(), dir.join(".meta/manifest.json")))
+                .find(|(_, manifest_path)| manifest_path.exists()),
+
+            Self::Relative(relative) => canonical_path
+                .ancestors()
+                .map(|dir| (dir.to_path_buf(), dir.join(relative)))
+                .find(|(_, manifest_path)| manifest_path.exists()),
+
+            Self::Absolute(absolute) => {
+                let manifest_path = absolute.canonicalize().ok()?;
+                let root = manifest_path.parent()?.parent()?.to_path_buf();
+                canonical_path
+                    .starts_with(&root)
+                    .then_some((root, manifest_path))
+            }
 
-    // Read manifest
-    let manifest_path = src_root.join(".meta/manifest.json");
-    let file = match std::fs::File::open(&manifest_path) {
-        Ok(f) => f,
-        Err(e) => {
-            debug!("Failed to open manifest at {manifest_path:?}: {e}");
-            return None;
-        }
-    };
-    let reader = io::BufReader::new(file);
-    let manifest: Manifest = match serde_json::from_reader(reader) {
-        Ok(m) => m,
-        Err(e) => {
-            warn!("Failed to parse manifest at {manifest_path:?}: {e}");
-            return None;
+            Self::Ignored => None,
         }
-    };
+    }
+}
 
-    let entries: HashSet<String> = manifest.entries.keys().cloned().collect();
+/// Which path of a directory reached through a symlink gets matched
+/// against the manifest.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum ZoneSymlinks {
+    /// The path with every symlink resolved, which misses zones when a
+    /// symlink leads outside the src root.
+    Canonical,
 
-    Some(ManifestInfo { src_root, entries })
-}
+    /// The path as it was reached, with `.` and `..` cleaned up but any
+    /// symlinks left alone, like `pwd -L`.
+    Logical,
 
-/// Check if a non-existent path is a valid ghost directory.
-/// Returns Some(ManifestInfo, canonical_path) if it's a valid ghost.
-pub fn is_valid_ghost_dir(path: &Path) -> Option<(ManifestInfo, PathBuf)> {
-    let (manifest_info, canonical_path) = find_manifest_for_ghost(path)?;
+    /// The canonical path if there’s a manifest above it, and the logical
+    /// path otherwise.
+    #[default]
+    Both,
+}
 
-    // Build the target prefix (e.g., "//areas/core/")
-    let rel_path = canonical_path.strip_prefix(&manifest_info.src_root).ok()?;
-    let prefix = format!("//{}/", rel_path.to_string_lossy());
+/// Whether directory names have to match manifest entries exactly.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ZoneCase {
+    /// `Areas` and `//areas` are different, as they are to git.
+    Exact,
 
-    // Check if any manifest entry starts with this prefix
-    let has_children = manifest_info.entries.iter().any(|key| key.starts_with(&prefix));
+    /// `Areas` and `//areas` are the same, as they are on a case-insensitive
+    /// filesystem, and the manifest’s spelling is used.
+    Fold,
+}
 
-    if has_children {
-        Some((manifest_info, canonical_path))
-    } else {
-        None
+impl Default for ZoneCase {
+    /// Case gets folded where filesystems usually ignore it.
+    fn default() -> Self {
+        if cfg!(any(target_os = "macos", windows)) {
+            Self::Fold
+        } else {
+            Self::Exact
+        }
     }
 }
 
-fn get_ghosts<'dir>(dir: &'dir Dir, manifest_info: Option<&ManifestInfo>, ghost_canonical: Option<&PathBuf>) -> Vec<File<'dir>> {
-    let Some(manifest_info) = manifest_info else {
-        return vec![];
-    };
+/// The usual start of a target path, such as `//areas/tools/dev`.
+pub const DEFAULT_TARGET_PREFIX: &str = "//";
 
-    // For ghost directories, use the pre-computed canonical path; otherwise canonicalize
-    let canonical_path = match ghost_canonical {
-        Some(p) => p.clone(),
-        None => match dir.path.canonicalize() {
-            Ok(p) => p,
-            Err(_) => return vec![],
-        },
-    };
+/// How directories get matched against the manifest. A `Dir` hands its
+/// options down to the directories read beneath it.
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DirOptions {
+    /// Where to look for the manifest.
+    pub manifest: ManifestLocation,
 
-    // Determine relative path and prefix
-    let Ok(rel_path) = canonical_path.strip_prefix(&manifest_info.src_root) else {
-        return vec![];
-    };
+    /// What target paths start with, for toolchains that label them
+    /// `@world//areas/tools/dev` or similar.
+    pub target_prefix: String,
 
-    let prefix = if rel_path.as_os_str().is_empty() {
-        "//".to_string()
-    } else {
-        format!("//{}/", rel_path.to_string_lossy())
-    };
+    /// Which path of a directory reached through a symlink gets matched
+    /// against the manifest.
+    pub zone_symlinks: ZoneSymlinks,
 
-    // Identify ghost children (both direct and intermediate)
-    let existing_names: HashSet<String> = dir.contents.iter()
-        .map(|e| File::filename(&e.path()))
-        .collect();
+    /// Whether directories match manifest entries that only differ from
+    /// them in case.
+    pub zone_case: ZoneCase,
 
-    // Track all intermediate directories we need to create ghosts for
-    let mut ghost_names: HashSet<String> = HashSet::new();
+    /// Whether ghosts are checked against git’s sparse-checkout patterns.
+    pub sparse_checkout: bool,
 
-    for key in &manifest_info.entries {
-        if let Some(suffix) = key.strip_prefix(&prefix) {
-            if !suffix.is_empty() {
-                // Get the first component of the path
-                let first_component = suffix.split('/').next().unwrap();
+    /// Whether targets that `.gitignore` ignores are left without ghosts.
+    pub ghosts_respect_gitignore: bool,
+
+    /// Target path patterns, from the user’s `ignore-ghosts` file, whose
+    /// ghosts are never listed.
+    pub ignored_ghosts: Vec<glob::Pattern>,
+
+    /// Whether zones stay listed when `.gitignore` ignores them, on disk
+    /// or as ghosts, so the listing still agrees with the manifest.
+    pub git_ignore_keeps_zones: bool,
+
+    /// Whether orphan directories get a hint of where Git moved them.
+    pub moved_hints: bool,
 
-                // If this component doesn't exist physically, it should be a ghost
-                if !existing_names.contains(first_component) {
-                    ghost_names.insert(first_component.to_string());
+    /// Whether ghosts are dated by the last commit that touched them.
+    pub ghost_dates: bool,
+
+    /// Whether a directory’s total size stops at the zones inside it.
+    pub sizes_within_zones: bool,
+
+    /// Whether every field of each manifest entry is kept, which only
+    /// `--filter-zone`, the owner column, and the like look at.
+    pub entry_metadata: bool,
+
+    /// Whether parsed manifests are cached on disk between invocations.
+    /// The command line turns it on unless `--no-cache` is given, but it’s
+    /// off here by default, so that embedding wls never writes to the
+    /// user’s cache directory without being asked to.
+    pub manifest_cache: bool,
+
+    /// Where to add up how long listing takes, if anywhere.
+    pub timings: Option<Arc<Timings>>,
+}
+
+impl DirOptions {
+    /// Whether a listed directory only needs the manifest entries at or
+    /// beneath it and the ones above it. Folding case has to see every
+    /// entry to know how the manifest spells the directory, and moved hints
+    /// look for where an orphan went anywhere in the src root.
+    fn scopes_manifest(&self) -> bool {
+        self.zone_case != ZoneCase::Fold && !self.moved_hints
+    }
+
+    /// Whether the manifest should never be looked for, in which case
+    /// nothing about it needs any paths canonicalizing or files reading.
+    fn ignores_manifest(&self) -> bool {
+        self.manifest == ManifestLocation::Ignored
+    }
+
+    /// The path of the directory at the given path to match against the
+    /// manifest, following the zone symlink policy.
+    fn manifest_path_of(&self, path: &Path) -> io::Result<PathBuf> {
+        let logical = || std::path::absolute(path).map(path_clean::clean);
+
+        match self.zone_symlinks {
+            ZoneSymlinks::Canonical => path.canonicalize(),
+            ZoneSymlinks::Logical => logical(),
+            ZoneSymlinks::Both => {
+                // Only a path that crosses a symlink has two ways of being
+                // matched, and only then is it worth looking for a manifest
+                // to choose between them
+                let canonical = path.canonicalize()?;
+                let logical = logical()?;
+                if logical == canonical || self.manifest.locate(&canonical).is_some() {
+                    return Ok(canonical);
                 }
+
+                debug!("Matching {path:?} against the manifest as {logical:?}, not {canonical:?}");
+                Ok(logical)
             }
         }
     }
+}
 
-    // Create ghost nodes for all identified names
-    let mut ghosts = Vec::new();
-    for name in ghost_names {
-        let ghost_path = dir.path.join(&name);
-        // Check if this ghost is itself a zone
-        let ghost_target = format!("{prefix}{name}");
-        let is_zone = manifest_info.is_zone(&ghost_target);
-        ghosts.push(File::new_ghost(ghost_path, dir, name, is_zone));
+impl Default for DirOptions {
+    fn default() -> Self {
+        Self {
+            manifest: ManifestLocation::default(),
+            target_prefix: DEFAULT_TARGET_PREFIX.into(),
+            zone_symlinks: ZoneSymlinks::default(),
+            zone_case: ZoneCase::default(),
+            sparse_checkout: false,
+            ghosts_respect_gitignore: false,
+            ignored_ghosts: Vec::new(),
+            git_ignore_keeps_zones: false,
+            moved_hints: false,
+            ghost_dates: false,
+            sizes_within_zones: false,
+            entry_metadata: false,
+            manifest_cache: false,
+            timings: None,
+        }
     }
+}
 
-    ghosts
+/// Find the manifest for the directory at the given canonical path. When
+/// it’s scoped, only the entries that directory can be listed with and the
+/// ones above it need parsing.
+fn find_manifest_from_canonical(
+    canonical_path: &Path,
+    options: &DirOptions,
+    scoped: bool,
+) -> Option<ManifestInfo> {
+    let (src_root, manifest_path) = options.manifest.locate(canonical_path)?;
+    let beneath = canonical_path
+        .strip_prefix(&src_root)
+        .ok()
+        .filter(|rel_path| scoped && !rel_path.as_os_str().is_empty())
+        .map(|rel_path| format!("{}{}", options.target_prefix, target_rel_path(rel_path)));
+
+    let ParsedManifest {
+        entries,
+        files,
+        zone_kinds,
+        deps,
+        tags,
+        metadata,
+        estimates,
+    } = read_manifest(&manifest_path, options, beneath.as_deref())?;
+    let sync_states = read_sync_states(&manifest_path);
+
+    let target_prefix = options.target_prefix.clone();
+    let sparse_checkout = options
+        .sparse_checkout
+        .then(|| SparseCheckout::find(&src_root))
+        .flatten();
+    let repo_config = RepoConfig::find(&manifest_path);
+    let folded =
+        (options.zone_case == ZoneCase::Fold).then(|| fold_targets(&entries, &target_prefix));
+    Some(ManifestInfo {
+        src_root,
+        entries,
+        files,
+        target_prefix,
+        sync_states,
+        zone_kinds,
+        deps,
+        tags,
+        metadata,
+        estimates,
+        sparse_checkout,
+        repo_config,
+        folded,
+        ignored_ghosts: options.ignored_ghosts.clone(),
+        child_names: OnceLock::new(),
+    })
 }
 
-/// A **Dir** provides a cached list of the file paths in a directory that's
-/// being listed.
-///
-/// This object gets passed to the Files themselves, in order for them to
-/// check the existence of surrounding files, then highlight themselves
-/// accordingly. (See `File#get_source_files`)
-pub struct Dir {
-    /// A vector of the files that have been read from this directory.
-    contents: Vec<DirEntry>,
+/// Every entry, and every directory on the way to one, keyed by its
+/// lowercase form. Where two only differ in case, the one that sorts first
+/// wins, so that it’s the same one every time.
+fn fold_targets(entries: &HashSet<String>, target_prefix: &str) -> HashMap<String, String> {
+    let mut folded: HashMap<String, String> = HashMap::new();
+
+    for entry in entries {
+        let rest = entry.strip_prefix(target_prefix).unwrap_or(entry);
+        let ends = rest.match_indices('/').map(|(i, _)| i).chain([rest.len()]);
+        for end in ends {
+            let target = &entry[..entry.len() - rest.len() + end];
+            folded
+                .entry(target.to_lowercase())
+                .and_modify(|spelling| {
+                    if target < spelling.as_str() {
+                        *spelling = target.to_string();
+                    }
+                })
+                .or_insert_with(|| target.to_string());
+        }
+    }
 
-    /// The path that was read.
-    pub path: PathBuf,
+    folded
+}
 
-    /// For ghost directories: the pre-computed canonical path and manifest info.
-    /// Ghost directories don't exist on disk, so we can't canonicalize them normally.
-    ghost_info: Option<(ManifestInfo, PathBuf)>,
+/// Get the parsed manifest at the given path, timing how long it took.
+fn read_manifest(
+    manifest_path: &Path,
+    options: &DirOptions,
+    beneath: Option<&str>,
+) -> Option<ParsedManifest> {
+    timings::time(
+        options.timings.as_deref(),
+        Phase::Manifest,
+        manifest_path,
+        || load_manifest(manifest_path, options, beneath),
+    )
 }
 
-impl Dir {
-    /// Create a new, empty `Dir` object representing the directory at the given path.
-    ///
-    /// This function does not attempt to read the contents of the directory; it merely
-    /// initializes an instance of `Dir` with an empty `DirEntry` list and the specified path.
-    /// To populate the `Dir` object with actual directory contents, use the `read` function.
-    pub fn new(path: PathBuf) -> Self {
-        Self {
-            contents: vec![],
-            path,
-            ghost_info: None,
-        }
+/// Get the parsed manifest at the given path from the daemon if one is
+/// running, then from the cache on disk, and only parse it if neither has
+/// it, caching what gets parsed for next time. The daemon and the cache
+/// hold every entry, since they don’t know what gets listed next, so only
+/// a manifest parsed without them gets limited to the entries at or
+/// beneath the given target path.
+fn load_manifest(
+    manifest_path: &Path,
+    options: &DirOptions,
+    beneath: Option<&str>,
+) -> Option<ParsedManifest> {
+    if let Some(parsed) = daemon::fetch_manifest(manifest_path) {
+        return Some(parsed);
+    }
+
+    let with_metadata = options.entry_metadata;
+    if !options.manifest_cache {
+        return parse_manifest(manifest_path, with_metadata, beneath);
+    }
+
+    if let Some(parsed) = manifest_cache::load(manifest_path, with_metadata) {
+        return Some(parsed);
+    }
+
+    let parsed = parse_manifest(manifest_path, with_metadata, None)?;
+    manifest_cache::store(manifest_path, with_metadata, &parsed);
+    Some(parsed)
+}
+
+/// The parts of `ManifestInfo` that come from the manifest file itself,
+/// which is all that `--daemon` and the cache on disk keep between
+/// invocations. Changing it means bumping the cache’s schema version.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct ParsedManifest {
+    pub entries: HashSet<String>,
+
+    /// The entries whose `type` says they’re a single file rather than a
+    /// directory.
+    #[serde(default)]
+    pub files: HashSet<String>,
+
+    pub zone_kinds: HashMap<String, ZoneKind>,
+    pub deps: HashMap<String, Vec<String>>,
+    pub tags: HashMap<String, Vec<String>>,
+    pub metadata: HashMap<String, Metadata>,
+
+    /// The rough sizes of the entries that have them.
+    #[serde(default)]
+    pub estimates: HashMap<String, Estimate>,
+}
+
+/// Read and parse the manifest at the given path, warning if it isn’t
+/// valid JSON. Each entry is only parsed as far as the fields that get
+/// used, with all of them being kept only when `--filter-zone` and the like
+/// need them. Given a target path, only the entries at or beneath it and
+/// the ones above it are kept, and the rest are skipped over without even
+/// their keys being copied.
+pub fn parse_manifest(
+    manifest_path: &Path,
+    with_metadata: bool,
+    beneath: Option<&str>,
+) -> Option<ParsedManifest> {
+    // The whole file is read first, rather than mapped, so that another
+    // process rewriting it can’t change it halfway through being parsed
+    let text = match std::fs::read(manifest_path) {
+        Ok(text) => text,
+        Err(e) => {
+            debug!("Failed to open manifest at {manifest_path:?}: {e}");
+            return None;
+        }
+    };
+
+    let visitor = ManifestVisitor {
+        with_metadata,
+        beneath,
+    };
+    let mut deserializer = serde_json::Deserializer::from_slice(&text);
+    let parsed = serde::Deserializer::deserialize_map(&mut deserializer, visitor)
+        .and_then(|parsed| deserializer.end().map(|()| parsed));
+    match parsed {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            if !warnings::write_json(Kind::Manifest, manifest_path.display(), &e) {
+                warn!("Failed to parse manifest at {manifest_path:?}: {e}");
+            }
+            None
+        }
+    }
+}
+
+/// A target path as it’s written in the manifest, borrowed from its text
+/// unless it has escapes in it.
+#[derive(Deserialize)]
+struct Target<'a>(#[serde(borrow)] Cow<'a, str>);
+
+/// Goes through the manifest’s entries one at a time, picking out the ones
+/// that get kept.
+struct ManifestVisitor<'a> {
+    with_metadata: bool,
+    beneath: Option<&'a str>,
+}
+
+impl ManifestVisitor<'_> {
+    /// Whether the entry with the given target path is at or beneath the
+    /// target being listed, or above it.
+    fn keeps(&self, target: &str) -> bool {
+        let Some(beneath) = self.beneath else {
+            return true;
+        };
+
+        let is_under = |inner: &str, outer: &str| {
+            inner
+                .strip_prefix(outer)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        };
+        is_under(target, beneath) || is_under(beneath, target)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for ManifestVisitor<'_> {
+    type Value = ParsedManifest;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("an object of manifest entries")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut parsed = ParsedManifest::default();
+        while let Some(Target(target)) = map.next_key()? {
+            if self.keeps(&target) {
+                let entry: &RawValue = map.next_value()?;
+                parsed.add(target.into_owned(), entry, self.with_metadata);
+            } else {
+                let _: serde::de::IgnoredAny = map.next_value()?;
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl ParsedManifest {
+    /// Picks out the fields that get used from the entry with the given
+    /// target path.
+    fn add(&mut self, target: String, entry: &RawValue, with_metadata: bool) {
+        let fields = EntryFields::of(entry);
+
+        if let Some(kind) = ZoneKind::from_entry(&fields) {
+            let _ = self.zone_kinds.insert(target.clone(), kind);
+        } else if is_file_entry(&fields) {
+            let _ = self.files.insert(target.clone());
+        }
+
+        let deps = deps_from_entry(&fields);
+        if !deps.is_empty() {
+            let _ = self.deps.insert(target.clone(), deps);
+        }
+
+        let tags = tags_from_entry(&fields);
+        if !tags.is_empty() {
+            let _ = self.tags.insert(target.clone(), tags);
+        }
+
+        if let Some(estimate) = Estimate::from_entry(&fields) {
+            let _ = self.estimates.insert(target.clone(), estimate);
+        }
+
+        if with_metadata {
+            let metadata = serde_json::from_str(entry.get())
+                .map(|value| metadata_from_entry(&value))
+                .unwrap_or_default();
+            if !metadata.is_empty() {
+                let _ = self.metadata.insert(target.clone(), metadata);
+            }
+        }
+
+        let _ = self.entries.insert(target);
+    }
+}
+
+/// Read the sync-state provider’s record of who last synced each zone,
+/// which lives next to the manifest. Not every checkout has one, so a
+/// missing file just means no zones have known sync states.
+fn read_sync_states(manifest_path: &Path) -> HashMap<String, SyncState> {
+    let sync_state_path = manifest_path.with_file_name("sync-state.json");
+    let file = match std::fs::File::open(&sync_state_path) {
+        Ok(f) => f,
+        Err(e) => {
+            debug!("No sync state at {sync_state_path:?}: {e}");
+            return HashMap::new();
+        }
+    };
+
+    match serde_json::from_reader(io::BufReader::new(file)) {
+        Ok(states) => states,
+        Err(e) => {
+            if !warnings::write_json(Kind::SyncState, sync_state_path.display(), &e) {
+                warn!("Failed to parse sync state at {sync_state_path:?}: {e}");
+            }
+            HashMap::new()
+        }
+    }
+}
+
+/// Check if a non-existent path is a valid ghost directory.
+/// Returns `Some((ManifestInfo, canonical_path))` if it's a valid ghost.
+pub fn is_valid_ghost_dir(path: &Path, options: &DirOptions) -> Option<(ManifestInfo, PathBuf)> {
+    let (manifest_info, canonical_path) = find_manifest_for_ghost(path, options)?;
+
+    // It's a ghost if any manifest entry is beneath it, or if it's a zone
+    // in its own right
+    let has_children = manifest_info.has_entries_beneath(&canonical_path);
+    let is_zone = manifest_info.is_zone_path(&canonical_path);
+
+    if has_children || is_zone {
+        Some((manifest_info, canonical_path))
+    } else {
+        None
+    }
+}
+
+/// Whether a path contains any of the characters that make it a glob
+/// pattern rather than a plain path.
+pub fn is_glob_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+/// Expand a glob pattern such as `areas/pay*` against both the filesystem
+/// and the manifest, so that ghosts match as well as files on disk. The
+/// paths are returned sorted and without duplicates.
+pub fn expand_glob(pattern: &Path, options: &DirOptions) -> Vec<PathBuf> {
+    let mut matched = BTreeSet::new();
+
+    if let Some(Ok(paths)) = pattern.to_str().map(glob::glob) {
+        matched.extend(paths.filter_map(Result::ok));
+    }
+
+    matched.extend(glob_ghosts(pattern, options));
+    matched.into_iter().collect()
+}
+
+/// Match the wildcard components of a glob pattern against the manifest
+/// entries (and their intermediate directories) under its literal prefix.
+fn glob_ghosts(pattern: &Path, options: &DirOptions) -> Vec<PathBuf> {
+    let components: Vec<&std::ffi::OsStr> = pattern.iter().collect();
+    let Some(split) = components
+        .iter()
+        .position(|c| is_glob_pattern(Path::new(c)))
+    else {
+        return vec![];
+    };
+
+    let literal: PathBuf = components[..split].iter().collect();
+    let base = if literal.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        literal.as_path()
+    };
+    let Some((manifest_info, canonical_path)) = find_manifest_for_ghost(base, options) else {
+        return vec![];
+    };
+    let Ok(rel_path) = canonical_path.strip_prefix(&manifest_info.src_root) else {
+        return vec![];
+    };
+
+    let prefix = manifest_info.children_prefix(rel_path);
+
+    let wildcards: Vec<String> = components[split..]
+        .iter()
+        .map(|c| c.to_string_lossy().into_owned())
+        .collect();
+    let Ok(suffix_pattern) = glob::Pattern::new(&wildcards.join("/")) else {
+        return vec![];
+    };
+    let match_options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+    };
+
+    let mut ghosts = BTreeSet::new();
+    for key in &manifest_info.entries {
+        let Some(suffix) = key.strip_prefix(&prefix) else {
+            continue;
+        };
+
+        // Cut the entry down to the same depth as the pattern, so that
+        // intermediate directories can match too
+        let parts: Vec<&str> = suffix.split('/').take(wildcards.len()).collect();
+        if parts.len() < wildcards.len() {
+            continue;
+        }
+
+        let candidate = parts.join("/");
+        if suffix_pattern.matches_with(&candidate, match_options) {
+            ghosts.insert(literal.join(path_for_target_rel(&candidate)));
+        }
+    }
+
+    ghosts.into_iter().collect()
+}
+
+/// Find the ghosts in a directory. With a Git cache to ask, targets that
+/// `.gitignore` ignores get no ghosts.
+fn get_ghosts<'dir>(
+    dir: &'dir Dir,
+    manifest_info: Option<&ManifestInfo>,
+    ignores: Option<&GitCache>,
+) -> (Vec<File<'dir>>, Vec<CaseCollision>) {
+    let (Some(manifest_info), Some(canonical_path)) = (manifest_info, dir.canonical_path()) else {
+        return (vec![], vec![]);
+    };
+
+    // Determine relative path and prefix
+    let Ok(rel_path) = canonical_path.strip_prefix(&manifest_info.src_root) else {
+        return (vec![], vec![]);
+    };
+
+    let prefix = manifest_info.children_prefix(rel_path);
+
+    let existing_names: HashSet<String> = dir
+        .contents
+        .iter()
+        .map(|e| e.name.to_string_lossy().into_owned())
+        .collect();
+    let (ghost_names, case_collisions) = ghost_candidates(
+        &manifest_info.entries,
+        &prefix,
+        &existing_names,
+        manifest_info.folded.is_some(),
+    );
+    let case_collisions = case_collisions
+        .into_iter()
+        .map(|(in_manifest, on_disk)| CaseCollision {
+            manifest_path: format!("{prefix}{in_manifest}"),
+            disk_path: dir.path.join(on_disk),
+        })
+        .collect();
+
+    // Generated output directories are often in the manifest but ignored,
+    // and only ever appear once something has been built
+    let mut ghost_names = ghost_names;
+    ghost_names.retain(|name| !manifest_info.ignores_ghost(&format!("{prefix}{name}")));
+    if let Some(git) = ignores {
+        ghost_names.retain(|name| {
+            let target = format!("{prefix}{name}");
+            let is_dir = !manifest_info.is_file_target(&target);
+            !git.is_ignored(&canonical_path.join(name), is_dir)
+                || (dir.options.git_ignore_keeps_zones && manifest_info.is_zone(&target))
+        });
+    }
+
+    // Create ghost nodes for all identified names
+    let mut ghosts = Vec::new();
+    for name in ghost_names {
+        let ghost_path = dir.path.join(&name);
+        // Check if this ghost is a file, or is itself a zone
+        let target = format!("{prefix}{name}");
+        let is_file = manifest_info.is_file_target(&target);
+        let zone = if is_file {
+            None
+        } else {
+            manifest_info.zone(&target, false)
+        };
+        let ghost_reason = manifest_info
+            .sparse_checkout
+            .as_ref()
+            .and_then(|sparse| sparse.ghost_reason(&canonical_path.join(&name)));
+        ghosts.push(File::new_ghost(
+            ghost_path,
+            dir,
+            name,
+            zone,
+            is_file,
+            ghost_reason,
+        ));
+    }
+
+    (ghosts, case_collisions)
+}
+
+/// Works out which children of the directory with the given manifest
+/// prefix should be ghosts, because a manifest entry passes through them
+/// but nothing by that name exists on disk.
+///
+/// A case-insensitive filesystem, or a manifest edited by hand, can leave
+/// an entry like `//areas/Payments` next to a `payments` directory. That
+/// is almost certainly the same area, so rather than showing a second,
+/// bogus ghost, the pair is returned as `(in_manifest, on_disk)` so that
+/// the user can be warned about it, unless case is being folded anyway.
+///
+/// The manifest’s entries are in no particular order, so the ghosts are
+/// sorted naturally by name, ignoring case, the same way the default name
+/// sort does, so that the same manifest always gives the same listing.
+/// Like that sort, this compares characters, and isn’t locale-aware.
+fn ghost_candidates(
+    entries: &HashSet<String>,
+    prefix: &str,
+    existing_names: &HashSet<String>,
+    fold_case: bool,
+) -> (Vec<String>, Vec<(String, String)>) {
+    let lowercase_names: HashMap<String, &String> = existing_names
+        .iter()
+        .map(|name| (name.to_lowercase(), name))
+        .collect();
+
+    // Track all intermediate directories we need to create ghosts for
+    let mut ghost_names = BTreeSet::new();
+    let mut collisions = BTreeSet::new();
+
+    for key in entries {
+        if let Some(suffix) = key.strip_prefix(prefix) {
+            if !suffix.is_empty() {
+                // Get the first component of the path
+                let first_component = suffix.split('/').next().unwrap();
+
+                // If this component doesn't exist physically, it should be a
+                // ghost, unless it only differs in case from one that does
+                if existing_names.contains(first_component) {
+                    continue;
+                }
+
+                match lowercase_names.get(&first_component.to_lowercase()) {
+                    Some(_) if fold_case => {}
+                    Some(on_disk) => {
+                        collisions.insert((first_component.to_string(), (*on_disk).clone()));
+                    }
+                    None => {
+                        ghost_names.insert(first_component.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut ghost_names: Vec<String> = ghost_names.into_iter().collect();
+    ghost_names
+        .sort_by(|a, b| natord::compare_ignore_case(a, b).then_with(|| natord::compare(a, b)));
+    (ghost_names, collisions.into_iter().collect())
+}
+
+/// A manifest entry whose name only differs in case from something that
+/// exists on disk, so it was not shown as a ghost.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseCollision {
+    /// The manifest path, such as `//areas/Payments`.
+    pub manifest_path: String,
+
+    /// The path on disk it collided with, such as `areas/payments`.
+    pub disk_path: PathBuf,
+}
+
+/// A **Dir** provides a cached list of the file paths in a directory that's
+/// being listed.
+///
+/// This object gets passed to the Files themselves, in order for them to
+/// check the existence of surrounding files, then highlight themselves
+/// accordingly. (See `File#get_source_files`)
+pub struct Dir {
+    /// A vector of the files that have been read from this directory.
+    contents: Vec<DirEntry>,
+
+    /// The path that was read.
+    pub path: PathBuf,
+
+    /// For ghost directories: the pre-computed canonical path and manifest info.
+    /// Ghost directories don't exist on disk, so we can't canonicalize them normally.
+    ghost_info: Option<(ManifestInfo, PathBuf)>,
+
+    /// The manifest this directory is under, looked up the first time one of
+    /// its files needs it.
+    manifest_info: OnceLock<Option<ManifestInfo>>,
+
+    /// The canonical path of this directory, worked out the first time it’s
+    /// needed. The canonical paths of its files are derived from it, so
+    /// that they don’t each have to be asked of the filesystem.
+    canonical_path: OnceLock<Option<PathBuf>>,
+
+    /// The device and inode numbers of this directory and each one above
+    /// it, with their paths, looked up the first time a symlink in it has to
+    /// be checked for a cycle.
+    ancestors: OnceLock<HashMap<(u64, u64), PathBuf>>,
+
+    /// How this directory gets matched against the manifest, which the
+    /// directories read beneath it share.
+    options: Arc<DirOptions>,
+}
+
+impl Dir {
+    /// Create a new, empty `Dir` object representing the directory at the given path.
+    ///
+    /// This function does not attempt to read the contents of the directory; it merely
+    /// initializes an instance of `Dir` with an empty `DirEntry` list and the specified path.
+    /// To populate the `Dir` object with actual directory contents, use the `read` function.
+    pub fn new(path: PathBuf, options: Arc<DirOptions>) -> Self {
+        Self {
+            contents: vec![],
+            path,
+            ghost_info: None,
+            manifest_info: OnceLock::new(),
+            canonical_path: OnceLock::new(),
+            ancestors: OnceLock::new(),
+            options,
+        }
     }
 
     /// Create a new Dir for a ghost directory that doesn't exist on disk.
-    /// The manifest_info and canonical_path are pre-computed since we can't
+    /// The `manifest_info` and `canonical_path` are pre-computed since we can't
     /// canonicalize a non-existent path.
-    pub fn new_ghost(path: PathBuf, manifest_info: ManifestInfo, canonical_path: PathBuf) -> Self {
+    pub fn new_ghost(
+        path: PathBuf,
+        manifest_info: ManifestInfo,
+        canonical_path: PathBuf,
+        options: Arc<DirOptions>,
+    ) -> Self {
         Self {
             contents: vec![],
             path,
             ghost_info: Some((manifest_info, canonical_path)),
+            manifest_info: OnceLock::new(),
+            canonical_path: OnceLock::new(),
+            ancestors: OnceLock::new(),
+            options,
+        }
+    }
+
+    /// How this directory gets matched against the manifest.
+    pub fn options(&self) -> &Arc<DirOptions> {
+        &self.options
+    }
+
+    /// The manifest this directory is under, if any. Ghost directories
+    /// already know theirs; for the rest, it’s looked up on first use.
+    pub fn manifest_info(&self) -> Option<&ManifestInfo> {
+        match self.ghost_info {
+            Some((ref manifest_info, _)) => Some(manifest_info),
+            None => self
+                .manifest_info
+                .get_or_init(|| {
+                    if self.options.ignores_manifest() {
+                        return None;
+                    }
+                    let scoped = self.options.scopes_manifest();
+                    find_manifest_from_canonical(self.canonical_path()?, &self.options, scoped)
+                })
+                .as_ref(),
+        }
+    }
+
+    /// This directory, or the one above it, that has the given device and
+    /// inode numbers, if any of them do.
+    pub fn ancestor_with_id(&self, id: (u64, u64)) -> Option<&Path> {
+        self.ancestors
+            .get_or_init(|| ancestor_ids(&self.path))
+            .get(&id)
+            .map(PathBuf::as_path)
+    }
+
+    /// The canonical path of this directory, if it can be worked out. Ghost
+    /// directories already know theirs; for the rest, it’s looked up on
+    /// first use. When the directory was reached through a symlink that
+    /// leads out of the src root, this can be the logical path instead,
+    /// depending on the zone symlink policy.
+    pub fn canonical_path(&self) -> Option<&Path> {
+        match self.ghost_info {
+            Some((_, ref canonical_path)) => Some(canonical_path),
+            None => self
+                .canonical_path
+                .get_or_init(|| match self.options.manifest_path_of(&self.path) {
+                    Ok(p) => Some(p),
+                    Err(e) => {
+                        debug!("Failed to canonicalize path {:?}: {e}", self.path);
+                        None
+                    }
+                })
+                .as_deref(),
         }
     }
 
@@ -278,6 +1623,21 @@ impl Dir {
         self.ghost_info.is_some()
     }
 
+    /// How many ghosts deep this directory is below the nearest directory
+    /// that’s really there, which is zero unless it’s a ghost itself.
+    pub fn ghost_level(&self) -> usize {
+        if !self.is_ghost() {
+            return 0;
+        }
+
+        // The last ancestor of a relative path is the empty path, which
+        // stands for the current directory rather than a ghost
+        self.path
+            .ancestors()
+            .take_while(|p| !p.as_os_str().is_empty() && !p.exists())
+            .count()
+    }
+
     /// Reads the contents of the directory into `DirEntry`.
     ///
     /// It is recommended to use this method in conjunction with `new` in recursive
@@ -294,7 +1654,12 @@ impl Dir {
 
         info!("Reading directory {:?}", &self.path);
 
-        self.contents = fs::read_dir(&self.path)?.collect::<Result<Vec<_>, _>>()?;
+        self.contents = timings::time(
+            self.options.timings.as_deref(),
+            Phase::ReadDir,
+            &self.path,
+            || entries::read(&self.path)?.collect::<io::Result<_>>(),
+        )?;
 
         info!("Read directory success {:?}", &self.path);
         Ok(self)
@@ -308,18 +1673,52 @@ impl Dir {
     /// The `read_dir` iterator doesn't actually yield the `.` and `..`
     /// entries, so if the user wants to see them, we'll have to add them
     /// ourselves after the files have been read.
-    pub fn read_dir(path: PathBuf) -> io::Result<Self> {
+    pub fn read_dir(path: PathBuf, options: Arc<DirOptions>) -> io::Result<Self> {
         info!("Reading directory {:?}", &path);
 
-        let contents = fs::read_dir(&path)?.collect::<Result<Vec<_>, _>>()?;
+        let contents = timings::time(options.timings.as_deref(), Phase::ReadDir, &path, || {
+            entries::read(&path)?.collect::<io::Result<_>>()
+        })?;
 
         info!("Read directory success {:?}", &path);
-        Ok(Self { contents, path, ghost_info: None })
+        Ok(Self {
+            contents,
+            path,
+            ghost_info: None,
+            manifest_info: OnceLock::new(),
+            canonical_path: OnceLock::new(),
+            ancestors: OnceLock::new(),
+            options,
+        })
+    }
+
+    /// Whether the files in this directory include its ghosts. Unless told
+    /// whether to, ghosts are listed if the src root says so.
+    pub fn lists_ghosts(&self, ghosts: Option<bool>) -> bool {
+        ghosts.unwrap_or_else(|| {
+            self.manifest_info()
+                .is_none_or(|m| m.repo_config.shows_ghosts())
+        })
+    }
+
+    /// Whether this is the src root itself, rather than something in it.
+    pub fn is_src_root(&self) -> bool {
+        match (self.manifest_info(), self.canonical_path()) {
+            (Some(manifest_info), Some(canonical_path)) => canonical_path == manifest_info.src_root,
+            _ => false,
+        }
     }
 
     /// Produce an iterator of IO results of trying to read all the files in
     /// this directory.
+    ///
+    /// Every ghost is produced, sorted by name, after the files on disk.
+    /// Anything that sorts collects them along with those files, and only
+    /// then filters, sorts, and cuts them down with `FileFilter::ghost_limit`,
+    /// so that each of those treats ghosts and files alike. Streaming
+    /// doesn’t sort at all, so it lists them where they come.
     #[must_use]
+    #[allow(clippy::fn_params_excessive_bools)]
     pub fn files<'dir, 'ig>(
         &'dir self,
         dots: DotFilter,
@@ -327,21 +1726,18 @@ impl Dir {
         git_ignoring: bool,
         deref_links: bool,
         total_size: bool,
-        no_ghosts: bool,
+        ghosts: Option<bool>,
     ) -> Files<'dir, 'ig> {
-        // For ghost dirs, use pre-loaded manifest; otherwise load it
-        let (manifest_info, ghost_canonical) = match &self.ghost_info {
-            Some((m, c)) => (Some(ManifestInfo {
-                src_root: m.src_root.clone(),
-                entries: m.entries.clone(),
-            }), Some(c.clone())),
-            None => (find_manifest(&self.path), None),
+        let manifest_info = if ghosts == Some(false) {
+            None
+        } else {
+            self.manifest_info()
         };
-
-        let ghosts = if no_ghosts {
-            vec![]
+        let (ghosts, case_collisions) = if self.lists_ghosts(ghosts) {
+            let ignores = git.filter(|_| self.options.ghosts_respect_gitignore);
+            get_ghosts(self, manifest_info, ignores)
         } else {
-            get_ghosts(self, manifest_info.as_ref(), ghost_canonical.as_ref())
+            (vec![], vec![])
         };
 
         Files {
@@ -354,14 +1750,39 @@ impl Dir {
             deref_links,
             total_size,
             ghosts: ghosts.into_iter(),
-            manifest_info,
+            case_collisions,
         }
     }
 
-    /// Whether this directory contains a file with the given path.
+    /// Whether this directory contains a file with the given path, either
+    /// on disk or as a ghost that the manifest says should be there.
     #[must_use]
     pub fn contains(&self, path: &Path) -> bool {
-        self.contents.iter().any(|p| p.path().as_path() == path)
+        self.contents
+            .iter()
+            .any(|e| self.path.join(&e.name) == path)
+            || self.contains_ghost(path)
+    }
+
+    /// Whether the given path is a child of this directory that the
+    /// manifest knows about, whether or not it’s on disk.
+    fn contains_ghost(&self, path: &Path) -> bool {
+        if path.parent() != Some(self.path.as_path()) {
+            return false;
+        }
+        let (Some(name), Some(manifest_info), Some(canonical_path)) = (
+            path.file_name(),
+            self.manifest_info(),
+            self.canonical_path(),
+        ) else {
+            return false;
+        };
+        let Ok(rel_path) = canonical_path.strip_prefix(&manifest_info.src_root) else {
+            return false;
+        };
+
+        let prefix = manifest_info.children_prefix(rel_path);
+        manifest_info.has_child(&prefix, &name.to_string_lossy())
     }
 
     /// Append a path onto the path specified by this directory.
@@ -371,6 +1792,27 @@ impl Dir {
     }
 }
 
+/// The device and inode numbers of the directory at the given path and each
+/// one above it, with the path to the closest one that has them. The paths
+/// are followed through any symlinks, just as recursing into them was.
+pub fn ancestor_ids(path: &Path) -> HashMap<(u64, u64), PathBuf> {
+    let mut ids = HashMap::new();
+
+    #[cfg(unix)]
+    if let Ok(path) = std::path::absolute(path) {
+        use std::os::unix::fs::MetadataExt;
+
+        for ancestor in path.ancestors() {
+            if let Ok(metadata) = std::fs::metadata(ancestor) {
+                ids.entry((metadata.dev(), metadata.ino()))
+                    .or_insert_with(|| ancestor.to_path_buf());
+            }
+        }
+    }
+
+    ids
+}
+
 /// Iterator over reading the contents of a directory as `File` objects.
 #[allow(clippy::struct_excessive_bools)]
 pub struct Files<'dir, 'ig> {
@@ -400,11 +1842,18 @@ pub struct Files<'dir, 'ig> {
     /// Iterator over ghost files to be displayed
     ghosts: std::vec::IntoIter<File<'dir>>,
 
-    /// Manifest info for determining zone status
-    manifest_info: Option<ManifestInfo>,
+    /// Manifest entries that were not shown as ghosts because they only
+    /// differ in case from something on disk.
+    case_collisions: Vec<CaseCollision>,
 }
 
 impl<'dir> Files<'dir, '_> {
+    /// The manifest entries that were left out of this listing because
+    /// their names only differ in case from something on disk.
+    pub fn case_collisions(&self) -> &[CaseCollision] {
+        &self.case_collisions
+    }
+
     fn parent(&self) -> PathBuf {
         // We can’t use `Path#parent` here because all it does is remove the
         // last path component, which is no good for us if the path is
@@ -419,8 +1868,8 @@ impl<'dir> Files<'dir, '_> {
     fn next_visible_file(&mut self) -> Option<File<'dir>> {
         loop {
             if let Some(entry) = self.inner.next() {
-                let path = entry.path();
-                let filename = File::filename(&path);
+                let path = self.dir.path.join(&entry.name);
+                let filename = entry.name.to_string_lossy().into_owned();
                 if !self.dotfiles && filename.starts_with('.') {
                     continue;
                 }
@@ -432,32 +1881,33 @@ impl<'dir> Files<'dir, '_> {
                     continue;
                 }
 
-                if self.git_ignoring {
-                    let git_status = self.git.map(|g| g.get(&path, false)).unwrap_or_default();
-                    if git_status.unstaged == GitStatus::Ignored {
-                        continue;
-                    }
+                let ignored = self.git_ignoring
+                    && self
+                        .git
+                        .map(|g| g.get(&path, false))
+                        .unwrap_or_default()
+                        .unstaged
+                        == GitStatus::Ignored;
+                if ignored && !self.dir.options.git_ignore_keeps_zones {
+                    continue;
                 }
 
-                let mut file = File::from_args(
+                // Whether this is a zone is worked out later, and only if
+                // something asks, from this directory’s canonical path
+                let file = File::from_entry(
                     path.clone(),
                     self.dir,
                     filename,
                     self.deref_links,
                     self.total_size,
-                    entry.file_type().ok(),
+                    entry.kind,
+                    &self.dir.options,
                 );
 
-                // Check if this file is a zone (only for directories)
-                if file.is_directory() {
-                    if let Some(ref manifest) = self.manifest_info {
-                        // Need to canonicalize the path for comparison with manifest entries
-                        if let Ok(canonical) = path.canonicalize() {
-                            if let Some(target_path) = manifest.target_path_for(&canonical) {
-                                file.is_zone = manifest.is_zone(&target_path);
-                            }
-                        }
-                    }
+                // An ignored zone is still in the manifest, so it’s kept if
+                // asked, even though nothing else that’s ignored is
+                if ignored && !file.is_zone() {
+                    continue;
                 }
 
                 // Windows has its own concept of hidden files, when dotfiles are
@@ -554,24 +2004,433 @@ impl DotFilter {
 }
 
 #[cfg(test)]
-mod test {
+pub(super) mod test {
     use super::*;
 
+    /// A manifest for a src root at `/test/src` with the given entries and
+    /// nothing else, for tests to fill in the rest of with `..`.
+    pub(in crate::fs) fn make_manifest(entries: &[&str]) -> ManifestInfo {
+        ManifestInfo {
+            src_root: PathBuf::from("/test/src"),
+            entries: entries.iter().map(|s| (*s).to_string()).collect(),
+            files: HashSet::new(),
+            target_prefix: DEFAULT_TARGET_PREFIX.to_string(),
+            sparse_checkout: None,
+            repo_config: RepoConfig::default(),
+            folded: None,
+            ignored_ghosts: Vec::new(),
+            child_names: OnceLock::new(),
+            sync_states: HashMap::new(),
+            zone_kinds: HashMap::new(),
+            deps: HashMap::new(),
+            tags: HashMap::new(),
+            metadata: HashMap::new(),
+            estimates: HashMap::new(),
+        }
+    }
+
     mod manifest_info {
         use super::*;
 
-        fn make_manifest(entries: &[&str]) -> ManifestInfo {
-            ManifestInfo {
-                src_root: PathBuf::from("/test/src"),
-                entries: entries.iter().map(|s| s.to_string()).collect(),
-            }
-        }
-
-        #[test]
-        fn is_zone_returns_true_for_matching_entry() {
-            let manifest = make_manifest(&["//areas/tools/dev", "//areas/apps/flow"]);
-            assert!(manifest.is_zone("//areas/tools/dev"));
-            assert!(manifest.is_zone("//areas/apps/flow"));
+        fn raw(value: &serde_json::Value) -> Box<RawValue> {
+            serde_json::value::to_raw_value(value).unwrap()
+        }
+
+        #[test]
+        fn unmaterialized_children_are_only_direct_ones() {
+            let manifest = make_manifest(&[
+                "//areas/tools/dev",
+                "//areas/tools/cli",
+                "//areas/lib",
+                "//top",
+            ]);
+            let on_disk = |path: &Path| path == Path::new("/test/src/areas/lib");
+            assert_eq!(
+                manifest.count_unmaterialized_children("//areas", on_disk),
+                1
+            );
+            assert_eq!(
+                manifest.count_unmaterialized_children("//areas/tools", on_disk),
+                2
+            );
+            assert_eq!(
+                manifest.count_unmaterialized_children("//areas/lib", on_disk),
+                0
+            );
+        }
+
+        #[test]
+        fn is_zone_path_checks_the_target() {
+            let manifest = make_manifest(&["//areas/tools/dev"]);
+            assert!(manifest.is_zone_path(Path::new("/test/src/areas/tools/dev")));
+            assert!(!manifest.is_zone_path(Path::new("/test/src/areas/tools")));
+            assert!(!manifest.is_zone_path(Path::new("/test/src")));
+            assert!(!manifest.is_zone_path(Path::new("/elsewhere/areas/tools/dev")));
+        }
+
+        #[test]
+        fn children_of_the_src_root() {
+            let manifest = make_manifest(&["//areas/tools/dev", "//areas/tools/cli", "//other"]);
+            let expected = vec![
+                ManifestChild {
+                    name: "areas".into(),
+                    is_zone: false,
+                    zones: 2,
+                    kind: None,
+                },
+                ManifestChild {
+                    name: "other".into(),
+                    is_zone: true,
+                    zones: 1,
+                    kind: None,
+                },
+            ];
+            assert_eq!(manifest.children(""), Some(expected.clone()));
+            assert_eq!(manifest.children("//"), Some(expected.clone()));
+            assert_eq!(manifest.children("."), Some(expected));
+        }
+
+        #[test]
+        fn children_have_their_kinds() {
+            let manifest = ManifestInfo {
+                zone_kinds: HashMap::from([("//areas/tools/dev".to_string(), ZoneKind::Tool)]),
+                ..make_manifest(&["//areas/tools/dev", "//areas/tools/cli"])
+            };
+            let kinds: Vec<_> = manifest
+                .children("//areas/tools")
+                .unwrap()
+                .into_iter()
+                .map(|c| c.kind)
+                .collect();
+            assert_eq!(kinds, vec![None, Some(ZoneKind::Tool)]);
+        }
+
+        #[test]
+        fn children_of_a_target() {
+            let manifest =
+                make_manifest(&["//areas/tools", "//areas/tools/dev", "//areas/toolshed"]);
+            let expected = vec![ManifestChild {
+                name: "dev".into(),
+                is_zone: true,
+                zones: 1,
+                kind: None,
+            }];
+            assert_eq!(manifest.children("//areas/tools"), Some(expected.clone()));
+            assert_eq!(manifest.children("areas/tools/"), Some(expected));
+        }
+
+        #[test]
+        fn children_of_a_leaf() {
+            let manifest = make_manifest(&["//areas/tools/dev"]);
+            assert_eq!(manifest.children("//areas/tools/dev"), Some(vec![]));
+        }
+
+        #[test]
+        fn materialized_counts_zones_beneath() {
+            let manifest = make_manifest(&[
+                "//areas",
+                "//areas/tools",
+                "//areas/tools/dev",
+                "//areas/apps",
+                "//other",
+            ]);
+            let on_disk = |path: &Path| path != Path::new("/test/src/areas/tools/dev");
+            assert_eq!(
+                manifest.count_materialized("//areas", on_disk),
+                Some(Materialized {
+                    present: 2,
+                    total: 3
+                })
+            );
+            assert_eq!(
+                manifest.count_materialized("//areas/tools/dev", on_disk),
+                None
+            );
+        }
+
+        #[test]
+        fn materialized_percent_rounds_down() {
+            assert_eq!(
+                Materialized {
+                    present: 2,
+                    total: 3
+                }
+                .percent(),
+                66
+            );
+            assert_eq!(
+                Materialized {
+                    present: 199,
+                    total: 200
+                }
+                .percent(),
+                99
+            );
+            assert_eq!(
+                Materialized {
+                    present: 4,
+                    total: 4
+                }
+                .percent(),
+                100
+            );
+        }
+
+        #[test]
+        fn children_of_an_unknown_target() {
+            let manifest = make_manifest(&["//areas/tools/dev"]);
+            assert_eq!(manifest.children("//areas/nothing"), None);
+            assert_eq!(manifest.children("//areas/tool"), None);
+        }
+
+        #[test]
+        fn zone_kinds_come_from_the_type_field() {
+            let value = raw(&serde_json::json!({ "type": "library", "owner": "x" }));
+            assert_eq!(
+                ZoneKind::from_entry(&EntryFields::of(&value)),
+                Some(ZoneKind::Library)
+            );
+            assert_eq!(
+                ZoneKind::from_entry(&EntryFields::of(&raw(
+                    &serde_json::json!({ "type": "app" })
+                ))),
+                Some(ZoneKind::App)
+            );
+            assert_eq!(
+                ZoneKind::from_entry(&EntryFields::of(&raw(
+                    &serde_json::json!({ "type": "tool" })
+                ))),
+                Some(ZoneKind::Tool)
+            );
+        }
+
+        #[test]
+        fn unknown_zone_kinds_are_ignored() {
+            assert_eq!(
+                ZoneKind::from_entry(&EntryFields::of(&raw(
+                    &serde_json::json!({ "type": "service" })
+                ))),
+                None
+            );
+            assert_eq!(
+                ZoneKind::from_entry(&EntryFields::of(&raw(&serde_json::json!({})))),
+                None
+            );
+            assert_eq!(
+                ZoneKind::from_entry(&EntryFields::of(&raw(&serde_json::json!("library")))),
+                None
+            );
+        }
+
+        #[test]
+        fn file_entries_have_the_file_type() {
+            assert!(is_file_entry(&EntryFields::of(&raw(
+                &serde_json::json!({ "type": "file" })
+            ))));
+            assert!(!is_file_entry(&EntryFields::of(&raw(
+                &serde_json::json!({ "type": "library" })
+            ))));
+            assert!(!is_file_entry(&EntryFields::of(&raw(&serde_json::json!(
+                {}
+            )))));
+        }
+
+        #[test]
+        fn estimates_come_from_the_size_and_file_count_fields() {
+            let value = raw(&serde_json::json!({ "size": 1_300_000_000_u64, "file_count": 4200 }));
+            assert_eq!(
+                Estimate::from_entry(&EntryFields::of(&value)),
+                Some(Estimate {
+                    bytes: Some(1_300_000_000),
+                    files: Some(4200)
+                })
+            );
+            assert_eq!(
+                Estimate::from_entry(&EntryFields::of(&raw(
+                    &serde_json::json!({ "file_count": 12 })
+                ))),
+                Some(Estimate {
+                    bytes: None,
+                    files: Some(12)
+                })
+            );
+            assert_eq!(
+                Estimate::from_entry(&EntryFields::of(&raw(
+                    &serde_json::json!({ "size": "big" })
+                ))),
+                None
+            );
+            assert_eq!(
+                Estimate::from_entry(&EntryFields::of(&raw(&serde_json::json!({})))),
+                None
+            );
+        }
+
+        #[test]
+        fn deps_come_from_the_deps_field() {
+            let value = raw(&serde_json::json!({ "deps": ["//areas/core", 3, "//areas/lib"] }));
+            assert_eq!(
+                deps_from_entry(&EntryFields::of(&value)),
+                vec!["//areas/core", "//areas/lib"]
+            );
+            assert_eq!(
+                deps_from_entry(&EntryFields::of(&raw(
+                    &serde_json::json!({ "deps": "//areas/core" })
+                ))),
+                Vec::<String>::new()
+            );
+            assert_eq!(
+                deps_from_entry(&EntryFields::of(&raw(&serde_json::json!({})))),
+                Vec::<String>::new()
+            );
+        }
+
+        #[test]
+        fn deps_and_reverse_deps() {
+            let manifest = ManifestInfo {
+                deps: HashMap::from([
+                    (
+                        "//areas/apps/flow".to_string(),
+                        vec!["//areas/lib".to_string(), "//areas/core".to_string()],
+                    ),
+                    (
+                        "//areas/tools/dev".to_string(),
+                        vec!["//areas/lib".to_string()],
+                    ),
+                ]),
+                ..make_manifest(&[
+                    "//areas/apps/flow",
+                    "//areas/tools/dev",
+                    "//areas/lib",
+                    "//areas/core",
+                ])
+            };
+            assert_eq!(
+                manifest.deps_of("//areas/apps/flow"),
+                ["//areas/lib", "//areas/core"]
+            );
+            assert!(manifest.deps_of("//areas/lib").is_empty());
+            assert_eq!(
+                manifest.reverse_deps_of("//areas/lib"),
+                vec!["//areas/apps/flow", "//areas/tools/dev"]
+            );
+            assert!(manifest.reverse_deps_of("//areas/apps/flow").is_empty());
+        }
+
+        #[test]
+        fn has_child_looks_through_entries() {
+            let manifest = make_manifest(&["//areas/tools/dev", "//areas/core"]);
+            assert!(manifest.has_child("//", "areas"));
+            assert!(manifest.has_child("//areas/", "tools"));
+            assert!(manifest.has_child("//areas/", "core"));
+            assert!(manifest.has_child("//areas/tools/", "dev"));
+            assert!(!manifest.has_child("//areas/", "dev"));
+            assert!(!manifest.has_child("//areas/", "cor"));
+        }
+
+        #[test]
+        fn tags_come_from_tags_and_labels() {
+            let value = raw(
+                &serde_json::json!({ "tags": ["deprecated", 1], "labels": ["tier1", "deprecated"] }),
+            );
+            assert_eq!(
+                tags_from_entry(&EntryFields::of(&value)),
+                vec!["deprecated", "tier1"]
+            );
+            assert_eq!(
+                tags_from_entry(&EntryFields::of(&raw(&serde_json::json!({})))),
+                Vec::<String>::new()
+            );
+            assert_eq!(
+                tags_from_entry(&EntryFields::of(&raw(&serde_json::json!(true)))),
+                Vec::<String>::new()
+            );
+        }
+
+        #[test]
+        fn metadata_flattens_the_entry() {
+            let value = serde_json::json!({ "owner": { "team": "payments" }, "tags": ["a", 1, {}], "stable": true, "deps": [] });
+            let metadata = metadata_from_entry(&value);
+            assert_eq!(
+                metadata.get("owner.team"),
+                Some(&vec!["payments".to_string()])
+            );
+            assert_eq!(
+                metadata.get("tags"),
+                Some(&vec!["a".to_string(), "1".to_string()])
+            );
+            assert_eq!(metadata.get("stable"), Some(&vec!["true".to_string()]));
+            assert_eq!(metadata.get("deps"), None);
+        }
+
+        #[test]
+        fn matches_at_or_beneath() {
+            use crate::fs::filter::ZoneCondition;
+
+            let manifest = ManifestInfo {
+                metadata: HashMap::from([(
+                    "//areas/tools/dev".to_string(),
+                    metadata_from_entry(&serde_json::json!({ "owner": "payments" })),
+                )]),
+                ..make_manifest(&["//areas/tools/dev", "//areas/apps/flow"])
+            };
+            let filter = ZoneFilter(vec![ZoneCondition::Equals(
+                "owner".into(),
+                "payments".into(),
+            )]);
+            assert!(manifest.has_match_at_or_beneath("//areas/tools", &filter));
+            assert!(!manifest.has_match_at_or_beneath("//areas/apps", &filter));
+        }
+
+        #[test]
+        fn tags_at_or_beneath() {
+            let manifest = ManifestInfo {
+                tags: HashMap::from([(
+                    "//areas/tools/dev".to_string(),
+                    vec!["deprecated".to_string()],
+                )]),
+                ..make_manifest(&[
+                    "//areas/tools/dev",
+                    "//areas/tools/devkit",
+                    "//areas/apps/flow",
+                ])
+            };
+            assert!(manifest.has_tag_at_or_beneath("//areas/tools/dev", "deprecated"));
+            assert!(manifest.has_tag_at_or_beneath("//areas/tools", "deprecated"));
+            assert!(!manifest.has_tag_at_or_beneath("//areas/tools/de", "deprecated"));
+            assert!(!manifest.has_tag_at_or_beneath("//areas/apps", "deprecated"));
+            assert!(!manifest.has_tag_at_or_beneath("//areas/tools", "tier1"));
+            assert_eq!(
+                manifest.zone("//areas/tools/dev", true).unwrap().tags,
+                ["deprecated"]
+            );
+        }
+
+        #[test]
+        fn zone_has_its_kind() {
+            let manifest = ManifestInfo {
+                zone_kinds: HashMap::from([("//areas/tools/dev".to_string(), ZoneKind::Tool)]),
+                ..make_manifest(&["//areas/tools/dev", "//areas/apps/flow"])
+            };
+            assert_eq!(
+                manifest
+                    .zone("//areas/tools/dev", true)
+                    .and_then(|z| z.kind),
+                Some(ZoneKind::Tool)
+            );
+            assert_eq!(
+                manifest
+                    .zone("//areas/apps/flow", true)
+                    .and_then(|z| z.kind),
+                None
+            );
+        }
+
+        #[test]
+        fn is_zone_returns_true_for_matching_entry() {
+            let manifest = make_manifest(&["//areas/tools/dev", "//areas/apps/flow"]);
+            assert!(manifest.is_zone("//areas/tools/dev"));
+            assert!(manifest.is_zone("//areas/apps/flow"));
         }
 
         #[test]
@@ -582,6 +2441,76 @@ mod test {
             assert!(!manifest.is_zone("//other/path"));
         }
 
+        #[test]
+        fn complete_matches_partial_last_component() {
+            let manifest = make_manifest(&[
+                "//areas/tools/dev",
+                "//areas/tools/cli",
+                "//areas/apps/flow",
+            ]);
+            assert_eq!(
+                manifest.complete("//areas/to"),
+                vec!["//areas/tools/cli", "//areas/tools/dev"]
+            );
+        }
+
+        #[test]
+        fn complete_matches_every_component_as_prefix() {
+            let manifest = make_manifest(&["//areas/tools/dev", "//areas/apps/flow"]);
+            assert_eq!(manifest.complete("//a/t"), vec!["//areas/tools/dev"]);
+            assert_eq!(manifest.complete("a/ap/f"), vec!["//areas/apps/flow"]);
+        }
+
+        #[test]
+        fn complete_with_empty_query_matches_everything() {
+            let manifest = make_manifest(&["//b", "//a"]);
+            assert_eq!(manifest.complete("//"), vec!["//a", "//b"]);
+        }
+
+        #[test]
+        fn complete_does_not_match_deeper_queries() {
+            let manifest = make_manifest(&["//areas/tools"]);
+            assert!(manifest.complete("//areas/tools/dev").is_empty());
+            assert!(manifest.complete("//other").is_empty());
+        }
+
+        #[test]
+        fn sync_state_converts_unix_time() {
+            let state = SyncState {
+                user: "alice".into(),
+                time: 86_400,
+            };
+            assert_eq!(
+                state.synced_at().map(|t| t.to_string()),
+                Some("1970-01-02 00:00:00".to_string())
+            );
+        }
+
+        #[test]
+        fn sync_state_parses_from_json() {
+            let json = r#"{"//areas/tools/dev": {"user": "alice", "time": 1700000000}}"#;
+            let states: HashMap<String, SyncState> = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                states.get("//areas/tools/dev"),
+                Some(&SyncState {
+                    user: "alice".into(),
+                    time: 1_700_000_000
+                })
+            );
+        }
+
+        #[test]
+        fn manifest_location_from_path() {
+            assert_eq!(
+                ManifestLocation::from(PathBuf::from("tools/zones.json")),
+                ManifestLocation::Relative(PathBuf::from("tools/zones.json"))
+            );
+            assert_eq!(
+                ManifestLocation::from(PathBuf::from("/repo/tools/zones.json")),
+                ManifestLocation::Absolute(PathBuf::from("/repo/tools/zones.json"))
+            );
+        }
+
         #[test]
         fn target_path_for_builds_correct_path() {
             let manifest = make_manifest(&[]);
@@ -593,6 +2522,161 @@ mod test {
             );
         }
 
+        #[test]
+        fn target_path_for_uses_custom_prefix() {
+            let manifest = ManifestInfo {
+                target_prefix: "@world//".into(),
+                ..make_manifest(&[])
+            };
+
+            let path = Path::new("/test/src/areas/tools/dev");
+            assert_eq!(
+                manifest.target_path_for(path),
+                Some("@world//areas/tools/dev".to_string())
+            );
+        }
+
+        #[test]
+        fn entries_beneath_a_path() {
+            let manifest = make_manifest(&["//areas/tools/dev", "//areas/tools"]);
+            assert!(manifest.has_entries_beneath(Path::new("/test/src/areas")));
+            assert!(manifest.has_entries_beneath(Path::new("/test/src/areas/tools")));
+            assert!(!manifest.has_entries_beneath(Path::new("/test/src/areas/tools/dev")));
+            assert!(!manifest.has_entries_beneath(Path::new("/other/areas")));
+        }
+
+        #[test]
+        fn children_prefix_at_src_root_and_below() {
+            let manifest = make_manifest(&[]);
+            assert_eq!(manifest.children_prefix(Path::new("")), "//");
+            assert_eq!(
+                manifest.children_prefix(Path::new("areas/tools")),
+                "//areas/tools/"
+            );
+        }
+
+        #[test]
+        fn complete_strips_custom_prefix() {
+            let manifest = ManifestInfo {
+                target_prefix: "@world//".into(),
+                ..make_manifest(&["@world//areas/tools/dev", "@world//areas/apps/flow"])
+            };
+            assert_eq!(
+                manifest.complete("@world//a/t"),
+                vec!["@world//areas/tools/dev"]
+            );
+            assert_eq!(manifest.complete("a/ap"), vec!["@world//areas/apps/flow"]);
+        }
+
+        #[test]
+        fn enclosing_zone_walks_up() {
+            let manifest = make_manifest(&["//areas/tools/dev", "//areas/tools/dev/sub"]);
+
+            let path = Path::new("/test/src/areas/tools/dev/lib/main.rs");
+            assert_eq!(
+                manifest.enclosing_zone(path),
+                Some((
+                    "//areas/tools/dev".to_string(),
+                    PathBuf::from("areas/tools/dev")
+                ))
+            );
+
+            let path = Path::new("/test/src/areas/tools/dev/sub");
+            assert_eq!(
+                manifest.enclosing_zone(path),
+                Some((
+                    "//areas/tools/dev/sub".to_string(),
+                    PathBuf::from("areas/tools/dev/sub")
+                ))
+            );
+        }
+
+        #[test]
+        fn orphans() {
+            let manifest = make_manifest(&["//areas/tools/dev"]);
+            assert!(manifest.is_orphan(Path::new("/test/src/areas/legacy")));
+            assert!(!manifest.is_orphan(Path::new("/test/src/areas")));
+            assert!(!manifest.is_orphan(Path::new("/test/src/areas/tools/dev")));
+            assert!(!manifest.is_orphan(Path::new("/test/src/areas/tools/dev/lib")));
+            assert!(!manifest.is_orphan(Path::new("/test/src")));
+        }
+
+        #[test]
+        fn enclosing_zone_outside_any_zone() {
+            let manifest = make_manifest(&["//areas/tools/dev"]);
+            assert_eq!(
+                manifest.enclosing_zone(Path::new("/test/src/areas/tools")),
+                None
+            );
+            assert_eq!(
+                manifest.enclosing_zone(Path::new("/elsewhere/areas/tools/dev")),
+                None
+            );
+        }
+
+        #[test]
+        fn folded_targets_are_spelled_as_in_the_manifest() {
+            let entries = ["//areas/tools/dev", "//Other"];
+            let manifest = ManifestInfo {
+                folded: Some(fold_targets(
+                    &entries.iter().map(|s| (*s).to_string()).collect(),
+                    "//",
+                )),
+                ..make_manifest(&entries)
+            };
+
+            let path = Path::new("/test/src/Areas/Tools/dev");
+            assert_eq!(
+                manifest.target_path_for(path),
+                Some("//areas/tools/dev".to_string())
+            );
+            assert!(manifest.is_zone_path(Path::new("/test/src/other")));
+            assert_eq!(manifest.children_prefix(Path::new("AREAS")), "//areas/");
+            assert!(manifest.has_child("//areas/", "Tools"));
+            assert_eq!(
+                manifest.target_path_for(Path::new("/test/src/elsewhere")),
+                Some("//elsewhere".to_string())
+            );
+        }
+
+        #[test]
+        fn exact_targets_are_not_folded() {
+            let manifest = make_manifest(&["//areas/tools/dev"]);
+            assert!(!manifest.is_zone_path(Path::new("/test/src/Areas/tools/dev")));
+            assert!(!manifest.has_child("//areas/", "Tools"));
+        }
+
+        #[test]
+        fn target_rel_paths_use_forward_slashes() {
+            let rel_path: PathBuf = ["areas", "tools", "dev"].iter().collect();
+            assert_eq!(target_rel_path(&rel_path), "areas/tools/dev");
+            assert_eq!(path_for_target_rel("areas/tools/dev"), rel_path);
+            assert_eq!(path_for_target_rel("areas/tools/dev/"), rel_path);
+        }
+
+        #[test]
+        #[cfg(windows)]
+        fn target_path_for_windows_paths() {
+            let manifest = ManifestInfo {
+                src_root: PathBuf::from(r"C:\test\src"),
+                ..make_manifest(&["//areas/tools/dev"])
+            };
+
+            let path = Path::new(r"C:\test\src\areas\tools\dev");
+            assert_eq!(
+                manifest.target_path_for(path),
+                Some("//areas/tools/dev".to_string())
+            );
+            assert_eq!(
+                manifest.children_prefix(Path::new(r"areas\tools")),
+                "//areas/tools/"
+            );
+            assert_eq!(
+                manifest.path_for("//areas/tools/dev"),
+                Some(path.to_path_buf())
+            );
+        }
+
         #[test]
         fn target_path_for_returns_none_for_src_root() {
             let manifest = make_manifest(&[]);
@@ -609,4 +2693,236 @@ mod test {
             assert_eq!(manifest.target_path_for(path), None);
         }
     }
+
+    mod parse_manifest {
+        use super::*;
+
+        fn parsed(text: &str, beneath: Option<&str>) -> ParsedManifest {
+            let file = tempfile::NamedTempFile::new().unwrap();
+            std::fs::write(file.path(), text).unwrap();
+            super::super::parse_manifest(file.path(), false, beneath).unwrap()
+        }
+
+        const MANIFEST: &str = r#"{
+            "//areas": {},
+            "//areas/tools": {"type": "tool"},
+            "//areas/tools/dev": {"tags": ["tier1"]},
+            "//areas/toolshed": {},
+            "//areas/apps/flow": {"deps": ["//areas/tools/dev"]}
+        }"#;
+
+        fn entries(parsed: &ParsedManifest) -> BTreeSet<&str> {
+            parsed.entries.iter().map(String::as_str).collect()
+        }
+
+        #[test]
+        fn everything() {
+            let parsed = parsed(MANIFEST, None);
+            assert_eq!(parsed.entries.len(), 5);
+            assert_eq!(parsed.zone_kinds["//areas/tools"], ZoneKind::Tool);
+            assert_eq!(parsed.deps["//areas/apps/flow"], ["//areas/tools/dev"]);
+        }
+
+        #[test]
+        fn beneath_a_target() {
+            let parsed = parsed(MANIFEST, Some("//areas/tools"));
+            assert_eq!(
+                entries(&parsed),
+                BTreeSet::from(["//areas", "//areas/tools", "//areas/tools/dev"])
+            );
+            assert_eq!(parsed.tags["//areas/tools/dev"], ["tier1"]);
+            assert!(parsed.deps.is_empty());
+        }
+
+        #[test]
+        fn escaped_targets() {
+            let parsed = parsed(r#"{"//areas/t\u006fols": {}}"#, Some("//areas/tools"));
+            assert_eq!(entries(&parsed), BTreeSet::from(["//areas/tools"]));
+        }
+    }
+
+    mod ghost_candidates {
+        use super::*;
+
+        fn set(names: &[&str]) -> HashSet<String> {
+            names.iter().map(|s| (*s).to_string()).collect()
+        }
+
+        #[test]
+        fn missing_children_become_ghosts() {
+            let entries = set(&["//areas/tools/dev", "//areas/apps/flow"]);
+            let (ghosts, collisions) =
+                ghost_candidates(&entries, "//areas/", &set(&["tools"]), false);
+            assert_eq!(ghosts, vec!["apps"]);
+            assert!(collisions.is_empty());
+        }
+
+        #[test]
+        fn ghosts_are_in_natural_order() {
+            let entries = set(&[
+                "//areas/app10/x",
+                "//areas/app9",
+                "//areas/Billing",
+                "//areas/app9/y",
+                "//areas/tools",
+            ]);
+            let (ghosts, _) = ghost_candidates(&entries, "//areas/", &set(&[]), false);
+            assert_eq!(ghosts, vec!["app9", "app10", "Billing", "tools"]);
+        }
+
+        #[test]
+        fn case_only_difference_is_a_collision() {
+            let entries = set(&["//areas/Payments/api", "//areas/Payments/web"]);
+            let (ghosts, collisions) =
+                ghost_candidates(&entries, "//areas/", &set(&["payments"]), false);
+            assert!(ghosts.is_empty());
+            assert_eq!(
+                collisions,
+                vec![("Payments".to_string(), "payments".to_string())]
+            );
+        }
+
+        #[test]
+        fn exact_match_wins_over_collision() {
+            let entries = set(&["//areas/payments/api"]);
+            let (ghosts, collisions) =
+                ghost_candidates(&entries, "//areas/", &set(&["payments", "Payments"]), false);
+            assert!(ghosts.is_empty());
+            assert!(collisions.is_empty());
+        }
+
+        #[test]
+        fn case_only_difference_is_fine_when_folding() {
+            let entries = set(&["//areas/Payments/api"]);
+            let (ghosts, collisions) =
+                ghost_candidates(&entries, "//areas/", &set(&["payments"]), true);
+            assert!(ghosts.is_empty());
+            assert!(collisions.is_empty());
+        }
+    }
+
+    mod lists_ghosts {
+        use super::*;
+
+        fn dir(config_ghosts: Option<bool>) -> Dir {
+            let mut manifest = make_manifest(&["//areas/tools/dev"]);
+            manifest.repo_config.ghosts = config_ghosts;
+            let path = PathBuf::from("/test/src/areas");
+            Dir::new_ghost(
+                path.clone(),
+                manifest,
+                path,
+                Arc::new(DirOptions::default()),
+            )
+        }
+
+        #[test]
+        fn the_src_root_decides_by_default() {
+            assert!(dir(None).lists_ghosts(None));
+            assert!(dir(Some(true)).lists_ghosts(None));
+            assert!(!dir(Some(false)).lists_ghosts(None));
+        }
+
+        #[test]
+        fn flags_override_the_src_root() {
+            assert!(dir(Some(false)).lists_ghosts(Some(true)));
+            assert!(!dir(Some(true)).lists_ghosts(Some(false)));
+        }
+    }
+
+    mod ghost_level {
+        use super::*;
+
+        fn level(path: &str) -> usize {
+            let path = PathBuf::from(path);
+            let manifest = make_manifest(&["//areas/tools/dev"]);
+            Dir::new_ghost(
+                path.clone(),
+                manifest,
+                path,
+                Arc::new(DirOptions::default()),
+            )
+            .ghost_level()
+        }
+
+        #[test]
+        fn counts_the_ghosts_above_an_absolute_path() {
+            assert_eq!(level("/wls-no-such-dir/areas"), 2);
+        }
+
+        #[test]
+        fn counts_the_ghosts_above_a_relative_path() {
+            assert_eq!(level("wls-no-such-dir/areas"), 2);
+        }
+
+        #[test]
+        fn stops_at_the_first_directory_on_disk() {
+            assert_eq!(level("src/wls-no-such-dir"), 1);
+        }
+    }
+
+    mod ignored_ghosts {
+        use super::*;
+
+        fn manifest(entries: &[&str], patterns: &[&str]) -> ManifestInfo {
+            ManifestInfo {
+                ignored_ghosts: patterns
+                    .iter()
+                    .map(|pattern| glob::Pattern::new(pattern).unwrap())
+                    .collect(),
+                ..make_manifest(entries)
+            }
+        }
+
+        #[test]
+        fn ignored_targets_have_no_ghosts() {
+            let manifest = manifest(
+                &[
+                    "//areas/legacy/billing",
+                    "//areas/legacy/old/x",
+                    "//areas/tools/dev",
+                ],
+                &["//areas/legacy/**", "//areas/legacy"],
+            );
+            let options = Arc::new(DirOptions::default());
+
+            let path = PathBuf::from("/test/src/areas/legacy");
+            let dir = Dir::new_ghost(path.clone(), manifest.clone(), path, Arc::clone(&options));
+            let (ghosts, _) = get_ghosts(&dir, dir.manifest_info(), None);
+            assert!(ghosts.is_empty());
+
+            let path = PathBuf::from("/test/src/areas");
+            let dir = Dir::new_ghost(path.clone(), manifest, path, options);
+            let (ghosts, _) = get_ghosts(&dir, dir.manifest_info(), None);
+            let names: Vec<&str> = ghosts.iter().map(|ghost| ghost.name.as_str()).collect();
+            assert_eq!(names, vec!["tools"]);
+        }
+
+        #[test]
+        fn stars_stay_within_a_directory() {
+            let manifest = manifest(&[], &["//*/legacy"]);
+            assert!(manifest.ignores_ghost("//areas/legacy"));
+            assert!(manifest.ignores_ghost("//areas/legacy/billing"));
+            assert!(!manifest.ignores_ghost("//areas/tools/legacy"));
+        }
+
+        #[test]
+        fn ignored_zones_are_not_counted() {
+            let manifest = manifest(
+                &["//areas/legacy/billing", "//areas/tools/dev", "//areas/lib"],
+                &["//areas/legacy"],
+            );
+
+            let materialized = manifest.count_materialized("//areas", |_| false).unwrap();
+            assert_eq!(materialized.total, 2);
+
+            let names: Vec<String> = manifest
+                .children("//areas")
+                .unwrap()
+                .into_iter()
+                .map(|child| child.name)
+                .collect();
+            assert_eq!(names, vec!["lib", "tools"]);
+        }
+    }
 }
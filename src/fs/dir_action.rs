@@ -6,6 +6,8 @@
 // SPDX-License-Identifier: MIT
 //! What to do when encountering a directory?
 
+use crate::fs::File;
+
 /// The action to take when trying to list a file that turns out to be a
 /// directory.
 ///
@@ -72,9 +74,29 @@ pub struct RecurseOptions {
     /// The maximum number of times that recursion should descend to, if one
     /// is specified.
     pub max_depth: Option<usize>,
+
+    /// Whether to skip directories that have already been listed, such as
+    /// when one argument is inside another.
+    pub dedupe: bool,
+
+    /// Whether to stop at zone boundaries, listing zones found inside the
+    /// arguments without descending into them.
+    pub stop_at_zones: bool,
+
+    /// How many levels of ghosts to expand beneath the nearest directory
+    /// that’s really there, if that’s limited separately from `max_depth`.
+    pub ghost_depth: Option<usize>,
 }
 
 impl RecurseOptions {
+    /// Returns whether a directory found while recursing should be listed
+    /// without descending into it, because it’s a zone and zone boundaries
+    /// aren’t to be crossed.
+    #[must_use]
+    pub fn stops_at(self, dir: &File<'_>) -> bool {
+        self.stop_at_zones && dir.is_zone()
+    }
+
     /// Returns whether a directory of the given depth would be too deep.
     #[must_use]
     pub fn is_too_deep(self, depth: usize) -> bool {
@@ -83,4 +105,14 @@ impl RecurseOptions {
             Some(d) => d <= depth,
         }
     }
+
+    /// Returns whether a ghost directory with the given number of ghosts
+    /// above it, up to the nearest real directory, should be expanded.
+    #[must_use]
+    pub fn expands_ghost(self, ghost_level: usize) -> bool {
+        match self.ghost_depth {
+            None => true,
+            Some(d) => ghost_level < d,
+        }
+    }
 }
@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Adding up how much space each zone takes up, for `--du`.
+//!
+//! The directory is walked once, and every file goes towards the nearest
+//! zone enclosing it, so a zone’s usage leaves out the zones inside it and
+//! nothing gets counted twice. Symlinks count as themselves, rather than
+//! whatever they point to, which keeps the walk inside the directory, and
+//! a file with several hard links is only counted the first time it’s met.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use log::warn;
+
+use crate::fs::ManifestInfo;
+
+/// How much space the files in a zone take up.
+#[derive(PartialEq, Eq, Debug, Default, Copy, Clone)]
+pub struct Usage {
+    /// The sizes of the files, added up.
+    pub bytes: u64,
+
+    /// The number of 512-byte blocks the files take up on disk.
+    pub blocks: u64,
+}
+
+impl Usage {
+    fn add(&mut self, metadata: &fs::Metadata) {
+        self.bytes += metadata.len();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            self.blocks += metadata.blocks();
+        }
+    }
+}
+
+/// Walks the directory at the given canonical path and returns how much
+/// space each zone in it takes up, keyed by target path. Files in the
+/// directory that aren’t in a zone inside it go towards the zone enclosing
+/// the directory, if there is one, and are left out otherwise. Every zone
+/// on disk gets an entry, even if there’s nothing in it.
+pub fn by_zone(manifest_info: &ManifestInfo, root: &Path) -> BTreeMap<String, Usage> {
+    let mut usages = BTreeMap::new();
+    let zone = manifest_info.enclosing_zone(root).map(|(target, _)| target);
+    if let Some(zone) = &zone {
+        let _ = usages.insert(zone.clone(), Usage::default());
+    }
+
+    walk(
+        manifest_info,
+        root,
+        zone.as_deref(),
+        &mut usages,
+        &mut HashSet::new(),
+    );
+    usages
+}
+
+/// Adds the files in the given directory to the usage of the zone they’re
+/// in, descending into its subdirectories.
+fn walk(
+    manifest_info: &ManifestInfo,
+    dir: &Path,
+    zone: Option<&str>,
+    usages: &mut BTreeMap<String, Usage>,
+    counted: &mut HashSet<(u64, u64)>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to read {dir:?} while adding up disk usage: {e}");
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        // This doesn’t follow symlinks
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            let path = entry.path();
+            let child_zone = manifest_info
+                .target_path_for(&path)
+                .filter(|target| manifest_info.is_zone(target));
+            if let Some(target) = &child_zone {
+                let _ = usages.entry(target.clone()).or_default();
+            }
+            walk(
+                manifest_info,
+                &path,
+                child_zone.as_deref().or(zone),
+                usages,
+                counted,
+            );
+        } else if let Some(zone) = zone {
+            if !counted_before(&metadata, counted) {
+                usages.entry(zone.to_string()).or_default().add(&metadata);
+            }
+        }
+    }
+}
+
+/// Whether this file is a hard link to one that’s already been counted,
+/// going by its device and inode numbers.
+#[cfg(unix)]
+fn counted_before(metadata: &fs::Metadata, counted: &mut HashSet<(u64, u64)>) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1 && !counted.insert((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn counted_before(_metadata: &fs::Metadata, _counted: &mut HashSet<(u64, u64)>) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::fs::dir::test::make_manifest;
+
+    fn manifest(src_root: &Path, entries: &[&str]) -> ManifestInfo {
+        ManifestInfo {
+            src_root: src_root.to_path_buf(),
+            ..make_manifest(entries)
+        }
+    }
+
+    /// A src root in a temporary directory with files of the given sizes,
+    /// and its canonical path.
+    fn src_root(files: &[(&str, usize)]) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, size) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![b'x'; *size]).unwrap();
+        }
+        let root = dir.path().canonicalize().unwrap();
+        (dir, root)
+    }
+
+    fn bytes(usages: &BTreeMap<String, Usage>) -> Vec<(&str, u64)> {
+        usages
+            .iter()
+            .map(|(target, usage)| (target.as_str(), usage.bytes))
+            .collect()
+    }
+
+    #[test]
+    fn files_go_to_the_nearest_zone() {
+        let (_dir, root) = src_root(&[
+            ("areas/tools/a", 10),
+            ("areas/tools/lib/b", 20),
+            ("areas/tools/dev/c", 40),
+            ("areas/loose", 80),
+        ]);
+        let manifest = manifest(&root, &["//areas/tools", "//areas/tools/dev"]);
+
+        let usages = by_zone(&manifest, &root);
+        assert_eq!(
+            bytes(&usages),
+            [("//areas/tools", 30), ("//areas/tools/dev", 40)]
+        );
+    }
+
+    #[test]
+    fn walking_inside_a_zone() {
+        let (_dir, root) = src_root(&[("areas/tools/lib/a", 10), ("areas/tools/b", 20)]);
+        let manifest = manifest(&root, &["//areas/tools"]);
+
+        let usages = by_zone(&manifest, &root.join("areas/tools/lib"));
+        assert_eq!(bytes(&usages), [("//areas/tools", 10)]);
+    }
+
+    #[test]
+    fn empty_zones_are_listed() {
+        let (_dir, root) = src_root(&[("areas/loose", 10)]);
+        fs::create_dir_all(root.join("areas/tools")).unwrap();
+        let manifest = manifest(&root, &["//areas/tools", "//areas/ghost"]);
+
+        let usages = by_zone(&manifest, &root);
+        assert_eq!(bytes(&usages), [("//areas/tools", 0)]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hard_links_are_counted_once() {
+        let (_dir, root) = src_root(&[("areas/tools/a", 10)]);
+        fs::create_dir_all(root.join("areas/tools/lib")).unwrap();
+        fs::hard_link(root.join("areas/tools/a"), root.join("areas/tools/lib/b")).unwrap();
+        let manifest = manifest(&root, &["//areas/tools"]);
+
+        let usages = by_zone(&manifest, &root);
+        assert_eq!(bytes(&usages), [("//areas/tools", 10)]);
+    }
+}
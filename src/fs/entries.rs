@@ -0,0 +1,324 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Reading the names and types of everything in a directory.
+//!
+//! `std::fs::read_dir` asks the kernel for entries with a buffer of a few
+//! kilobytes at a time, and hands each one back as a `DirEntry` that keeps
+//! the whole directory handle alive alongside its name. On a networked
+//! filesystem each of those small reads is a round trip, so a directory with
+//! tens of thousands of entries spends most of its time waiting.
+//!
+//! On Linux, the entries are read here with `getdents64` directly, using a
+//! much larger buffer, and only their names and types are kept. They’re
+//! still handed out one at a time as they’re read, so nothing gets copied
+//! twice. Everywhere else, `read_dir` is used and its entries are boiled
+//! down to the same.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How much to ask the kernel for in each `getdents64` call. The names of
+/// a few thousand entries fit in this at once.
+#[cfg(target_os = "linux")]
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Something that was in a directory when it was read.
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    /// The name of the entry, without the directory’s path.
+    pub name: OsString,
+
+    /// What type of file the entry is, if the filesystem said. Some don’t,
+    /// in which case it has to be asked for with a `stat`.
+    pub kind: Option<EntryKind>,
+}
+
+/// The type of a file, as a directory listing can tell it without a `stat`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum EntryKind {
+    Directory,
+    File,
+    Symlink,
+    Pipe,
+    CharDevice,
+    BlockDevice,
+    Socket,
+
+    /// Something else, which only some platforms have.
+    Other,
+}
+
+impl From<fs::FileType> for EntryKind {
+    fn from(file_type: fs::FileType) -> Self {
+        #[cfg(unix)]
+        use std::os::unix::fs::FileTypeExt;
+
+        if file_type.is_dir() {
+            return Self::Directory;
+        }
+        if file_type.is_file() {
+            return Self::File;
+        }
+        if file_type.is_symlink() {
+            return Self::Symlink;
+        }
+
+        #[cfg(unix)]
+        {
+            if file_type.is_fifo() {
+                return Self::Pipe;
+            }
+            if file_type.is_char_device() {
+                return Self::CharDevice;
+            }
+            if file_type.is_block_device() {
+                return Self::BlockDevice;
+            }
+            if file_type.is_socket() {
+                return Self::Socket;
+            }
+        }
+
+        Self::Other
+    }
+}
+
+impl EntryKind {
+    /// The kind of file a `d_type` field says an entry is, unless it says
+    /// it doesn’t know.
+    #[cfg(target_os = "linux")]
+    fn from_d_type(d_type: u8) -> Option<Self> {
+        match d_type {
+            libc::DT_DIR => Some(Self::Directory),
+            libc::DT_REG => Some(Self::File),
+            libc::DT_LNK => Some(Self::Symlink),
+            libc::DT_FIFO => Some(Self::Pipe),
+            libc::DT_CHR => Some(Self::CharDevice),
+            libc::DT_BLK => Some(Self::BlockDevice),
+            libc::DT_SOCK => Some(Self::Socket),
+            libc::DT_UNKNOWN => None,
+            _ => Some(Self::Other),
+        }
+    }
+}
+
+/// Starts reading the entries in the directory at the given path, apart
+/// from `.` and `..`, in whatever order the filesystem returns them.
+pub fn read(path: &Path) -> io::Result<Entries> {
+    #[cfg(target_os = "linux")]
+    return Entries::open(path, BUFFER_SIZE);
+
+    #[cfg(not(target_os = "linux"))]
+    return fs::read_dir(path).map(Entries);
+}
+
+/// The entries in a directory, read as they’re asked for.
+#[cfg(not(target_os = "linux"))]
+pub struct Entries(fs::ReadDir);
+
+#[cfg(not(target_os = "linux"))]
+impl Iterator for Entries {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.0.next()? {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok(DirEntry {
+            name: entry.file_name(),
+            kind: entry.file_type().ok().map(EntryKind::from),
+        }))
+    }
+}
+
+/// The entries in a directory, read as they’re asked for, a buffer of
+/// `getdents64` records at a time.
+#[cfg(target_os = "linux")]
+pub struct Entries {
+    dir: fs::File,
+    buffer: Vec<u8>,
+
+    /// How much of the buffer the last call filled.
+    filled: usize,
+
+    /// Where the next record in the buffer starts.
+    offset: usize,
+
+    /// Whether the kernel has run out of entries, or failed.
+    finished: bool,
+}
+
+#[cfg(target_os = "linux")]
+impl Entries {
+    /// Opens the directory at the given path, to be read with a buffer of
+    /// the given size at a time.
+    fn open(path: &Path, buffer_size: usize) -> io::Result<Self> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let dir = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY)
+            .open(path)?;
+
+        Ok(Self {
+            dir,
+            buffer: vec![0_u8; buffer_size],
+            filled: 0,
+            offset: 0,
+            finished: false,
+        })
+    }
+
+    /// Asks the kernel for the next batch of records, returning how many
+    /// bytes of the buffer they take up, which is 0 once there are none.
+    fn fill(&mut self) -> io::Result<usize> {
+        use std::os::unix::io::AsRawFd;
+
+        loop {
+            // SAFETY: the buffer is valid for writes of its whole length
+            // for the duration of the call, and the descriptor belongs to
+            // a directory this owns, so it stays open until after it.
+            let read = unsafe {
+                libc::syscall(
+                    libc::SYS_getdents64,
+                    self.dir.as_raw_fd(),
+                    self.buffer.as_mut_ptr(),
+                    self.buffer.len(),
+                )
+            };
+            if let Ok(read) = usize::try_from(read) {
+                return Ok(read);
+            }
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(error);
+            }
+        }
+    }
+
+    /// The next record in the buffer, and how long it is, if it’s whole.
+    fn record(&self) -> Option<(DirEntry, usize)> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        // Each record starts with its inode number and offset, then its
+        // length, its type, and its name ending in a null byte.
+        const RECLEN: usize = 16;
+        const TYPE: usize = 18;
+        const NAME: usize = 19;
+
+        let record = &self.buffer[self.offset..self.filled];
+        let len = usize::from(u16::from_ne_bytes([
+            *record.get(RECLEN)?,
+            *record.get(RECLEN + 1)?,
+        ]));
+        let name = record.get(NAME..len)?;
+        let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+
+        let entry = DirEntry {
+            name: OsStr::from_bytes(name).to_os_string(),
+            kind: EntryKind::from_d_type(record[TYPE]),
+        };
+        Some((entry, len))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Iterator for Entries {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.offset < self.filled {
+                let Some((entry, len)) = self.record() else {
+                    self.finished = true;
+                    self.filled = 0;
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "truncated directory entry",
+                    )));
+                };
+                self.offset += len;
+
+                if entry.name != "." && entry.name != ".." {
+                    return Some(Ok(entry));
+                }
+                continue;
+            }
+
+            if self.finished {
+                return None;
+            }
+            match self.fill() {
+                Ok(0) => self.finished = true,
+                Ok(filled) => {
+                    self.filled = filled;
+                    self.offset = 0;
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sorted(mut entries: Vec<DirEntry>) -> Vec<(String, Option<EntryKind>)> {
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        entries
+            .into_iter()
+            .map(|e| (e.name.to_string_lossy().into_owned(), e.kind))
+            .collect()
+    }
+
+    #[test]
+    fn reads_names_and_kinds() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path();
+        fs::create_dir(path.join("dir")).unwrap();
+        fs::write(path.join("file"), "hello").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("file", path.join("link")).unwrap();
+
+        let entries = sorted(read(path).unwrap().collect::<io::Result<_>>().unwrap());
+        assert_eq!(entries[0], ("dir".into(), Some(EntryKind::Directory)));
+        assert_eq!(entries[1], ("file".into(), Some(EntryKind::File)));
+        #[cfg(unix)]
+        assert_eq!(entries[2], ("link".into(), Some(EntryKind::Symlink)));
+    }
+
+    #[test]
+    fn missing_directories_are_errors() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path();
+        assert!(matches!(
+            read(&path.join("nope")),
+            Err(e) if e.kind() == io::ErrorKind::NotFound
+        ));
+        assert!(read(path).unwrap().next().is_none());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn reads_more_than_fits_in_one_buffer() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path();
+        for i in 0..200 {
+            fs::write(path.join(format!("file-{i:03}")), "").unwrap();
+        }
+
+        let entries = Entries::open(path, 1024).unwrap();
+        let entries = sorted(entries.collect::<io::Result<_>>().unwrap());
+        assert_eq!(entries.len(), 200);
+        assert_eq!(entries[0].0, "file-000");
+        assert_eq!(entries[199].0, "file-199");
+    }
+}
@@ -0,0 +1,264 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Working out who owns a file from its repository’s CODEOWNERS file, for
+//! the `--owners` column.
+//!
+//! The manifest only gives zones owners, but CODEOWNERS can give one to
+//! any file. It’s looked for where GitHub looks for it, in `.github/`, at
+//! the top, and in `docs/`, at the root of the Git repository the file is
+//! in. Finding that root means walking up from each file’s directory, so
+//! the answer is kept for every directory on the way, and each CODEOWNERS
+//! file is only read once.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use glob::{MatchOptions, Pattern};
+use log::{debug, warn};
+
+/// Where in a repository CODEOWNERS files go, in the order GitHub checks.
+const LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The CODEOWNERS file that covers each directory looked up so far,
+/// including the directories no file covers.
+static BY_DIRECTORY: LazyLock<Mutex<HashMap<PathBuf, Option<Arc<CodeOwners>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A parsed CODEOWNERS file, and the root of the repository it’s in, which
+/// its patterns are relative to.
+#[derive(Debug)]
+pub struct CodeOwners {
+    root: PathBuf,
+    rules: Vec<Rule>,
+}
+
+/// One line of a CODEOWNERS file.
+#[derive(Debug)]
+struct Rule {
+    pattern: Pattern,
+
+    /// Whether the pattern ended in a slash, so only matches directories.
+    directories_only: bool,
+
+    /// Whether the pattern covers what’s inside the directories it matches,
+    /// which it does unless it ends in `/*`.
+    covers_contents: bool,
+
+    /// The owners, which can be none, to leave the files unowned.
+    owners: Vec<String>,
+}
+
+impl CodeOwners {
+    /// Parses the text of a CODEOWNERS file in the repository at the given
+    /// root, skipping comments, blank lines, and patterns that aren’t valid.
+    pub fn parse(root: PathBuf, text: &str) -> Self {
+        let rules = text.lines().filter_map(Rule::parse).collect();
+        Self { root, rules }
+    }
+
+    /// The owners of the file at the given absolute path, which are those on
+    /// the last line that matches it, if any does and gives it owners.
+    pub fn owners(&self, path: &Path, is_directory: bool) -> Option<&[String]> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        if relative.as_os_str().is_empty() {
+            return None;
+        }
+
+        let rule = self
+            .rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(relative, is_directory))?;
+        if rule.owners.is_empty() {
+            None
+        } else {
+            Some(&rule.owners)
+        }
+    }
+}
+
+impl Rule {
+    fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        let pattern = words.next().filter(|word| !word.starts_with('#'))?;
+        let pattern = pattern.strip_prefix('\\').unwrap_or(pattern);
+        let owners = words
+            .take_while(|word| !word.starts_with('#'))
+            .map(String::from)
+            .collect();
+
+        let directories_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let covers_contents = pattern != "*" && !pattern.ends_with("/*");
+
+        // Patterns with a slash anywhere but the end are relative to the
+        // root; the rest match at any depth.
+        let glob = match pattern.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if pattern.contains('/') => pattern.to_string(),
+            None => format!("**/{pattern}"),
+        };
+
+        match Pattern::new(&glob) {
+            Ok(pattern) => Some(Self {
+                pattern,
+                directories_only,
+                covers_contents,
+                owners,
+            }),
+            Err(e) => {
+                debug!("Skipping CODEOWNERS pattern {glob:?}: {e}");
+                None
+            }
+        }
+    }
+
+    fn matches(&self, relative: &Path, is_directory: bool) -> bool {
+        if (is_directory || !self.directories_only)
+            && self.pattern.matches_path_with(relative, MATCH_OPTIONS)
+        {
+            return true;
+        }
+
+        self.covers_contents
+            && relative
+                .ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .any(|dir| self.pattern.matches_path_with(dir, MATCH_OPTIONS))
+    }
+}
+
+/// The CODEOWNERS file that covers the directory at the given absolute
+/// path, if it’s in a Git repository that has one.
+pub fn for_directory(dir: &Path) -> Option<Arc<CodeOwners>> {
+    if let Some(found) = BY_DIRECTORY.lock().unwrap().get(dir) {
+        return found.clone();
+    }
+
+    let found = if dir.join(".git").exists() {
+        read_code_owners(dir)
+    } else {
+        dir.parent().and_then(for_directory)
+    };
+
+    let _ = BY_DIRECTORY
+        .lock()
+        .unwrap()
+        .insert(dir.to_path_buf(), found.clone());
+    found
+}
+
+/// Reads the CODEOWNERS file of the repository at the given root, if it
+/// has one.
+fn read_code_owners(root: &Path) -> Option<Arc<CodeOwners>> {
+    for location in LOCATIONS {
+        let path = root.join(location);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                debug!("Reading owners from {}", path.display());
+                return Some(Arc::new(CodeOwners::parse(root.to_path_buf(), &text)));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to read {}: {e}", path.display()),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn owners_of(text: &str, path: &str, is_directory: bool) -> Option<String> {
+        let code_owners = CodeOwners::parse(PathBuf::from("/repo"), text);
+        code_owners
+            .owners(&Path::new("/repo").join(path), is_directory)
+            .map(|owners| owners.join(" "))
+    }
+
+    #[test]
+    fn the_last_match_wins() {
+        let text = "* @everyone\n*.rs @rustaceans\n";
+        assert_eq!(
+            owners_of(text, "src/main.rs", false).as_deref(),
+            Some("@rustaceans")
+        );
+        assert_eq!(
+            owners_of(text, "README.md", false).as_deref(),
+            Some("@everyone")
+        );
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let text = "# The owners\n\n/docs @writers @editors # and reviewers\n";
+        assert_eq!(
+            owners_of(text, "docs/index.md", false).as_deref(),
+            Some("@writers @editors")
+        );
+    }
+
+    #[test]
+    fn directories_cover_their_contents() {
+        let text = "apps/ @apps\n/areas/tools @tools\n";
+        assert_eq!(
+            owners_of(text, "areas/tools/dev/src/lib.rs", false).as_deref(),
+            Some("@tools")
+        );
+        assert_eq!(
+            owners_of(text, "nested/apps/flow/main.go", false).as_deref(),
+            Some("@apps")
+        );
+        assert_eq!(owners_of(text, "apps", true).as_deref(), Some("@apps"));
+        assert_eq!(owners_of(text, "apps", false), None);
+        assert_eq!(owners_of(text, "other/tools/lib.rs", false), None);
+    }
+
+    #[test]
+    fn a_single_star_stays_in_its_directory() {
+        let text = "docs/* @writers\n";
+        assert_eq!(
+            owners_of(text, "docs/index.md", false).as_deref(),
+            Some("@writers")
+        );
+        assert_eq!(owners_of(text, "docs/build/index.md", false), None);
+    }
+
+    #[test]
+    fn double_stars_match_any_depth() {
+        let text = "areas/**/BUILD @build\n";
+        assert_eq!(
+            owners_of(text, "areas/BUILD", false).as_deref(),
+            Some("@build")
+        );
+        assert_eq!(
+            owners_of(text, "areas/tools/dev/BUILD", false).as_deref(),
+            Some("@build")
+        );
+        assert_eq!(owners_of(text, "BUILD", false), None);
+    }
+
+    #[test]
+    fn lines_without_owners_leave_files_unowned() {
+        let text = "* @everyone\n/generated\n";
+        assert_eq!(owners_of(text, "generated/schema.rs", false), None);
+        assert_eq!(
+            owners_of(text, "schema.rs", false).as_deref(),
+            Some("@everyone")
+        );
+    }
+
+    #[test]
+    fn the_root_itself_has_no_owners() {
+        assert_eq!(owners_of("* @everyone\n", "", true), None);
+    }
+}
@@ -6,17 +6,22 @@
 // SPDX-License-Identifier: MIT
 //! Getting the Git status of files and directories.
 
+use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
 #[cfg(target_family = "unix")]
 use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
 
 use git2::StatusEntry;
 use log::{debug, error, info, warn};
 
+use crate::fs::dir::target_rel_path;
 use crate::fs::fields as f;
+use crate::fs::timings::{self, Phase, Timings};
+use crate::warnings::{self, Kind};
 
 /// A **Git cache** is assembled based on the user’s input arguments.
 ///
@@ -28,9 +33,39 @@ pub struct GitCache {
 
     /// Paths that we’ve confirmed do not have Git repositories underneath them.
     misses: Vec<PathBuf>,
+
+    /// Where to add up how long asking Git takes, if anywhere.
+    timings: Option<Arc<Timings>>,
+
+    /// Repositories inside the ones above, such as submodules and linked
+    /// worktrees, opened the first time something in one is looked up.
+    /// Each is shared so the lock needn’t be held while it’s queried.
+    nested: Mutex<Vec<Arc<GitRepo>>>,
+
+    /// Whether each directory looked at so far is the root of a repository
+    /// inside the one it’s in.
+    roots: Mutex<HashMap<PathBuf, bool>>,
 }
 
 impl GitCache {
+    /// Adds how long asking Git takes to the given timings.
+    #[must_use]
+    pub fn timed(mut self, timings: Option<Arc<Timings>>) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    /// Queries each repository with one `git status` run scoped to the
+    /// paths being listed, instead of having `libgit2` walk all of it,
+    /// which is a lot faster in repositories with enormous indexes.
+    #[must_use]
+    pub fn batched(mut self, batched: bool) -> Self {
+        for repo in &mut self.repos {
+            repo.batched = batched;
+        }
+        self
+    }
+
     #[must_use]
     pub fn has_anything_for(&self, index: &Path) -> bool {
         self.repos.iter().any(|e| e.has_path(index))
@@ -38,11 +73,124 @@ impl GitCache {
 
     #[must_use]
     pub fn get(&self, index: &Path, prefix_lookup: bool) -> f::Git {
-        self.repos
-            .iter()
-            .find(|repo| repo.has_path(index))
-            .map(|repo| repo.search(index, prefix_lookup))
+        timings::time(self.timings.as_deref(), Phase::Git, index, || {
+            self.with_repo(index, |repo| repo.search(index, prefix_lookup))
+                .unwrap_or_default()
+        })
+    }
+
+    /// How many files under the given directory have uncommitted changes,
+    /// for showing an aggregated status for a whole zone.
+    #[must_use]
+    pub fn counts(&self, index: &Path) -> f::GitCounts {
+        timings::time(self.timings.as_deref(), Phase::Git, index, || {
+            self.with_repo(index, |repo| {
+                repo.with_statuses(|statuses| statuses.dir_counts(index))
+            })
             .unwrap_or_default()
+        })
+    }
+
+    /// Whether the zone at the given directory has uncommitted changes, and
+    /// how far the commits touching it are ahead of or behind the tracking
+    /// branch, or `None` if it isn’t in a repository.
+    #[must_use]
+    pub fn zone_health(&self, index: &Path) -> Option<f::ZoneHealth> {
+        timings::time(self.timings.as_deref(), Phase::Git, index, || {
+            self.with_repo(index, |repo| {
+                let counts = repo.with_statuses(|statuses| statuses.dir_counts(index));
+                let (ahead, behind) = repo.ahead_behind(index);
+                f::ZoneHealth {
+                    counts,
+                    ahead,
+                    behind,
+                }
+            })
+        })
+    }
+
+    /// Whether the given absolute path, which doesn’t have to exist, is
+    /// ignored by the `.gitignore` files of the repository it’s in.
+    #[must_use]
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        timings::time(self.timings.as_deref(), Phase::Git, path, || {
+            self.with_repo(path, |repo| {
+                path.strip_prefix(&repo.workdir)
+                    .is_ok_and(|rel_path| repo.is_ignored(rel_path, is_dir))
+            })
+            .unwrap_or(false)
+        })
+    }
+
+    /// The name of the repository containing the given absolute path, and
+    /// the path relative to the repository’s working directory.
+    #[must_use]
+    pub fn repo_relative_path(&self, path: &Path) -> Option<(String, PathBuf)> {
+        self.with_repo(path, |repo| {
+            let rel_path = path.strip_prefix(&repo.workdir).ok()?;
+            let name = repo.workdir.file_name()?.to_string_lossy().into_owned();
+            Some((name, rel_path.to_path_buf()))
+        })
+        .flatten()
+    }
+
+    /// Runs the given function over the repository the given path is in.
+    ///
+    /// That’s usually one of the repositories the arguments were in, but a
+    /// submodule, linked worktree, or other repository checked out inside
+    /// one has statuses of its own, which its parent knows nothing about:
+    /// a path in one of those goes to that repository instead, which gets
+    /// opened the first time it’s needed.
+    fn with_repo<T>(&self, path: &Path, f: impl FnOnce(&GitRepo) -> T) -> Option<T> {
+        let repo = self
+            .repos
+            .iter()
+            .find(|repo| repo.has_path(path) || path.starts_with(&repo.workdir))?;
+        let Some(root) = self.nested_root(repo, path) else {
+            return Some(f(repo));
+        };
+
+        match self.nested_repo(repo, root) {
+            Some(inner) => Some(f(&inner)),
+            None => Some(f(repo)),
+        }
+    }
+
+    /// The repository rooted at the given path inside the given one,
+    /// opening it if it hasn’t been already. Returns `None` if it can’t be
+    /// opened, in which case it isn’t treated as a root any more.
+    fn nested_repo(&self, repo: &GitRepo, root: PathBuf) -> Option<Arc<GitRepo>> {
+        let mut nested = self.nested.lock().unwrap();
+        if let Some(inner) = nested.iter().find(|e| e.has_workdir(&root)) {
+            return Some(Arc::clone(inner));
+        }
+
+        let Ok(mut inner) = GitRepo::discover(root.clone(), git2::RepositoryOpenFlags::NO_SEARCH)
+        else {
+            // Stop looking at it, and fall back to its parent
+            let _ = self.roots.lock().unwrap().insert(root, false);
+            return None;
+        };
+        debug!("Opened nested Git repo at {root:?}");
+        inner.batched = repo.batched;
+        let inner = Arc::new(inner);
+        nested.push(Arc::clone(&inner));
+        Some(inner)
+    }
+
+    /// The root of the innermost repository inside the given one that the
+    /// given path is in, if it’s in one at all.
+    fn nested_root(&self, repo: &GitRepo, path: &Path) -> Option<PathBuf> {
+        let path = reorient(path);
+        let mut roots = self.roots.lock().unwrap();
+        path.ancestors()
+            .take_while(|dir| dir.starts_with(&repo.workdir) && *dir != repo.workdir)
+            .find(|dir| {
+                *roots
+                    .entry(dir.to_path_buf())
+                    .or_insert_with(|| dir.join(".git").exists())
+            })
+            .map(Path::to_path_buf)
     }
 }
 
@@ -56,6 +204,9 @@ impl FromIterator<PathBuf> for GitCache {
         let mut git = Self {
             repos: Vec::with_capacity(iter.size_hint().0),
             misses: Vec::new(),
+            timings: None,
+            nested: Mutex::new(Vec::new()),
+            roots: Mutex::new(HashMap::new()),
         };
 
         if let Ok(path) = env::var("GIT_DIR") {
@@ -122,6 +273,15 @@ pub struct GitRepo {
     /// Any other paths that were checked only to result in this same
     /// repository.
     extra_paths: Vec<PathBuf>,
+
+    /// Whether to ask `git status` about only the paths above, rather than
+    /// asking `libgit2` about the whole repository.
+    batched: bool,
+
+    /// Another handle on the repository, opened the first time a path
+    /// needs checking against the ignore rules, since `contents` gives its
+    /// own up once the statuses have been read.
+    ignores: Mutex<Option<git2::Repository>>,
 }
 
 /// A repository’s queried state.
@@ -150,22 +310,118 @@ impl GitRepo {
     /// repository is moved out, but before the results have been moved in!
     /// See <https://stackoverflow.com/q/45985827/3484614>
     fn search(&self, index: &Path, prefix_lookup: bool) -> f::Git {
+        self.with_statuses(|statuses| statuses.status(index, prefix_lookup))
+    }
+
+    /// Runs the given function over this repository’s statuses, querying
+    /// them first if this is the first time they’re needed.
+    fn with_statuses<T>(&self, f: impl FnOnce(&Git) -> T) -> T {
         use std::mem::replace;
 
         let mut contents = self.contents.lock().unwrap();
         if let GitContents::After { ref statuses } = *contents {
             debug!("Git repo {:?} has been found in cache", &self.workdir);
-            return statuses.status(index, prefix_lookup);
+            return f(statuses);
         }
 
         debug!("Querying Git repo {:?} for the first time", &self.workdir);
         let repo = replace(&mut *contents, GitContents::Processing).inner_repo();
-        let statuses = repo_to_statuses(&repo, &self.workdir);
-        let result = statuses.status(index, prefix_lookup);
+        let statuses = if self.batched {
+            self.batch_statuses()
+                .unwrap_or_else(|| repo_to_statuses(&repo, &self.workdir))
+        } else {
+            repo_to_statuses(&repo, &self.workdir)
+        };
+        let result = f(&statuses);
         let _processing = replace(&mut *contents, GitContents::After { statuses });
         result
     }
 
+    /// Runs `git status` once over every path this repository was checked
+    /// for, returning `None` if it couldn’t be run so that `libgit2` can be
+    /// asked instead.
+    fn batch_statuses(&self) -> Option<Git> {
+        let pathspecs = std::iter::once(&self.original_path)
+            .chain(&self.extra_paths)
+            .map(|path| match reorient(path).strip_prefix(&self.workdir) {
+                Ok(rel) if rel.as_os_str().is_empty() => PathBuf::from("."),
+                Ok(rel) => rel.to_path_buf(),
+                Err(_) => PathBuf::from("."),
+            })
+            .collect::<Vec<_>>();
+
+        info!(
+            "Getting Git statuses for {pathspecs:?} in repo with workdir {:?}",
+            self.workdir
+        );
+        let output = Command::new("git")
+            .current_dir(&self.workdir)
+            .args(["--no-optional-locks", "status", "--porcelain=v1", "-z"])
+            .args(["--ignored=matching", "--untracked-files=all", "--"])
+            .args(&pathspecs)
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => {
+                let mut statuses = parse_porcelain(&output.stdout, &self.workdir);
+                statuses.push((self.workdir.join(".git"), git2::Status::IGNORED));
+                Some(Git { statuses })
+            }
+            Ok(output) => {
+                warn!(
+                    "git status failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                None
+            }
+            Err(e) => {
+                warn!("Failed to run git status: {e}");
+                None
+            }
+        }
+    }
+
+    /// How many commits touching the given directory are on `HEAD` but not
+    /// its upstream, and the other way round. Having no upstream counts as
+    /// being level with it.
+    fn ahead_behind(&self, dir: &Path) -> (usize, usize) {
+        let pathspec = match reorient(dir).strip_prefix(&self.workdir) {
+            Ok(rel) if !rel.as_os_str().is_empty() => rel.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let output = Command::new("git")
+            .current_dir(&self.workdir)
+            .args([
+                "rev-list",
+                "--left-right",
+                "--count",
+                "HEAD...@{upstream}",
+                "--",
+            ])
+            .arg(&pathspec)
+            .output();
+
+        match output {
+            Ok(output) if output.status.success() => parse_left_right(&output.stdout)
+                .unwrap_or_else(|| {
+                    warn!("Unexpected rev-list output for {pathspec:?}");
+                    (0, 0)
+                }),
+            Ok(output) => {
+                debug!(
+                    "No upstream to compare {pathspec:?} with: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                (0, 0)
+            }
+            Err(e) => {
+                warn!("Failed to run git rev-list: {e}");
+                (0, 0)
+            }
+        }
+    }
+
     /// Whether this repository has the given working directory.
     fn has_workdir(&self, path: &Path) -> bool {
         self.workdir == path
@@ -177,6 +433,30 @@ impl GitRepo {
             || self.extra_paths.iter().any(|e| path.starts_with(e))
     }
 
+    /// Whether the given path relative to the working directory is ignored,
+    /// which it can be without existing.
+    fn is_ignored(&self, rel_path: &Path, is_dir: bool) -> bool {
+        let mut ignores = self.ignores.lock().unwrap();
+        if ignores.is_none() {
+            *ignores = git2::Repository::open(&self.workdir).ok();
+        }
+        let Some(repo) = ignores.as_ref() else {
+            return false;
+        };
+
+        // Patterns such as `out/` only match directories, which libgit2 can
+        // only tell a path that isn’t there is from its trailing slash
+        let mut path = target_rel_path(rel_path);
+        if is_dir {
+            path.push('/');
+        }
+
+        repo.is_path_ignored(&path).unwrap_or_else(|e| {
+            warn!("Failed to check whether {path:?} is ignored: {e}");
+            false
+        })
+    }
+
     /// Open a Git repository. Depending on the flags, the path is either
     /// the repository's "gitdir" (or a "gitlink" to the gitdir), or the
     /// path is the start of a rootwards search for the repository.
@@ -199,6 +479,8 @@ impl GitRepo {
                 workdir,
                 original_path: path,
                 extra_paths: Vec::new(),
+                batched: false,
+                ignores: Mutex::new(None),
             })
         } else {
             warn!("Repository has no workdir?");
@@ -241,13 +523,91 @@ fn repo_to_statuses(repo: &git2::Repository, workdir: &Path) -> Git {
             statuses.push((workdir.join(".git"), git2::Status::IGNORED));
         }
         Err(e) => {
-            error!("Error looking up Git statuses: {e:?}");
+            if !warnings::write_json(Kind::Degraded, workdir.display(), &e) {
+                error!("Error looking up Git statuses: {e:?}");
+            }
         }
     }
 
     Git { statuses }
 }
 
+/// Turns the output of `git status --porcelain=v1 -z` into the same
+/// statuses `libgit2` would give, with paths under the working directory.
+fn parse_porcelain(output: &[u8], workdir: &Path) -> Vec<(PathBuf, git2::Status)> {
+    let mut statuses = Vec::new();
+    let mut entries = output.split(|b| *b == 0).filter(|entry| !entry.is_empty());
+
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            warn!("Ignoring malformed git status entry {entry:?}");
+            continue;
+        }
+
+        let (code, path) = (&entry[..2], &entry[3..]);
+        let status = porcelain_status(code[0], code[1]);
+
+        // A rename or copy is followed by the path it came from
+        if matches!(code[0], b'R' | b'C') {
+            let _from = entries.next();
+        }
+
+        statuses.push((workdir.join(path_from_bytes(path)), status));
+    }
+
+    statuses
+}
+
+/// Reads the two counts `git rev-list --left-right --count` prints.
+fn parse_left_right(output: &[u8]) -> Option<(usize, usize)> {
+    let output = std::str::from_utf8(output).ok()?;
+    let (left, right) = output.trim().split_once('\t')?;
+    Some((left.parse().ok()?, right.parse().ok()?))
+}
+
+/// The `libgit2` status for the two status letters of a porcelain entry.
+#[rustfmt::skip]
+fn porcelain_status(index: u8, worktree: u8) -> git2::Status {
+    use git2::Status as S;
+
+    match (index, worktree) {
+        (b'?', b'?')                                          => return S::WT_NEW,
+        (b'!', b'!')                                          => return S::IGNORED,
+        (b'D', b'D') | (b'A', b'A') | (b'U', _) | (_, b'U')  => return S::CONFLICTED,
+        _ => {}
+    }
+
+    let index = match index {
+        b'M'        => S::INDEX_MODIFIED,
+        b'T'        => S::INDEX_TYPECHANGE,
+        b'A' | b'C' => S::INDEX_NEW,
+        b'D'        => S::INDEX_DELETED,
+        b'R'        => S::INDEX_RENAMED,
+        _           => S::empty(),
+    };
+
+    let worktree = match worktree {
+        b'M' => S::WT_MODIFIED,
+        b'T' => S::WT_TYPECHANGE,
+        b'A' => S::WT_NEW,
+        b'D' => S::WT_DELETED,
+        b'R' => S::WT_RENAMED,
+        _    => S::empty(),
+    };
+
+    index | worktree
+}
+
+#[cfg(target_family = "unix")]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
 #[allow(clippy::unnecessary_wraps)]
 fn get_path_from_status_entry(e: &StatusEntry<'_>) -> Option<PathBuf> {
     #[cfg(target_family = "unix")]
@@ -333,6 +693,28 @@ impl Git {
         let unstaged = working_tree_status(s);
         f::Git { staged, unstaged }
     }
+
+    /// Count the files under a directory with uncommitted changes. Ignored
+    /// files don’t count, and an untracked directory counts as one file,
+    /// the same as in `git status`.
+    fn dir_counts(&self, dir: &Path) -> f::GitCounts {
+        let path = reorient(dir);
+
+        let mut counts = f::GitCounts::default();
+        for (_, status) in self.statuses.iter().filter(|p| p.0.starts_with(&path)) {
+            if status.contains(git2::Status::IGNORED) || status.is_empty() {
+                continue;
+            }
+
+            if *status == git2::Status::WT_NEW {
+                counts.untracked += 1;
+            } else {
+                counts.modified += 1;
+            }
+        }
+
+        counts
+    }
 }
 
 /// Converts a path to an absolute path based on the current directory.
@@ -438,7 +820,9 @@ impl f::SubdirGitRepo {
                     };
                 }
                 Err(e) => {
-                    error!("Error looking up Git statuses: {e:?}");
+                    if !warnings::write_json(Kind::Degraded, dir.display(), &e) {
+                        error!("Error looking up Git statuses: {e:?}");
+                    }
                 }
             }
         }
@@ -452,3 +836,90 @@ impl f::SubdirGitRepo {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn porcelain() {
+        let output = b" M src/main.rs\0?? new.txt\0R  to.rs\0from.rs\0!! target/\0UU both.rs\0";
+        let statuses = parse_porcelain(output, Path::new("/repo"));
+        assert_eq!(
+            statuses,
+            vec![
+                (
+                    PathBuf::from("/repo/src/main.rs"),
+                    git2::Status::WT_MODIFIED
+                ),
+                (PathBuf::from("/repo/new.txt"), git2::Status::WT_NEW),
+                (PathBuf::from("/repo/to.rs"), git2::Status::INDEX_RENAMED),
+                (PathBuf::from("/repo/target/"), git2::Status::IGNORED),
+                (PathBuf::from("/repo/both.rs"), git2::Status::CONFLICTED),
+            ]
+        );
+    }
+
+    #[test]
+    fn left_right() {
+        assert_eq!(parse_left_right(b"2\t0\n"), Some((2, 0)));
+        assert_eq!(parse_left_right(b"fatal"), None);
+    }
+
+    #[test]
+    fn staged_and_modified() {
+        assert_eq!(
+            porcelain_status(b'A', b'M'),
+            git2::Status::INDEX_NEW | git2::Status::WT_MODIFIED
+        );
+        assert_eq!(porcelain_status(b'D', b' '), git2::Status::INDEX_DELETED);
+    }
+
+    #[test]
+    fn ignored_paths_need_not_exist() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        git2::Repository::init(dir).unwrap();
+        std::fs::write(dir.join(".gitignore"), "out/\n*.gen\n").unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        let git: GitCache = std::iter::once(dir.clone()).collect();
+        assert!(git.is_ignored(&dir.join("areas/out"), true));
+        assert!(!git.is_ignored(&dir.join("areas/out"), false));
+        assert!(git.is_ignored(&dir.join("areas/x.gen"), false));
+        assert!(!git.is_ignored(&dir.join("areas/tools"), true));
+        assert!(!git.is_ignored(Path::new("/elsewhere/out"), true));
+    }
+
+    #[test]
+    fn nested_repos_have_their_own_statuses() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        git2::Repository::init(dir).unwrap();
+        git2::Repository::init(dir.join("vendor/zlib")).unwrap();
+        std::fs::write(dir.join("vendor/zlib/new.c"), "").unwrap();
+        std::fs::write(dir.join("outer.c"), "").unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        let git: GitCache = std::iter::once(dir.clone()).collect();
+        assert!(matches!(
+            git.get(&dir.join("outer.c"), false).unstaged,
+            f::GitStatus::New
+        ));
+        assert!(matches!(
+            git.get(&dir.join("vendor/zlib/new.c"), false).unstaged,
+            f::GitStatus::New
+        ));
+        assert_eq!(
+            git.counts(&dir.join("vendor/zlib")),
+            f::GitCounts {
+                modified: 0,
+                untracked: 1
+            }
+        );
+        assert_eq!(
+            git.repo_relative_path(&dir.join("vendor/zlib/new.c")),
+            Some((String::from("zlib"), PathBuf::from("new.c")))
+        );
+    }
+}
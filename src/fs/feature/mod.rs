@@ -4,8 +4,12 @@
 // SPDX-FileCopyrightText: 2023-2024 Christina Sørensen, eza contributors
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
+pub mod codeowners;
 pub mod xattr;
 
+#[cfg(target_os = "linux")]
+pub mod overlay;
+
 #[cfg(feature = "git")]
 pub mod git;
 
@@ -13,9 +17,12 @@ pub mod git;
 pub mod git {
     use std::iter::FromIterator;
     use std::path::{Path, PathBuf};
+    use std::sync::Arc;
 
     use crate::fs::fields as f;
 
+    use crate::fs::timings::Timings;
+
     pub struct GitCache;
 
     impl FromIterator<PathBuf> for GitCache {
@@ -28,6 +35,16 @@ pub mod git {
     }
 
     impl GitCache {
+        #[must_use]
+        pub fn timed(self, _timings: Option<Arc<Timings>>) -> Self {
+            self
+        }
+
+        #[must_use]
+        pub fn batched(self, _batched: bool) -> Self {
+            self
+        }
+
         pub fn has_anything_for(&self, _index: &Path) -> bool {
             false
         }
@@ -35,6 +52,22 @@ pub mod git {
         pub fn get(&self, _index: &Path, _prefix_lookup: bool) -> f::Git {
             unreachable!();
         }
+
+        pub fn counts(&self, _index: &Path) -> f::GitCounts {
+            unreachable!();
+        }
+
+        pub fn zone_health(&self, _index: &Path) -> Option<f::ZoneHealth> {
+            None
+        }
+
+        pub fn is_ignored(&self, _path: &Path, _is_dir: bool) -> bool {
+            false
+        }
+
+        pub fn repo_relative_path(&self, _path: &Path) -> Option<(String, PathBuf)> {
+            None
+        }
     }
 
     impl f::SubdirGitRepo {
@@ -0,0 +1,44 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Working out which overlayfs layer a file comes from, so that developers
+//! in a container can see what they changed on top of the image.
+
+use std::path::Path;
+
+use crate::fs::feature::xattr::FileAttributes;
+use crate::fs::fields as f;
+use crate::fs::mounts::all_mounts;
+
+/// Finds the layer of the file at the given absolute path, if it’s on an
+/// overlay mount whose upper directory can be seen from here.
+///
+/// The merged view hides overlayfs’s own extended attributes, so this looks
+/// the file up in the upper directory instead. Anything not there comes from
+/// a lower layer. Anything there with an `overlay.origin` attribute was
+/// copied up from a lower layer to be changed, and anything else is new.
+pub fn layer(path: &Path) -> Option<f::Layer> {
+    let mount = all_mounts()
+        .values()
+        .filter(|m| m.upperdir.is_some() && path.starts_with(&m.dest))
+        .max_by_key(|m| m.dest.components().count())?;
+
+    let upperdir = mount.upperdir.as_ref()?;
+    if !upperdir.is_dir() {
+        return None;
+    }
+
+    let upper_path = upperdir.join(path.strip_prefix(&mount.dest).ok()?);
+    if upper_path.symlink_metadata().is_err() {
+        return Some(f::Layer::Lower);
+    }
+
+    let copied_up = upper_path
+        .symlink_attributes()
+        .is_ok_and(|attrs| attrs.iter().any(|a| a.name.ends_with("overlay.origin")));
+
+    Some(if copied_up {
+        f::Layer::CopiedUp
+    } else {
+        f::Layer::Upper
+    })
+}
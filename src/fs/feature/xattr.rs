@@ -588,7 +588,13 @@ const ATTRIBUTE_DISPLAYS: &[AttributeDisplay] = &[
     },
 ];
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(target_os = "linux")]
+const ATTRIBUTE_DISPLAYS: &[AttributeDisplay] = &[AttributeDisplay {
+    attribute: "security.capability",
+    display: display_capability,
+}];
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
 const ATTRIBUTE_DISPLAYS: &[AttributeDisplay] = &[];
 
 // com.apple.lastuseddate is two 64-bit values representing the seconds and nano seconds
@@ -665,6 +671,122 @@ fn display_macl(attribute: &Attribute) -> Option<String> {
         })
 }
 
+// The names of the Linux capabilities, indexed by their bit numbers, as
+// listed in linux/capability.h.
+#[cfg(target_os = "linux")]
+const CAPABILITY_NAMES: [&str; 41] = [
+    "cap_chown",
+    "cap_dac_override",
+    "cap_dac_read_search",
+    "cap_fowner",
+    "cap_fsetid",
+    "cap_kill",
+    "cap_setgid",
+    "cap_setuid",
+    "cap_setpcap",
+    "cap_linux_immutable",
+    "cap_net_bind_service",
+    "cap_net_broadcast",
+    "cap_net_admin",
+    "cap_net_raw",
+    "cap_ipc_lock",
+    "cap_ipc_owner",
+    "cap_sys_module",
+    "cap_sys_rawio",
+    "cap_sys_chroot",
+    "cap_sys_ptrace",
+    "cap_sys_pacct",
+    "cap_sys_admin",
+    "cap_sys_boot",
+    "cap_sys_nice",
+    "cap_sys_resource",
+    "cap_sys_time",
+    "cap_sys_tty_config",
+    "cap_mknod",
+    "cap_lease",
+    "cap_audit_write",
+    "cap_audit_control",
+    "cap_setfcap",
+    "cap_mac_override",
+    "cap_mac_admin",
+    "cap_syslog",
+    "cap_wake_alarm",
+    "cap_block_suspend",
+    "cap_audit_read",
+    "cap_perfmon",
+    "cap_bpf",
+    "cap_checkpoint_restore",
+];
+
+// security.capability is a vfs_cap_data: a little-endian header holding the
+// revision and the effective flag, then a permitted and an inheritable mask
+// for each 32 capabilities (one pair in revision 1, two after that), then,
+// in revision 3, the root user ID of the namespace. It’s shown the way
+// getcap shows it, with capabilities that have the same flags grouped.
+#[cfg(target_os = "linux")]
+fn display_capability(attribute: &Attribute) -> Option<String> {
+    const REVISION_MASK: u32 = 0xFF00_0000;
+    const EFFECTIVE: u32 = 0x0000_0001;
+
+    let value = attribute.value.as_ref()?;
+    let word = |i: usize| {
+        let bytes = value.get(i * 4..i * 4 + 4)?;
+        Some(u32::from_le_bytes(<[u8; 4]>::try_from(bytes).ok()?))
+    };
+
+    let magic = word(0)?;
+    let (pairs, length) = match magic & REVISION_MASK {
+        0x0100_0000 => (1, 3),
+        0x0200_0000 => (2, 5),
+        0x0300_0000 => (2, 6),
+        _ => return None,
+    };
+    if value.len() != length * 4 {
+        return None;
+    }
+
+    let mut permitted = 0_u64;
+    let mut inheritable = 0_u64;
+    for pair in 0..pairs {
+        permitted |= u64::from(word(1 + pair * 2)?) << (pair * 32);
+        inheritable |= u64::from(word(2 + pair * 2)?) << (pair * 32);
+    }
+    let effective = magic & EFFECTIVE != 0;
+
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for bit in 0..64 {
+        let (p, i) = (permitted >> bit & 1 == 1, inheritable >> bit & 1 == 1);
+        if !p && !i {
+            continue;
+        }
+
+        let flags = format!(
+            "{}{}{}",
+            if effective && p { "e" } else { "" },
+            if i { "i" } else { "" },
+            if p { "p" } else { "" },
+        );
+        let name = CAPABILITY_NAMES
+            .get(bit)
+            .map_or_else(|| format!("cap_{bit}"), |name| (*name).to_string());
+
+        match groups.iter_mut().find(|(f, _)| *f == flags) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((flags, vec![name])),
+        }
+    }
+
+    let mut display = groups
+        .iter()
+        .map(|(flags, names)| format!("{}={flags}", names.join(",")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if magic & REVISION_MASK == 0x0300_0000 {
+        display.push_str(&format!(" [rootid={}]", word(5)?));
+    }
+    Some(display)
+}
+
 // plist::XmlWriter takes the writer instead of borrowing it.  This is a
 // wrapper around a borrowed vector that just forwards the Write trait
 // calls to the borrowed vector.
@@ -712,3 +834,58 @@ fn plist_value_display(value: &[u8]) -> Option<String> {
         .map(|s| format!("<plist version=\"1.0\">{}</plist>", s.replace('\n', "")))
     })
 }
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod test {
+    use super::*;
+
+    fn capability(words: &[u32]) -> Attribute {
+        Attribute {
+            name: "security.capability".into(),
+            value: Some(words.iter().flat_map(|w| w.to_le_bytes()).collect()),
+        }
+    }
+
+    #[test]
+    fn effective_capabilities() {
+        let attr = capability(&[0x0200_0001, 1 << 10 | 1 << 13, 0, 0, 0]);
+        assert_eq!(
+            display_capability(&attr).unwrap(),
+            "cap_net_bind_service,cap_net_raw=ep"
+        );
+    }
+
+    #[test]
+    fn permitted_and_inheritable_are_grouped_apart() {
+        let attr = capability(&[0x0200_0000, 1, 1 << 1, 0, 0]);
+        assert_eq!(
+            display_capability(&attr).unwrap(),
+            "cap_chown=p cap_dac_override=i"
+        );
+    }
+
+    #[test]
+    fn high_capabilities_and_root_ids() {
+        let attr = capability(&[0x0300_0001, 0, 0, 1 << 7, 0, 1000]);
+        assert_eq!(
+            display_capability(&attr).unwrap(),
+            "cap_bpf=ep [rootid=1000]"
+        );
+    }
+
+    #[test]
+    fn revision_one() {
+        let attr = capability(&[0x0100_0000, 1 << 21, 0]);
+        assert_eq!(display_capability(&attr).unwrap(), "cap_sys_admin=p");
+    }
+
+    #[test]
+    fn malformed_capabilities_are_left_alone() {
+        assert_eq!(display_capability(&capability(&[0x0200_0001, 1])), None);
+        assert_eq!(
+            display_capability(&capability(&[0x0900_0000, 0, 0, 0, 0])),
+            None
+        );
+    }
+}
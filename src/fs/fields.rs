@@ -20,6 +20,8 @@
 #![allow(non_camel_case_types)]
 #![allow(clippy::struct_excessive_bools)]
 
+use std::path::PathBuf;
+
 /// The type of a file’s group ID.
 pub type gid_t = u32;
 
@@ -193,6 +195,14 @@ pub enum Size {
     /// This is what ls does as well. Without it, the devices will just have
     /// file sizes of zero.
     DeviceIDs(DeviceIDs),
+
+    /// This file is a ghost zone, and the manifest says roughly how many
+    /// bytes it would take up if it were materialized.
+    Estimate(u64),
+
+    /// This file is a ghost zone, and the manifest only says roughly how
+    /// many files it has.
+    EstimatedFiles(u64),
 }
 
 /// The major and minor device IDs that gets displayed for device files.
@@ -263,8 +273,136 @@ impl Default for Git {
     }
 }
 
+/// Which overlayfs layer a file comes from, when it’s on an overlay mount
+/// such as a container’s root filesystem.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Layer {
+    /// The file is only in a lower, read-only layer, such as the image.
+    Lower,
+
+    /// The file was copied up from a lower layer to be changed.
+    CopiedUp,
+
+    /// The file was created in the writable upper layer.
+    Upper,
+}
+
+/// What a directory inside a Git repository is the root of, when it’s a
+/// checkout of its own rather than part of the repository around it.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum NestedRepo {
+    /// A submodule, whose `.git` file points into its superproject’s
+    /// `.git/modules`.
+    Submodule,
+
+    /// A linked worktree, whose `.git` file points into the main
+    /// worktree’s `.git/worktrees`.
+    Worktree,
+
+    /// Any other repository, such as one cloned inside another.
+    Repository,
+}
+
+impl NestedRepo {
+    /// Works out which kind of checkout has a `.git` file with the given
+    /// contents, which is a `gitdir:` line pointing at its Git directory.
+    pub fn from_gitlink(contents: &str) -> Option<Self> {
+        let gitdir = contents
+            .lines()
+            .next()?
+            .strip_prefix("gitdir:")?
+            .trim()
+            .replace('\\', "/");
+        Some(if gitdir.contains("/modules/") {
+            Self::Submodule
+        } else if gitdir.contains("/worktrees/") {
+            Self::Worktree
+        } else {
+            Self::Repository
+        })
+    }
+}
+
+/// The filesystem a file is on, and where it’s mounted, so that it’s clear
+/// which directories of a checkout are on slow network mounts.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Filesystem {
+    pub kind: FilesystemKind,
+
+    /// The filesystem type, as the kernel names it, such as `nfs4`.
+    pub fstype: String,
+
+    /// Where the filesystem is mounted.
+    pub mount_point: PathBuf,
+}
+
+/// The broad kind of filesystem a file is on.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum FilesystemKind {
+    /// A filesystem on a local disk, or in memory.
+    Local,
+
+    /// An overlay, such as a container’s root filesystem.
+    Overlay,
+
+    /// A filesystem on another machine, where every lookup is a round trip.
+    Network,
+}
+
+/// How many files inside a zone have uncommitted changes, so that zone
+/// owners can see at a glance which zones have work in progress.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct GitCounts {
+    /// Tracked files with changes, staged or not.
+    pub modified: usize,
+
+    /// Files git doesn’t know about yet.
+    pub untracked: usize,
+}
+
+impl GitCounts {
+    /// Whether there is nothing uncommitted at all.
+    pub fn is_empty(self) -> bool {
+        self.modified == 0 && self.untracked == 0
+    }
+}
+
+/// Whether a zone has work that hasn’t been committed or pushed, so that
+/// zones people treat as projects of their own can be checked on at once.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+pub struct ZoneHealth {
+    /// The files inside it with uncommitted changes.
+    pub counts: GitCounts,
+
+    /// Commits touching it that the tracking branch doesn’t have yet.
+    pub ahead: usize,
+
+    /// Commits touching it on the tracking branch that aren’t checked out.
+    pub behind: usize,
+}
+
+/// How many of the zones beneath a zone are on disk, so that sparse
+/// checkouts that are missing something can be spotted.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Materialized {
+    /// The zones beneath it that have a directory on disk.
+    pub present: usize,
+
+    /// All the zones beneath it in the manifest.
+    pub total: usize,
+}
+
+impl Materialized {
+    /// How much of it is on disk, from 0 to 100, rounded down so that 100
+    /// only ever means everything.
+    pub fn percent(self) -> usize {
+        (self.present * 100).checked_div(self.total).unwrap_or(100)
+    }
+}
+
 pub enum SecurityContextType<'a> {
     SELinux(&'a str),
+    Smack(&'a str),
     None,
 }
 
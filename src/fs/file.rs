@@ -6,12 +6,11 @@
 // SPDX-License-Identifier: MIT
 //! Files, and methods and fields to access their metadata.
 
-#[cfg(unix)]
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fs::FileType;
 use std::io;
 #[cfg(unix)]
-use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 #[cfg(windows)]
 use std::os::windows::fs::MetadataExt;
 use std::path::{Path, PathBuf};
@@ -19,7 +18,7 @@ use std::path::{Path, PathBuf};
 use std::str;
 #[cfg(unix)]
 use std::sync::Mutex;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 use std::time::SystemTime;
 
 use chrono::prelude::*;
@@ -28,14 +27,28 @@ use log::{debug, error, trace};
 #[cfg(unix)]
 use std::sync::LazyLock;
 
-use crate::fs::dir::Dir;
+use crate::fs::dir::{
+    ancestor_ids, find_manifest, find_manifest_for_ghost, Dir, DirOptions, Estimate, ManifestInfo,
+    SyncState,
+};
+use crate::fs::disk_usage::Usage;
+use crate::fs::entries::EntryKind;
+#[cfg(target_os = "linux")]
+use crate::fs::feature;
 use crate::fs::feature::xattr;
 use crate::fs::feature::xattr::{Attribute, FileAttributes};
 use crate::fs::fields as f;
 use crate::fs::fields::SecurityContextType;
+use crate::fs::filter::ZoneFilter;
+use crate::fs::ghost_dates;
+use crate::fs::moves;
 use crate::fs::recursive_size::RecursiveSize;
+use crate::fs::sparse::GhostReason;
+use crate::fs::timings::{self, Phase};
+use crate::fs::zone::Zone;
 
 use super::mounts::all_mounts;
+use super::mounts::mount_containing;
 use super::mounts::MountedFs;
 
 // Maps (device_id, inode) => (size_in_bytes, size_in_blocks)
@@ -46,6 +59,13 @@ use super::mounts::MountedFs;
 static DIRECTORY_SIZE_CACHE: LazyLock<Mutex<HashMap<(u64, u64), (u64, u64)>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
 
+/// Forgets the recursive directory sizes worked out so far, so that listing
+/// again after something changed doesn’t show the old ones.
+pub fn clear_directory_size_cache() {
+    #[cfg(unix)]
+    DIRECTORY_SIZE_CACHE.lock().unwrap().clear();
+}
+
 /// A **File** is a wrapper around one of Rust’s `PathBuf` values, along with
 /// associated data about the file.
 ///
@@ -53,6 +73,7 @@ static DIRECTORY_SIZE_CACHE: LazyLock<Mutex<HashMap<(u64, u64), (u64, u64)>>> =
 /// once, have its file extension extracted at least once, and have its metadata
 /// information queried at least once, so it makes sense to do all this at the
 /// start and hold on to all the information.
+#[allow(clippy::struct_excessive_bools)]
 pub struct File<'dir> {
     /// The filename portion of this file’s path, including the extension.
     ///
@@ -77,6 +98,10 @@ pub struct File<'dir> {
     /// The cached filetype for this file
     pub filetype: OnceLock<Option<std::fs::FileType>>,
 
+    /// What type of file the directory listing said this is, which saves
+    /// a `stat` when the filesystem knows.
+    kind: Option<EntryKind>,
+
     /// A cached `metadata` (`stat`) call for this file.
     ///
     /// This too is queried multiple times, and is *not* cached by the OS, as
@@ -120,8 +145,23 @@ pub struct File<'dir> {
     /// Whether this file is a "ghost" node (exists in manifest but not on disk).
     pub is_ghost: bool,
 
-    /// Whether this file is a "zone" (has a direct entry in the manifest).
-    pub is_zone: bool,
+    /// Whether this ghost is a single file the manifest has an entry for,
+    /// such as a generated proto, rather than a directory.
+    pub is_file_ghost: bool,
+
+    /// For a ghost, whether the sparse checkout left it out on purpose, if
+    /// checking was asked for.
+    pub ghost_reason: Option<GhostReason>,
+
+    /// The zone this file is, if it has a direct entry in the manifest.
+    ///
+    /// Finding out means canonicalizing the path, so it’s only done for the
+    /// files that actually get displayed, and cached after that.
+    zone: OnceLock<Option<Zone>>,
+
+    /// How the directory this file is in gets matched against the manifest,
+    /// which any directory read from this file shares.
+    options: &'dir Arc<DirOptions>,
 }
 
 impl<'dir> File<'dir> {
@@ -132,6 +172,37 @@ impl<'dir> File<'dir> {
         deref_links: bool,
         total_size: bool,
         filetype: Option<std::fs::FileType>,
+        options: &'dir Arc<DirOptions>,
+    ) -> File<'dir>
+    where
+        PD: Into<Option<&'dir Dir>>,
+        FN: Into<Option<String>>,
+    {
+        let mut file = File::from_entry(
+            path,
+            parent_dir,
+            filename,
+            deref_links,
+            total_size,
+            filetype.map(EntryKind::from),
+            options,
+        );
+        if let Some(filetype) = filetype {
+            file.filetype = OnceLock::from(Some(filetype));
+        }
+        file
+    }
+
+    /// Makes a file for something a directory listing turned up, with the
+    /// type it said the file is, if it did.
+    pub(crate) fn from_entry<PD, FN>(
+        path: PathBuf,
+        parent_dir: PD,
+        filename: FN,
+        deref_links: bool,
+        total_size: bool,
+        kind: Option<EntryKind>,
+        options: &'dir Arc<DirOptions>,
     ) -> File<'dir>
     where
         PD: Into<Option<&'dir Dir>>,
@@ -150,13 +221,6 @@ impl<'dir> File<'dir> {
 
         debug!("deref_links {deref_links}");
 
-        let filetype = match filetype {
-            Some(f) => OnceLock::from(Some(f)),
-            None => OnceLock::new(),
-        };
-
-        debug!("deref_links {deref_links}");
-
         let mut file = File {
             name,
             ext,
@@ -165,12 +229,16 @@ impl<'dir> File<'dir> {
             is_all_all,
             deref_links,
             recursive_size,
-            filetype,
+            filetype: OnceLock::new(),
+            kind,
             metadata: OnceLock::new(),
             extended_attributes: OnceLock::new(),
             absolute_path: OnceLock::new(),
             is_ghost: false,
-            is_zone: false,
+            is_file_ghost: false,
+            ghost_reason: None,
+            zone: OnceLock::new(),
+            options,
         };
 
         if total_size {
@@ -189,7 +257,6 @@ impl<'dir> File<'dir> {
         let ext = File::ext(&path);
 
         let is_all_all = true;
-        let parent_dir = Some(parent_dir);
         let recursive_size = if total_size {
             RecursiveSize::Unknown
         } else {
@@ -200,7 +267,7 @@ impl<'dir> File<'dir> {
             name: name.into(),
             ext,
             path,
-            parent_dir,
+            parent_dir: Some(parent_dir),
             is_all_all,
             deref_links: false,
             recursive_size,
@@ -208,8 +275,12 @@ impl<'dir> File<'dir> {
             absolute_path: OnceLock::new(),
             extended_attributes: OnceLock::new(),
             filetype: OnceLock::new(),
+            kind: None,
             is_ghost: false,
-            is_zone: false,
+            is_file_ghost: false,
+            ghost_reason: None,
+            zone: OnceLock::new(),
+            options: parent_dir.options(),
         };
 
         if total_size {
@@ -233,18 +304,19 @@ impl<'dir> File<'dir> {
         path: PathBuf,
         parent_dir: &'dir Dir,
         name: String,
-        is_zone: bool,
+        zone: Option<Zone>,
+        is_file: bool,
+        ghost_reason: Option<GhostReason>,
     ) -> File<'dir> {
         let ext = File::ext(&path);
         let is_all_all = false;
-        let parent_dir = Some(parent_dir);
         let recursive_size = RecursiveSize::None;
 
         File {
             name,
             ext,
             path,
-            parent_dir,
+            parent_dir: Some(parent_dir),
             is_all_all,
             deref_links: false,
             recursive_size,
@@ -252,11 +324,248 @@ impl<'dir> File<'dir> {
             absolute_path: OnceLock::new(),
             extended_attributes: OnceLock::new(),
             filetype: OnceLock::new(),
+            kind: None,
+            is_ghost: true,
+            is_file_ghost: is_file,
+            ghost_reason,
+            zone: OnceLock::from(zone),
+            options: parent_dir.options(),
+        }
+    }
+
+    /// A zone on disk listed by `--du`, whose size is the space the files
+    /// in it take up, leaving out the zones inside it.
+    pub fn new_zone_usage(
+        path: PathBuf,
+        zone: Zone,
+        usage: Usage,
+        options: &'dir Arc<DirOptions>,
+    ) -> File<'dir> {
+        let mut file = File::from_args(path, None, None, false, false, None, options);
+        file.recursive_size = RecursiveSize::Some(usage.bytes, usage.blocks);
+        file.zone = OnceLock::from(Some(zone));
+        file
+    }
+
+    /// A ghost given as an argument rather than found in a directory, which
+    /// gets listed as an entry of its own, with its zone if it’s one.
+    pub fn new_ghost_argument(
+        path: PathBuf,
+        manifest_info: &ManifestInfo,
+        canonical_path: &Path,
+        options: &'dir Arc<DirOptions>,
+    ) -> File<'dir> {
+        let name = File::filename(&path);
+        let ext = File::ext(&path);
+        let target = manifest_info.target_path_for(canonical_path);
+        let is_file = target
+            .as_deref()
+            .is_some_and(|target| manifest_info.is_file_target(target));
+        let zone = target
+            .filter(|_| !is_file)
+            .and_then(|target| manifest_info.zone(&target, false));
+        let ghost_reason = manifest_info
+            .sparse_checkout
+            .as_ref()
+            .and_then(|sparse| sparse.ghost_reason(canonical_path));
+
+        File {
+            name,
+            ext,
+            path,
+            parent_dir: None,
+            is_all_all: false,
+            deref_links: false,
+            recursive_size: RecursiveSize::None,
+            metadata: OnceLock::new(),
+            absolute_path: OnceLock::new(),
+            extended_attributes: OnceLock::new(),
+            filetype: OnceLock::new(),
+            kind: None,
             is_ghost: true,
-            is_zone,
+            is_file_ghost: is_file,
+            ghost_reason,
+            zone: OnceLock::from(zone),
+            options,
+        }
+    }
+
+    /// The zone this file is, if it has a direct entry in the manifest.
+    /// Only directories in a listed directory can be zones.
+    pub fn zone(&self) -> Option<&Zone> {
+        self.zone
+            .get_or_init(|| {
+                if self.is_all_all || !self.is_directory() {
+                    return None;
+                }
+
+                let manifest_info = self.parent_dir?.manifest_info()?;
+                timings::time(
+                    self.options.timings.as_deref(),
+                    Phase::Zone,
+                    &self.path,
+                    || {
+                        let canonical = self.canonical_path()?;
+                        manifest_info.zone(&manifest_info.target_path_for(&canonical)?, true)
+                    },
+                )
+            })
+            .as_ref()
+    }
+
+    /// The canonical path of this directory. When it was read from a listed
+    /// directory, it’s that directory’s canonical path plus this file’s
+    /// name, which saves asking the filesystem for every entry. That only
+    /// holds for entries that aren’t symlinks, which is all that zones and
+    /// orphans can be.
+    fn canonical_path(&self) -> Option<PathBuf> {
+        match self.parent_dir {
+            Some(dir) => Some(dir.canonical_path()?.join(&self.name)),
+            None => self.path.canonicalize().ok(),
+        }
+    }
+
+    /// Whether this file is a zone.
+    pub fn is_zone(&self) -> bool {
+        self.zone().is_some()
+    }
+
+    /// The manifest target path of this zone (such as `//areas/tools/dev`),
+    /// if it is one.
+    pub fn zone_target(&self) -> Option<&str> {
+        self.zone().map(|z| z.target.as_str())
+    }
+
+    /// The target path of the nearest zone enclosing this file, which is the
+    /// file itself if it’s a zone.
+    pub fn enclosing_zone_target(&self) -> Option<String> {
+        if let Some(target) = self.zone_target() {
+            return Some(target.to_string());
+        }
+
+        let (target, _) = self
+            .manifest_info()?
+            .enclosing_zone(&self.canonical_path()?)?;
+        Some(target)
+    }
+
+    /// The manifest target path of this file (such as
+    /// `//areas/tools/dev/main.rs`), if it’s beneath a src root.
+    pub fn target_path(&self) -> Option<String> {
+        if let Some(target) = self.zone_target() {
+            return Some(target.to_string());
+        }
+
+        if self.is_ghost {
+            let (manifest, canonical_path) = find_manifest_for_ghost(&self.path, self.options)?;
+            return manifest.target_path_for(&canonical_path);
+        }
+
+        self.manifest_info()?
+            .target_path_for(&self.canonical_path()?)
+    }
+
+    /// The manifest of the src root this file is beneath, which is its
+    /// directory’s if that has been read already.
+    fn manifest_info(&self) -> Option<Cow<'_, ManifestInfo>> {
+        match self.parent_dir.and_then(Dir::manifest_info) {
+            Some(manifest) => Some(Cow::Borrowed(manifest)),
+            None => find_manifest(&self.path, self.options).map(Cow::Owned),
         }
     }
 
+    /// Who last synced this zone, and when, if it is one and the sync-state
+    /// provider knows.
+    pub fn zone_sync(&self) -> Option<&SyncState> {
+        self.zone().and_then(|z| z.sync.as_ref())
+    }
+
+    /// Who owns this zone, from the `owner` field of its manifest entry, if
+    /// it is one and the entry’s fields were kept.
+    pub fn zone_owner(&self) -> Option<String> {
+        let owners = self.zone()?.metadata.as_ref()?.get("owner")?;
+        Some(owners.join(","))
+    }
+
+    /// The `type` field of this zone’s manifest entry, if it is one and its
+    /// entry has one, such as `library` or `service`.
+    pub fn zone_type(&self) -> Option<String> {
+        let kinds = self.zone()?.metadata.as_ref()?.get("type")?;
+        Some(kinds.join(","))
+    }
+
+    /// Who owns this file in its repository’s CODEOWNERS file, if it has one
+    /// and a line in it covers the file. Unlike `zone_owner`, any file can
+    /// have these, ghosts included.
+    pub fn code_owners(&self) -> Option<String> {
+        let path = std::path::absolute(self.absolute_path()?).ok()?;
+        let code_owners = feature::codeowners::for_directory(path.parent()?)?;
+        let owners = code_owners.owners(&path, self.is_directory())?;
+        Some(owners.join(","))
+    }
+
+    /// The tags this zone has in the manifest, if it is one.
+    pub fn zone_tags(&self) -> &[String] {
+        self.zone().map_or(&[], |z| z.tags.as_slice())
+    }
+
+    /// Whether this is a directory that’s either a zone with the given tag
+    /// or on the way to one, so `--tag` keeps it.
+    pub fn leads_to_tag(&self, tag: &str) -> bool {
+        self.leads_to(|manifest_info, target| manifest_info.has_tag_at_or_beneath(target, tag))
+    }
+
+    /// Whether this is a directory that’s either a zone whose manifest entry
+    /// meets the filter or on the way to one, so `--filter-zone` keeps it.
+    pub fn leads_to_match(&self, filter: &ZoneFilter) -> bool {
+        self.leads_to(|manifest_info, target| manifest_info.has_match_at_or_beneath(target, filter))
+    }
+
+    fn leads_to(&self, found: impl Fn(&ManifestInfo, &str) -> bool) -> bool {
+        if self.is_all_all || !self.is_directory() {
+            return false;
+        }
+
+        let Some(manifest_info) = self.parent_dir.and_then(Dir::manifest_info) else {
+            return false;
+        };
+        self.canonical_path()
+            .and_then(|canonical| manifest_info.target_path_for(&canonical))
+            .is_some_and(|target| found(manifest_info, &target))
+    }
+
+    /// How many of the zones beneath this one are on disk, if it’s a zone
+    /// with any beneath it.
+    pub fn materialized(&self) -> Option<f::Materialized> {
+        let zone = self.zone()?;
+        self.parent_dir?.manifest_info()?.materialized(&zone.target)
+    }
+
+    /// How many of the things directly inside this zone that the manifest
+    /// knows of aren’t on disk, or 0 if it isn’t a zone.
+    pub fn unmaterialized_children(&self) -> usize {
+        let Some(zone) = self.zone() else {
+            return 0;
+        };
+        self.parent_dir
+            .and_then(Dir::manifest_info)
+            .map_or(0, |manifest_info| {
+                manifest_info.unmaterialized_children(&zone.target)
+            })
+    }
+
+    /// The target path that this directory was probably moved to, with
+    /// `--moved-hints`, if the manifest knows nothing about it but git’s
+    /// history says its files went somewhere it does.
+    pub fn moved_to(&self) -> Option<String> {
+        if !self.options.moved_hints || self.is_ghost || self.is_all_all || !self.is_directory() {
+            return None;
+        }
+
+        let manifest_info = self.parent_dir?.manifest_info()?;
+        moves::moved_to(manifest_info, &self.canonical_path()?)
+    }
+
     /// A file’s name is derived from its string. This needs to handle directories
     /// such as `/` or `..`, which have no `file_name` component. So instead, just
     /// use the last component as the name.
@@ -285,9 +594,10 @@ impl<'dir> File<'dir> {
         name.rfind('.').map(|p| name[p + 1..].to_ascii_lowercase())
     }
 
-    /// Read the extended attributes of a file path.
+    /// Read the extended attributes of a file path. Ghosts aren’t on disk,
+    /// so they have none, rather than an error.
     fn gather_extended_attributes(&self) -> Vec<Attribute> {
-        if xattr::ENABLED {
+        if xattr::ENABLED && !self.is_ghost {
             let attributes = if self.deref_links {
                 self.path.attributes()
             } else {
@@ -315,11 +625,23 @@ impl<'dir> File<'dir> {
             .as_ref()
     }
 
+    /// What type of file this is, from the directory listing if it said,
+    /// and from the metadata otherwise.
+    fn kind(&self) -> Option<EntryKind> {
+        self.kind
+            .or_else(|| self.filetype().map(|t| EntryKind::from(*t)))
+    }
+
     pub fn metadata(&self) -> Result<&std::fs::Metadata, &io::Error> {
         self.metadata
             .get_or_init(|| {
                 debug!("Statting file {:?}", &self.path);
-                std::fs::symlink_metadata(&self.path)
+                timings::time(
+                    self.options.timings.as_deref(),
+                    Phase::Stat,
+                    &self.path,
+                    || std::fs::symlink_metadata(&self.path),
+                )
             })
             .as_ref()
     }
@@ -333,9 +655,9 @@ impl<'dir> File<'dir> {
     /// Whether this file is a directory on the filesystem.
     pub fn is_directory(&self) -> bool {
         if self.is_ghost {
-            return true;
+            return !self.is_file_ghost;
         }
-        self.filetype().is_some_and(std::fs::FileType::is_dir)
+        self.kind() == Some(EntryKind::Directory)
     }
 
     /// Whether this file is a directory, or a symlink pointing to a directory.
@@ -359,7 +681,28 @@ impl<'dir> File<'dir> {
     /// file is actually a directory. To verify that, use `is_directory()`.
     pub fn to_dir(&self) -> Dir {
         trace!("read_dir: initializating dir from path");
-        Dir::new(self.path.clone())
+        Dir::new(self.path.clone(), Arc::clone(self.options))
+    }
+
+    /// Initializes a ghost `Dir` for this ghost, so that the ghosts beneath
+    /// it can be listed. Returns `None` if the manifest it came from can no
+    /// longer be found.
+    pub fn to_ghost_dir(&self) -> Option<Dir> {
+        if self.is_file_ghost {
+            return None;
+        }
+
+        let (manifest_info, canonical_path) = match self.parent_dir {
+            Some(dir) => (dir.manifest_info()?.clone(), self.canonical_path()?),
+            None => find_manifest_for_ghost(&self.path, self.options)?,
+        };
+
+        Some(Dir::new_ghost(
+            self.path.clone(),
+            manifest_info,
+            canonical_path,
+            Arc::clone(self.options),
+        ))
     }
 
     /// If this file is a directory on the filesystem, then clone its
@@ -370,16 +713,16 @@ impl<'dir> File<'dir> {
     /// if a `File` is a directory or not! For that, just use `is_directory()`.
     pub fn read_dir(&self) -> io::Result<Dir> {
         trace!("read_dir: reading dir");
-        Dir::read_dir(self.path.clone())
+        Dir::read_dir(self.path.clone(), Arc::clone(self.options))
     }
 
     /// Whether this file is a regular file on the filesystem — that is, not a
     /// directory, a link, or anything else treated specially.
     pub fn is_file(&self) -> bool {
         if self.is_ghost {
-            return false;
+            return self.is_file_ghost;
         }
-        self.filetype().is_some_and(std::fs::FileType::is_file)
+        self.kind() == Some(EntryKind::File)
     }
 
     /// Whether this file is both a regular file *and* executable for the
@@ -405,7 +748,35 @@ impl<'dir> File<'dir> {
         if self.is_ghost {
             return false;
         }
-        self.filetype().is_some_and(FileType::is_symlink)
+        self.kind() == Some(EntryKind::Symlink)
+    }
+
+    /// The directory this symlink leads to, if it’s one that the symlink is
+    /// already inside, so following it while recursing would list the same
+    /// directories over and over forever. Whoever decides not to follow it
+    /// should say so with [`crate::warnings::link_cycle`].
+    #[cfg(unix)]
+    pub fn linked_ancestor(&self) -> Option<PathBuf> {
+        if self.is_ghost || !self.is_link() {
+            return None;
+        }
+        let target = std::fs::metadata(&self.path).ok()?;
+
+        let id = (target.dev(), target.ino());
+        match self.parent_dir {
+            Some(dir) => dir.ancestor_with_id(id).map(Path::to_path_buf),
+            None => self
+                .path
+                .parent()
+                .and_then(|parent| ancestor_ids(parent).remove(&id)),
+        }
+    }
+
+    /// Directories can’t be told apart by their device and inode numbers
+    /// here, so symlinks to them are always followed.
+    #[cfg(not(unix))]
+    pub fn linked_ancestor(&self) -> Option<PathBuf> {
+        None
     }
 
     /// Whether this file is a named pipe on the filesystem.
@@ -414,7 +785,7 @@ impl<'dir> File<'dir> {
         if self.is_ghost {
             return false;
         }
-        self.filetype().is_some_and(FileTypeExt::is_fifo)
+        self.kind() == Some(EntryKind::Pipe)
     }
 
     /// Whether this file is a char device on the filesystem.
@@ -423,7 +794,7 @@ impl<'dir> File<'dir> {
         if self.is_ghost {
             return false;
         }
-        self.filetype().is_some_and(FileTypeExt::is_char_device)
+        self.kind() == Some(EntryKind::CharDevice)
     }
 
     /// Whether this file is a block device on the filesystem.
@@ -432,7 +803,7 @@ impl<'dir> File<'dir> {
         if self.is_ghost {
             return false;
         }
-        self.filetype().is_some_and(FileTypeExt::is_block_device)
+        self.kind() == Some(EntryKind::BlockDevice)
     }
 
     /// Whether this file is a socket on the filesystem.
@@ -441,17 +812,22 @@ impl<'dir> File<'dir> {
         if self.is_ghost {
             return false;
         }
-        self.filetype().is_some_and(FileTypeExt::is_socket)
+        self.kind() == Some(EntryKind::Socket)
     }
 
-    /// Determine the full path resolving all symbolic links on demand.
+    /// Determine the full path resolving all symbolic links on demand. A
+    /// ghost’s is the path it would have if it were on disk, found from
+    /// the canonical path of the directory it’s in.
     pub fn absolute_path(&self) -> Option<&PathBuf> {
-        if self.is_ghost {
-            return Some(&self.path);
-        }
         self.absolute_path
             .get_or_init(|| {
-                if self.is_link() && self.link_target().is_broken() {
+                if self.is_ghost {
+                    let canonical = match self.parent_dir {
+                        Some(dir) => dir.canonical_path().map(|p| p.join(&self.name)),
+                        None => find_manifest_for_ghost(&self.path, self.options).map(|(_, p)| p),
+                    };
+                    canonical.or_else(|| std::path::absolute(&self.path).ok())
+                } else if self.is_link() && self.link_target().is_broken() {
                     // workaround for broken symlinks to get absolute path for parent and then
                     // append name of file; std::fs::canonicalize requires all path components
                     // (including the last one) to exist
@@ -486,6 +862,52 @@ impl<'dir> File<'dir> {
         None
     }
 
+    /// The filesystem this file is on, and where it’s mounted. Ghosts aren’t
+    /// on any filesystem.
+    pub fn filesystem(&self) -> Option<f::Filesystem> {
+        if self.is_ghost {
+            return None;
+        }
+
+        let mount = mount_containing(self.absolute_path()?)?;
+        Some(f::Filesystem {
+            kind: mount.kind(),
+            fstype: mount.fstype.clone(),
+            mount_point: mount.dest.clone(),
+        })
+    }
+
+    /// Which overlayfs layer this file comes from, if it’s on an overlay
+    /// mount. Ghosts aren’t in any layer.
+    pub fn layer(&self) -> Option<f::Layer> {
+        if self.is_ghost {
+            return None;
+        }
+
+        #[cfg(target_os = "linux")]
+        return self
+            .absolute_path()
+            .and_then(|p| feature::overlay::layer(p));
+
+        #[cfg(not(target_os = "linux"))]
+        return None;
+    }
+
+    /// What this directory is the root of, if it’s a Git checkout of its
+    /// own, such as a submodule, rather than just a directory.
+    pub fn nested_repo(&self) -> Option<f::NestedRepo> {
+        if self.is_ghost || !self.is_directory() {
+            return None;
+        }
+
+        let dot_git = self.path.join(".git");
+        let metadata = std::fs::symlink_metadata(&dot_git).ok()?;
+        if metadata.is_dir() {
+            return Some(f::NestedRepo::Repository);
+        }
+        f::NestedRepo::from_gitlink(&std::fs::read_to_string(&dot_git).ok()?)
+    }
+
     /// Re-prefixes the path pointed to by this file, if it’s a symlink, to
     /// make it an absolute path that can be accessed from whichever
     /// directory exa is being run from.
@@ -517,16 +939,27 @@ impl<'dir> File<'dir> {
         // we actually look up and turn into a `File` — which needs to be
         // absolute to be accessible from any directory.
         debug!("Reading link {:?}", &self.path);
-        let path = match std::fs::read_link(&self.path) {
-            Ok(p) => p,
+        let followed = timings::time(
+            self.options.timings.as_deref(),
+            Phase::Deref,
+            &self.path,
+            || {
+                let path = std::fs::read_link(&self.path)?;
+                let absolute_path = self.reorient_target_path(&path);
+
+                // Use plain `metadata` instead of `symlink_metadata` - we *want*
+                // to follow links.
+                let metadata = std::fs::metadata(&absolute_path);
+                Ok((path, absolute_path, metadata))
+            },
+        );
+
+        let (path, absolute_path, metadata) = match followed {
+            Ok(followed) => followed,
             Err(e) => return FileTarget::Err(e),
         };
 
-        let absolute_path = self.reorient_target_path(&path);
-
-        // Use plain `metadata` instead of `symlink_metadata` - we *want* to
-        // follow links.
-        match std::fs::metadata(&absolute_path) {
+        match metadata {
             Ok(metadata) => {
                 let ext = File::ext(&path);
                 let name = File::filename(&path);
@@ -537,6 +970,7 @@ impl<'dir> File<'dir> {
                     path,
                     ext,
                     filetype: OnceLock::from(Some(metadata.file_type())),
+                    kind: None,
                     metadata: OnceLock::from(Ok(metadata)),
                     name,
                     is_all_all: false,
@@ -545,7 +979,10 @@ impl<'dir> File<'dir> {
                     absolute_path: absolute_path_cell,
                     recursive_size: RecursiveSize::None,
                     is_ghost: false,
-                    is_zone: false,
+                    is_file_ghost: false,
+                    ghost_reason: None,
+                    zone: OnceLock::new(),
+                    options: self.options,
                 };
                 FileTarget::Ok(Box::new(file))
             }
@@ -677,7 +1114,15 @@ impl<'dir> File<'dir> {
     #[cfg(unix)]
     pub fn size(&self) -> f::Size {
         if self.is_ghost {
-            return f::Size::Some(0);
+            return match self.zone().and_then(|zone| zone.estimate) {
+                Some(Estimate {
+                    bytes: Some(bytes), ..
+                }) => f::Size::Estimate(bytes),
+                Some(Estimate {
+                    files: Some(files), ..
+                }) => f::Size::EstimatedFiles(files),
+                _ => f::Size::Some(0),
+            };
         }
 
         if self.deref_links && self.is_link() {
@@ -721,21 +1166,68 @@ impl<'dir> File<'dir> {
     /// Calculate the total directory size recursively.  If not a directory `None`
     /// will be returned.  The directory size is cached for recursive directory
     /// listing.
+    ///
+    /// With `--total-size-within-zone`, zones inside this directory are left
+    /// out of its size, as they’re owned by someone else.
     #[cfg(unix)]
     fn recursive_directory_size(&self) -> RecursiveSize {
-        if self.is_directory() {
-            let key = (
-                self.metadata().map_or(0, MetadataExt::dev),
-                self.metadata().map_or(0, MetadataExt::ino),
-            );
-            if let Some(size) = DIRECTORY_SIZE_CACHE.lock().unwrap().get(&key) {
-                return RecursiveSize::Some(size.0, size.1);
-            }
-            Dir::read_dir(self.path.clone()).map_or(RecursiveSize::Unknown, |dir| {
+        if !self.is_directory() {
+            return RecursiveSize::None;
+        }
+
+        if !self.options.sizes_within_zones {
+            return self.directory_size(None);
+        }
+
+        let manifest = match self.parent_dir.and_then(Dir::manifest_info) {
+            Some(manifest) => Some(Cow::Borrowed(manifest)),
+            None => find_manifest(&self.path, self.options).map(Cow::Owned),
+        };
+        match (manifest, self.canonical_path()) {
+            (Some(manifest), Some(canonical)) => self.directory_size(Some((&manifest, &canonical))),
+            _ => self.directory_size(None),
+        }
+    }
+
+    /// Sums the sizes of everything inside this directory, skipping any
+    /// zones if given the manifest and this directory’s canonical path. The
+    /// canonical paths of subdirectories are worked out from that, rather
+    /// than asking the filesystem for each one.
+    #[cfg(unix)]
+    fn directory_size(&self, zones: Option<(&ManifestInfo, &Path)>) -> RecursiveSize {
+        let key = (
+            self.metadata().map_or(0, MetadataExt::dev),
+            self.metadata().map_or(0, MetadataExt::ino),
+        );
+        if let Some(size) = DIRECTORY_SIZE_CACHE.lock().unwrap().get(&key) {
+            return RecursiveSize::Some(size.0, size.1);
+        }
+        Dir::read_dir(self.path.clone(), Arc::clone(self.options)).map_or(
+            RecursiveSize::Unknown,
+            |dir| {
                 let mut size = 0;
                 let mut blocks = 0;
-                for file in dir.files(super::DotFilter::Dotfiles, None, false, false, true, true) {
-                    match file.recursive_directory_size() {
+                for file in dir.files(
+                    super::DotFilter::Dotfiles,
+                    None,
+                    false,
+                    false,
+                    false,
+                    Some(false),
+                ) {
+                    let file_size = if !file.is_directory() {
+                        RecursiveSize::None
+                    } else if let Some((manifest, canonical)) = zones {
+                        let child = canonical.join(&file.name);
+                        if manifest.is_zone_path(&child) {
+                            continue;
+                        }
+                        file.directory_size(Some((manifest, &child)))
+                    } else {
+                        file.directory_size(None)
+                    };
+
+                    match file_size {
                         RecursiveSize::Some(bytes, blks) => {
                             size += bytes;
                             blocks += blks;
@@ -752,10 +1244,8 @@ impl<'dir> File<'dir> {
                     .unwrap()
                     .insert(key, (size, blocks));
                 RecursiveSize::Some(size, blocks)
-            })
-        } else {
-            RecursiveSize::None
-        }
+            },
+        )
     }
 
     /// Windows version always returns None.  The metadata for
@@ -775,6 +1265,23 @@ impl<'dir> File<'dir> {
             .unwrap_bytes_or(self.metadata().map_or(0, std::fs::Metadata::len))
     }
 
+    /// The size to sort this file by: its length, or for a ghost, what the
+    /// manifest estimates, so that sorting by size puts it among the files
+    /// that are there. This is kept apart from `length` so that nothing
+    /// shows an estimate as though it were read from the disk.
+    pub fn length_for_sorting(&self) -> u64 {
+        if !self.is_ghost {
+            return self.length();
+        }
+
+        match self.zone().and_then(|zone| zone.estimate) {
+            Some(Estimate {
+                bytes: Some(bytes), ..
+            }) => bytes,
+            _ => 0,
+        }
+    }
+
     /// Is the file is using recursive size calculation
     #[inline]
     pub fn is_recursive_size(&self) -> bool {
@@ -832,10 +1339,17 @@ impl<'dir> File<'dir> {
     /// make it difficult to get any info about a dir by it's size, so this may be it.
     fn is_empty_directory(&self) -> bool {
         trace!("is_empty_directory: reading dir");
-        match Dir::read_dir(self.path.clone()) {
+        match Dir::read_dir(self.path.clone(), Arc::clone(self.options)) {
             // . & .. are skipped, if the returned iterator has .next(), it's not empty
             Ok(has_files) => has_files
-                .files(super::DotFilter::Dotfiles, None, false, false, false, true)
+                .files(
+                    super::DotFilter::Dotfiles,
+                    None,
+                    false,
+                    false,
+                    false,
+                    Some(false),
+                )
                 .next()
                 .is_none(),
             Err(_) => false,
@@ -856,10 +1370,13 @@ impl<'dir> File<'dir> {
         .map(|dt| dt.naive_local())
     }
 
-    /// The time the file was modified.
+    /// The time the file was modified, which for a ghost is when the last
+    /// commit touching it was, if `--ghost-dates` asks for that.
     pub fn modified_time(&self) -> Option<NaiveDateTime> {
         if self.is_ghost {
-            return None;
+            return self
+                .last_commit_time()
+                .and_then(Self::systemtime_to_naivedatetime);
         }
         if self.is_link() && self.deref_links {
             return match self.link_target_recurse() {
@@ -873,6 +1390,24 @@ impl<'dir> File<'dir> {
             .and_then(Self::systemtime_to_naivedatetime)
     }
 
+    /// When the last commit touching this ghost was made.
+    fn last_commit_time(&self) -> Option<SystemTime> {
+        if !self.options.ghost_dates {
+            return None;
+        }
+
+        let (src_root, canonical_path) = if let Some(dir) = self.parent_dir {
+            (
+                dir.manifest_info()?.src_root.clone(),
+                dir.canonical_path()?.join(&self.name),
+            )
+        } else {
+            let (manifest, canonical_path) = find_manifest_for_ghost(&self.path, self.options)?;
+            (manifest.src_root, canonical_path)
+        };
+        ghost_dates::last_commit(&src_root, &canonical_path)
+    }
+
     /// This file’s last changed timestamp, if available on this platform.
     #[cfg(unix)]
     pub fn changed_time(&self) -> Option<NaiveDateTime> {
@@ -938,7 +1473,11 @@ impl<'dir> File<'dir> {
     #[cfg(unix)]
     pub fn type_char(&self) -> f::Type {
         if self.is_ghost {
-            return f::Type::Directory;
+            return if self.is_file_ghost {
+                f::Type::File
+            } else {
+                f::Type::Directory
+            };
         }
         if self.is_file() {
             f::Type::File
@@ -1026,19 +1565,21 @@ impl<'dir> File<'dir> {
     /// This file’s security context field.
     #[cfg(unix)]
     pub fn security_context(&self) -> f::SecurityContext<'_> {
-        let context = match self
-            .extended_attributes()
-            .iter()
-            .find(|a| a.name == "security.selinux")
-        {
-            Some(attr) => match &attr.value {
-                None => SecurityContextType::None,
-                Some(value) => match str::from_utf8(value) {
-                    Ok(v) => SecurityContextType::SELinux(v.trim_end_matches(char::from(0))),
-                    Err(_) => SecurityContextType::None,
-                },
-            },
-            None => SecurityContextType::None,
+        let label = |name: &str| {
+            self.extended_attributes()
+                .iter()
+                .find(|a| a.name == name)
+                .and_then(|attr| attr.value.as_deref())
+                .and_then(|value| str::from_utf8(value).ok())
+                .map(|v| v.trim_end_matches(char::from(0)))
+        };
+
+        let context = if let Some(context) = label("security.selinux") {
+            SecurityContextType::SELinux(context)
+        } else if let Some(label) = label("security.SMACK64") {
+            SecurityContextType::Smack(label)
+        } else {
+            SecurityContextType::None
         };
 
         f::SecurityContext { context }
@@ -1211,3 +1752,69 @@ mod filename_test {
         assert_eq!("/", File::filename(Path::new("/")));
     }
 }
+
+#[cfg(test)]
+mod ghost_path_test {
+    use super::File;
+    use crate::fs::{Dir, DirOptions};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    #[test]
+    fn ghosts_are_beneath_the_canonical_directory() {
+        let root = tempfile::tempdir().unwrap();
+        let tmp = root.path();
+
+        // Listed through a path with a `..` in it, which the ghost's own
+        // path keeps but its absolute path doesn't
+        let dir = Dir::new(
+            tmp.join("..").join(tmp.file_name().unwrap()),
+            Arc::new(DirOptions::default()),
+        );
+        let ghost = File::new_ghost(
+            dir.join(Path::new("apps")),
+            &dir,
+            "apps".into(),
+            None,
+            false,
+            None,
+        );
+        assert_eq!(
+            ghost.absolute_path(),
+            Some(&tmp.canonicalize().unwrap().join("apps"))
+        );
+    }
+}
+
+#[cfg(all(test, unix))]
+mod link_cycle_test {
+    use crate::fs::{Dir, DirOptions, DotFilter};
+    use std::os::unix::fs::symlink;
+    use std::sync::Arc;
+
+    #[test]
+    fn links_back_up_the_tree_are_cut() {
+        let root = tempfile::tempdir().unwrap();
+        let tmp = root.path();
+        std::fs::create_dir_all(tmp.join("a/b")).unwrap();
+        std::fs::create_dir_all(tmp.join("c")).unwrap();
+        symlink(tmp.join("a"), tmp.join("a/b/up")).unwrap();
+        symlink("..", tmp.join("a/b/parent")).unwrap();
+        symlink(tmp.join("c"), tmp.join("a/b/across")).unwrap();
+
+        let dir = Dir::read_dir(tmp.join("a/b"), Arc::new(DirOptions::default())).unwrap();
+        let mut cut: Vec<_> = dir
+            .files(DotFilter::JustFiles, None, false, false, false, Some(false))
+            .map(|f| (f.name.clone(), f.linked_ancestor().is_some()))
+            .collect();
+        cut.sort();
+        assert_eq!(
+            cut,
+            vec![
+                ("across".into(), false),
+                ("parent".into(), true),
+                ("up".into(), true)
+            ]
+        );
+    }
+}
@@ -11,8 +11,10 @@ use std::iter::FromIterator;
 #[cfg(unix)]
 use std::os::unix::fs::MetadataExt;
 
+use crate::fs::Dir;
 use crate::fs::DotFilter;
 use crate::fs::File;
+use crate::fs::Metadata;
 
 /// Flags used to manage the **file filter** process
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -42,8 +44,15 @@ pub enum FileFilterFlags {
     /// types of file. Some users prefer it like this.
     ListDirsLast,
 
-    /// Whether to disable ghost nodes
-    NoGhosts,
+    /// Whether zones should be listed first, then other directories, and
+    /// other types of file last.
+    ListZonesFirst,
+
+    /// Whether ghosts should be listed before everything on disk.
+    ListGhostsFirst,
+
+    /// Whether ghosts should be listed after everything on disk.
+    ListGhostsLast,
 }
 
 /// The **file filter** processes a list of files before displaying them to
@@ -94,35 +103,94 @@ pub struct FileFilter {
     /// Whether to explicitly show symlinks
     pub show_symlinks: bool,
 
-    /// Whether to disable ghost nodes
-    pub no_ghosts: bool,
+    /// Whether to list ghost nodes, or `None` to leave it to the
+    /// `wls.toml` next to the manifest
+    pub ghosts: Option<bool>,
+
+    /// How many ghosts to show when listing the src root itself, or `None`
+    /// to show all of them.
+    pub ghost_limit: Option<usize>,
+
+    /// Only list zones with this tag, and the directories leading to them.
+    pub tag: Option<String>,
+
+    /// Only list zones whose manifest entries meet these conditions, and
+    /// the directories leading to them.
+    pub zone_filter: Option<ZoneFilter>,
 }
 
 impl FileFilter {
     /// Remove every file in the given vector that does *not* pass the
     /// filter predicate for files found inside a directory.
-    #[rustfmt::skip]
     pub fn filter_child_files(&self, is_recurse: bool, files: &mut Vec<File<'_>>) {
+        files.retain(|f| self.shows_child_file(is_recurse, f));
+    }
+
+    /// How many ghosts the given directory can list. Only the src root has
+    /// a limit, as it can have thousands of top-level ghosts, which would
+    /// bury the directories that are actually there.
+    pub fn ghost_limit(&self, dir: &Dir) -> GhostLimit {
+        GhostLimit {
+            allowed: self.ghost_limit.filter(|_| dir.is_src_root()),
+            hidden: 0,
+        }
+    }
+
+    /// Whether a single file found inside a directory passes the filter
+    /// predicate, for when files are handled one at a time.
+    pub fn shows_child_file(&self, is_recurse: bool, f: &File<'_>) -> bool {
+        if self.ignore_patterns.is_ignored(&f.name) {
+            return false;
+        }
+
+        if self.tag.as_ref().is_some_and(|tag| !f.leads_to_tag(tag)) {
+            return false;
+        }
+
+        if self
+            .zone_filter
+            .as_ref()
+            .is_some_and(|filter| !f.leads_to_match(filter))
+        {
+            return false;
+        }
+
+        self.shows_file_type(is_recurse, f)
+    }
+
+    /// Whether a file found inside a directory is of a type that the
+    /// `--only-dirs`, `--only-files` and symlink flags let through.
+    /// Directories still get through `--only-files` when recursing, so
+    /// that there’s something to recurse into.
+    #[rustfmt::skip]
+    fn shows_file_type(&self, is_recurse: bool, f: &File<'_>) -> bool {
         use FileFilterFlags::{NoSymlinks, OnlyDirs, OnlyFiles, ShowSymlinks};
 
-        files.retain(|f| !self.ignore_patterns.is_ignored(&f.name));
-        files.retain(|f| {
-            match (
-                self.flags.contains(&OnlyDirs),
-                self.flags.contains(&OnlyFiles),
-                self.flags.contains(&NoSymlinks),
-                self.flags.contains(&ShowSymlinks),
-            ) {
-                (true, false, false, false) => f.is_directory(),
-                (true, false, true, false) => f.is_directory(),
-                (true, false, false, true) => f.is_directory() || f.points_to_directory(),
-                (false, true, false, false) => if is_recurse { true } else {f.is_file() },
-                (false, true, false, true) => if is_recurse { true } else { f.is_file() || f.is_link() && !f.points_to_directory()
-                },
-                (false, false, true, false) => !f.is_link(),
-                _ => true,
-            }
-        });
+        // Ghosts are never symlinks, so only whether they’re ghost
+        // directories, zones among them, or ghost files matters
+        if f.is_ghost {
+            return match (self.flags.contains(&OnlyDirs), self.flags.contains(&OnlyFiles)) {
+                (true, false) => f.is_directory(),
+                (false, true) => is_recurse || f.is_file(),
+                _             => true,
+            };
+        }
+
+        match (
+            self.flags.contains(&OnlyDirs),
+            self.flags.contains(&OnlyFiles),
+            self.flags.contains(&NoSymlinks),
+            self.flags.contains(&ShowSymlinks),
+        ) {
+            (true, false, false, false) => f.is_directory(),
+            (true, false, true, false) => f.is_directory(),
+            (true, false, false, true) => f.is_directory() || f.points_to_directory(),
+            (false, true, false, false) => if is_recurse { true } else {f.is_file() },
+            (false, true, false, true) => if is_recurse { true } else { f.is_file() || f.is_link() && !f.points_to_directory()
+            },
+            (false, false, true, false) => !f.is_link(),
+            _ => true,
+        }
     }
 
     /// Remove every file in the given vector that does *not* pass the
@@ -149,7 +217,9 @@ impl FileFilter {
             files.reverse();
         }
 
-        if self.flags.contains(&FileFilterFlags::ListDirsFirst) {
+        if self.flags.contains(&FileFilterFlags::ListZonesFirst) {
+            files.sort_by_key(|f| (!f.as_ref().is_zone(), !f.as_ref().points_to_directory()));
+        } else if self.flags.contains(&FileFilterFlags::ListDirsFirst) {
             // This relies on the fact that `sort_by` is *stable*: it will keep
             // adjacent elements next to each other.
             files.sort_by(|a, b| {
@@ -164,6 +234,58 @@ impl FileFilter {
                     .cmp(&b.as_ref().points_to_directory())
             });
         }
+
+        // Ghosts get grouped last of all, so that they lead or trail the
+        // listing whichever way everything else was sorted
+        if self.flags.contains(&FileFilterFlags::ListGhostsFirst) {
+            files.sort_by(|a, b| b.as_ref().is_ghost.cmp(&a.as_ref().is_ghost));
+        } else if self.flags.contains(&FileFilterFlags::ListGhostsLast) {
+            files.sort_by(|a, b| a.as_ref().is_ghost.cmp(&b.as_ref().is_ghost));
+        }
+    }
+}
+
+/// How many more ghosts a directory’s listing can show, counted down as
+/// they’re kept, so that a listing done in chunks, or one file at a time,
+/// shares one limit between them.
+///
+/// The ghosts that are kept are the first ones to get here, so this goes
+/// after the files have been filtered and sorted, where they are. When
+/// streaming, nothing is sorted, and the ghosts come after everything on
+/// disk in the order [`Dir::files`] gives them, which is by name.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct GhostLimit {
+    /// How many more ghosts can be shown, or `None` for any number.
+    allowed: Option<usize>,
+
+    /// How many ghosts have been left out so far.
+    hidden: usize,
+}
+
+impl GhostLimit {
+    /// Whether the given file gets listed, counting it if it’s a ghost.
+    pub fn keeps(&mut self, file: &File<'_>) -> bool {
+        match self.allowed.as_mut() {
+            Some(allowed) if file.is_ghost => {
+                if *allowed == 0 {
+                    self.hidden += 1;
+                    return false;
+                }
+                *allowed -= 1;
+                true
+            }
+            _ => true,
+        }
+    }
+
+    /// Leaves out the ghosts past the limit.
+    pub fn apply(&mut self, files: &mut Vec<File<'_>>) {
+        files.retain(|file| self.keeps(file));
+    }
+
+    /// How many ghosts have been left out so far.
+    pub fn hidden(&self) -> usize {
+        self.hidden
     }
 }
 
@@ -237,6 +359,11 @@ pub enum SortField {
     /// The file's name, however if the name of the file begins with `.`
     /// ignore the leading `.` and then sort as Name
     NameMixHidden(SortCase),
+
+    /// Zones first, then other directories, then everything else, each
+    /// sorted by name. Ghosts sort alongside the real entries, as their
+    /// names are the last part of their target paths.
+    Zone,
 }
 
 /// Whether a field should be sorted case-sensitively or case-insensitively.
@@ -277,7 +404,7 @@ impl SortField {
             Self::Name(ABCabc)  => natord::compare(&a.name, &b.name),
             Self::Name(AaBbCc)  => natord::compare_ignore_case(&a.name, &b.name),
 
-            Self::Size          => a.length().cmp(&b.length()),
+            Self::Size          => a.length_for_sorting().cmp(&b.length_for_sorting()),
 
             #[cfg(unix)]
             Self::FileInode     => {
@@ -312,9 +439,26 @@ impl SortField {
                 Self::strip_dot(&a.name),
                 Self::strip_dot(&b.name)
             ),
+
+            Self::Zone => match Self::zone_group(a).cmp(&Self::zone_group(b)) {
+                Ordering::Equal  => natord::compare(&a.name, &b.name),
+                order            => order,
+            },
         };
     }
 
+    /// Which group a file goes in when sorting by zone: zones, then other
+    /// directories (ghosts count as directories), then everything else.
+    fn zone_group(file: &File<'_>) -> u8 {
+        if file.is_zone() {
+            0
+        } else if file.is_directory() {
+            1
+        } else {
+            2
+        }
+    }
+
     fn strip_dot(n: &str) -> &str {
         match n.strip_prefix('.') {
             Some(s) => s,
@@ -384,6 +528,42 @@ impl IgnorePatterns {
     }
 }
 
+/// Conditions on the fields of zones’ manifest entries, from
+/// `--filter-zone`. A zone has to meet every one of them to be listed.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ZoneFilter(pub Vec<ZoneCondition>);
+
+/// One condition on a field of a zone’s manifest entry.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ZoneCondition {
+    /// The field has this value, or has it in its array: `owner=payments`.
+    Equals(String, String),
+
+    /// The field is missing, or doesn’t have this value: `owner!=payments`.
+    NotEquals(String, String),
+
+    /// The field has any value at all: `owner`.
+    Has(String),
+}
+
+impl ZoneFilter {
+    /// Whether a zone with the given manifest metadata meets every
+    /// condition.
+    pub fn matches(&self, metadata: Option<&Metadata>) -> bool {
+        let values = |field: &str| {
+            metadata
+                .and_then(|m| m.get(field))
+                .map_or(&[][..], Vec::as_slice)
+        };
+
+        self.0.iter().all(|condition| match condition {
+            ZoneCondition::Equals(field, value) => values(field).contains(value),
+            ZoneCondition::NotEquals(field, value) => !values(field).contains(value),
+            ZoneCondition::Has(field) => !values(field).is_empty(),
+        })
+    }
+}
+
 /// Whether to ignore or display files that Git would ignore.
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub enum GitIgnore {
@@ -394,6 +574,253 @@ pub enum GitIgnore {
     Off,
 }
 
+#[cfg(test)]
+mod test_zone_filter {
+    use super::*;
+
+    fn metadata(fields: &[(&str, &[&str])]) -> Metadata {
+        fields
+            .iter()
+            .map(|(field, values)| {
+                (
+                    (*field).to_string(),
+                    values.iter().map(|v| (*v).to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn equals_any_value() {
+        let filter = ZoneFilter(vec![ZoneCondition::Equals(
+            "owner".into(),
+            "payments".into(),
+        )]);
+        assert!(filter.matches(Some(&metadata(&[("owner", &["infra", "payments"])]))));
+        assert!(!filter.matches(Some(&metadata(&[("owner", &["infra"])]))));
+        assert!(!filter.matches(None));
+    }
+
+    #[test]
+    fn not_equals_allows_missing() {
+        let filter = ZoneFilter(vec![ZoneCondition::NotEquals(
+            "owner".into(),
+            "payments".into(),
+        )]);
+        assert!(filter.matches(None));
+        assert!(filter.matches(Some(&metadata(&[("owner", &["infra"])]))));
+        assert!(!filter.matches(Some(&metadata(&[("owner", &["payments"])]))));
+    }
+
+    #[test]
+    fn every_condition() {
+        let filter = ZoneFilter(vec![
+            ZoneCondition::Has("owner".into()),
+            ZoneCondition::Equals("type".into(), "library".into()),
+        ]);
+        assert!(filter.matches(Some(&metadata(&[
+            ("owner", &["infra"]),
+            ("type", &["library"])
+        ]))));
+        assert!(!filter.matches(Some(&metadata(&[("type", &["library"])]))));
+    }
+}
+
+#[cfg(test)]
+mod test_ghost_types {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use crate::fs::dir::test::make_manifest;
+    use crate::fs::{Dir, DirOptions};
+
+    fn filter(flags: &[FileFilterFlags]) -> FileFilter {
+        FileFilter {
+            sort_field: SortField::default(),
+            flags: flags.to_vec(),
+            dot_filter: DotFilter::default(),
+            ignore_patterns: IgnorePatterns::empty(),
+            git_ignore: GitIgnore::Off,
+            no_symlinks: false,
+            show_symlinks: false,
+            ghosts: None,
+            ghost_limit: None,
+            tag: None,
+            zone_filter: None,
+        }
+    }
+
+    fn areas() -> Dir {
+        Dir::new(
+            PathBuf::from("/test/src/areas"),
+            Arc::new(DirOptions::default()),
+        )
+    }
+
+    /// A ghost in the given directory, which is a zone if it’s in the
+    /// manifest.
+    fn ghost<'dir>(dir: &'dir Dir, name: &str, is_file: bool) -> File<'dir> {
+        let zone = make_manifest(&["//areas/other"]).zone(&format!("//areas/{name}"), false);
+        File::new_ghost(
+            dir.join(Path::new(name)),
+            dir,
+            name.into(),
+            zone,
+            is_file,
+            None,
+        )
+    }
+
+    /// A ghost zone, a ghost directory and a ghost file.
+    fn ghosts(dir: &Dir) -> [File<'_>; 3] {
+        [
+            ghost(dir, "other", false),
+            ghost(dir, "apps", false),
+            ghost(dir, "gen.pb", true),
+        ]
+    }
+
+    /// Which of a ghost zone, a ghost directory and a ghost file the
+    /// filter with the given flags lets through.
+    fn shown(flags: &[FileFilterFlags], is_recurse: bool) -> [bool; 3] {
+        let dir = areas();
+        ghosts(&dir).map(|file| filter(flags).shows_child_file(is_recurse, &file))
+    }
+
+    #[test]
+    fn chunks_share_one_ghost_limit() {
+        let dir = areas();
+        let ghost = |name| ghost(&dir, name, false);
+        let mut limit = GhostLimit {
+            allowed: Some(3),
+            hidden: 0,
+        };
+        let mut first = vec![ghost("a"), ghost("b")];
+        let mut second = vec![ghost("c"), ghost("d")];
+        limit.apply(&mut first);
+        assert_eq!(limit.hidden(), 0);
+        limit.apply(&mut second);
+        assert_eq!(limit.hidden(), 1);
+        assert_eq!(
+            second.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+            ["c"]
+        );
+    }
+
+    #[test]
+    fn no_flags_show_every_ghost() {
+        assert_eq!(shown(&[], false), [true, true, true]);
+        assert_eq!(shown(&[], true), [true, true, true]);
+    }
+
+    #[test]
+    fn only_dirs_shows_ghost_directories_and_zones() {
+        assert_eq!(
+            shown(&[FileFilterFlags::OnlyDirs], false),
+            [true, true, false]
+        );
+        assert_eq!(
+            shown(&[FileFilterFlags::OnlyDirs], true),
+            [true, true, false]
+        );
+        assert_eq!(
+            shown(
+                &[FileFilterFlags::OnlyDirs, FileFilterFlags::ShowSymlinks],
+                false
+            ),
+            [true, true, false]
+        );
+        assert_eq!(
+            shown(
+                &[FileFilterFlags::OnlyDirs, FileFilterFlags::NoSymlinks],
+                false
+            ),
+            [true, true, false]
+        );
+    }
+
+    #[test]
+    fn only_files_shows_ghost_files() {
+        assert_eq!(
+            shown(&[FileFilterFlags::OnlyFiles], false),
+            [false, false, true]
+        );
+        assert_eq!(
+            shown(
+                &[FileFilterFlags::OnlyFiles, FileFilterFlags::ShowSymlinks],
+                false
+            ),
+            [false, false, true]
+        );
+    }
+
+    #[test]
+    fn only_files_keeps_ghost_directories_to_recurse_into() {
+        assert_eq!(
+            shown(&[FileFilterFlags::OnlyFiles], true),
+            [true, true, true]
+        );
+    }
+
+    #[test]
+    fn ghosts_are_not_symlinks() {
+        assert_eq!(
+            shown(&[FileFilterFlags::NoSymlinks], false),
+            [true, true, true]
+        );
+        assert_eq!(
+            shown(&[FileFilterFlags::ShowSymlinks], false),
+            [true, true, true]
+        );
+    }
+
+    #[test]
+    fn both_only_flags_show_everything() {
+        assert_eq!(
+            shown(
+                &[FileFilterFlags::OnlyDirs, FileFilterFlags::OnlyFiles],
+                false
+            ),
+            [true, true, true]
+        );
+    }
+
+    /// The order the filter with the given flags sorts a ghost zone, a
+    /// ghost directory and a ghost file into.
+    fn sorted(flags: &[FileFilterFlags]) -> Vec<String> {
+        let dir = areas();
+        let mut files = ghosts(&dir);
+        filter(flags).sort_files(&mut files);
+        files.iter().map(|file| file.name.clone()).collect()
+    }
+
+    #[test]
+    fn ghost_directories_and_zones_group_with_directories() {
+        assert_eq!(sorted(&[]), ["apps", "gen.pb", "other"]);
+        assert_eq!(
+            sorted(&[FileFilterFlags::ListDirsFirst]),
+            ["apps", "other", "gen.pb"]
+        );
+        assert_eq!(
+            sorted(&[FileFilterFlags::ListDirsLast]),
+            ["gen.pb", "apps", "other"]
+        );
+    }
+
+    #[test]
+    fn zones_first_puts_zones_above_directories() {
+        assert_eq!(
+            sorted(&[FileFilterFlags::ListZonesFirst]),
+            ["other", "apps", "gen.pb"]
+        );
+        assert_eq!(
+            sorted(&[FileFilterFlags::ListZonesFirst, FileFilterFlags::Reverse]),
+            ["other", "apps", "gen.pb"]
+        );
+    }
+}
+
 #[cfg(test)]
 mod test_ignores {
     use super::*;
@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Dating ghosts by the last commit that touched them, for `--ghost-dates`.
+//!
+//! A ghost has no modified time of its own, since it isn’t on disk, but
+//! git still knows when its target last changed. Asking git costs a process
+//! per target, so each answer is kept for the rest of the run, which
+//! matters when a sort and a column both want the same ghost’s date.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{debug, warn};
+
+/// The commit times looked up so far, keyed by canonical path, including
+/// the ghosts git knew nothing about.
+static LAST_COMMITS: LazyLock<Mutex<HashMap<PathBuf, Option<SystemTime>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// When the last commit touching the given canonical path, beneath the
+/// given src root, was made, if the src root is in a git repository and
+/// any commit on `HEAD` touched it.
+pub fn last_commit(src_root: &Path, canonical_path: &Path) -> Option<SystemTime> {
+    if let Some(time) = LAST_COMMITS.lock().unwrap().get(canonical_path) {
+        return *time;
+    }
+
+    let time = read_last_commit(src_root, canonical_path);
+    let _ = LAST_COMMITS
+        .lock()
+        .unwrap()
+        .insert(canonical_path.to_path_buf(), time);
+    time
+}
+
+/// Runs `git log` for the given path, from the src root.
+fn read_last_commit(src_root: &Path, canonical_path: &Path) -> Option<SystemTime> {
+    let pathspec = canonical_path.strip_prefix(src_root).ok()?;
+    if pathspec.as_os_str().is_empty() {
+        return None;
+    }
+
+    let output = Command::new("git")
+        .current_dir(src_root)
+        .args(["--no-optional-locks", "log", "-1", "--format=%ct", "--"])
+        .arg(pathspec)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => parse_commit_time(&output.stdout),
+        Ok(output) => {
+            debug!(
+                "No commits for {pathspec:?}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Failed to run git log: {e}");
+            None
+        }
+    }
+}
+
+/// Parses the Unix timestamp that `git log --format=%ct` prints, which is
+/// nothing at all when no commit touched the path.
+fn parse_commit_time(stdout: &[u8]) -> Option<SystemTime> {
+    let seconds = std::str::from_utf8(stdout).ok()?.trim().parse().ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(
+            parse_commit_time(b"1700000000\n"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+    }
+
+    #[test]
+    fn no_commits_is_no_time() {
+        assert_eq!(parse_commit_time(b""), None);
+    }
+
+    #[test]
+    fn garbage_is_no_time() {
+        assert_eq!(parse_commit_time(b"yesterday\n"), None);
+    }
+
+    #[test]
+    fn the_src_root_itself_is_not_looked_up() {
+        assert_eq!(
+            read_last_commit(Path::new("/test/src"), Path::new("/test/src")),
+            None
+        );
+    }
+}
@@ -0,0 +1,214 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Keeping each parsed manifest on disk between invocations.
+//!
+//! Only picking out the fields of the manifest that get used still
+//! means scanning every byte of its JSON, which in a large monorepo is tens
+//! of megabytes on every listing inside the src root. Once a manifest has
+//! been parsed, what was picked out of it is written to
+//! `$XDG_CACHE_HOME/wls/manifests` in a binary format, and the next
+//! invocation reads that back instead, without any JSON being involved, as
+//! long as the manifest still has the same size and modification time.
+//! `--no-cache` skips all of this.
+//!
+//! Unlike `--daemon`, nothing needs to be left running, but each invocation
+//! still has to read the cached index, so the daemon is asked first.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::ParsedManifest;
+
+/// The version of what gets cached, so that one written by a version of wls
+/// that kept different things is never read back. This has to go up
+/// whenever `Cached` or `ParsedManifest` changes.
+const SCHEMA_VERSION: u32 = 3;
+
+/// What a manifest looked like on disk when it was parsed. If either of
+/// these has changed since, it has to be parsed again.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Copy, Clone)]
+struct Stamp {
+    len: u64,
+    modified: SystemTime,
+}
+
+impl Stamp {
+    fn of(manifest_path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(manifest_path)?;
+        Ok(Self {
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+/// One cached index, borrowing the manifest when it’s being written and
+/// owning it when it’s been read back.
+#[derive(Serialize, Deserialize)]
+struct Cached<P> {
+    schema_version: u32,
+    manifest_path: PathBuf,
+    stamp: Stamp,
+
+    /// Whether every field of each entry was kept, which an invocation with
+    /// `--filter-zone` needs.
+    with_metadata: bool,
+
+    parsed: P,
+}
+
+/// The cached index of the manifest at the given path, if there is one
+/// that’s still up to date and kept everything that’s needed.
+pub fn load(manifest_path: &Path, with_metadata: bool) -> Option<ParsedManifest> {
+    load_from(&cache_path(manifest_path)?, manifest_path, with_metadata)
+}
+
+/// Writes the index parsed from the manifest at the given path to the
+/// cache. Failing to is never worth more than a debug message, since the
+/// manifest can always be parsed again.
+pub fn store(manifest_path: &Path, with_metadata: bool, parsed: &ParsedManifest) {
+    let Some(cache_path) = cache_path(manifest_path) else {
+        return;
+    };
+
+    if let Err(e) = store_to(&cache_path, manifest_path, with_metadata, parsed) {
+        debug!("Failed to cache manifest at {cache_path:?}: {e}");
+    }
+}
+
+/// Where the index of the manifest at the given path gets cached, named
+/// after a hash of its path so that each src root gets its own.
+fn cache_path(manifest_path: &Path) -> Option<PathBuf> {
+    let dir = dirs::cache_dir()?.join("wls").join("manifests");
+    Some(dir.join(format!("{:016x}.cbor", path_hash(manifest_path))))
+}
+
+/// The 64-bit FNV-1a hash of the given path, which unlike the standard
+/// library’s hasher stays the same from one build of wls to the next.
+fn path_hash(path: &Path) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    path.as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+        })
+}
+
+fn load_from(
+    cache_path: &Path,
+    manifest_path: &Path,
+    with_metadata: bool,
+) -> Option<ParsedManifest> {
+    let bytes = fs::read(cache_path).ok()?;
+    let cached: Cached<ParsedManifest> = match ciborium::from_reader(bytes.as_slice()) {
+        Ok(cached) => cached,
+        Err(e) => {
+            debug!("Ignoring unreadable cached manifest at {cache_path:?}: {e}");
+            return None;
+        }
+    };
+
+    let stamp = Stamp::of(manifest_path).ok()?;
+    if cached.schema_version != SCHEMA_VERSION
+        || cached.manifest_path != manifest_path
+        || cached.stamp != stamp
+        || (with_metadata && !cached.with_metadata)
+    {
+        debug!("Cached manifest at {cache_path:?} is out of date");
+        return None;
+    }
+
+    debug!("Read cached manifest for {manifest_path:?} from {cache_path:?}");
+    Some(cached.parsed)
+}
+
+/// Writes the index next to where it belongs and renames it into place,
+/// so another invocation never reads one that’s half written.
+fn store_to(
+    cache_path: &Path,
+    manifest_path: &Path,
+    with_metadata: bool,
+    parsed: &ParsedManifest,
+) -> io::Result<()> {
+    let cached = Cached {
+        schema_version: SCHEMA_VERSION,
+        manifest_path: manifest_path.to_path_buf(),
+        stamp: Stamp::of(manifest_path)?,
+        with_metadata,
+        parsed,
+    };
+
+    if let Some(dir) = cache_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut bytes = Vec::new();
+    ciborium::into_writer(&cached, &mut bytes).map_err(io::Error::other)?;
+
+    let partial = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, cache_path).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parsed() -> ParsedManifest {
+        let mut parsed = ParsedManifest::default();
+        let _ = parsed.entries.insert("//areas/core".into());
+        let _ = parsed
+            .tags
+            .insert("//areas/core".into(), vec!["infra".into()]);
+        parsed
+    }
+
+    #[test]
+    fn cached_manifests_come_back_until_they_change() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let manifest_path = dir.join("manifest.json");
+        let cache_path = dir.join("cache").join("index.cbor");
+        fs::write(&manifest_path, r#"{"//areas/core": {"tags": ["infra"]}}"#).unwrap();
+
+        assert_eq!(load_from(&cache_path, &manifest_path, false), None);
+        store_to(&cache_path, &manifest_path, false, &parsed()).unwrap();
+        assert_eq!(
+            load_from(&cache_path, &manifest_path, false),
+            Some(parsed())
+        );
+
+        // The metadata wasn’t kept, so this needs the manifest parsing again
+        assert_eq!(load_from(&cache_path, &manifest_path, true), None);
+
+        fs::write(&manifest_path, r#"{"//areas/core": {}, "//areas/lib": {}}"#).unwrap();
+        assert_eq!(load_from(&cache_path, &manifest_path, false), None);
+
+        assert_eq!(load_from(&cache_path, &dir.join("other.json"), false), None);
+    }
+
+    #[test]
+    fn path_hashes_stay_the_same() {
+        assert_eq!(
+            path_hash(Path::new("/src/.meta/manifest.json")),
+            0x3738_5940_3fc5_99e0
+        );
+    }
+
+    #[test]
+    fn each_src_root_has_its_own_cache() {
+        let world = cache_path(Path::new("/home/user/world/src/.meta/manifest.json")).unwrap();
+        let other = cache_path(Path::new("/home/user/other/src/.meta/manifest.json")).unwrap();
+        assert_ne!(world, other);
+    }
+}
@@ -0,0 +1,298 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Checking the manifest for mistakes, for `--check-manifest`.
+//!
+//! When listing, a manifest that can’t be parsed is only logged, and zones
+//! quietly stop being shown. Entries that parse but can never match a
+//! directory, such as `//areas//tools` or ones missing the `//`, are worse:
+//! nothing says anything at all. This goes through the manifest looking for
+//! those, so that CI can lint it with wls itself.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::Value;
+
+use crate::fs::ZoneKind;
+
+/// How bad a problem is.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Severity {
+    /// The entry, or the whole manifest, won’t work as intended.
+    Error,
+
+    /// The entry works, but something about it is ignored.
+    Warning,
+}
+
+/// One problem found in the manifest.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Diagnostic {
+    /// The line of the manifest the problem is on, if it can be pinned down.
+    pub line: Option<usize>,
+
+    /// How bad the problem is.
+    pub severity: Severity,
+
+    /// A short name for the kind of problem, such as `duplicate-key`.
+    pub code: &'static str,
+
+    /// What’s wrong, for a person to read.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "{line}: ")?;
+        }
+
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}[{}]: {}", self.code, self.message)
+    }
+}
+
+/// The manifest’s entries in the order they’re written, keeping any keys
+/// that appear more than once, which a map would silently merge.
+struct Entries(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an object of target paths")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+/// Checks the text of a manifest whose target paths start with the given
+/// prefix, returning every problem found, in the order they appear.
+pub fn check(text: &str, target_prefix: &str) -> Vec<Diagnostic> {
+    let entries = match serde_json::from_str::<Entries>(text) {
+        Ok(Entries(entries)) => entries,
+        Err(e) => {
+            return vec![Diagnostic {
+                line: Some(e.line()),
+                severity: Severity::Error,
+                code: "syntax",
+                message: e.to_string(),
+            }];
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (target, value) in &entries {
+        let occurrence = seen.entry(target).or_default();
+        let line = key_line(text, target, *occurrence);
+        *occurrence += 1;
+
+        let mut report = |severity, code, message| {
+            diagnostics.push(Diagnostic {
+                line,
+                severity,
+                code,
+                message,
+            });
+        };
+
+        if *occurrence == 2 {
+            report(
+                Severity::Error,
+                "duplicate-key",
+                format!("{target} appears more than once"),
+            );
+        }
+
+        match target.strip_prefix(target_prefix) {
+            None => report(
+                Severity::Error,
+                "outside-prefix",
+                format!("{target} doesn’t start with {target_prefix}"),
+            ),
+            Some(rel_path) if !is_normalized(rel_path) => report(
+                Severity::Error,
+                "not-normalized",
+                format!("{target} isn’t a normalized path, so it can never match a directory"),
+            ),
+            Some(_) => {}
+        }
+
+        let Some(metadata) = value.as_object() else {
+            report(
+                Severity::Error,
+                "malformed-metadata",
+                format!("{target} should map to an object, not {}", type_name(value)),
+            );
+            continue;
+        };
+
+        match metadata.get("type") {
+            None => {}
+            Some(kind @ Value::String(name)) => {
+                if serde_json::from_value::<ZoneKind>(kind.clone()).is_err() {
+                    report(
+                        Severity::Warning,
+                        "unknown-type",
+                        format!("{target} has the unknown type {name:?}, which is ignored"),
+                    );
+                }
+            }
+            Some(other) => report(
+                Severity::Error,
+                "malformed-metadata",
+                format!(
+                    "{target} should have a string type, not {}",
+                    type_name(other)
+                ),
+            ),
+        }
+    }
+
+    diagnostics
+}
+
+/// Whether a target path, without its prefix, is what the path of a
+/// directory relative to the src root would turn into: not empty, and with
+/// no empty, `.`, or `..` components.
+fn is_normalized(rel_path: &str) -> bool {
+    rel_path
+        .split('/')
+        .all(|component| !matches!(component, "" | "." | ".."))
+}
+
+/// The line, counting from one, that a key is on the given time it appears,
+/// counting from zero. Keys are only found when they’re written without
+/// escapes, which target paths don’t need.
+fn key_line(text: &str, key: &str, occurrence: usize) -> Option<usize> {
+    let quoted = format!("\"{key}\"");
+    let (offset, _) = text
+        .match_indices(&quoted)
+        .filter(|(offset, _)| text[offset + quoted.len()..].trim_start().starts_with(':'))
+        .nth(occurrence)?;
+
+    Some(text[..offset].matches('\n').count() + 1)
+}
+
+/// How to describe a JSON value’s type in a message.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn codes(text: &str) -> Vec<(Option<usize>, &'static str)> {
+        check(text, "//")
+            .into_iter()
+            .map(|d| (d.line, d.code))
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        let text =
+            "{\n  \"//areas/tools/dev\": {\"type\": \"tool\"},\n  \"//areas/apps/flow\": {}\n}\n";
+        assert_eq!(codes(text), vec![]);
+    }
+
+    #[test]
+    fn syntax_error() {
+        assert_eq!(
+            codes("{\n  \"//areas/tools/dev\": {},\n}\n"),
+            vec![(Some(3), "syntax")]
+        );
+    }
+
+    #[test]
+    fn not_an_object() {
+        assert_eq!(codes("[\"//areas/tools/dev\"]"), vec![(Some(1), "syntax")]);
+    }
+
+    #[test]
+    fn duplicate_key() {
+        let text = "{\n  \"//areas/tools/dev\": {},\n  \"//areas/tools/dev\": {}\n}\n";
+        assert_eq!(codes(text), vec![(Some(3), "duplicate-key")]);
+    }
+
+    #[test]
+    fn outside_prefix() {
+        assert_eq!(
+            codes("{\"areas/tools/dev\": {}}"),
+            vec![(Some(1), "outside-prefix")]
+        );
+    }
+
+    #[test]
+    fn not_normalized() {
+        for target in [
+            "//areas//dev",
+            "//areas/dev/",
+            "///areas",
+            "//areas/./dev",
+            "//areas/../dev",
+            "//",
+        ] {
+            let text = format!("{{\"{target}\": {{}}}}");
+            assert_eq!(codes(&text), vec![(Some(1), "not-normalized")], "{target}");
+        }
+    }
+
+    #[test]
+    fn malformed_metadata() {
+        assert_eq!(
+            codes("{\"//areas/dev\": true}"),
+            vec![(Some(1), "malformed-metadata")]
+        );
+        assert_eq!(
+            codes("{\"//areas/dev\": {\"type\": 3}}"),
+            vec![(Some(1), "malformed-metadata")]
+        );
+    }
+
+    #[test]
+    fn unknown_type() {
+        let diagnostics = check("{\"//areas/dev\": {\"type\": \"service\"}}", "//");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].code, "unknown-type");
+    }
+
+    #[test]
+    fn keys_in_values_are_not_lines() {
+        let text = "{\n  \"//a\": {\"deps\": [\"//b\"]},\n  \"//b\": 1\n}\n";
+        assert_eq!(codes(text), vec![(Some(3), "malformed-metadata")]);
+    }
+
+    #[test]
+    fn other_prefix() {
+        assert_eq!(check("{\"@world//areas/dev\": {}}", "@world//"), vec![]);
+    }
+}
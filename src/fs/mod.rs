@@ -5,14 +5,34 @@
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
 mod dir;
-pub use self::dir::{Dir, DotFilter, is_valid_ghost_dir};
+pub use self::dir::{
+    expand_glob, find_manifest, find_manifest_for_ghost, find_repo_config, is_glob_pattern,
+    is_valid_ghost_dir, manifest_path, parse_manifest, CaseCollision, Dir, DirOptions, DotFilter,
+    Estimate, ManifestChild, ManifestInfo, ManifestLocation, Metadata, ParsedManifest, SyncState,
+    ZoneCase, ZoneKind, ZoneSymlinks, DEFAULT_TARGET_PREFIX,
+};
 
 mod file;
-pub use self::file::{File, FileTarget};
+pub use self::file::{clear_directory_size_cache, File, FileTarget};
 
+pub mod daemon;
 pub mod dir_action;
+pub mod disk_usage;
+pub mod entries;
 pub mod feature;
 pub mod fields;
 pub mod filter;
+pub mod ghost_dates;
+pub mod manifest_cache;
+pub mod manifest_check;
 pub mod mounts;
+pub mod moves;
+pub mod orphans;
+pub mod prefetch;
 pub mod recursive_size;
+pub mod repo_config;
+pub mod sparse;
+pub mod timings;
+
+mod zone;
+pub use self::zone::Zone;
@@ -6,6 +6,7 @@
 // SPDX-License-Identifier: MIT
 use crate::fs::mounts::{Error, MountedFs};
 use proc_mounts::MountList;
+use std::path::PathBuf;
 
 /// Get a list of all mounted filesystems
 pub fn mounts() -> Result<Vec<MountedFs>, Error> {
@@ -17,6 +18,15 @@ pub fn mounts() -> Result<Vec<MountedFs>, Error> {
             dest: mount.dest.clone(),
             fstype: mount.fstype.clone(),
             source: mount.source.to_string_lossy().into(),
+            upperdir: (mount.fstype == "overlay")
+                .then(|| {
+                    mount
+                        .options
+                        .iter()
+                        .find_map(|o| o.strip_prefix("upperdir="))
+                })
+                .flatten()
+                .map(PathBuf::from),
         })
         .collect())
 }
@@ -5,9 +5,11 @@
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use crate::fs::fields as f;
+
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "macos")]
@@ -24,6 +26,41 @@ pub struct MountedFs {
     pub dest: PathBuf,
     pub fstype: String,
     pub source: String,
+
+    /// For an overlay mount, the writable directory that changes go to.
+    pub upperdir: Option<PathBuf>,
+}
+
+/// Filesystem types that live on another machine.
+const NETWORK_FSTYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "lustre",
+    "9p",
+    "fuse.sshfs",
+    "webdav",
+];
+
+/// Filesystem types that lay one directory over another.
+const OVERLAY_FSTYPES: &[&str] = &["overlay", "fuse-overlayfs", "aufs"];
+
+impl MountedFs {
+    /// What kind of filesystem this is, going by its type.
+    pub fn kind(&self) -> f::FilesystemKind {
+        if NETWORK_FSTYPES.contains(&self.fstype.as_str()) {
+            f::FilesystemKind::Network
+        } else if OVERLAY_FSTYPES.contains(&self.fstype.as_str()) {
+            f::FilesystemKind::Overlay
+        } else {
+            f::FilesystemKind::Local
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -79,3 +116,53 @@ pub(super) fn all_mounts() -> &'static HashMap<PathBuf, MountedFs> {
         mount_map
     })
 }
+
+/// The filesystem that the given absolute path is on, which is the one
+/// mounted deepest among those above it.
+pub(super) fn mount_containing(path: &Path) -> Option<&'static MountedFs> {
+    deepest_mount(all_mounts().values(), path)
+}
+
+fn deepest_mount<'a>(
+    mounts: impl Iterator<Item = &'a MountedFs>,
+    path: &Path,
+) -> Option<&'a MountedFs> {
+    mounts
+        .filter(|m| path.starts_with(&m.dest))
+        .max_by_key(|m| m.dest.components().count())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mount(dest: &str, fstype: &str) -> MountedFs {
+        MountedFs {
+            dest: dest.into(),
+            fstype: fstype.into(),
+            source: String::new(),
+            upperdir: None,
+        }
+    }
+
+    #[test]
+    fn the_deepest_mount_wins() {
+        let mounts = [
+            mount("/", "overlay"),
+            mount("/src", "nfs4"),
+            mount("/src/areas/tools", "ext4"),
+        ];
+        let found =
+            |path: &str| deepest_mount(mounts.iter(), Path::new(path)).map(|m| m.fstype.as_str());
+        assert_eq!(found("/src/areas/apps"), Some("nfs4"));
+        assert_eq!(found("/src/areas/tools/dev"), Some("ext4"));
+        assert_eq!(found("/srcs"), Some("overlay"));
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(mount("/src", "nfs4").kind(), f::FilesystemKind::Network);
+        assert_eq!(mount("/", "overlay").kind(), f::FilesystemKind::Overlay);
+        assert_eq!(mount("/home", "btrfs").kind(), f::FilesystemKind::Local);
+    }
+}
@@ -0,0 +1,209 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Guessing where orphan directories were moved to, from the renames in
+//! git’s history.
+//!
+//! An orphan is a directory in the src root that the manifest knows nothing
+//! about: it isn’t a zone, it isn’t inside one, and there are none beneath
+//! it. They tend to get left behind when a reorganization moves a zone and
+//! the old directory still has untracked or ignored files in it. If git’s
+//! rename detection says the orphan’s tracked files went somewhere that the
+//! manifest does know about, that’s probably where the orphan belongs.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::fs::ManifestInfo;
+
+/// How many commits back from `HEAD` to look for renames.
+#[cfg(feature = "git")]
+const HISTORY_DEPTH: usize = 200;
+
+/// The files that recent commits renamed in one repository.
+#[derive(Debug)]
+struct Renames {
+    /// The working directory of the repository, canonicalized.
+    workdir: PathBuf,
+
+    /// The old and new path of each rename, relative to the working
+    /// directory, newest first.
+    moves: Vec<(PathBuf, PathBuf)>,
+}
+
+/// The renames read so far, one for each repository, so that the history
+/// only gets read once however many directories are listed.
+static RENAMES: Mutex<Vec<Arc<Renames>>> = Mutex::new(Vec::new());
+
+impl Renames {
+    /// The renames in the repository containing the given canonical path,
+    /// reading them from its history the first time they’re needed.
+    fn find(canonical_path: &Path) -> Option<Arc<Self>> {
+        let mut cache = RENAMES.lock().ok()?;
+        if let Some(renames) = cache
+            .iter()
+            .find(|r| canonical_path.starts_with(&r.workdir))
+        {
+            return Some(Arc::clone(renames));
+        }
+
+        let renames = Arc::new(Self::read(canonical_path)?);
+        cache.push(Arc::clone(&renames));
+        Some(renames)
+    }
+
+    /// Reads the renames out of the last `HISTORY_DEPTH` commits, comparing
+    /// each one with its first parent.
+    #[cfg(feature = "git")]
+    fn read(canonical_path: &Path) -> Option<Self> {
+        use log::debug;
+
+        let repo = match git2::Repository::discover(canonical_path) {
+            Ok(repo) => repo,
+            Err(e) => {
+                debug!("No Git repository for {canonical_path:?}: {e}");
+                return None;
+            }
+        };
+        let workdir = repo.workdir()?.canonicalize().ok()?;
+
+        let mut walk = repo.revwalk().ok()?;
+        walk.push_head().ok()?;
+
+        let mut find_options = git2::DiffFindOptions::new();
+        find_options.renames(true);
+
+        let mut moves = Vec::new();
+        for oid in walk.take(HISTORY_DEPTH).filter_map(Result::ok) {
+            let Ok(commit) = repo.find_commit(oid) else {
+                continue;
+            };
+            let Ok(parent) = commit.parent(0) else {
+                continue;
+            };
+            let (Ok(old_tree), Ok(new_tree)) = (parent.tree(), commit.tree()) else {
+                continue;
+            };
+
+            let mut diff = match repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None) {
+                Ok(diff) => diff,
+                Err(e) => {
+                    debug!("Failed to diff commit {oid}: {e}");
+                    continue;
+                }
+            };
+            if let Err(e) = diff.find_similar(Some(&mut find_options)) {
+                debug!("Failed to find renames in commit {oid}: {e}");
+                continue;
+            }
+
+            for delta in diff.deltas().filter(|d| d.status() == git2::Delta::Renamed) {
+                if let (Some(old), Some(new)) = (delta.old_file().path(), delta.new_file().path()) {
+                    moves.push((old.to_path_buf(), new.to_path_buf()));
+                }
+            }
+        }
+
+        Some(Self { workdir, moves })
+    }
+
+    #[cfg(not(feature = "git"))]
+    fn read(_canonical_path: &Path) -> Option<Self> {
+        None
+    }
+
+    /// The directory that the files in the given one were moved to, relative
+    /// to the working directory. Each renamed file that kept its path within
+    /// the directory votes for where the directory went, and the one with
+    /// the most votes that `is_wanted` accepts wins.
+    fn destination(&self, rel_dir: &Path, is_wanted: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+        let mut votes: HashMap<&Path, usize> = HashMap::new();
+        for (old, new) in &self.moves {
+            let Ok(rest) = old.strip_prefix(rel_dir) else {
+                continue;
+            };
+            if rest.as_os_str().is_empty() || !new.ends_with(rest) {
+                continue;
+            }
+
+            if let Some(dest) = new.ancestors().nth(rest.components().count()) {
+                if dest != rel_dir {
+                    *votes.entry(dest).or_default() += 1;
+                }
+            }
+        }
+
+        votes
+            .into_iter()
+            .filter(|(dest, _)| is_wanted(dest))
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(dest, _)| dest.to_path_buf())
+    }
+}
+
+/// The target path that the orphan at the given canonical path was probably
+/// moved to, if it’s an orphan and its files were moved into or as a zone.
+pub fn moved_to(manifest_info: &ManifestInfo, canonical_path: &Path) -> Option<String> {
+    if !manifest_info.is_orphan(canonical_path) {
+        return None;
+    }
+
+    let renames = Renames::find(canonical_path)?;
+    let rel_dir = canonical_path.strip_prefix(&renames.workdir).ok()?;
+    let dest = renames.destination(rel_dir, |dest| {
+        manifest_info
+            .enclosing_zone(&renames.workdir.join(dest))
+            .is_some()
+    })?;
+
+    manifest_info.target_path_for(&renames.workdir.join(dest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn renames(moves: &[(&str, &str)]) -> Renames {
+        Renames {
+            workdir: PathBuf::from("/repo"),
+            moves: moves
+                .iter()
+                .map(|(old, new)| (PathBuf::from(old), PathBuf::from(new)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn destination_by_most_votes() {
+        let renames = renames(&[
+            ("src/areas/legacy/a.rs", "src/areas/tools/legacy/a.rs"),
+            (
+                "src/areas/legacy/lib/b.rs",
+                "src/areas/tools/legacy/lib/b.rs",
+            ),
+            ("src/areas/legacy/c.rs", "src/areas/apps/c.rs"),
+        ]);
+        assert_eq!(
+            renames.destination(Path::new("src/areas/legacy"), |_| true),
+            Some(PathBuf::from("src/areas/tools/legacy"))
+        );
+    }
+
+    #[test]
+    fn destination_must_be_wanted() {
+        let renames = renames(&[("src/areas/legacy/a.rs", "src/areas/tools/legacy/a.rs")]);
+        assert_eq!(
+            renames.destination(Path::new("src/areas/legacy"), |_| false),
+            None
+        );
+    }
+
+    #[test]
+    fn renamed_files_do_not_vote() {
+        let renames = renames(&[("src/areas/legacy/a.rs", "src/areas/tools/legacy/b.rs")]);
+        assert_eq!(
+            renames.destination(Path::new("src/areas/legacy"), |_| true),
+            None
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Finding the orphans in a src root, for `--orphans`: directories on disk
+//! that the manifest knows nothing about.
+//!
+//! Walking the whole src root would be far too slow for a pre-commit hook,
+//! but most of it never needs looking at. Everything inside a zone belongs
+//! to the zone, and everything inside an orphan is orphaned along with it,
+//! so the walk only goes into directories with zones somewhere beneath
+//! them. That way it follows the shape of the manifest rather than the size
+//! of the disk, and `--orphan-depth` can bound it further still.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::fs::ManifestInfo;
+
+/// How much of a directory the manifest accounts for.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+enum Coverage {
+    /// It’s a zone, so everything in it is accounted for.
+    Zone,
+
+    /// Nothing in the manifest is at or beneath it.
+    Orphan,
+
+    /// There are zones somewhere beneath it, so its other children have to
+    /// be looked at.
+    Partial,
+}
+
+impl Coverage {
+    /// How much of the directory at the given canonical path, which isn’t
+    /// inside a zone, the manifest accounts for.
+    fn of(manifest_info: &ManifestInfo, canonical_path: &Path) -> Option<Self> {
+        let target = manifest_info.target_path_for(canonical_path)?;
+        Some(if manifest_info.is_zone(&target) {
+            Self::Zone
+        } else if !manifest_info.has_entries_beneath(canonical_path) {
+            Self::Orphan
+        } else {
+            Self::Partial
+        })
+    }
+}
+
+/// Finds the orphans in the src root, going at most `max_depth` directories
+/// down if given, and returns their canonical paths in sorted order.
+pub fn find(manifest_info: &ManifestInfo, max_depth: Option<usize>) -> Vec<PathBuf> {
+    let mut orphans = Vec::new();
+    walk(
+        manifest_info,
+        &manifest_info.src_root,
+        1,
+        max_depth,
+        &mut orphans,
+    );
+    orphans.sort_unstable();
+    orphans
+}
+
+/// Adds the orphans among the subdirectories of the given one, which is
+/// `depth - 1` directories below the src root, descending into the ones
+/// that are only partly accounted for.
+fn walk(
+    manifest_info: &ManifestInfo,
+    dir: &Path,
+    depth: usize,
+    max_depth: Option<usize>,
+    orphans: &mut Vec<PathBuf>,
+) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to read {dir:?} while looking for orphans: {e}");
+            return;
+        }
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if is_hidden || !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+
+        let path = entry.path();
+        match Coverage::of(manifest_info, &path) {
+            Some(Coverage::Orphan) => orphans.push(path),
+            Some(Coverage::Partial) if max_depth.is_none_or(|max| depth < max) => {
+                walk(manifest_info, &path, depth + 1, max_depth, orphans);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::dir::test::make_manifest;
+
+    #[test]
+    fn zones_are_covered() {
+        let manifest = make_manifest(&["//areas/tools/dev"]);
+        assert_eq!(
+            Coverage::of(&manifest, Path::new("/test/src/areas/tools/dev")),
+            Some(Coverage::Zone)
+        );
+    }
+
+    #[test]
+    fn parents_of_zones_are_partly_covered() {
+        let manifest = make_manifest(&["//areas/tools/dev"]);
+        assert_eq!(
+            Coverage::of(&manifest, Path::new("/test/src/areas")),
+            Some(Coverage::Partial)
+        );
+    }
+
+    #[test]
+    fn everything_else_is_an_orphan() {
+        let manifest = make_manifest(&["//areas/tools/dev"]);
+        assert_eq!(
+            Coverage::of(&manifest, Path::new("/test/src/areas/legacy")),
+            Some(Coverage::Orphan)
+        );
+    }
+
+    #[test]
+    fn outside_the_src_root() {
+        let manifest = make_manifest(&["//areas/tools/dev"]);
+        assert_eq!(Coverage::of(&manifest, Path::new("/elsewhere")), None);
+    }
+}
@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Fetching file metadata ahead of time, on a pool of threads.
+//!
+//! Listing a directory stats every file in it, usually more than once:
+//! filtering, sorting, and rendering all want to know about sizes, types,
+//! and timestamps. Each `File` caches its own metadata, but the first
+//! `stat` for each one happens one at a time, wherever it’s first needed.
+//! On local disks that’s fine, but on network filesystems such as NFS every
+//! one of those calls is a round trip, and a large directory spends most of
+//! its time waiting.
+//!
+//! So after a directory’s files have been read, but before they’re filtered
+//! or sorted, their metadata is fetched concurrently using a bounded pool of
+//! threads. Each result lands in the file’s own cache as soon as it comes
+//! back, and everything afterwards reads it from there.
+
+use std::ffi::OsStr;
+use std::sync::OnceLock;
+
+use log::*;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::fs::File;
+
+/// How many `stat` calls to have in flight at once when nothing else has
+/// been configured. This is more than the number of cores on most machines,
+/// because the threads spend their time waiting on I/O rather than working.
+const DEFAULT_THREADS: usize = 16;
+
+/// The pool used to fetch metadata, or `None` if prefetching is turned off.
+static POOL: OnceLock<Option<ThreadPool>> = OnceLock::new();
+
+/// Sets the number of threads used to fetch metadata, based on the value of
+/// an environment variable. A value of `0` turns prefetching off entirely,
+/// and an unset or invalid value uses the default.
+pub fn configure<T: AsRef<OsStr>>(ev: Option<T>) {
+    let threads = match ev.map(|ev| ev.as_ref().to_string_lossy().into_owned()) {
+        Some(ev) => ev.parse().unwrap_or_else(|e| {
+            warn!("Invalid stat thread count {ev:?}: {e}");
+            DEFAULT_THREADS
+        }),
+        None => DEFAULT_THREADS,
+    };
+
+    if POOL.set(build_pool(threads)).is_err() {
+        warn!("Metadata prefetching was configured twice");
+    }
+}
+
+fn build_pool(threads: usize) -> Option<ThreadPool> {
+    if threads == 0 {
+        return None;
+    }
+
+    match ThreadPoolBuilder::new().num_threads(threads).build() {
+        Ok(pool) => Some(pool),
+        Err(e) => {
+            warn!("Failed to build stat thread pool: {e}");
+            None
+        }
+    }
+}
+
+/// Fetches the metadata of every file in the slice concurrently, caching it
+/// in each file. Errors aren’t reported here: they get cached as well, and
+/// are dealt with by whatever asks for the metadata next.
+pub fn metadata(files: &[File<'_>]) {
+    // Not worth waking the pool for a handful of files
+    if files.len() < 2 {
+        return;
+    }
+
+    let Some(pool) = POOL.get_or_init(|| build_pool(DEFAULT_THREADS)) else {
+        return;
+    };
+
+    debug!("Prefetching metadata for {} files", files.len());
+    pool.install(|| {
+        files.par_iter().filter(|f| !f.is_ghost).for_each(|f| {
+            let _ = f.metadata();
+        });
+    });
+}
@@ -0,0 +1,191 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Reading the defaults a src root sets for everyone listing inside it.
+//!
+//! These come from `wls.toml` next to the manifest, which gets found during
+//! the same walk as the manifest itself:
+//!
+//! ```toml
+//! ghosts = false
+//! flags = ["--zone-colors", "--sort=zone"]
+//!
+//! [[zones]]
+//! targets = "//areas/payments/**"
+//! icon = "󰄀"
+//! color = "green"
+//! ```
+//!
+//! Zone rules are tried in order, and the first one matching a zone’s
+//! target path that sets an icon or colour decides it. These are the
+//! weakest defaults there are: the user’s own config file, the environment,
+//! and the command line all win over them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, warn};
+use nu_ansi_term::Color;
+use serde::Deserialize;
+
+use crate::options::config::deserialize_color;
+use crate::warnings::{self, Kind};
+
+/// The name of the file next to the manifest.
+const FILE_NAME: &str = "wls.toml";
+
+/// The defaults a src root sets.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct RepoConfig {
+    /// Whether ghosts are listed when nothing else says whether they are.
+    pub ghosts: Option<bool>,
+
+    /// Flags that go before the ones on the command line, so that those
+    /// can override them, as they’re written, before any are checked.
+    pub flags: Vec<String>,
+
+    /// How zones matching each pattern are shown.
+    zones: Vec<ZoneRule>,
+}
+
+/// How the zones whose target paths match a pattern are shown.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct ZoneRule {
+    targets: glob::Pattern,
+    icon: Option<char>,
+    colour: Option<Color>,
+}
+
+/// The file, as it’s written.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct RepoFile {
+    ghosts: Option<bool>,
+    flags: Vec<String>,
+    zones: Vec<ZoneRuleFile>,
+}
+
+#[derive(Deserialize)]
+struct ZoneRuleFile {
+    targets: String,
+    icon: Option<char>,
+    #[serde(default, deserialize_with = "deserialize_color")]
+    color: Option<Color>,
+}
+
+impl RepoConfig {
+    /// Reads the defaults set for the src root with the manifest at the
+    /// given path. Having no file is the same as having an empty one, and
+    /// so is having one that can’t be parsed, after a warning.
+    pub fn find(manifest_path: &Path) -> Self {
+        let path = Self::path(manifest_path);
+        let file = read(&path);
+
+        let mut config = Self {
+            ghosts: file.ghosts,
+            flags: file.flags,
+            zones: Vec::new(),
+        };
+
+        for rule in file.zones {
+            match glob::Pattern::new(&rule.targets) {
+                Ok(targets) => config.zones.push(ZoneRule {
+                    targets,
+                    icon: rule.icon,
+                    colour: rule.color,
+                }),
+                Err(e) => warn!("Ignoring zone rule for {:?} in {path:?}: {e}", rule.targets),
+            }
+        }
+
+        config
+    }
+
+    /// Where the file is for the manifest at the given path.
+    pub fn path(manifest_path: &Path) -> PathBuf {
+        manifest_path.with_file_name(FILE_NAME)
+    }
+
+    /// Whether ghosts are listed, unless something else says otherwise.
+    pub fn shows_ghosts(&self) -> bool {
+        self.ghosts != Some(false)
+    }
+
+    /// The icon to show for the zone with the given target path, if a rule
+    /// sets one.
+    pub fn zone_icon(&self, target: &str) -> Option<char> {
+        self.rules_for(target).find_map(|rule| rule.icon)
+    }
+
+    /// The colour to show the zone with the given target path in, if a
+    /// rule sets one.
+    pub fn zone_colour(&self, target: &str) -> Option<Color> {
+        self.rules_for(target).find_map(|rule| rule.colour)
+    }
+
+    fn rules_for<'a>(&'a self, target: &'a str) -> impl Iterator<Item = &'a ZoneRule> {
+        self.zones
+            .iter()
+            .filter(move |rule| rule.targets.matches(target))
+    }
+}
+
+/// Reads the file, which it’s fine not to have.
+fn read(path: &Path) -> RepoFile {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            debug!("No repository config at {path:?}: {e}");
+            return RepoFile::default();
+        }
+    };
+
+    match parse(&text) {
+        Ok(file) => file,
+        Err(e) => {
+            if !warnings::write_json(Kind::RepoConfig, path.display(), &e) {
+                warn!("Failed to parse repository config at {path:?}: {e}");
+            }
+            RepoFile::default()
+        }
+    }
+}
+
+fn parse(text: &str) -> Result<RepoFile, String> {
+    toml_edit::de::from_str(text).map_err(|e| e.message().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ghosts() {
+        assert_eq!(parse("").unwrap().ghosts, None);
+        assert_eq!(parse("ghosts = false").unwrap().ghosts, Some(false));
+        assert!(parse("ghosts = 'no'").is_err());
+    }
+
+    #[test]
+    fn next_to_the_manifest() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join(".meta")).unwrap();
+        let manifest_path = dir.join(".meta").join("manifest.json");
+        fs::write(
+            dir.join(".meta").join(FILE_NAME),
+            "ghosts = false\nflags = ['--zone-colors', 'areas']\n\
+             [[zones]]\ntargets = '//areas/tools/*'\nicon = 't'\n\
+             [[zones]]\ntargets = '//areas/**'\nicon = 'a'\ncolor = 'red'\n",
+        )
+        .unwrap();
+        fs::write(dir.join(".wls.toml"), "ghosts = true\n").unwrap();
+
+        let config = RepoConfig::find(&manifest_path);
+        assert!(!config.shows_ghosts());
+        assert_eq!(config.flags, vec!["--zone-colors", "areas"]);
+        assert_eq!(config.zone_icon("//areas/tools/dev"), Some('t'));
+        assert_eq!(config.zone_icon("//areas/apps/flow"), Some('a'));
+        assert_eq!(config.zone_colour("//areas/tools/dev"), Some(Color::Red));
+        assert_eq!(config.zone_icon("//other"), None);
+    }
+}
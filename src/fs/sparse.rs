@@ -0,0 +1,159 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Reading git’s sparse-checkout patterns, to tell ghosts that were left
+//! out of the checkout on purpose from ones that have gone missing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+/// Why a ghost isn’t on disk, when the sparse checkout can say.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum GhostReason {
+    /// The sparse-checkout patterns leave this path out, so it was never
+    /// meant to be on disk.
+    Excluded,
+
+    /// The sparse-checkout patterns include this path, but it isn’t there.
+    Missing,
+}
+
+/// The directories that a sparse checkout includes.
+#[derive(Clone, Debug)]
+pub struct SparseCheckout {
+    /// The working directory of the repository.
+    workdir: PathBuf,
+
+    /// The included directories, relative to the working directory. In cone
+    /// mode, everything under them is checked out, along with the files
+    /// (but not the directories) in each of their ancestors.
+    dirs: Vec<PathBuf>,
+}
+
+impl SparseCheckout {
+    /// Finds the repository containing the given canonical path and reads
+    /// its sparse-checkout patterns, if it has any.
+    pub fn find(canonical_path: &Path) -> Option<Self> {
+        let (workdir, git_dir) = canonical_path
+            .ancestors()
+            .find_map(|dir| Some((dir.to_path_buf(), git_dir(dir)?)))?;
+
+        let patterns_path = git_dir.join("info").join("sparse-checkout");
+        match fs::read_to_string(&patterns_path) {
+            Ok(patterns) => Some(Self::parse(workdir, &patterns)),
+            Err(e) => {
+                debug!("No sparse checkout at {patterns_path:?}: {e}");
+                None
+            }
+        }
+    }
+
+    /// Reads the included directories out of the patterns. Cone mode writes
+    /// these as `/areas/tools/dev/`. Their ancestors are listed too, but
+    /// each is followed by a negated pattern such as `!/areas/*/` that only
+    /// checks out the files in it, and those are left out of the list.
+    fn parse(workdir: PathBuf, patterns: &str) -> Self {
+        let lines: Vec<&str> = patterns
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+
+        let files_only: Vec<&str> = lines
+            .iter()
+            .filter_map(|line| line.strip_prefix('!')?.strip_suffix("/*/"))
+            .map(|dir| dir.trim_start_matches('/'))
+            .collect();
+
+        let dirs = lines
+            .iter()
+            .filter(|line| !line.starts_with('!'))
+            .map(|line| {
+                line.trim_start_matches('/')
+                    .trim_end_matches('*')
+                    .trim_end_matches('/')
+            })
+            .filter(|dir| !dir.is_empty() && !files_only.contains(dir))
+            .map(PathBuf::from)
+            .collect();
+
+        Self { workdir, dirs }
+    }
+
+    /// Why the ghost at the given canonical path isn’t on disk. Its
+    /// ancestors and descendants of included directories are wanted, and
+    /// everything else was excluded on purpose.
+    pub fn ghost_reason(&self, canonical_path: &Path) -> Option<GhostReason> {
+        let rel_path = canonical_path.strip_prefix(&self.workdir).ok()?;
+        let wanted = self
+            .dirs
+            .iter()
+            .any(|dir| rel_path.starts_with(dir) || dir.starts_with(rel_path));
+
+        Some(if wanted {
+            GhostReason::Missing
+        } else {
+            GhostReason::Excluded
+        })
+    }
+}
+
+/// The git directory of a working directory, following the `gitdir:` line
+/// of a `.git` file for worktrees and submodules.
+fn git_dir(workdir: &Path) -> Option<PathBuf> {
+    let dot_git = workdir.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let target = contents
+        .lines()
+        .find_map(|line| line.strip_prefix("gitdir:"))?;
+    Some(workdir.join(target.trim()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cone() -> SparseCheckout {
+        SparseCheckout::parse(
+            PathBuf::from("/repo"),
+            "/*\n!/*/\n/src/\n!/src/*/\n/src/areas/tools/dev/\n",
+        )
+    }
+
+    #[test]
+    fn parses_cone_patterns() {
+        assert_eq!(cone().dirs, vec![PathBuf::from("src/areas/tools/dev")]);
+    }
+
+    #[test]
+    fn included_paths_are_missing() {
+        let sparse = cone();
+        assert_eq!(
+            sparse.ghost_reason(Path::new("/repo/src/areas/tools/dev")),
+            Some(GhostReason::Missing)
+        );
+        assert_eq!(
+            sparse.ghost_reason(Path::new("/repo/src/areas/tools")),
+            Some(GhostReason::Missing)
+        );
+    }
+
+    #[test]
+    fn other_paths_are_excluded() {
+        let sparse = cone();
+        assert_eq!(
+            sparse.ghost_reason(Path::new("/repo/src/areas/apps")),
+            Some(GhostReason::Excluded)
+        );
+    }
+
+    #[test]
+    fn paths_outside_the_repo() {
+        assert_eq!(cone().ghost_reason(Path::new("/elsewhere")), None);
+    }
+}
@@ -0,0 +1,310 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Timing the slow parts of listing a directory, for `--timings`.
+//!
+//! Most listings are quick, but one automount that has to be woken up or one
+//! symlink into a dead network share can make a whole listing take seconds.
+//! Loading the manifest, reading each directory, every `stat`, link
+//! dereference, Git lookup, and zone check, and drawing the output are
+//! timed and added up per phase, and in verbose mode per entry as well, so
+//! the culprit can be named. Drawing asks for whatever hasn’t been looked
+//! up yet, so the render phase includes some of the time in the others.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How many of the slowest entries verbose mode reports.
+const SLOWEST_ENTRIES: usize = 10;
+
+/// How much detail to report about where the time went.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TimingsMode {
+    /// The total time spent in each phase.
+    Summary,
+
+    /// The totals, followed by the slowest individual entries.
+    Verbose,
+}
+
+/// One of the things that can be slow to find out about an entry.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Phase {
+    /// Reading the entry’s metadata.
+    Stat,
+
+    /// Reading a symlink and following it to its target.
+    Deref,
+
+    /// Looking up the entry’s Git status.
+    Git,
+
+    /// Checking whether the entry is a zone in the manifest.
+    Zone,
+
+    /// Loading the manifest, whether that’s parsing it or fetching it from
+    /// the cache or the daemon.
+    Manifest,
+
+    /// Reading the entries of a directory.
+    ReadDir,
+
+    /// Sorting and drawing the entries of a directory.
+    Render,
+}
+
+impl Phase {
+    const ALL: [Self; 7] = [
+        Self::Manifest,
+        Self::ReadDir,
+        Self::Stat,
+        Self::Deref,
+        Self::Git,
+        Self::Zone,
+        Self::Render,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Stat => "stat",
+            Self::Deref => "deref",
+            Self::Git => "git",
+            Self::Zone => "zone",
+            Self::Manifest => "manifest",
+            Self::ReadDir => "readdir",
+            Self::Render => "render",
+        }
+    }
+}
+
+/// The time spent in each phase, and how many times each was timed.
+#[derive(Debug, Default, Copy, Clone)]
+struct Totals {
+    durations: [Duration; Phase::ALL.len()],
+    counts: [usize; Phase::ALL.len()],
+}
+
+impl Totals {
+    fn add(&mut self, phase: Phase, duration: Duration) {
+        self.durations[phase as usize] += duration;
+        self.counts[phase as usize] += 1;
+    }
+
+    fn total(&self) -> Duration {
+        self.durations.iter().sum()
+    }
+}
+
+/// Everything timed so far.
+#[derive(Debug)]
+struct Recorder {
+    mode: TimingsMode,
+    started: Instant,
+    totals: Totals,
+
+    /// The totals for each entry, only kept in verbose mode.
+    entries: HashMap<PathBuf, Totals>,
+}
+
+/// Where the time has gone so far, shared between everything that gets
+/// timed, which can be on several threads at once.
+#[derive(Debug)]
+pub struct Timings(Mutex<Recorder>);
+
+impl Timings {
+    /// Starts timing things, in as much detail as the given mode asks for.
+    pub fn new(mode: TimingsMode) -> Self {
+        Self(Mutex::new(Recorder {
+            mode,
+            started: Instant::now(),
+            totals: Totals::default(),
+            entries: HashMap::new(),
+        }))
+    }
+
+    fn recorder(&self) -> MutexGuard<'_, Recorder> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Writes out where the time went.
+    pub fn report(&self, w: &mut impl Write) -> io::Result<()> {
+        let recorder = self.recorder();
+
+        write!(
+            w,
+            "wls: timings: {} total",
+            Millis(recorder.started.elapsed())
+        )?;
+        for phase in Phase::ALL {
+            let i = phase as usize;
+            write!(
+                w,
+                ", {} {} ({})",
+                phase.name(),
+                Millis(recorder.totals.durations[i]),
+                recorder.totals.counts[i]
+            )?;
+        }
+        writeln!(w)?;
+
+        if recorder.mode == TimingsMode::Verbose {
+            for (path, totals) in slowest(&recorder.entries, SLOWEST_ENTRIES) {
+                write!(
+                    w,
+                    "wls: timings: {:>10} {}",
+                    Millis(totals.total()),
+                    path.display()
+                )?;
+                let phases = Phase::ALL
+                    .iter()
+                    .filter(|phase| totals.counts[**phase as usize] > 0)
+                    .map(|phase| {
+                        format!(
+                            "{} {}",
+                            phase.name(),
+                            Millis(totals.durations[*phase as usize])
+                        )
+                    });
+                writeln!(w, " ({})", phases.collect::<Vec<_>>().join(", "))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs the given function, adding the time it took to the given phase for
+/// the entry at the given path if there are timings to add it to.
+pub fn time<T>(timings: Option<&Timings>, phase: Phase, path: &Path, f: impl FnOnce() -> T) -> T {
+    time_in(timings, phase, Some(path), f)
+}
+
+/// Runs the given function, adding the time it took to the given phase,
+/// and to the entry at the given path if there is one, for the files given
+/// as arguments that aren’t in any one directory.
+pub fn time_in<T>(
+    timings: Option<&Timings>,
+    phase: Phase,
+    path: Option<&Path>,
+    f: impl FnOnce() -> T,
+) -> T {
+    let Some(timings) = timings else {
+        return f();
+    };
+
+    let start = Instant::now();
+    let result = f();
+    let duration = start.elapsed();
+
+    let mut recorder = timings.recorder();
+    recorder.totals.add(phase, duration);
+    if let (TimingsMode::Verbose, Some(path)) = (recorder.mode, path) {
+        recorder
+            .entries
+            .entry(path.to_path_buf())
+            .or_default()
+            .add(phase, duration);
+    }
+
+    result
+}
+
+/// The entries that took the longest altogether, slowest first.
+fn slowest(entries: &HashMap<PathBuf, Totals>, count: usize) -> Vec<(&PathBuf, &Totals)> {
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then_with(|| a.0.cmp(b.0)));
+    entries.truncate(count);
+    entries
+}
+
+/// A duration written in milliseconds, such as `2013.48ms`.
+struct Millis(Duration);
+
+impl fmt::Display for Millis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = format!("{:.2}ms", self.0.as_secs_f64() * 1000.0);
+        f.pad(&millis)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn totals(stat: u64, git: u64) -> Totals {
+        let mut totals = Totals::default();
+        totals.add(Phase::Stat, Duration::from_millis(stat));
+        totals.add(Phase::Git, Duration::from_millis(git));
+        totals
+    }
+
+    #[test]
+    fn slowest_first() {
+        let mut entries = HashMap::new();
+        entries.insert(PathBuf::from("quick"), totals(1, 1));
+        entries.insert(PathBuf::from("automount"), totals(2000, 0));
+        entries.insert(PathBuf::from("medium"), totals(5, 10));
+
+        let names: Vec<_> = slowest(&entries, 2)
+            .into_iter()
+            .map(|(p, _)| p.clone())
+            .collect();
+        assert_eq!(
+            names,
+            vec![PathBuf::from("automount"), PathBuf::from("medium")]
+        );
+    }
+
+    #[test]
+    fn totals_add_up() {
+        let totals = totals(3, 4);
+        assert_eq!(totals.total(), Duration::from_millis(7));
+        assert_eq!(totals.counts, [1, 0, 1, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn verbose_reports_each_path() {
+        let timings = Timings::new(TimingsMode::Verbose);
+        assert_eq!(
+            time(Some(&timings), Phase::Stat, Path::new("automount"), || 7),
+            7
+        );
+        assert_eq!(time(None, Phase::Git, Path::new("untimed"), || 8), 8);
+
+        let mut report = Vec::new();
+        timings.report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains(", stat "), "{report}");
+        assert!(report.contains(" automount (stat "), "{report}");
+        assert!(!report.contains("untimed"), "{report}");
+    }
+
+    #[test]
+    fn phases_without_an_entry_only_count_towards_the_totals() {
+        let timings = Timings::new(TimingsMode::Verbose);
+        time_in(Some(&timings), Phase::Render, None, || ());
+
+        let mut report = Vec::new();
+        timings.report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(
+            report.contains(", render ") && report.ends_with("(1)\n"),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn millis() {
+        assert_eq!(
+            Millis(Duration::from_micros(2_013_480)).to_string(),
+            "2013.48ms"
+        );
+        assert_eq!(
+            format!("{:>8}", Millis(Duration::from_millis(1))),
+            "  1.00ms"
+        );
+    }
+}
@@ -0,0 +1,281 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Zones: the directories that have entries of their own in the manifest.
+//!
+//! A `Zone` is built from a `ManifestInfo`, and everything it knows comes
+//! from the manifest and the files next to it, so how zones nest can be
+//! worked out without any of their directories being there. Nothing here
+//! looks at the disk: whoever asks says which directories are there.
+
+use std::path::{Path, PathBuf};
+
+use nu_ansi_term::Color;
+
+use crate::fs::Metadata;
+use crate::fs::{Estimate, ManifestInfo, SyncState, ZoneKind};
+
+/// What the manifest knows about a directory that is a zone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Zone {
+    /// The zone’s target path, such as `//areas/tools/dev`.
+    pub target: String,
+
+    /// The src root whose manifest the zone is in.
+    pub src_root: PathBuf,
+
+    /// Where the zone’s directory is, or would be if it were materialized.
+    pub path: PathBuf,
+
+    /// Whether the zone’s directory is on disk, rather than the zone being
+    /// a ghost.
+    pub materialized: bool,
+
+    /// Every field of the zone’s manifest entry, if they were kept.
+    pub metadata: Option<Metadata>,
+
+    /// Who last synced this zone, and when, if the sync-state provider
+    /// knows.
+    pub sync: Option<SyncState>,
+
+    /// What the zone holds, if its manifest entry says.
+    pub kind: Option<ZoneKind>,
+
+    /// The tags the zone’s manifest entry has.
+    pub tags: Vec<String>,
+
+    /// The icon the src root’s zone rules give the zone, if any.
+    pub icon: Option<char>,
+
+    /// The colour the src root’s zone rules give the zone, if any.
+    pub colour: Option<Color>,
+
+    /// How big the manifest says the zone is, if it says.
+    pub estimate: Option<Estimate>,
+}
+
+impl Zone {
+    /// Whether the given path is inside this zone, or is the zone itself.
+    /// Paths in zones nested inside this one count as well.
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.path)
+    }
+
+    /// The zones directly beneath this one, with no other zone in between,
+    /// sorted by target path. Each is materialized if this one is and the
+    /// given function says its directory is there.
+    pub fn children(&self, manifest: &ManifestInfo, is_dir: impl Fn(&Path) -> bool) -> Vec<Self> {
+        let prefix = format!("{}/", self.target);
+        let mut targets: Vec<&str> = manifest
+            .entries
+            .iter()
+            .filter(|entry| entry.starts_with(&prefix))
+            .filter(|entry| parent_target(manifest, entry) == Some(self.target.as_str()))
+            .map(String::as_str)
+            .collect();
+        targets.sort_unstable();
+
+        targets
+            .into_iter()
+            .filter_map(|target| {
+                let materialized = self.materialized && is_on_disk(manifest, target, &is_dir);
+                manifest.zone(target, materialized)
+            })
+            .collect()
+    }
+
+    /// The nearest zone that this one is nested inside, if it’s inside any,
+    /// which is materialized if this one is or the given function says its
+    /// directory is there.
+    pub fn parent_zone(
+        &self,
+        manifest: &ManifestInfo,
+        is_dir: impl Fn(&Path) -> bool,
+    ) -> Option<Self> {
+        let parent = parent_target(manifest, &self.target)?;
+
+        // A zone on disk is inside a directory on disk
+        let materialized = self.materialized || is_on_disk(manifest, parent, &is_dir);
+        manifest.zone(parent, materialized)
+    }
+}
+
+/// The target path of the nearest zone above the given one.
+fn parent_target<'a>(manifest: &ManifestInfo, target: &'a str) -> Option<&'a str> {
+    let mut target = target;
+    while let Some((parent, _)) = target.rsplit_once('/') {
+        if manifest.is_zone(parent) {
+            return Some(parent);
+        }
+        target = parent;
+    }
+
+    None
+}
+
+/// Whether the given function says the directory for the given target path
+/// is on disk.
+fn is_on_disk(manifest: &ManifestInfo, target: &str, is_dir: impl Fn(&Path) -> bool) -> bool {
+    manifest.path_for(target).is_some_and(|path| is_dir(&path))
+}
+
+impl ManifestInfo {
+    /// Check if a target path (relative to `src_root`) is a zone
+    pub fn is_zone(&self, target_path: &str) -> bool {
+        self.entries.contains(target_path)
+    }
+
+    /// Whether the directory at the given canonical path is a zone.
+    pub fn is_zone_path(&self, canonical_path: &Path) -> bool {
+        self.target_path_for(canonical_path)
+            .is_some_and(|target| self.is_zone(&target))
+    }
+
+    /// The zone with the given target path, if there is one, which is a
+    /// ghost unless it’s said to be materialized.
+    pub fn zone(&self, target_path: &str, materialized: bool) -> Option<Zone> {
+        if !self.is_zone(target_path) {
+            return None;
+        }
+
+        Some(Zone {
+            target: target_path.to_string(),
+            src_root: self.src_root.clone(),
+            path: self.path_for(target_path)?,
+            materialized,
+            metadata: self.metadata.get(target_path).cloned(),
+            sync: self.sync_state(target_path).cloned(),
+            kind: self.zone_kinds.get(target_path).copied(),
+            tags: self.tags.get(target_path).cloned().unwrap_or_default(),
+            icon: self.repo_config.zone_icon(target_path),
+            colour: self.repo_config.zone_colour(target_path),
+            estimate: self.estimates.get(target_path).copied(),
+        })
+    }
+
+    /// The nearest zone enclosing the given canonical path, which may be
+    /// the path itself, along with the zone’s path relative to `src_root`.
+    pub fn enclosing_zone(&self, canonical_path: &Path) -> Option<(String, PathBuf)> {
+        canonical_path
+            .ancestors()
+            .take_while(|dir| dir.starts_with(&self.src_root))
+            .find_map(|dir| {
+                let target = self.target_path_for(dir)?;
+                let rel_path = dir.strip_prefix(&self.src_root).ok()?;
+                self.is_zone(&target)
+                    .then(|| (target, rel_path.to_path_buf()))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::dir::test::make_manifest;
+
+    fn targets(zones: &[Zone]) -> Vec<&str> {
+        zones.iter().map(|zone| zone.target.as_str()).collect()
+    }
+
+    #[test]
+    fn zones_know_where_they_are() {
+        let manifest = make_manifest(&["//areas/tools/dev"]);
+        let zone = manifest.zone("//areas/tools/dev", false).unwrap();
+        assert_eq!(zone.src_root, PathBuf::from("/test/src"));
+        assert_eq!(zone.path, PathBuf::from("/test/src/areas/tools/dev"));
+        assert!(!zone.materialized);
+    }
+
+    #[test]
+    fn only_entries_are_zones() {
+        let manifest = make_manifest(&["//areas/tools/dev"]);
+        assert_eq!(manifest.zone("//areas/tools", true), None);
+    }
+
+    #[test]
+    fn contains_paths_inside_it() {
+        let manifest = make_manifest(&["//areas/tools"]);
+        let zone = manifest.zone("//areas/tools", true).unwrap();
+        assert!(zone.contains(Path::new("/test/src/areas/tools")));
+        assert!(zone.contains(Path::new("/test/src/areas/tools/dev/main.rs")));
+        assert!(!zone.contains(Path::new("/test/src/areas/toolshed")));
+        assert!(!zone.contains(Path::new("/test/src/areas")));
+    }
+
+    #[test]
+    fn children_skip_nested_zones() {
+        let manifest = make_manifest(&[
+            "//areas",
+            "//areas/tools/dev",
+            "//areas/tools/dev/api",
+            "//areas/apps",
+            "//areasx",
+        ]);
+        let zone = manifest.zone("//areas", false).unwrap();
+        assert_eq!(
+            targets(&zone.children(&manifest, |_| false)),
+            ["//areas/apps", "//areas/tools/dev"]
+        );
+    }
+
+    #[test]
+    fn leaves_have_no_children() {
+        let manifest = make_manifest(&["//areas/tools/dev"]);
+        let zone = manifest.zone("//areas/tools/dev", false).unwrap();
+        assert!(zone.children(&manifest, |_| false).is_empty());
+    }
+
+    #[test]
+    fn parent_zone_is_the_nearest() {
+        let manifest = make_manifest(&["//areas", "//areas/tools", "//areas/tools/dev/api"]);
+        let zone = manifest.zone("//areas/tools/dev/api", false).unwrap();
+        let parent = zone.parent_zone(&manifest, |_| false).unwrap();
+        assert_eq!(parent.target, "//areas/tools");
+        assert_eq!(
+            parent
+                .parent_zone(&manifest, |_| false)
+                .map(|zone| zone.target),
+            Some("//areas".into())
+        );
+    }
+
+    #[test]
+    fn top_level_zones_have_no_parent() {
+        let manifest = make_manifest(&["//areas", "//areasx/tools"]);
+        assert_eq!(
+            manifest
+                .zone("//areas", false)
+                .unwrap()
+                .parent_zone(&manifest, |_| false),
+            None
+        );
+        assert_eq!(
+            manifest
+                .zone("//areasx/tools", false)
+                .unwrap()
+                .parent_zone(&manifest, |_| false),
+            None
+        );
+    }
+
+    #[test]
+    fn children_on_disk_are_materialized() {
+        let manifest = make_manifest(&["//areas", "//areas/tools", "//areas/apps"]);
+        let zone = manifest.zone("//areas", true).unwrap();
+        let children = zone.children(&manifest, |path| path.ends_with("tools"));
+        let materialized: Vec<_> = children
+            .iter()
+            .map(|zone| (zone.target.as_str(), zone.materialized))
+            .collect();
+        assert_eq!(
+            materialized,
+            [("//areas/apps", false), ("//areas/tools", true)]
+        );
+    }
+
+    #[test]
+    fn parents_of_materialized_zones_are_materialized() {
+        let manifest = make_manifest(&["//areas", "//areas/tools"]);
+        let zone = manifest.zone("//areas/tools", true).unwrap();
+        assert!(zone.parent_zone(&manifest, |_| false).unwrap().materialized);
+    }
+}
@@ -0,0 +1,102 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! `--journal`, which lets a recursive listing that was interrupted pick up
+//! where it stopped.
+//!
+//! Listing a whole src root over NFS can take hours, and starting again from
+//! the top after a dropped connection makes that impractical. Instead, every
+//! directory whose listing has been written out, along with everything
+//! beneath it, is appended to the journal as it finishes. A rerun with the
+//! same journal skips those directories, so only the directories that were
+//! in progress get listed again. Once a listing gets all the way to the end,
+//! the journal is removed, so that the next run starts from scratch.
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use log::*;
+
+/// The directories that earlier runs finished, and the file to record the
+/// ones this run finishes in.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    completed: HashSet<PathBuf>,
+}
+
+impl Journal {
+    /// Opens the journal at the given path, reading the directories that
+    /// earlier runs finished, or creating it if there haven’t been any.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let completed = BufReader::new(&file)
+            .lines()
+            .collect::<io::Result<HashSet<_>>>()?
+            .into_iter()
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect::<HashSet<_>>();
+        debug!(
+            "Journal {path:?} has {} finished directories",
+            completed.len()
+        );
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            completed,
+        })
+    }
+
+    /// Whether an earlier run finished listing the directory with the given
+    /// canonical path and everything beneath it.
+    pub fn is_complete(&self, canonical_path: &Path) -> bool {
+        self.completed.contains(canonical_path)
+    }
+
+    /// Records that the directory with the given canonical path and
+    /// everything beneath it have been listed. The listing itself has to
+    /// have been flushed first, or an interruption could lose it.
+    pub fn complete(&mut self, canonical_path: &Path) -> io::Result<()> {
+        writeln!(self.file, "{}", canonical_path.display())?;
+        self.completed.insert(canonical_path.to_path_buf());
+        Ok(())
+    }
+
+    /// Removes the journal, once the listing has got all the way to the end.
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resumes_from_earlier_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal");
+
+        let mut journal = Journal::open(&path).unwrap();
+        assert!(!journal.is_complete(Path::new("/src/areas")));
+        journal.complete(Path::new("/src/areas")).unwrap();
+        assert!(journal.is_complete(Path::new("/src/areas")));
+        drop(journal);
+
+        let journal = Journal::open(&path).unwrap();
+        assert!(journal.is_complete(Path::new("/src/areas")));
+        assert!(!journal.is_complete(Path::new("/src")));
+
+        journal.finish().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -8,6 +8,7 @@
 pub mod fs;
 #[allow(unused)]
 pub mod info;
+pub mod list;
 #[allow(unused)]
 pub mod logger;
 #[allow(unused)]
@@ -16,3 +17,4 @@ pub mod options;
 pub mod output;
 #[allow(unused)]
 pub mod theme;
+pub mod warnings;
@@ -0,0 +1,244 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Listing a directory from Rust, for tools that want what `wls` knows
+//! about a monorepo without running it and reading its output.
+//!
+//! `list` reads a directory the way `wls` does, ghosts and zones included,
+//! and returns plain `Entry` values that don’t borrow from anything. For
+//! more than that, the `fs` module has the `Dir`, `File`, `ManifestInfo`
+//! and `Zone` types these are built from.
+//!
+//! ```
+//! use wls::list::{list, ListOptions};
+//!
+//! let entries = list("src", &ListOptions::default()).unwrap();
+//! assert!(entries.iter().any(|entry| entry.name == "main.rs"));
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::fs::filter::{SortCase, SortField};
+use crate::fs::{is_valid_ghost_dir, Dir, DirOptions, DotFilter, File, Zone};
+
+/// How to list a directory.
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct ListOptions {
+    /// Whether to list files whose names start with a dot.
+    pub all: bool,
+
+    /// Whether to list ghosts, or, if `None`, whatever the src root’s
+    /// config says.
+    pub ghosts: Option<bool>,
+
+    /// Where to find the manifest, and how to read it.
+    pub dirs: DirOptions,
+}
+
+/// What sort of thing an entry is.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[non_exhaustive]
+pub enum EntryKind {
+    Directory,
+    Symlink,
+    File,
+    Pipe,
+    Socket,
+    CharDevice,
+    BlockDevice,
+    Other,
+}
+
+impl EntryKind {
+    fn of(file: &File<'_>) -> Self {
+        if file.is_directory() {
+            Self::Directory
+        } else if file.is_link() {
+            Self::Symlink
+        } else if file.is_file() {
+            Self::File
+        } else if file.is_pipe() {
+            Self::Pipe
+        } else if file.is_socket() {
+            Self::Socket
+        } else if file.is_char_device() {
+            Self::CharDevice
+        } else if file.is_block_device() {
+            Self::BlockDevice
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// One file in a listed directory.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Entry {
+    /// The file’s path, which starts with the listed directory’s path.
+    pub path: PathBuf,
+
+    /// The file’s name.
+    pub name: String,
+
+    /// What sort of thing the file is.
+    pub kind: EntryKind,
+
+    /// The file’s size in bytes, if it’s a regular file.
+    pub size: Option<u64>,
+
+    /// The time the file was modified, if it’s on disk.
+    pub modified: Option<SystemTime>,
+
+    /// Whether the file is a ghost: in the manifest, but not on disk.
+    pub ghost: bool,
+
+    /// The zone the file is, if it has a direct entry in the manifest.
+    pub zone: Option<Zone>,
+
+    /// The file’s manifest target path, if it’s beneath a src root.
+    pub target: Option<String>,
+}
+
+impl Entry {
+    fn new(file: &File<'_>) -> Self {
+        let kind = EntryKind::of(file);
+        let metadata = if file.is_ghost {
+            None
+        } else {
+            file.metadata().ok()
+        };
+
+        Self {
+            path: file.path.clone(),
+            name: file.name.clone(),
+            kind,
+            size: metadata
+                .filter(|_| kind == EntryKind::File)
+                .map(std::fs::Metadata::len),
+            modified: metadata.and_then(|metadata| metadata.modified().ok()),
+            ghost: file.is_ghost,
+            zone: file.zone().cloned(),
+            target: file.target_path(),
+        }
+    }
+}
+
+/// Lists the files in the directory at the given path, sorted by name, in
+/// the same way as `wls` does. A ghost directory lists what the manifest
+/// has beneath it, and a path that isn’t a directory lists just itself.
+pub fn list(path: impl AsRef<Path>, options: &ListOptions) -> io::Result<Vec<Entry>> {
+    let path = path.as_ref().to_path_buf();
+    let dir_options = Arc::new(options.dirs.clone());
+
+    let dir = match std::fs::symlink_metadata(&path) {
+        Ok(metadata) if metadata.is_dir() => Dir::read_dir(path, Arc::clone(&dir_options))?,
+        Ok(metadata) => {
+            let file = File::from_args(
+                path,
+                None,
+                None,
+                false,
+                false,
+                Some(metadata.file_type()),
+                &dir_options,
+            );
+            return Ok(vec![Entry::new(&file)]);
+        }
+        Err(e) => {
+            let Some((manifest_info, canonical_path)) = is_valid_ghost_dir(&path, &dir_options)
+            else {
+                return Err(e);
+            };
+            Dir::new_ghost(
+                path,
+                manifest_info,
+                canonical_path,
+                Arc::clone(&dir_options),
+            )
+        }
+    };
+
+    let dots = if options.all {
+        DotFilter::Dotfiles
+    } else {
+        DotFilter::JustFiles
+    };
+    let mut files: Vec<File<'_>> = dir
+        .files(dots, None, false, false, false, options.ghosts)
+        .collect();
+    files.sort_by(|a, b| SortField::Name(SortCase::AaBbCc).compare_files(a, b));
+
+    Ok(files.iter().map(Entry::new).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_dir(files: &[&str]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        for file in files {
+            std::fs::write(dir.path().join(file), "hello").unwrap();
+        }
+        dir
+    }
+
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn lists_sorted_by_name() {
+        let dir = temp_dir(&["b", "A", "c10", "c9"]);
+        let entries = list(dir.path(), &ListOptions::default()).unwrap();
+        assert_eq!(names(&entries), ["A", "b", "c9", "c10", "sub"]);
+    }
+
+    #[test]
+    fn dotfiles_need_all() {
+        let dir = temp_dir(&[".hidden", "shown"]);
+        assert_eq!(
+            names(&list(dir.path(), &ListOptions::default()).unwrap()),
+            ["shown", "sub"]
+        );
+
+        let options = ListOptions {
+            all: true,
+            ..ListOptions::default()
+        };
+        assert_eq!(
+            names(&list(dir.path(), &options).unwrap()),
+            [".hidden", "shown", "sub"]
+        );
+    }
+
+    #[test]
+    fn kinds_and_sizes() {
+        let dir = temp_dir(&["file"]);
+        let entries = list(dir.path(), &ListOptions::default()).unwrap();
+        assert_eq!(entries[0].kind, EntryKind::File);
+        assert_eq!(entries[0].size, Some(5));
+        assert_eq!(entries[1].kind, EntryKind::Directory);
+        assert_eq!(entries[1].size, None);
+        assert!(!entries[1].ghost);
+    }
+
+    #[test]
+    fn files_list_themselves() {
+        let dir = temp_dir(&["file"]);
+        assert_eq!(
+            names(&list(dir.path().join("file"), &ListOptions::default()).unwrap()),
+            ["file"]
+        );
+    }
+
+    #[test]
+    fn missing_directories_are_errors() {
+        assert!(list("/this/does/not/exist", &ListOptions::default()).is_err());
+    }
+}
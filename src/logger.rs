@@ -43,6 +43,11 @@ impl log::Log for Logger {
     }
 
     fn log(&self, record: &log::Record<'_>) {
+        if !crate::warnings::colours() {
+            eprintln!("[{} {}] {}", record.level(), record.target(), record.args());
+            return;
+        }
+
         let open = Colour::Fixed(243).paint("[");
         let level = level(record.level());
         let close = Colour::Fixed(243).paint("]");
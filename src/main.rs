@@ -27,29 +27,56 @@
 #![allow(clippy::upper_case_acronyms)]
 #![allow(clippy::wildcard_imports)]
 
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::io::{self, stdin, ErrorKind, IsTerminal, Read, Write};
-use std::path::{Component, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::process::exit;
+use std::sync::Arc;
 
 use nu_ansi_term::{AnsiStrings as ANSIStrings, Style};
 
+use crate::fs::dir_action::DirAction;
+use crate::fs::disk_usage::Usage;
 use crate::fs::feature::git::GitCache;
 use crate::fs::filter::{FileFilterFlags::OnlyFiles, GitIgnore};
-use crate::fs::{Dir, File, is_valid_ghost_dir};
+use crate::fs::manifest_check::{self, Severity};
+use crate::fs::timings::{self, Phase};
+use crate::fs::{
+    expand_glob, find_manifest, find_manifest_for_ghost, is_glob_pattern, is_valid_ghost_dir,
+    CaseCollision, Dir, DirOptions, File, ManifestInfo,
+};
+use crate::journal::Journal;
 use crate::options::stdin::FilesInput;
+use crate::options::zone_query::ZoneQuery;
 use crate::options::{vars, Options, OptionsResult, Vars};
-use crate::output::{details, escape, file_name, grid, grid_details, lines, Mode, View};
+use crate::output::common_prefix;
+use crate::output::describe::ZoneDescription;
+use crate::output::free_space::FreeSpace;
+use crate::output::zone_summary::ZoneSummary;
+use crate::output::{
+    delimited, details, escape, file_name, grid, grid_details, json_lines, lines, manifest_only,
+    print0, template, Mode, View,
+};
 use crate::theme::Theme;
+use crate::warnings::Kind;
+use crate::watch::Watch;
 use log::*;
 
 mod fs;
 mod info;
+mod journal;
 mod logger;
 mod options;
 mod output;
+mod selftest;
+#[cfg(test)]
+mod testutil;
 mod theme;
+mod warnings;
+mod watch;
 
 fn main() {
     #[cfg(unix)]
@@ -63,15 +90,35 @@ fn main() {
             .or_else(|| env::var_os(vars::EXA_DEBUG)),
     );
 
+    fs::prefetch::configure(env::var_os(vars::WLS_STAT_THREADS));
+    fs::daemon::configure(env::var_os(vars::WLS_DAEMON_SOCKET));
+
     let stdout_istty = io::stdout().is_terminal();
 
     let mut input = String::new();
     let args: Vec<_> = env::args_os().skip(1).collect();
-    match Options::parse(args.iter().map(std::convert::AsRef::as_ref), &LiveVars) {
-        OptionsResult::Ok(options, mut input_paths) => {
+    let result = Options::parse(args.iter().map(std::convert::AsRef::as_ref), &LiveVars);
+
+    match result {
+        OptionsResult::Ok(mut options, mut input_paths) => {
+            warnings::set_json(options.errors == warnings::Format::Json);
+            warnings::set_colours(
+                options
+                    .theme
+                    .stderr_colours
+                    .enabled(io::stderr().is_terminal()),
+            );
+            if !options.repo_flags.args.is_empty() {
+                debug!(
+                    "Using default flags from the src root: {:?}",
+                    options.repo_flags
+                );
+            }
+            options.repo_flags.warn();
+
             // List the current directory by default.
             // (This has to be done here, otherwise git_options won’t see it.)
-            if input_paths.is_empty() {
+            if input_paths.is_empty() && options.globs.0.is_empty() {
                 match &options.stdin {
                     FilesInput::Args => {
                         input_paths = vec![OsStr::new(".")];
@@ -87,6 +134,10 @@ fn main() {
                                 .filter(|s| !s.is_empty())
                                 .collect::<Vec<_>>(),
                         );
+                        if elides_common_prefix(&options) {
+                            options.view.file_style.common_prefix =
+                                common_prefix::find(input_paths.iter().map(Path::new));
+                        }
                     }
                 }
             }
@@ -97,7 +148,14 @@ fn main() {
 
             let console_width = options.view.width.actual_terminal_width();
             let theme = options.theme.to_theme(stdout_istty);
-            let exa = Exa {
+            let journal = match options.journal.as_deref().map(Journal::open).transpose() {
+                Ok(journal) => journal,
+                Err(e) => {
+                    eprintln!("wls: journal: {e}");
+                    exit(exits::RUNTIME_ERROR);
+                }
+            };
+            let mut exa = Exa {
                 options,
                 writer,
                 input_paths,
@@ -105,10 +163,23 @@ fn main() {
                 console_width,
                 git,
                 git_repos,
+                listed_dirs: HashSet::new(),
+                journal,
             };
 
             info!("matching on exa.run");
-            match exa.run() {
+            let result = if exa.options.watch {
+                exa.watch()
+            } else {
+                exa.run()
+            };
+            if let Some(timings) = &exa.options.dirs.timings {
+                if let Err(e) = timings.report(&mut io::stderr()) {
+                    warn!("Failed to report timings: {e}");
+                }
+            }
+
+            match result {
                 Ok(exit_status) => {
                     trace!("exa.run: exit Ok({exit_status})");
                     exit(exit_status);
@@ -135,11 +206,50 @@ fn main() {
             print!("{version_str}");
         }
 
+        OptionsResult::Completions(completions) => {
+            print!("{completions}");
+        }
+
+        OptionsResult::ZoneQuery(query, dirs, errors) => {
+            warnings::set_json(errors == warnings::Format::Json);
+            match run_zone_query(&query, &dirs) {
+                Ok(exit_status) => exit(exit_status),
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => exit(exits::SUCCESS),
+                Err(e) => {
+                    if !warnings::write_json(Kind::of_io_error(&e), "", &e) {
+                        warnings::write_text(&e);
+                    }
+                    exit(exits::RUNTIME_ERROR);
+                }
+            }
+        }
+
+        OptionsResult::SelfTest(dir) => match selftest::run(&dir) {
+            Ok(exit_status) => exit(exit_status),
+            Err(e) => {
+                eprintln!("wls: selftest: {e}");
+                exit(exits::RUNTIME_ERROR);
+            }
+        },
+
+        OptionsResult::Daemon => {
+            if let Err(e) = fs::daemon::serve() {
+                eprintln!("wls: daemon: {e}");
+                exit(exits::RUNTIME_ERROR);
+            }
+        }
+
         OptionsResult::InvalidOptions(error) => {
-            eprintln!("wls: {error}");
+            warnings::set_json(
+                options::errors_from_args(args.iter().map(OsString::as_os_str))
+                    == warnings::Format::Json,
+            );
+            if !warnings::write_json(Kind::InvalidOptions, "", &error) {
+                eprintln!("wls: {error}");
 
-            if let Some(s) = error.suggestion() {
-                eprintln!("{s}");
+                if let Some(s) = error.suggestion() {
+                    eprintln!("{s}");
+                }
             }
 
             exit(exits::OPTIONS_ERROR);
@@ -174,6 +284,13 @@ pub struct Exa<'args> {
     pub git: Option<GitCache>,
 
     pub git_repos: bool,
+
+    /// The canonical paths of the directories listed so far, used by
+    /// `--dedupe` to avoid listing a directory twice.
+    pub listed_dirs: HashSet<PathBuf>,
+
+    /// The journal of finished directories, if `--journal` was given.
+    pub journal: Option<Journal>,
 }
 
 /// The “real” environment variables type.
@@ -184,13 +301,177 @@ impl Vars for LiveVars {
     fn get(&self, name: &'static str) -> Option<OsString> {
         env::var_os(name)
     }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.get_with_fallback(vars::WLS_CONFIG_DIR, vars::EZA_CONFIG_DIR)
+            .map(PathBuf::from)
+            .or_else(|| dirs::config_dir().map(|dir| dir.join("wls")))
+    }
+}
+
+/// Answers a question about the manifest of a src root, printing the answer
+/// to stdout. Completions come from the src root containing the current
+/// directory, and zone lookups from the one containing the path asked about.
+fn run_zone_query(query: &ZoneQuery, dirs: &DirOptions) -> io::Result<i32> {
+    let mut writer = io::stdout().lock();
+    match query {
+        ZoneQuery::Complete(prefix) => {
+            let Some(manifest) = find_manifest(Path::new("."), dirs) else {
+                warn_no_manifest(Path::new("."));
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            for entry in manifest.complete(prefix) {
+                writeln!(writer, "{entry}")?;
+            }
+        }
+
+        ZoneQuery::Of(path) => {
+            // The path might be a ghost, or inside one
+            let Some((manifest, canonical_path)) = find_manifest_for_ghost(path, dirs) else {
+                warn_no_manifest(path);
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            let Some((target, rel_path)) = manifest.enclosing_zone(&canonical_path) else {
+                if !warnings::write_json(Kind::NotInManifest, path.display(), "not inside a zone") {
+                    eprintln!("wls: {} is not inside a zone", path.display());
+                }
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            writeln!(writer, "{target}\t{}", rel_path.display())?;
+        }
+
+        ZoneQuery::CheckManifest => {
+            let Some(manifest_path) = fs::manifest_path(Path::new("."), dirs) else {
+                warn_no_manifest(Path::new("."));
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            let text = std::fs::read_to_string(&manifest_path)?;
+            let diagnostics = manifest_check::check(&text, &dirs.target_prefix);
+            for diagnostic in &diagnostics {
+                writeln!(writer, "{}:{diagnostic}", manifest_path.display())?;
+            }
+
+            if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                return Ok(exits::RUNTIME_ERROR);
+            }
+        }
+
+        ZoneQuery::Orphans(max_depth) => {
+            let Some(manifest) = find_manifest(Path::new("."), dirs) else {
+                warn_no_manifest(Path::new("."));
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            let orphans = fs::orphans::find(&manifest, *max_depth);
+            for orphan in &orphans {
+                if let Some(target) = manifest.target_path_for(orphan) {
+                    writeln!(writer, "{target}")?;
+                }
+            }
+
+            if !orphans.is_empty() {
+                return Ok(exits::RUNTIME_ERROR);
+            }
+        }
+
+        ZoneQuery::Deps { target, reverse } => {
+            let Some(manifest) = find_manifest(Path::new("."), dirs) else {
+                warn_no_manifest(Path::new("."));
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            let target = qualified_target(&manifest, target);
+            if !manifest.is_zone(&target) {
+                warn_not_in_manifest(&target);
+                return Ok(exits::RUNTIME_ERROR);
+            }
+
+            if *reverse {
+                for dependent in manifest.reverse_deps_of(&target) {
+                    writeln!(writer, "{dependent}")?;
+                }
+            } else {
+                for dep in manifest.deps_of(&target) {
+                    writeln!(writer, "{dep}")?;
+                }
+            }
+        }
+
+        ZoneQuery::Describe(target) => {
+            let Some(manifest) = find_manifest(Path::new("."), dirs) else {
+                warn_no_manifest(Path::new("."));
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            let target = qualified_target(&manifest, target);
+            let Some(path) = manifest
+                .path_for(&target)
+                .filter(|_| manifest.is_zone(&target))
+            else {
+                warn_not_in_manifest(&target);
+                return Ok(exits::RUNTIME_ERROR);
+            };
+            let Some(zone) = manifest.zone(&target, path.is_dir()) else {
+                warn_not_in_manifest(&target);
+                return Ok(exits::RUNTIME_ERROR);
+            };
+
+            let mut description = ZoneDescription::of(&zone);
+            if zone.materialized {
+                description.beneath = manifest.materialized(&target);
+
+                let usages = fs::disk_usage::by_zone(&manifest, &zone.path);
+                let own = usages.get(&target).copied().unwrap_or_default();
+                let total = usages
+                    .values()
+                    .fold(Usage::default(), |total, usage| Usage {
+                        bytes: total.bytes + usage.bytes,
+                        blocks: total.blocks + usage.blocks,
+                    });
+                description.usage = Some((total, own));
+
+                let git: GitCache = std::iter::once(zone.path.clone()).collect();
+                description.git = git.zone_health(&zone.path);
+            }
+
+            write!(writer, "{description}")?;
+        }
+    }
+
+    Ok(exits::SUCCESS)
+}
+
+/// The target path given to a zone query, with the target prefix added if
+/// it was left off, the same as the listing lets it be.
+fn qualified_target(manifest: &ManifestInfo, target: &str) -> String {
+    if target.starts_with(&manifest.target_prefix) {
+        target.to_string()
+    } else {
+        format!("{}{}", manifest.target_prefix, target.trim_matches('/'))
+    }
+}
+
+/// Whether paths read from `--stdin` are shown relative to the directory
+/// they all share, which is printed once at the top instead. Absolute paths
+/// were asked for explicitly with `--absolute`, and streamed records are
+/// read by programs rather than people, so both keep the whole path.
+fn elides_common_prefix(options: &Options) -> bool {
+    !options.view.mode.is_streamed() && options.view.file_style.absolute == file_name::Absolute::Off
 }
 
 /// Create a Git cache populated with the arguments that are going to be
 /// listed before they’re actually listed, if the options demand it.
 fn git_options(options: &Options, args: &[&OsStr]) -> Option<GitCache> {
     if options.should_scan_for_git() {
-        Some(args.iter().map(PathBuf::from).collect())
+        let git: GitCache = args.iter().map(PathBuf::from).collect();
+        Some(
+            git.timed(options.dirs.timings.clone())
+                .batched(options.git_batch),
+        )
     } else {
         None
     }
@@ -256,15 +537,70 @@ impl Exa<'_> {
     /// # Errors
     ///
     /// Will return `Err` if printing to stderr fails.
-    pub fn run(mut self) -> io::Result<i32> {
+    pub fn run(&mut self) -> io::Result<i32> {
         debug!("Running with options: {:#?}", self.options);
 
+        if self.options.manifest_only {
+            return self.print_manifest_only();
+        }
+
+        if self.options.disk_usage {
+            return self.print_disk_usage();
+        }
+
+        if let Mode::Delimited(ref opts) = self.options.view.mode {
+            let writer =
+                delimited::Writer::new(opts, self.git.as_ref(), &self.theme, self.git_repos);
+            writer.write_header(&mut self.writer)?;
+        }
+
+        if let Some(prefix) = &self.options.view.file_style.common_prefix {
+            let mut bits = Vec::new();
+            escape(
+                prefix.display().to_string(),
+                &mut bits,
+                Style::default(),
+                Style::default(),
+                self.options.view.file_style.quote_style,
+            );
+            writeln!(&mut self.writer, "{}:\n", ANSIStrings(&bits))?;
+        }
+
+        let dir_options = Arc::clone(&self.options.dirs);
         let mut files = Vec::new();
         let mut dirs = Vec::new();
         let mut exit_status = 0;
 
-        for file_path in &self.input_paths {
-            let path = PathBuf::from(file_path);
+        // Expand glob patterns ourselves, rather than leaving it to the
+        // shell, so that ghosts can match as well as files on disk. Shells
+        // pass patterns that matched nothing through untouched.
+        let mut input_paths = Vec::new();
+        let arguments = self.input_paths.iter().map(|p| (PathBuf::from(p), false));
+        let globs = self
+            .options
+            .globs
+            .0
+            .iter()
+            .map(|p| (PathBuf::from(p), true));
+        for (path, from_glob_flag) in arguments.chain(globs) {
+            let unexpanded = is_glob_pattern(&path) && path.symlink_metadata().is_err();
+            if !from_glob_flag && !unexpanded {
+                input_paths.push(path);
+                continue;
+            }
+
+            let expanded = expand_glob(&path, &dir_options);
+            if expanded.is_empty() {
+                exit_status = 2;
+                if !warnings::write_json(Kind::NoMatches, path.display(), "no matches found") {
+                    writeln!(io::stderr(), "{path:?}: no matches found")?;
+                }
+            }
+            input_paths.extend(expanded);
+        }
+
+        for path in input_paths {
+            let file_path = &path;
             let f = File::from_args(
                 path.clone(),
                 None,
@@ -272,23 +608,47 @@ impl Exa<'_> {
                 self.options.view.deref_links,
                 self.options.view.total_size,
                 None,
+                &dir_options,
             );
 
             // We don't know whether this file exists, so we have to try to get
             // the metadata to verify.
             if let Err(e) = f.metadata() {
-                // Check if this is a valid ghost directory
-                if let Some((manifest_info, canonical_path)) = is_valid_ghost_dir(&path) {
+                // Check if this is a valid ghost directory. It gets listed as
+                // an entry of its own if directories are being listed as
+                // files, or if there's nothing beneath it to list.
+                if let Some((manifest_info, canonical_path)) =
+                    is_valid_ghost_dir(&path, &dir_options)
+                {
+                    if self.treat_dirs_as_files()
+                        || !manifest_info.has_entries_beneath(&canonical_path)
+                    {
+                        trace!("matched ghost entry: {:?}", path);
+                        files.push(File::new_ghost_argument(
+                            path,
+                            &manifest_info,
+                            &canonical_path,
+                            &dir_options,
+                        ));
+                        continue;
+                    }
                     trace!("matched ghost directory: {:?}", path);
-                    dirs.push(Dir::new_ghost(path, manifest_info, canonical_path));
+                    dirs.push(Dir::new_ghost(
+                        path,
+                        manifest_info,
+                        canonical_path,
+                        Arc::clone(&dir_options),
+                    ));
                     continue;
                 }
                 exit_status = 2;
-                writeln!(io::stderr(), "{file_path:?}: {e}")?;
+                if !warnings::write_json(Kind::Io, file_path.display(), e) {
+                    writeln!(io::stderr(), "{file_path:?}: {e}")?;
+                }
                 continue;
             }
 
-            if f.points_to_directory() && !self.options.dir_action.treat_dirs_as_files() {
+            if f.points_to_directory() && !self.treat_dirs_as_files() {
                 trace!("matching on new Dir");
                 dirs.push(f.to_dir());
             } else {
@@ -304,9 +664,277 @@ impl Exa<'_> {
         let is_only_dir = dirs.len() == 1 && no_files;
 
         self.options.filter.filter_argument_files(&mut files);
+        self.dedupe_tree_roots(&mut files);
+        let summary = ZoneSummary::of(&files);
+        let hints = self.materialize_hints(&files);
         self.print_files(None, files)?;
+        self.print_materialize_hints(&hints)?;
+        if !no_files {
+            self.print_zone_summary(summary)?;
+        }
 
-        self.print_dirs(dirs, no_files, is_only_dir, exit_status)
+        let exit_status = if self.options.view.mode.is_streamed() {
+            self.stream_dirs(dirs, exit_status)?
+        } else {
+            self.print_dirs(dirs, no_files, is_only_dir, exit_status)?
+        };
+
+        if let Some(journal) = self.journal.take() {
+            journal.finish()?;
+        }
+        Ok(exit_status)
+    }
+
+    /// Lists the zones beneath each argument, and the one it’s in, as the
+    /// rows of the table, each sized by the files in it that aren’t in a
+    /// zone inside it. Each directory is only walked once, however deeply
+    /// its zones nest.
+    fn print_disk_usage(&mut self) -> io::Result<i32> {
+        let dir_options = Arc::clone(&self.options.dirs);
+        let mut exit_status = exits::SUCCESS;
+        let mut files = Vec::new();
+
+        for arg in &self.input_paths {
+            let path = PathBuf::from(arg);
+            let canonical_path = match path.canonicalize() {
+                Ok(canonical_path) => canonical_path,
+                Err(e) => {
+                    if !warnings::write_json(Kind::Io, path.display(), &e) {
+                        writeln!(io::stderr(), "{path:?}: {e}")?;
+                    }
+                    exit_status = 2;
+                    continue;
+                }
+            };
+
+            let Some(manifest) = find_manifest(&path, &dir_options) else {
+                warn_no_manifest(&path);
+                exit_status = 2;
+                continue;
+            };
+
+            for (target, usage) in fs::disk_usage::by_zone(&manifest, &canonical_path) {
+                let Some(zone) = manifest.zone(&target, true) else {
+                    continue;
+                };
+
+                // Zones are named by where they are from the argument, or
+                // from the current directory when that’s the argument
+                let zone_path = match zone.path.strip_prefix(&canonical_path) {
+                    Ok(rel_path) if rel_path.as_os_str().is_empty() => path.clone(),
+                    Ok(rel_path) if path == Path::new(".") => rel_path.to_path_buf(),
+                    Ok(rel_path) => path.join(rel_path),
+                    Err(_) => zone.path.clone(),
+                };
+                files.push(File::new_zone_usage(zone_path, zone, usage, &dir_options));
+            }
+        }
+
+        self.options.filter.sort_files(&mut files);
+        self.print_files(None, files)?;
+        Ok(exit_status)
+    }
+
+    /// Lists the children of each argument from the manifest alone.
+    /// Arguments can be target paths, which are looked up in the src root
+    /// containing the current directory, or paths relative to the current
+    /// directory, which are looked up in whichever src root they’re in and
+    /// don’t have to exist. Only the manifest is read, along with whether
+    /// each child is on disk, so this works before anything is checked out.
+    fn print_manifest_only(&mut self) -> io::Result<i32> {
+        let dirs = &self.options.dirs;
+        let cwd_manifest = find_manifest(Path::new("."), dirs);
+        let cwd = env::current_dir()?.canonicalize()?;
+
+        let mut exit_status = exits::SUCCESS;
+        let show_headers = self.input_paths.len() > 1;
+        for (i, arg) in self.input_paths.iter().enumerate() {
+            let arg = arg.to_string_lossy();
+            let found = if arg.starts_with(&dirs.target_prefix) {
+                let Some(manifest) = &cwd_manifest else {
+                    warn_no_manifest(Path::new("."));
+                    return Ok(exits::RUNTIME_ERROR);
+                };
+                Some((Cow::Borrowed(manifest), arg.to_string()))
+            } else {
+                find_manifest_for_ghost(&path_clean::clean(cwd.join(&*arg)), dirs).and_then(
+                    |(manifest, path)| {
+                        let target = if path == manifest.src_root {
+                            manifest.target_prefix.clone()
+                        } else {
+                            manifest.target_path_for(&path)?
+                        };
+                        Some((Cow::Owned(manifest), target))
+                    },
+                )
+            };
+
+            let found = found.and_then(|(manifest, target)| {
+                Some((manifest.children(&target)?, manifest.path_for(&target)))
+            });
+            let Some((children, dir)) = found else {
+                if !warnings::write_json(Kind::NotInManifest, &arg, "not in the manifest") {
+                    writeln!(io::stderr(), "{arg}: not in the manifest")?;
+                }
+                exit_status = 2;
+                continue;
+            };
+
+            if show_headers {
+                if i > 0 {
+                    writeln!(self.writer)?;
+                }
+                writeln!(self.writer, "{arg}:")?;
+            }
+
+            let render = manifest_only::Render {
+                children: &children,
+                dir: dir.as_deref(),
+                colours: &self.theme,
+            };
+            render.render(&mut self.writer)?;
+        }
+
+        Ok(exit_status)
+    }
+
+    /// Lists everything, and then lists it all again every time something
+    /// changes, until interrupted or watching stops working.
+    fn watch(&mut self) -> io::Result<i32> {
+        let mut paths: Vec<PathBuf> = self.input_paths.iter().map(PathBuf::from).collect();
+        if paths.is_empty() {
+            paths.push(PathBuf::from("."));
+        }
+        let recursive = self.options.dir_action.recurse_options().is_some();
+        let watch = Watch::new(&paths, recursive, &self.options.dirs).map_err(io::Error::other)?;
+
+        loop {
+            if self.writer.is_terminal() {
+                write!(self.writer, "\x1b[2J\x1b[H")?;
+            }
+            let exit_status = self.run()?;
+            self.writer.flush()?;
+
+            if !watch.wait() {
+                return Ok(exit_status);
+            }
+
+            // Everything is cached for the length of a single listing, so
+            // start again from scratch
+            self.git = git_options(&self.options, &self.input_paths);
+            self.listed_dirs.clear();
+            fs::clear_directory_size_cache();
+        }
+    }
+
+    /// Writes every entry in the given directories as a line of JSON the
+    /// moment it comes out of the `Files` iterator, recursing if asked to.
+    /// Nothing is sorted, so only the child directories are held on to.
+    fn stream_dirs(&mut self, dir_files: Vec<Dir>, mut exit_status: i32) -> io::Result<i32> {
+        let recurse_opts = self.options.dir_action.recurse_options();
+        let recursing = recurse_opts.is_some();
+        let only_files = self.options.filter.flags.contains(&OnlyFiles);
+        let git_ignore = self.options.filter.git_ignore == GitIgnore::CheckAndIgnore;
+        let follow_links = self.options.view.follow_links;
+
+        for mut dir in dir_files {
+            if self.already_listed(&dir.path) || self.finished_earlier(&dir.path) {
+                continue;
+            }
+
+            let dir = match dir.read() {
+                Ok(dir) => dir,
+                Err(e) => {
+                    if e.kind() == ErrorKind::PermissionDenied {
+                        exit_status = exits::PERMISSION_DENIED;
+                    }
+                    if !warnings::write_json(Kind::of_io_error(&e), dir.path.display(), &e) {
+                        warnings::write_text(format_args!("{}: {e}", dir.path.display()));
+                    }
+                    continue;
+                }
+            };
+
+            let depth = dir
+                .path
+                .components()
+                .filter(|&c| c != Component::CurDir)
+                .count()
+                + 1;
+            let descend = recurse_opts.is_some_and(|r| !r.is_too_deep(depth));
+
+            let delimited = match self.options.view.mode {
+                Mode::Delimited(ref opts) => Some(delimited::Writer::new(
+                    opts,
+                    self.git.as_ref(),
+                    &self.theme,
+                    self.git_repos,
+                )),
+                _ => None,
+            };
+
+            let mut child_dirs = Vec::new();
+            let mut ghost_limit = self.options.filter.ghost_limit(dir);
+            let mut files = dir.files(
+                self.options.filter.dot_filter,
+                self.git.as_ref(),
+                git_ignore,
+                self.options.view.deref_links,
+                self.options.view.total_size,
+                self.options.filter.ghosts,
+            );
+            for file in files.by_ref() {
+                if !self.options.filter.shows_child_file(recursing, &file) {
+                    continue;
+                }
+
+                // Nothing gets sorted when streaming, so the ghosts that are
+                // kept are just the first ones read
+                if !ghost_limit.keeps(&file) {
+                    continue;
+                }
+
+                let is_dir = if follow_links {
+                    file.points_to_directory()
+                } else {
+                    file.is_directory()
+                };
+
+                if !(recursing && only_files && file.is_directory()) {
+                    match (&delimited, &self.options.view.mode) {
+                        (Some(writer), _) => writer.write_entry(&mut self.writer, &file)?,
+                        (None, Mode::Print0(opts)) => {
+                            print0::write_entry(&mut self.writer, &file, *opts)?;
+                        }
+                        (None, Mode::Template(template)) => {
+                            template::write_entry(&mut self.writer, &file, template)?;
+                        }
+                        (None, _) => json_lines::write_entry(&mut self.writer, &file)?,
+                    }
+                }
+                let stops = recurse_opts.is_some_and(|r| r.stops_at(&file));
+                if descend
+                    && is_dir
+                    && !file.is_all_all
+                    && !stops
+                    && (!follow_links || !cuts_link_cycle(&file))
+                {
+                    // Ghosts have no directory on disk to read
+                    if !file.is_ghost {
+                        child_dirs.push(file.to_dir());
+                    } else if recurse_opts.is_some_and(|r| r.expands_ghost(dir.ghost_level())) {
+                        child_dirs.extend(file.to_ghost_dir());
+                    }
+                }
+            }
+
+            warn_case_collisions(files.case_collisions());
+
+            exit_status = self.stream_dirs(child_dirs, exit_status)?;
+            self.finish_dir(&dir.path)?;
+        }
+
+        Ok(exit_status)
     }
 
     fn print_dirs(
@@ -324,20 +952,27 @@ impl Exa<'_> {
         let mut denied_dirs = vec![];
 
         for mut dir in dir_files {
+            if self.already_listed(&dir.path) || self.finished_earlier(&dir.path) {
+                continue;
+            }
+
             let dir = match dir.read() {
                 Ok(dir) => dir,
                 Err(e) => {
+                    if warnings::write_json(Kind::of_io_error(&e), dir.path.display(), &e) {
+                        continue;
+                    }
                     if e.kind() == ErrorKind::PermissionDenied {
-                        eprintln!(
+                        warnings::write_text(format_args!(
                             "Permission denied: {} - code: {}",
                             dir.path.display(),
                             exits::PERMISSION_DENIED
-                        );
+                        ));
                         denied_dirs.push(dir.path);
                         continue;
                     }
 
-                    eprintln!("{}: {}", dir.path.display(), e);
+                    warnings::write_text(format_args!("{}: {e}", dir.path.display()));
                     continue;
                 }
             };
@@ -353,7 +988,12 @@ impl Exa<'_> {
             if !is_only_dir {
                 let mut bits = Vec::new();
                 escape(
-                    dir.path.display().to_string(),
+                    common_prefix::strip(
+                        &dir.path,
+                        self.options.view.file_style.common_prefix.as_deref(),
+                    )
+                    .display()
+                    .to_string(),
                     &mut bits,
                     Style::default(),
                     Style::default(),
@@ -362,23 +1002,39 @@ impl Exa<'_> {
                 writeln!(&mut self.writer, "{}:", ANSIStrings(&bits))?;
             }
 
+            if self.streams_rows() {
+                let child_dirs = self.print_rows_in_chunks(dir)?;
+                self.print_free_space(&dir.path)?;
+                self.print_dirs(child_dirs, false, false, exit_status)?;
+                self.finish_dir(&dir.path)?;
+                continue;
+            }
+
             let mut children = Vec::new();
             let git_ignore = self.options.filter.git_ignore == GitIgnore::CheckAndIgnore;
-            for file in dir.files(
+            let mut files = dir.files(
                 self.options.filter.dot_filter,
                 self.git.as_ref(),
                 git_ignore,
                 self.options.view.deref_links,
                 self.options.view.total_size,
-                self.options.filter.no_ghosts,
-            ) {
+                self.options.filter.ghosts,
+            );
+            for file in files.by_ref() {
                 children.push(file);
             }
+            warn_case_collisions(files.case_collisions());
+            fs::prefetch::metadata(&children);
             let recursing = self.options.dir_action.recurse_options().is_some();
             self.options
                 .filter
                 .filter_child_files(recursing, &mut children);
             self.options.filter.sort_files(&mut children);
+            let mut ghost_limit = self.options.filter.ghost_limit(dir);
+            ghost_limit.apply(&mut children);
+            let hidden_ghosts = ghost_limit.hidden();
+            let summary = ZoneSummary::of(&children);
+            let hints = self.materialize_hints(&children);
 
             if let Some(recurse_opts) = self.options.dir_action.recurse_options() {
                 let depth = dir
@@ -387,30 +1043,29 @@ impl Exa<'_> {
                     .filter(|&c| c != Component::CurDir)
                     .count()
                     + 1;
-                let follow_links = self.options.view.follow_links;
                 if !recurse_opts.tree && !recurse_opts.is_too_deep(depth) {
-                    let child_dirs = children
-                        .iter()
-                        .filter(|f| {
-                            (if follow_links {
-                                f.points_to_directory()
-                            } else {
-                                f.is_directory()
-                            }) && !f.is_all_all
-                        })
-                        .map(fs::File::to_dir)
-                        .collect::<Vec<Dir>>();
+                    let child_dirs = self.child_dirs(dir, &children);
 
                     self.print_files(Some(dir), children)?;
+                    self.print_hidden_ghosts(hidden_ghosts)?;
+                    self.print_materialize_hints(&hints)?;
+                    self.print_zone_summary(summary)?;
+                    self.print_free_space(&dir.path)?;
                     match self.print_dirs(child_dirs, false, false, exit_status) {
                         Ok(_) => (),
                         Err(e) => return Err(e),
                     }
+                    self.finish_dir(&dir.path)?;
                     continue;
                 }
             }
 
             self.print_files(Some(dir), children)?;
+            self.print_hidden_ghosts(hidden_ghosts)?;
+            self.print_materialize_hints(&hints)?;
+            self.print_zone_summary(summary)?;
+            self.print_free_space(&dir.path)?;
+            self.finish_dir(&dir.path)?;
         }
 
         if !denied_dirs.is_empty() {
@@ -426,7 +1081,272 @@ impl Exa<'_> {
         Ok(exit_status)
     }
 
-    /// Prints the list of files using whichever view is selected.
+    /// Whether directory arguments are printed as files rather than listed.
+    /// A tree is drawn from its root, but streamed records have no tree to
+    /// draw, so they list the directories the same as `--recurse` would.
+    fn treat_dirs_as_files(&self) -> bool {
+        match self.options.dir_action {
+            DirAction::Recurse(_) if self.options.view.mode.is_streamed() => false,
+            action => action.treat_dirs_as_files(),
+        }
+    }
+
+    /// Whether `--dedupe` was given and the directory has already been
+    /// listed. Otherwise, remembers it as listed.
+    fn already_listed(&mut self, path: &Path) -> bool {
+        match self.options.dir_action.recurse_options() {
+            Some(recurse_opts) if recurse_opts.dedupe => {
+                !self.listed_dirs.insert(canonical_or_same(path))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether an earlier run with the same `--journal` already listed the
+    /// directory and everything beneath it.
+    fn finished_earlier(&self, path: &Path) -> bool {
+        self.journal
+            .as_ref()
+            .is_some_and(|journal| journal.is_complete(&canonical_or_same(path)))
+    }
+
+    /// Records in the `--journal` that the directory and everything beneath
+    /// it have been listed, once that listing is safely written out.
+    fn finish_dir(&mut self, path: &Path) -> io::Result<()> {
+        let Some(journal) = &mut self.journal else {
+            return Ok(());
+        };
+
+        self.writer.flush()?;
+        journal.complete(&canonical_or_same(path))
+    }
+
+    /// With `--tree --dedupe`, removes the directory arguments that would be
+    /// listed as part of another argument’s tree anyway, as well as repeats.
+    fn dedupe_tree_roots(&self, files: &mut Vec<File<'_>>) {
+        let Some(recurse_opts) = self.options.dir_action.recurse_options() else {
+            return;
+        };
+        if !recurse_opts.tree || !recurse_opts.dedupe {
+            return;
+        }
+
+        let roots = files
+            .iter()
+            .filter(|f| f.points_to_directory())
+            .map(|f| canonical_or_same(&f.path))
+            .collect::<HashSet<_>>();
+
+        // A tree lists everything under its root, unless it was cut short
+        // by --level before reaching this directory
+        let mut seen = HashSet::new();
+        files.retain(|f| {
+            if !f.points_to_directory() {
+                return true;
+            }
+
+            let canonical = canonical_or_same(&f.path);
+            let in_tree = canonical
+                .ancestors()
+                .enumerate()
+                .skip(1)
+                .any(|(depth, ancestor)| {
+                    roots.contains(ancestor) && !recurse_opts.is_too_deep(depth)
+                });
+            !in_tree && seen.insert(canonical)
+        });
+    }
+
+    /// Tells the user that some of the ghosts at the src root were left out
+    /// of the listing, and how to see them.
+    fn print_hidden_ghosts(&mut self, hidden: usize) -> io::Result<()> {
+        if hidden == 0 {
+            return Ok(());
+        }
+
+        let plural = if hidden == 1 { "area" } else { "areas" };
+        writeln!(
+            &mut self.writer,
+            "… and {hidden} more unmaterialized {plural} (use --all-ghosts)"
+        )
+    }
+
+    /// Works out the commands that would materialize the ghost zones in a
+    /// listing, if the user asked for them.
+    fn materialize_hints(&self, files: &[File<'_>]) -> Vec<String> {
+        match self.options.view.materialize {
+            Some(ref hint) => hint.commands(files),
+            None => Vec::new(),
+        }
+    }
+
+    /// Prints the commands from `materialize_hints`, under a heading.
+    fn print_materialize_hints(&mut self, hints: &[String]) -> io::Result<()> {
+        if hints.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(&mut self.writer, "To materialize:")?;
+        for hint in hints {
+            writeln!(&mut self.writer, "  {hint}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints how much of a listing is made up of zones, and how many of
+    /// those are ghosts, if the user asked for it.
+    fn print_zone_summary(&mut self, summary: ZoneSummary) -> io::Result<()> {
+        if self.options.view.zone_summary {
+            writeln!(&mut self.writer, "{summary}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints how much space is left on the disk the directory is on, if the
+    /// user asked for it. Ghosts aren’t on any disk, so they get nothing.
+    fn print_free_space(&mut self, path: &Path) -> io::Result<()> {
+        if !self.options.view.free_space {
+            return Ok(());
+        }
+
+        match FreeSpace::of(path) {
+            Some(space) => writeln!(&mut self.writer, "{space}"),
+            None => Ok(()),
+        }
+    }
+
+    /// The directories among a directory’s children that get recursed into
+    /// and listed after it, if the listing is recursive but not a tree.
+    fn child_dirs(&self, dir: &Dir, children: &[File<'_>]) -> Vec<Dir> {
+        let Some(recurse_opts) = self.options.dir_action.recurse_options() else {
+            return Vec::new();
+        };
+
+        let depth = dir
+            .path
+            .components()
+            .filter(|&c| c != Component::CurDir)
+            .count()
+            + 1;
+        if recurse_opts.tree || recurse_opts.is_too_deep(depth) {
+            return Vec::new();
+        }
+
+        let follow_links = self.options.view.follow_links;
+        children
+            .iter()
+            .filter(|f| {
+                (if follow_links {
+                    f.points_to_directory()
+                } else {
+                    f.is_directory()
+                }) && !f.is_all_all
+                    && !recurse_opts.stops_at(f)
+                    && (!follow_links || !cuts_link_cycle(f))
+            })
+            .filter_map(|f| {
+                if !f.is_ghost {
+                    Some(f.to_dir())
+                } else if recurse_opts.expands_ghost(dir.ghost_level()) {
+                    f.to_ghost_dir()
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the long view’s rows get printed in chunks as they’re read,
+    /// which trees can’t do, as they have to see everything beneath them.
+    fn streams_rows(&self) -> bool {
+        let Mode::Details(ref opts) = self.options.view.mode else {
+            return false;
+        };
+        opts.stream
+            && opts.table.is_some()
+            && !self
+                .options
+                .dir_action
+                .recurse_options()
+                .is_some_and(|r| r.tree)
+    }
+
+    /// Prints a directory’s entries in the long view a chunk at a time, as
+    /// they’re read, rather than waiting for all of them. Each chunk is
+    /// sorted and lined up on its own, so the columns can change width from
+    /// one chunk to the next. Returns the directories to list after it.
+    fn print_rows_in_chunks(&mut self, dir: &Dir) -> io::Result<Vec<Dir>> {
+        let Mode::Details(ref opts) = self.options.view.mode else {
+            return Ok(Vec::new());
+        };
+        let recursing = self.options.dir_action.recurse_options().is_some();
+        let only_files = self.options.filter.flags.contains(&OnlyFiles);
+        let git_ignoring = self.options.filter.git_ignore == GitIgnore::CheckAndIgnore;
+
+        let mut summary = ZoneSummary::default();
+        let mut hints = Vec::new();
+        let mut child_dirs = Vec::new();
+        let mut header = opts.header;
+        let mut ghost_limit = self.options.filter.ghost_limit(dir);
+
+        let mut files = dir.files(
+            self.options.filter.dot_filter,
+            self.git.as_ref(),
+            git_ignoring,
+            self.options.view.deref_links,
+            self.options.view.total_size,
+            self.options.filter.ghosts,
+        );
+        loop {
+            let mut chunk: Vec<_> = files.by_ref().take(details::STREAM_CHUNK_SIZE).collect();
+            if chunk.is_empty() {
+                break;
+            }
+
+            fs::prefetch::metadata(&chunk);
+            self.options
+                .filter
+                .filter_child_files(recursing, &mut chunk);
+            self.options.filter.sort_files(&mut chunk);
+            ghost_limit.apply(&mut chunk);
+            summary += ZoneSummary::of(&chunk);
+            hints.extend(self.materialize_hints(&chunk));
+            child_dirs.extend(self.child_dirs(dir, &chunk));
+            if recursing && only_files {
+                chunk.retain(|f| !f.is_directory());
+            }
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let r = details::Render {
+                dir: Some(dir),
+                files: chunk,
+                theme: &self.theme,
+                file_style: &self.options.view.file_style,
+                opts,
+                recurse: self.options.dir_action.recurse_options(),
+                filter: &self.options.filter,
+                git_ignoring,
+                git: self.git.as_ref(),
+                git_repos: self.git_repos,
+            };
+            r.render_chunk(&mut self.writer, header)?;
+            self.writer.flush()?;
+            header = false;
+        }
+
+        warn_case_collisions(files.case_collisions());
+        self.print_hidden_ghosts(ghost_limit.hidden())?;
+        self.print_materialize_hints(&hints)?;
+        self.print_zone_summary(summary)?;
+        Ok(child_dirs)
+    }
+
+    /// Prints the list of files using whichever view is selected, with the
+    /// ghosts under a heading of their own if they’re to be split out.
     fn print_files(&mut self, dir: Option<&Dir>, mut files: Vec<File<'_>>) -> io::Result<()> {
         if files.is_empty() {
             return Ok(());
@@ -439,6 +1359,58 @@ impl Exa<'_> {
                 .filter(|f| !f.is_directory())
                 .collect::<Vec<_>>();
         }
+
+        if !self.splits_ghosts() {
+            return self.render_files(dir, files);
+        }
+
+        let (ghosts, files): (Vec<_>, Vec<_>) = files.into_iter().partition(|f| f.is_ghost);
+        self.render_files(dir, files)?;
+        if !ghosts.is_empty() {
+            let heading = self
+                .theme
+                .ui
+                .filekinds
+                .unwrap_or_default()
+                .ghost()
+                .paint("not materialized:");
+            writeln!(&mut self.writer, "{heading}")?;
+            self.render_files(dir, ghosts)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether ghosts get a section of their own. Trees draw ghosts where
+    /// they belong among their siblings, and machine output has no sections,
+    /// so those are left alone.
+    fn splits_ghosts(&self) -> bool {
+        let is_tree = matches!(self.options.view.mode, Mode::Details(_))
+            && self
+                .options
+                .dir_action
+                .recurse_options()
+                .is_some_and(|r| r.tree);
+        self.options.view.split_ghosts && !is_tree && !self.options.view.mode.is_streamed()
+    }
+
+    /// Renders files using whichever view is selected, timing how long
+    /// that took.
+    fn render_files(&mut self, dir: Option<&Dir>, files: Vec<File<'_>>) -> io::Result<()> {
+        let timings = self.options.dirs.timings.clone();
+        timings::time_in(
+            timings.as_deref(),
+            Phase::Render,
+            dir.map(|d| d.path.as_path()),
+            || self.render_view(dir, files),
+        )
+    }
+
+    /// Renders files using whichever view is selected.
+    fn render_view(&mut self, dir: Option<&Dir>, files: Vec<File<'_>>) -> io::Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
         let theme = &self.theme;
         let View {
             ref mode,
@@ -473,6 +1445,27 @@ impl Exa<'_> {
                 r.render(&mut self.writer)
             }
 
+            (Mode::JsonLines, _) => {
+                let r = json_lines::Render { files };
+                r.render(&mut self.writer)
+            }
+
+            (Mode::Delimited(ref opts), _) => {
+                let writer = delimited::Writer::new(opts, self.git.as_ref(), theme, self.git_repos);
+                let r = delimited::Render { files, writer };
+                r.render(&mut self.writer)
+            }
+
+            (Mode::Print0(opts), _) => {
+                let r = print0::Render { files, opts: *opts };
+                r.render(&mut self.writer)
+            }
+
+            (Mode::Template(ref template), _) => {
+                let r = template::Render { files, template };
+                r.render(&mut self.writer)
+            }
+
             (Mode::Lines, _) => {
                 let filter = &self.options.filter;
                 let r = lines::Render {
@@ -557,6 +1550,64 @@ impl Exa<'_> {
     }
 }
 
+/// Warns about manifest entries that were not shown as ghosts because a
+/// directory on disk has the same name in a different case.
+fn warn_case_collisions(collisions: &[CaseCollision]) {
+    for collision in collisions {
+        let message = format!(
+            "{} in the manifest only differs in case",
+            collision.manifest_path
+        );
+        if warnings::write_json(Kind::CaseCollision, collision.disk_path.display(), message) {
+            continue;
+        }
+
+        warnings::write_text(format_args!(
+            "{} in the manifest only differs in case from {}",
+            collision.manifest_path,
+            collision.disk_path.display()
+        ));
+    }
+}
+
+/// Says that there’s no manifest above the given path, as JSON if that’s
+/// how warnings are being written.
+fn warn_no_manifest(path: &Path) {
+    if warnings::write_json(Kind::Manifest, path.display(), "no manifest found above it") {
+        return;
+    }
+
+    if path == Path::new(".") {
+        eprintln!("wls: no manifest found above the current directory");
+    } else {
+        eprintln!("wls: no manifest found above {}", path.display());
+    }
+}
+
+/// Says that a target asked about isn’t in the manifest, as JSON if that’s
+/// how warnings are being written.
+fn warn_not_in_manifest(target: &str) {
+    if !warnings::write_json(Kind::NotInManifest, target, "not in the manifest") {
+        eprintln!("wls: {target} is not in the manifest");
+    }
+}
+
+/// Whether recursing should stop at this symlink, because it leads back to
+/// a directory it’s already inside, warning that it did if so.
+fn cuts_link_cycle(file: &File<'_>) -> bool {
+    let Some(ancestor) = file.linked_ancestor() else {
+        return false;
+    };
+    warnings::link_cycle(&file.path, &ancestor);
+    true
+}
+
+/// The canonical form of a path, for telling whether two paths are the same
+/// directory. Ghosts don’t exist on disk, so they stay as they are.
+fn canonical_or_same(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
 mod exits {
 
     /// Exit code for when exa runs OK.
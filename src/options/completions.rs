@@ -0,0 +1,249 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Printing shell completion scripts with `--completions`.
+//!
+//! The scripts are built from the list of flags wls actually accepts, so
+//! they can’t fall behind it. When a path being completed contains `//`,
+//! they call back into `wls --zone-complete` to offer target paths from the
+//! manifest, including ones for zones that aren’t on disk, and fall back to
+//! completing files otherwise.
+
+use std::ffi::OsString;
+use std::fmt;
+
+use crate::options::parser::{Arg, MatchedFlags, TakesValue};
+use crate::options::{flags, OptionsError};
+
+/// A shell that wls can print a completion script for.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// The completion script to print for a shell.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Completions(pub Shell);
+
+impl Completions {
+    /// Determines which shell to print a completion script for, if any.
+    /// Like `--help`, this takes over the whole invocation.
+    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Option<Self>, OptionsError> {
+        let Some(word) = matches.get(&flags::COMPLETIONS)? else {
+            return Ok(None);
+        };
+
+        let shell = match word.to_str() {
+            Some("bash") => Shell::Bash,
+            Some("zsh") => Shell::Zsh,
+            Some("fish") => Shell::Fish,
+            _ => {
+                return Err(OptionsError::BadArgument(
+                    &flags::COMPLETIONS,
+                    OsString::from(word),
+                ))
+            }
+        };
+        Ok(Some(Self(shell)))
+    }
+}
+
+/// The flags whose values are target paths or other paths, rather than one
+/// of a fixed list of words.
+fn takes_path(arg: &Arg) -> bool {
+    matches!(arg.takes_value, TakesValue::Necessary(None))
+}
+
+/// The words a flag’s value can be, if there’s a fixed list of them.
+fn values(arg: &Arg) -> Option<&'static [&'static str]> {
+    match arg.takes_value {
+        TakesValue::Necessary(values) | TakesValue::Optional(values, _) => values,
+        TakesValue::Forbidden => None,
+    }
+}
+
+fn write_bash(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+        f,
+        "# bash completion for wls, printed by `wls --completions bash`"
+    )?;
+    writeln!(f, "_wls_paths() {{")?;
+    writeln!(f, "    case \"$1\" in")?;
+    writeln!(
+        f,
+        "        *//*) COMPREPLY=($(wls --zone-complete \"$1\" 2>/dev/null)) ;;"
+    )?;
+    writeln!(f, "        *) COMPREPLY=($(compgen -f -- \"$1\")) ;;")?;
+    writeln!(f, "    esac")?;
+    writeln!(f, "}}")?;
+    writeln!(f)?;
+    writeln!(f, "_wls() {{")?;
+    writeln!(f, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+    writeln!(f, "    local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+    writeln!(f)?;
+    writeln!(f, "    case \"$prev\" in")?;
+    for arg in flags::ALL_ARGS.0 {
+        match arg.takes_value {
+            TakesValue::Necessary(Some(values)) => {
+                let words = values.join(" ");
+                writeln!(
+                    f,
+                    "        --{}) COMPREPLY=($(compgen -W \"{words}\" -- \"$cur\")); return ;;",
+                    arg.long
+                )?;
+            }
+            TakesValue::Necessary(None) => {
+                writeln!(f, "        --{}) _wls_paths \"$cur\"; return ;;", arg.long)?;
+            }
+            _ => {}
+        }
+    }
+    writeln!(f, "    esac")?;
+    writeln!(f)?;
+    writeln!(f, "    case \"$cur\" in")?;
+    let longs = flags::ALL_ARGS
+        .0
+        .iter()
+        .map(|arg| format!("--{}", arg.long))
+        .collect::<Vec<_>>();
+    writeln!(
+        f,
+        "        -*) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;",
+        longs.join(" ")
+    )?;
+    writeln!(f, "        *) _wls_paths \"$cur\" ;;")?;
+    writeln!(f, "    esac")?;
+    writeln!(f, "}}")?;
+    writeln!(f)?;
+    writeln!(f, "complete -o filenames -o bashdefault -F _wls wls")
+}
+
+fn write_zsh(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "#compdef wls")?;
+    writeln!(
+        f,
+        "# zsh completion for wls, printed by `wls --completions zsh`"
+    )?;
+    writeln!(f)?;
+    writeln!(f, "_wls_paths() {{")?;
+    writeln!(f, "    if [[ $PREFIX == *//* ]]; then")?;
+    writeln!(f, "        local -a targets")?;
+    writeln!(
+        f,
+        "        targets=(${{(f)\"$(wls --zone-complete \"$PREFIX\" 2>/dev/null)\"}})"
+    )?;
+    writeln!(f, "        compadd -Q -U -- $targets")?;
+    writeln!(f, "    else")?;
+    writeln!(f, "        _files")?;
+    writeln!(f, "    fi")?;
+    writeln!(f, "}}")?;
+    writeln!(f)?;
+    writeln!(f, "_wls() {{")?;
+    writeln!(f, "    _arguments -s \\")?;
+    for arg in flags::ALL_ARGS.0 {
+        let action = match (values(arg), takes_path(arg)) {
+            (Some(values), _) => format!(":value:({})", values.join(" ")),
+            (None, true) => ":value:_wls_paths".to_string(),
+            (None, false) => String::new(),
+        };
+        let separator = match arg.takes_value {
+            TakesValue::Optional(..) => "=-",
+            _ => "",
+        };
+
+        writeln!(f, "        '--{}{separator}{action}' \\", arg.long)?;
+        if let Some(short) = arg.short {
+            writeln!(f, "        '-{}{action}' \\", char::from(short))?;
+        }
+    }
+    writeln!(f, "        '*:file:_wls_paths'")?;
+    writeln!(f, "}}")?;
+    writeln!(f)?;
+    writeln!(f, "_wls \"$@\"")
+}
+
+fn write_fish(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+        f,
+        "# fish completion for wls, printed by `wls --completions fish`"
+    )?;
+    writeln!(f, "function __wls_paths")?;
+    writeln!(f, "    set -l token (commandline -ct)")?;
+    writeln!(f, "    if string match -q -- '*//*' $token")?;
+    writeln!(f, "        wls --zone-complete $token 2>/dev/null")?;
+    writeln!(f, "    else")?;
+    writeln!(f, "        __fish_complete_path $token")?;
+    writeln!(f, "    end")?;
+    writeln!(f, "end")?;
+    writeln!(f)?;
+    writeln!(f, "complete -c wls -f -a '(__wls_paths)'")?;
+    for arg in flags::ALL_ARGS.0 {
+        write!(f, "complete -c wls")?;
+        if let Some(short) = arg.short {
+            write!(f, " -s '{}'", char::from(short))?;
+        }
+        write!(f, " -l {}", arg.long)?;
+
+        match (arg.takes_value, values(arg)) {
+            (TakesValue::Necessary(_), Some(values)) => write!(f, " -x -a '{}'", values.join(" "))?,
+            (TakesValue::Necessary(_), None) => write!(f, " -x -a '(__wls_paths)'")?,
+            (_, Some(values)) => write!(f, " -a '{}'", values.join(" "))?,
+            (_, None) => {}
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Completions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Shell::Bash => write_bash(f),
+            Shell::Zsh => write_zsh(f),
+            Shell::Fish => write_fish(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::{Options, OptionsResult};
+    use std::ffi::OsStr;
+
+    #[test]
+    fn completions() {
+        let args = vec![OsStr::new("--completions"), OsStr::new("zsh")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::Completions(Completions(Shell::Zsh))
+        ));
+    }
+
+    #[test]
+    fn unknown_shell() {
+        let args = vec![OsStr::new("--completions=tcsh")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::InvalidOptions(OptionsError::BadArgument(_, ref word)) if word == "tcsh"
+        ));
+    }
+
+    #[test]
+    fn scripts_complete_every_flag_and_target() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = Completions(shell).to_string();
+            assert!(script.contains("wls --zone-complete"), "{shell:?}");
+            for arg in flags::ALL_ARGS.0 {
+                assert!(
+                    script.contains(arg.long),
+                    "{shell:?} is missing --{}",
+                    arg.long
+                );
+            }
+        }
+    }
+}
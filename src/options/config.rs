@@ -6,7 +6,7 @@
 // SPDX-License-Identifier: MIT
 use crate::theme::ThemeFileType as FileType;
 use crate::theme::{
-    FileKinds, FileNameStyle, Git, GitRepo, IconStyle, Links, Permissions, SELinuxContext,
+    FileKinds, FileNameStyle, Git, GitRepo, IconStyle, Links, Palette, Permissions, SELinuxContext,
     SecurityContext, Size, UiStyles, Users,
 };
 use nu_ansi_term::{Color, Style};
@@ -113,7 +113,7 @@ fn color_from_str(s: &str) -> Option<Color> {
 }
 
 #[rustfmt::skip]
-fn deserialize_color<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
+pub fn deserialize_color<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
 where D: Deserializer<'de> {
     Ok(color_from_str(&String::deserialize(deserializer)?))
 }
@@ -495,6 +495,7 @@ impl FromOverride<SELinuxContextOverride> for SELinuxContext {
 pub struct SecurityContextOverride {
     pub none:    Option<StyleOverride>, // Sn
     pub selinux: Option<SELinuxContextOverride>,
+    pub smack:   Option<StyleOverride>, // Sm
 }
 
 impl FromOverride<SecurityContextOverride> for SecurityContext {
@@ -502,6 +503,7 @@ impl FromOverride<SecurityContextOverride> for SecurityContext {
         SecurityContext {
             none: FromOverride::from(value.none, default.none),
             selinux: FromOverride::from(value.selinux, default.selinux),
+            smack: FromOverride::from(value.smack, default.smack),
         }
     }
 }
@@ -544,6 +546,7 @@ impl FromOverride<FileTypeOverride> for FileType {
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct UiStylesOverride {
     pub colourful: Option<bool>,
+    pub palette:   Option<Palette>,
 
     pub filekinds:        Option<FileKindsOverride>,
     pub perms:            Option<PermissionsOverride>,
@@ -570,6 +573,12 @@ pub struct UiStylesOverride {
 
     pub zone_sigil:           Option<StyleOverride>,  // zs
     pub zone_sigil_ghost:     Option<StyleOverride>,  // zg
+    pub zone_library:         Option<StyleOverride>,  // zl
+    pub zone_app:             Option<StyleOverride>,  // za
+    pub zone_tool:            Option<StyleOverride>,  // zt
+
+    pub filesystem_overlay:   Option<StyleOverride>,  // Fo
+    pub filesystem_network:   Option<StyleOverride>,  // Fn
 
     pub filenames: Option<HashMap<String, FileNameStyleOverride>>,
     pub extensions: Option<HashMap<String, FileNameStyleOverride>>,
@@ -579,6 +588,7 @@ impl FromOverride<UiStylesOverride> for UiStyles {
     fn from(value: UiStylesOverride, default: Self) -> Self {
         UiStyles {
             colourful: value.colourful,
+            palette: value.palette.or(default.palette),
 
             filekinds: FromOverride::from(value.filekinds, default.filekinds),
             perms: FromOverride::from(value.perms, default.perms),
@@ -608,6 +618,18 @@ impl FromOverride<UiStylesOverride> for UiStyles {
 
             zone_sigil: FromOverride::from(value.zone_sigil, default.zone_sigil),
             zone_sigil_ghost: FromOverride::from(value.zone_sigil_ghost, default.zone_sigil_ghost),
+            zone_library: FromOverride::from(value.zone_library, default.zone_library),
+            zone_app: FromOverride::from(value.zone_app, default.zone_app),
+            zone_tool: FromOverride::from(value.zone_tool, default.zone_tool),
+
+            filesystem_overlay: FromOverride::from(
+                value.filesystem_overlay,
+                default.filesystem_overlay,
+            ),
+            filesystem_network: FromOverride::from(
+                value.filesystem_network,
+                default.filesystem_network,
+            ),
 
             filenames: FromOverride::from(value.filenames, default.filenames),
             extensions: FromOverride::from(value.extensions, default.extensions),
@@ -620,12 +642,19 @@ impl ThemeConfig {
         ThemeConfig { location: path }
     }
     #[must_use]
-    pub fn to_theme(&self) -> Option<UiStyles> {
+    pub fn to_theme(&self, palette: Option<Palette>) -> Option<UiStyles> {
         let ui_styles_override: Option<UiStylesOverride> = {
             let file = std::fs::File::open(&self.location).ok()?;
             serde_norway::from_reader(&file).ok()
         };
-        FromOverride::from(ui_styles_override, Some(UiStyles::default()))
+
+        // The palette recolours the defaults, so that anything the theme
+        // file sets itself still wins.
+        let mut default = UiStyles::default();
+        if let Some(palette) = palette.or_else(|| ui_styles_override.as_ref()?.palette) {
+            default.set_palette(palette);
+        }
+        FromOverride::from(ui_styles_override, Some(default))
     }
 }
 
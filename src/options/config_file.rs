@@ -0,0 +1,189 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The config file, `config.toml` next to `theme.yml`, which sets defaults
+//! that would otherwise need an ever-growing shell alias.
+//!
+//! Each setting stands in for one of the environment variables, so it gets
+//! checked the same way the variable would be. Anything given on the command
+//! line or set in the environment wins over it.
+//!
+//! ```toml
+//! icons = true
+//! ghosts = false
+//! manifest-path = ".meta/manifest.json"
+//! zone-colors = true
+//! materialize-command = "wsync add {target}"
+//! ```
+
+use std::ffi::OsString;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::options::{OptionsError, Vars};
+
+/// The settings in the config file, all of which can be left out.
+#[derive(PartialEq, Eq, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    /// Whether to show icons when writing to a terminal, like `--icons`,
+    /// which can only be turned on.
+    icons: Option<bool>,
+
+    /// Whether to show ghosts, like `--ghosts` or `--no-ghosts`.
+    ghosts: Option<bool>,
+
+    /// How many ghosts to show when listing the src root itself, with `0`
+    /// showing all of them, like `--all-ghosts`.
+    ghost_limit: Option<usize>,
+
+    /// Where the manifest is, like `--manifest`.
+    manifest_path: Option<PathBuf>,
+
+    /// What target paths start with, like `--target-prefix`.
+    target_prefix: Option<String>,
+
+    /// Whether directories have to match the manifest’s case, like
+    /// `--zone-case`.
+    zone_case: Option<String>,
+
+    /// Whether to colour zones by their target paths, like `--zone-colors`,
+    /// which can only be turned on.
+    zone_colors: Option<bool>,
+
+    /// The command `--materialize` prints for each ghost zone.
+    materialize_command: Option<String>,
+
+    /// Where `--hyperlink` links zones to, like `--zone-url`.
+    zone_url: Option<String>,
+}
+
+impl ConfigFile {
+    /// Reads the config file, from the config directory if one was set for
+    /// the theme file too, or from `~/.config/wls` otherwise. Having no
+    /// config file is the same as having an empty one.
+    pub fn deduce<V: Vars>(vars: &V) -> Result<Self, OptionsError> {
+        let Some(path) = vars.config_dir().map(|dir| dir.join("config.toml")) else {
+            return Ok(Self::default());
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).map_err(|e| OptionsError::Config(path, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(OptionsError::Config(path, e.to_string())),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let config: Self = toml_edit::de::from_str(text).map_err(|e| e.message().to_string())?;
+
+        // Icons and zone colours are off unless something turns them on, so
+        // there’s nothing for `false` to do, and accepting it would only
+        // suggest that it overrides the environment
+        for (name, setting) in [("icons", config.icons), ("zone-colors", config.zone_colors)] {
+            if setting == Some(false) {
+                return Err(format!(
+                    "{name} can only be turned on; leave it out to keep it off"
+                ));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The value the environment variable with the given name would have to
+    /// have to do the same as this config file, if it sets anything for it.
+    fn var(&self, name: &str) -> Option<OsString> {
+        let on = |setting: Option<bool>| setting.map(|_| OsString::from("1"));
+
+        match name {
+            "WLS_ICONS_AUTO" => on(self.icons),
+            "WLS_GHOSTS" => self
+                .ghosts
+                .map(|on| OsString::from(if on { "1" } else { "0" })),
+            "WLS_GHOST_LIMIT" => self.ghost_limit.map(|limit| limit.to_string().into()),
+            "WLS_MANIFEST_PATH" => self.manifest_path.clone().map(PathBuf::into_os_string),
+            "WLS_TARGET_PREFIX" => self.target_prefix.clone().map(OsString::from),
+            "WLS_ZONE_CASE" => self.zone_case.clone().map(OsString::from),
+            "WLS_ZONE_COLORS" => on(self.zone_colors),
+            "WLS_MATERIALIZE_COMMAND" => self.materialize_command.clone().map(OsString::from),
+            "WLS_ZONE_URL" => self.zone_url.clone().map(OsString::from),
+            _ => None,
+        }
+    }
+
+    /// The environment, with this config file filling in for any variables
+    /// that aren’t set.
+    pub fn under<V: Vars>(self, vars: &V) -> Configured<'_, V> {
+        Configured { vars, config: self }
+    }
+}
+
+/// The environment variables, falling back to the config file.
+pub struct Configured<'a, V> {
+    vars: &'a V,
+    config: ConfigFile,
+}
+
+impl<V: Vars> Vars for Configured<'_, V> {
+    fn get(&self, name: &'static str) -> Option<OsString> {
+        self.vars.get(name).or_else(|| self.config.var(name))
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.vars.config_dir()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::vars;
+
+    #[test]
+    fn empty() {
+        assert_eq!(ConfigFile::parse(""), Ok(ConfigFile::default()));
+    }
+
+    #[test]
+    fn settings() {
+        let config = ConfigFile::parse(
+            "icons = true\nghosts = false\nghost-limit = 20\nmanifest-path = 'm.json'\n",
+        )
+        .unwrap();
+        assert_eq!(config.var(vars::WLS_ICONS_AUTO), Some("1".into()));
+        assert_eq!(config.var(vars::WLS_GHOSTS), Some("0".into()));
+        assert_eq!(config.var(vars::WLS_GHOST_LIMIT), Some("20".into()));
+        assert_eq!(config.var(vars::WLS_MANIFEST_PATH), Some("m.json".into()));
+        assert_eq!(config.var(vars::WLS_ZONE_COLORS), None);
+    }
+
+    #[test]
+    fn turned_off() {
+        assert!(ConfigFile::parse("icons = false\n").is_err());
+        assert!(ConfigFile::parse("zone-colors = false\n").is_err());
+    }
+
+    #[test]
+    fn unknown_setting() {
+        assert!(ConfigFile::parse("icon = true").is_err());
+    }
+
+    #[test]
+    fn environment_wins() {
+        let config = ConfigFile::parse("target-prefix = '//'\n").unwrap();
+        let env = Some(OsString::from("@"));
+        assert_eq!(
+            config.under(&env).get(vars::WLS_TARGET_PREFIX),
+            Some("@".into())
+        );
+
+        let config = ConfigFile::parse("target-prefix = '//'\n").unwrap();
+        let env: Option<OsString> = None;
+        assert_eq!(
+            config.under(&env).get(vars::WLS_TARGET_PREFIX),
+            Some("//".into())
+        );
+    }
+}
@@ -6,7 +6,7 @@
 // SPDX-License-Identifier: MIT
 //! Parsing the options for `DirAction`.
 
-use crate::options::parser::MatchedFlags;
+use crate::options::parser::{Arg, MatchedFlags};
 use crate::options::{flags, NumberSource, OptionsError};
 
 use crate::fs::dir_action::{DirAction, RecurseOptions};
@@ -30,6 +30,24 @@ impl DirAction {
                     &flags::RECURSE,
                     &flags::TREE,
                 ));
+            } else if !recurse && !tree && matches.has(&flags::DEDUPE)? {
+                return Err(OptionsError::Useless2(
+                    &flags::DEDUPE,
+                    &flags::RECURSE,
+                    &flags::TREE,
+                ));
+            } else if !recurse && !tree && matches.has(&flags::NO_CROSS_ZONES)? {
+                return Err(OptionsError::Useless2(
+                    &flags::NO_CROSS_ZONES,
+                    &flags::RECURSE,
+                    &flags::TREE,
+                ));
+            } else if !recurse && !tree && matches.count(&flags::GHOST_DEPTH) > 0 {
+                return Err(OptionsError::Useless2(
+                    &flags::GHOST_DEPTH,
+                    &flags::RECURSE,
+                    &flags::TREE,
+                ));
             } else if recurse && as_file {
                 return Err(OptionsError::Conflict(
                     &flags::RECURSE,
@@ -59,27 +77,39 @@ impl DirAction {
 
 impl RecurseOptions {
     /// Determine which files should be recursed into, based on the `--level`
-    /// flag’s value, and whether the `--tree` flag was passed, which was
-    /// determined earlier. The maximum level should be a number, and this
-    /// will fail with an `Err` if it isn’t.
+    /// and `--ghost-depth` flags’ values, and whether the `--tree` flag was
+    /// passed, which was determined earlier. Both limits should be numbers,
+    /// and this will fail with an `Err` if either isn’t.
     pub fn deduce(matches: &MatchedFlags<'_>, tree: bool) -> Result<Self, OptionsError> {
-        if let Some(level) = matches.get(&flags::LEVEL)? {
-            let arg_str = level.to_string_lossy();
-            match arg_str.parse() {
-                Ok(l) => Ok(Self {
-                    tree,
-                    max_depth: Some(l),
-                }),
-                Err(e) => {
-                    let source = NumberSource::Arg(&flags::LEVEL);
-                    Err(OptionsError::FailedParse(arg_str.to_string(), source, e))
-                }
+        let dedupe = matches.has(&flags::DEDUPE)?;
+        let stop_at_zones = matches.has(&flags::NO_CROSS_ZONES)?;
+        let max_depth = Self::deduce_depth(matches, &flags::LEVEL)?;
+        let ghost_depth = Self::deduce_depth(matches, &flags::GHOST_DEPTH)?;
+
+        Ok(Self {
+            tree,
+            max_depth,
+            dedupe,
+            stop_at_zones,
+            ghost_depth,
+        })
+    }
+
+    fn deduce_depth(
+        matches: &MatchedFlags<'_>,
+        flag: &'static Arg,
+    ) -> Result<Option<usize>, OptionsError> {
+        let Some(level) = matches.get(flag)? else {
+            return Ok(None);
+        };
+
+        let arg_str = level.to_string_lossy();
+        match arg_str.parse() {
+            Ok(l) => Ok(Some(l)),
+            Err(e) => {
+                let source = NumberSource::Arg(flag);
+                Err(OptionsError::FailedParse(arg_str.to_string(), source, e))
             }
-        } else {
-            Ok(Self {
-                tree,
-                max_depth: None,
-            })
         }
     }
 }
@@ -103,6 +133,9 @@ mod test {
                     &flags::TREAT_DIRS_AS_FILES,
                     &flags::TREE,
                     &flags::LEVEL,
+                    &flags::DEDUPE,
+                    &flags::NO_CROSS_ZONES,
+                    &flags::GHOST_DEPTH,
                 ];
                 for result in parse_for_test($inputs.as_ref(), TEST_ARGS, $stricts, |mf| {
                     $type::deduce(mf, true)
@@ -122,26 +155,39 @@ mod test {
 
     // Recursing
     use self::DirAction::Recurse;
-    test!(rec_short:       DirAction <- ["-R"];                           Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None })));
-    test!(rec_long:        DirAction <- ["--recurse"];                    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None })));
-    test!(rec_lim_short:   DirAction <- ["-RL4"];                         Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(4) })));
-    test!(rec_lim_short_2: DirAction <- ["-RL=5"];                        Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(5) })));
-    test!(rec_lim_long:    DirAction <- ["--recurse", "--level", "666"];  Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(666) })));
-    test!(rec_lim_long_2:  DirAction <- ["--recurse", "--level=0118"];    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(118) })));
-    test!(tree:            DirAction <- ["--tree"];                       Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
-    test!(rec_tree:        DirAction <- ["--recurse", "--tree"];          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
-    test!(rec_short_tree:  DirAction <- ["-TR"];                          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
+    test!(rec_short:       DirAction <- ["-R"];                           Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_long:        DirAction <- ["--recurse"];                    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_lim_short:   DirAction <- ["-RL4"];                         Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(4), dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_lim_short_2: DirAction <- ["-RL=5"];                        Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(5), dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_lim_long:    DirAction <- ["--recurse", "--level", "666"];  Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(666), dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_lim_long_2:  DirAction <- ["--recurse", "--level=0118"];    Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(118), dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(tree:            DirAction <- ["--tree"];                       Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_tree:        DirAction <- ["--recurse", "--tree"];          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_short_tree:  DirAction <- ["-TR"];                          Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: None })));
 
     // Overriding --list-dirs, --recurse, and --tree
-    test!(dirs_recurse:    DirAction <- ["--treat-dirs-as-files", "--recurse"];     Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: None })));
-    test!(dirs_tree:       DirAction <- ["--treat-dirs-as-files", "--tree"];        Last => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None })));
+    test!(dirs_recurse:    DirAction <- ["--treat-dirs-as-files", "--recurse"];     Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: None })));
+    test!(dirs_tree:       DirAction <- ["--treat-dirs-as-files", "--tree"];        Last => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: None })));
     test!(just_level:      DirAction <- ["--level=4"];                    Last => Ok(DirAction::List));
 
     test!(dirs_recurse_2:  DirAction <- ["--treat-dirs-as-files", "--recurse"]; Complain => Err(OptionsError::Conflict(&flags::RECURSE, &flags::TREAT_DIRS_AS_FILES)));
     test!(dirs_tree_2:     DirAction <- ["--treat-dirs-as-files", "--tree"];    Complain => Err(OptionsError::Conflict(&flags::TREE,    &flags::TREAT_DIRS_AS_FILES)));
     test!(just_level_2:    DirAction <- ["--level=4"];                Complain => Err(OptionsError::Useless2(&flags::LEVEL, &flags::RECURSE, &flags::TREE)));
 
+    // Deduplicating overlapping arguments
+    test!(rec_dedupe:      DirAction <- ["-R", "--dedupe"];               Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, dedupe: true, stop_at_zones: false, ghost_depth: None })));
+    test!(tree_dedupe:     DirAction <- ["-T", "--dedupe"];               Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, dedupe: true, stop_at_zones: false, ghost_depth: None })));
+    test!(rec_zones:       DirAction <- ["-R", "--no-cross-zones"];       Both => Ok(Recurse(RecurseOptions { tree: false, max_depth: None, dedupe: false, stop_at_zones: true, ghost_depth: None })));
+    test!(tree_zones:      DirAction <- ["-T", "--no-cross-zones"];       Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, dedupe: false, stop_at_zones: true, ghost_depth: None })));
+    test!(just_zones:      DirAction <- ["--no-cross-zones"];         Complain => Err(OptionsError::Useless2(&flags::NO_CROSS_ZONES, &flags::RECURSE, &flags::TREE)));
+    test!(just_dedupe:     DirAction <- ["--dedupe"];                 Complain => Err(OptionsError::Useless2(&flags::DEDUPE, &flags::RECURSE, &flags::TREE)));
+
+    // Limiting ghost expansion separately
+    test!(tree_ghosts:     DirAction <- ["-T", "--ghost-depth=1"];        Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: None, dedupe: false, stop_at_zones: false, ghost_depth: Some(1) })));
+    test!(tree_both:       DirAction <- ["-TL3", "--ghost-depth", "0"];   Both => Ok(Recurse(RecurseOptions { tree: true,  max_depth: Some(3), dedupe: false, stop_at_zones: false, ghost_depth: Some(0) })));
+    test!(just_ghosts:     DirAction <- ["--ghost-depth=2"];          Complain => Err(OptionsError::Useless2(&flags::GHOST_DEPTH, &flags::RECURSE, &flags::TREE)));
+
     // Overriding levels
-    test!(overriding_1:    DirAction <- ["-RL=6", "-L=7"];                Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(7) })));
+    test!(overriding_1:    DirAction <- ["-RL=6", "-L=7"];                Last => Ok(Recurse(RecurseOptions { tree: false, max_depth: Some(7), dedupe: false, stop_at_zones: false, ghost_depth: None })));
     test!(overriding_2:    DirAction <- ["-RL=6", "-L=7"];            Complain => Err(OptionsError::Duplicate(Flag::Short(b'L'), Flag::Short(b'L'))));
 }
@@ -7,6 +7,7 @@
 use std::ffi::OsString;
 use std::fmt;
 use std::num::ParseIntError;
+use std::path::PathBuf;
 
 use crate::options::flags;
 use crate::options::parser::{Arg, Flag, ParseError};
@@ -45,6 +46,9 @@ pub enum OptionsError {
 
     /// A glob ignore was given that failed to be parsed as a pattern.
     FailedGlobPattern(String),
+
+    /// The config file couldn’t be read or parsed.
+    Config(PathBuf, String),
 }
 
 /// The source of a string that failed to be parsed as a number.
@@ -102,6 +106,7 @@ impl fmt::Display for OptionsError {
             Self::TreeAllAll                 => write!(f, "Option --tree is useless given --all --all"),
             Self::FailedParse(s, n, e)       => write!(f, "Value {s:?} not valid for {n}: {e}"),
             Self::FailedGlobPattern(ref e)   => write!(f, "Failed to parse glob pattern: {e}"),
+            Self::Config(path, e)            => write!(f, "Failed to read config file {}: {e}", path.display()),
         };
     }
 }
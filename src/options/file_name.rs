@@ -11,6 +11,7 @@ use crate::options::{flags, NumberSource, OptionsError};
 use crate::output::file_name::{
     Absolute, Classify, EmbedHyperlinks, Options, QuoteStyle, ShowIcons,
 };
+use crate::output::url_template::UrlTemplate;
 
 impl Options {
     pub fn deduce<V: Vars>(
@@ -23,21 +24,30 @@ impl Options {
 
         let quote_style = QuoteStyle::deduce(matches)?;
         let embed_hyperlinks = EmbedHyperlinks::deduce(matches)?;
+        let zone_url = deduce_zone_url(matches, vars, embed_hyperlinks)?;
 
         let absolute = Absolute::deduce(matches)?;
 
         // --no-sigils flag takes precedence, otherwise check env var
-        let no_sigils = matches.has(&flags::NO_SIGILS)?
-            || vars.get(vars::WLS_NO_SIGILS).is_some();
+        let no_sigils = matches.has(&flags::NO_SIGILS)? || vars.get(vars::WLS_NO_SIGILS).is_some();
+
+        let hashed_zone_colours =
+            matches.has(&flags::ZONE_COLORS)? || vars.get(vars::WLS_ZONE_COLORS).is_some();
+
+        let zone_badges = matches.has(&flags::ZONE_BADGES)?;
 
         Ok(Self {
             classify,
             show_icons,
             quote_style,
             embed_hyperlinks,
+            zone_url,
             absolute,
             is_a_tty,
             no_sigils,
+            hashed_zone_colours,
+            zone_badges,
+            common_prefix: None,
         })
     }
 }
@@ -66,7 +76,10 @@ impl ShowIcons {
             Automatic,
         }
 
-        let force_icons = vars.get(vars::WLS_ICONS_AUTO).or_else(|| vars.get(vars::EZA_ICONS_AUTO)).is_some();
+        let force_icons = vars
+            .get(vars::WLS_ICONS_AUTO)
+            .or_else(|| vars.get(vars::EZA_ICONS_AUTO))
+            .is_some();
         let mode_opt = matches.get(&flags::ICONS)?;
         if !force_icons && !matches.has(&flags::ICONS)? && mode_opt.is_none() {
             return Ok(Self::Never);
@@ -131,6 +144,40 @@ impl EmbedHyperlinks {
     }
 }
 
+/// Determine where zones get linked to. The `--zone-url` flag takes
+/// precedence over the environment variable, and only the flag complains
+/// about there being no hyperlinks to put it in.
+fn deduce_zone_url<V: Vars>(
+    matches: &MatchedFlags<'_>,
+    vars: &V,
+    embed_hyperlinks: EmbedHyperlinks,
+) -> Result<Option<UrlTemplate>, OptionsError> {
+    if let Some(template) = matches.get(&flags::ZONE_URL)? {
+        if embed_hyperlinks == EmbedHyperlinks::Off {
+            return Err(OptionsError::Useless(
+                &flags::ZONE_URL,
+                false,
+                &flags::HYPERLINK,
+            ));
+        }
+
+        return match template.to_str() {
+            Some(template) => Ok(Some(UrlTemplate(template.to_string()))),
+            None => Err(OptionsError::BadArgument(&flags::ZONE_URL, template.into())),
+        };
+    }
+
+    Ok(
+        match vars
+            .get(vars::WLS_ZONE_URL)
+            .and_then(|s| s.into_string().ok())
+        {
+            Some(template) if !template.is_empty() => Some(UrlTemplate(template)),
+            _ => None,
+        },
+    )
+}
+
 impl Absolute {
     fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
         match matches.get(&flags::ABSOLUTE)? {
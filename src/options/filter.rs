@@ -7,16 +7,22 @@
 //! Parsing the options for `FileFilter`.
 
 use crate::fs::filter::{
-    FileFilter, FileFilterFlags, GitIgnore, IgnorePatterns, SortCase, SortField,
+    FileFilter, FileFilterFlags, GitIgnore, IgnorePatterns, SortCase, SortField, ZoneCondition,
+    ZoneFilter,
 };
 use crate::fs::DotFilter;
 
 use crate::options::parser::MatchedFlags;
-use crate::options::{flags, OptionsError};
+use crate::options::vars::{self, Vars};
+use crate::options::{flags, NumberSource, OptionsError};
+
+/// How many ghosts to show at the src root when nothing else has been
+/// configured.
+const DEFAULT_GHOST_LIMIT: usize = 100;
 
 impl FileFilter {
     /// Determines which of all the file filter options to use.
-    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+    pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         use FileFilterFlags as FFF;
         let mut filter_flags: Vec<FileFilterFlags> = vec![];
 
@@ -28,13 +34,17 @@ impl FileFilter {
             (matches.has(&flags::SHOW_SYMLINKS)?, FFF::ShowSymlinks),
             (matches.has(&flags::DIRS_LAST)?, FFF::ListDirsLast),
             (matches.has(&flags::DIRS_FIRST)?, FFF::ListDirsFirst),
-            (matches.has(&flags::NO_GHOSTS)?, FFF::NoGhosts),
+            (matches.has(&flags::ZONES_FIRST)?, FFF::ListZonesFirst),
         ] {
             if *has {
                 filter_flags.push(flag.clone());
             }
         }
 
+        if let Some(flag) = Self::deduce_ghost_order(matches)? {
+            filter_flags.push(flag);
+        }
+
         #[rustfmt::skip]
         return Ok(Self {
             no_symlinks:      filter_flags.contains(&FFF::NoSymlinks),
@@ -44,14 +54,107 @@ impl FileFilter {
             dot_filter:       DotFilter::deduce(matches)?,
             ignore_patterns:  IgnorePatterns::deduce(matches)?,
             git_ignore:       GitIgnore::deduce(matches)?,
-            no_ghosts:        filter_flags.contains(&FFF::NoGhosts),
+            ghosts:           Self::deduce_ghosts(matches, vars)?,
+            ghost_limit:      Self::deduce_ghost_limit(matches, vars)?,
+            tag:              Self::deduce_tag(matches)?,
+            zone_filter:      Self::deduce_zone_filter(matches)?,
         });
     }
+
+    /// Determines whether ghosts go before or after everything else, from
+    /// a `ghosts-first` or `ghosts-last` modifier given to `--sort`, with
+    /// the last one winning.
+    fn deduce_ghost_order(
+        matches: &MatchedFlags<'_>,
+    ) -> Result<Option<FileFilterFlags>, OptionsError> {
+        let Some(word) = matches.get(&flags::SORT)? else {
+            return Ok(None);
+        };
+
+        Ok(word
+            .to_string_lossy()
+            .split(',')
+            .rev()
+            .find_map(|part| match part {
+                "ghosts-first" => Some(FileFilterFlags::ListGhostsFirst),
+                "ghosts-last" => Some(FileFilterFlags::ListGhostsLast),
+                _ => None,
+            }))
+    }
+
+    /// Determines which tag zones have to have to be listed, if any.
+    fn deduce_tag(matches: &MatchedFlags<'_>) -> Result<Option<String>, OptionsError> {
+        Ok(matches
+            .get(&flags::TAG)?
+            .map(|tag| tag.to_string_lossy().into_owned()))
+    }
+
+    /// Determines which conditions zones’ manifest entries have to meet to
+    /// be listed, if any.
+    fn deduce_zone_filter(matches: &MatchedFlags<'_>) -> Result<Option<ZoneFilter>, OptionsError> {
+        let Some(expr) = matches.get(&flags::FILTER_ZONE)? else {
+            return Ok(None);
+        };
+
+        match expr.to_str().and_then(parse_zone_filter) {
+            Some(filter) => Ok(Some(filter)),
+            None => Err(OptionsError::BadArgument(&flags::FILTER_ZONE, expr.into())),
+        }
+    }
+
+    /// Whether to list ghosts, with the last of `--ghosts` and `--no-ghosts`
+    /// winning, then the environment. If neither says, it’s left to the src
+    /// root being listed.
+    fn deduce_ghosts<V: Vars>(
+        matches: &MatchedFlags<'_>,
+        vars: &V,
+    ) -> Result<Option<bool>, OptionsError> {
+        let flag =
+            matches.has_where(|f| f.matches(&flags::GHOSTS) || f.matches(&flags::NO_GHOSTS))?;
+        if let Some(flag) = flag {
+            return Ok(Some(flag.matches(&flags::GHOSTS)));
+        }
+
+        Ok(match vars.get(vars::WLS_GHOSTS) {
+            Some(s) if s == "0" => Some(false),
+            Some(s) if s == "1" => Some(true),
+            _ => None,
+        })
+    }
+
+    /// Determines how many ghosts to show at the src root. `--all-ghosts`
+    /// lifts the limit entirely, as does setting the environment variable
+    /// to `0`.
+    fn deduce_ghost_limit<V: Vars>(
+        matches: &MatchedFlags<'_>,
+        vars: &V,
+    ) -> Result<Option<usize>, OptionsError> {
+        if matches.has(&flags::ALL_GHOSTS)? {
+            return Ok(None);
+        }
+
+        let Some(limit) = vars
+            .get(vars::WLS_GHOST_LIMIT)
+            .and_then(|s| s.into_string().ok())
+        else {
+            return Ok(Some(DEFAULT_GHOST_LIMIT));
+        };
+
+        match limit.parse() {
+            Ok(0) => Ok(None),
+            Ok(limit) => Ok(Some(limit)),
+            Err(e) => {
+                let source = NumberSource::Env(vars::WLS_GHOST_LIMIT);
+                Err(OptionsError::FailedParse(limit, source, e))
+            }
+        }
+    }
 }
 
 impl SortField {
     /// Determines which sort field to use based on the `--sort` argument.
-    /// This argument’s value can be one of several flags, listed above.
+    /// This argument’s value can be one of several flags, listed above,
+    /// along with modifiers after commas, such as `size,ghosts-last`.
     /// Returns the default sort field if none is given, or `Err` if the
     /// value doesn’t correspond to a sort field we know about.
     fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
@@ -64,7 +167,16 @@ impl SortField {
             return Err(OptionsError::BadArgument(&flags::SORT, word.into()));
         };
 
-        let field = match word {
+        let mut fields = word
+            .split(',')
+            .filter(|part| !matches!(*part, "ghosts-first" | "ghosts-last"));
+        let field = match (fields.next(), fields.next()) {
+            (None, _) => return Ok(Self::default()),
+            (Some(field), None) => field,
+            (Some(_), Some(_)) => return Err(OptionsError::BadArgument(&flags::SORT, word.into())),
+        };
+
+        let field = match field {
             "name" | "filename" => Self::Name(SortCase::AaBbCc),
             "Name" | "Filename" => Self::Name(SortCase::ABCabc),
             ".name" | ".filename" => Self::NameMixHidden(SortCase::AaBbCc),
@@ -90,6 +202,7 @@ impl SortField {
             #[cfg(unix)]
             "inode" => Self::FileInode,
             "type" => Self::FileType,
+            "zone" => Self::Zone,
             "none" => Self::Unsorted,
             _ => {
                 return Err(OptionsError::BadArgument(&flags::SORT, word.into()));
@@ -206,6 +319,41 @@ impl GitIgnore {
     }
 }
 
+/// Parses a `--filter-zone` expression, which is a comma-separated list of
+/// conditions that all have to hold: `field=value`, `field!=value`, or just
+/// `field` for any value at all. Returns `None` if any of them is malformed.
+fn parse_zone_filter(expr: &str) -> Option<ZoneFilter> {
+    let mut conditions = Vec::new();
+
+    for condition in expr.split(',') {
+        let condition = match condition.split_once('=') {
+            Some((field, value)) => {
+                let (field, negated) = match field.strip_suffix('!') {
+                    Some(field) => (field, true),
+                    None => (field, false),
+                };
+                let (field, value) = (field.trim(), value.trim());
+                if field.is_empty() || value.is_empty() {
+                    return None;
+                }
+
+                if negated {
+                    ZoneCondition::NotEquals(field.into(), value.into())
+                } else {
+                    ZoneCondition::Equals(field.into(), value.into())
+                }
+            }
+            None => match condition.trim() {
+                "" => return None,
+                field => ZoneCondition::Has(field.into()),
+            },
+        };
+        conditions.push(condition);
+    }
+
+    Some(ZoneFilter(conditions))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -259,6 +407,13 @@ mod test {
         test!(mix_hidden_lowercase:     SortField <- ["--sort", ".name"];  Both => Ok(SortField::NameMixHidden(SortCase::AaBbCc)));
         test!(mix_hidden_uppercase:     SortField <- ["--sort", ".Name"];  Both => Ok(SortField::NameMixHidden(SortCase::ABCabc)));
 
+        test!(zone:          SortField <- ["--sort=zone"];     Both => Ok(SortField::Zone));
+
+        // Modifiers
+        test!(ghosts_last:   SortField <- ["--sort=size,ghosts-last"];  Both => Ok(SortField::Size));
+        test!(ghosts_only:   SortField <- ["--sort=ghosts-first"];      Both => Ok(SortField::default()));
+        test!(two_fields:    SortField <- ["--sort=size,name"];         Both => Err(OptionsError::BadArgument(&flags::SORT, OsString::from("size,name"))));
+
         // Errors
         test!(error:         SortField <- ["--sort=colour"];   Both => Err(OptionsError::BadArgument(&flags::SORT, OsString::from("colour"))));
 
@@ -269,6 +424,24 @@ mod test {
         test!(overridden_4:  SortField <- ["--sort", "none",  "--sort=Extension"];  Complain => Err(OptionsError::Duplicate(Flag::Long("sort"), Flag::Long("sort"))));
     }
 
+    mod ghost_orders {
+        use super::*;
+        use crate::fs::filter::FileFilterFlags as FFF;
+
+        struct GhostOrder;
+
+        impl GhostOrder {
+            fn deduce(matches: &MatchedFlags<'_>) -> Result<Option<FFF>, OptionsError> {
+                FileFilter::deduce_ghost_order(matches)
+            }
+        }
+
+        test!(empty:        GhostOrder <- [];                                     Both => Ok(None));
+        test!(no_modifier:  GhostOrder <- ["--sort=size"];                        Both => Ok(None));
+        test!(first:        GhostOrder <- ["--sort=name,ghosts-first"];           Both => Ok(Some(FFF::ListGhostsFirst)));
+        test!(last_wins:    GhostOrder <- ["-sghosts-first,ghosts-last,size"];    Both => Ok(Some(FFF::ListGhostsLast)));
+    }
+
     mod dot_filters {
         use super::*;
 
@@ -321,4 +494,158 @@ mod test {
         test!(off:  GitIgnore <- [];                Both => Ok(GitIgnore::Off));
         test!(on:   GitIgnore <- ["--git-ignore"];  Both => Ok(GitIgnore::CheckAndIgnore));
     }
+
+    mod ghost_limits {
+        use super::*;
+        use crate::options::parser::Arg;
+        use crate::options::test::parse_for_test;
+        use crate::options::test::Strictnesses::*;
+
+        static TEST_ARGS: &[&Arg] = &[&flags::ALL_GHOSTS];
+
+        fn limit(inputs: &[&str], env: Option<&str>) -> Vec<Result<Option<usize>, OptionsError>> {
+            let vars = env.map(OsString::from);
+            parse_for_test(inputs, TEST_ARGS, Both, |mf| {
+                FileFilter::deduce_ghost_limit(mf, &vars)
+            })
+        }
+
+        #[test]
+        fn default() {
+            assert!(limit(&[], None)
+                .into_iter()
+                .all(|r| r == Ok(Some(DEFAULT_GHOST_LIMIT))));
+        }
+
+        #[test]
+        fn all_ghosts() {
+            assert!(limit(&["--all-ghosts"], Some("10"))
+                .into_iter()
+                .all(|r| r == Ok(None)));
+        }
+
+        #[test]
+        fn env() {
+            assert!(limit(&[], Some("10"))
+                .into_iter()
+                .all(|r| r == Ok(Some(10))));
+        }
+
+        #[test]
+        fn env_zero() {
+            assert!(limit(&[], Some("0")).into_iter().all(|r| r == Ok(None)));
+        }
+
+        #[test]
+        fn env_bad() {
+            assert!(limit(&[], Some("lots")).into_iter().all(|r| r.is_err()));
+        }
+    }
+
+    mod ghosts {
+        use super::*;
+        use crate::options::parser::Arg;
+        use crate::options::test::parse_for_test;
+        use crate::options::test::Strictnesses::*;
+
+        static TEST_ARGS: &[&Arg] = &[&flags::GHOSTS, &flags::NO_GHOSTS];
+
+        fn ghosts(inputs: &[&str], env: Option<&str>) -> Vec<Result<Option<bool>, OptionsError>> {
+            let vars = env.map(OsString::from);
+            parse_for_test(inputs, TEST_ARGS, Last, |mf| {
+                FileFilter::deduce_ghosts(mf, &vars)
+            })
+        }
+
+        #[test]
+        fn left_to_the_src_root() {
+            assert_eq!(ghosts(&[], None), vec![Ok(None)]);
+        }
+
+        #[test]
+        fn flags() {
+            assert_eq!(ghosts(&["--no-ghosts"], None), vec![Ok(Some(false))]);
+            assert_eq!(
+                ghosts(&["--no-ghosts", "--ghosts"], None),
+                vec![Ok(Some(true))]
+            );
+        }
+
+        #[test]
+        fn env() {
+            assert_eq!(ghosts(&[], Some("0")), vec![Ok(Some(false))]);
+            assert_eq!(ghosts(&[], Some("1")), vec![Ok(Some(true))]);
+            assert_eq!(ghosts(&["--ghosts"], Some("0")), vec![Ok(Some(true))]);
+        }
+    }
+
+    mod tags {
+        use super::*;
+        use crate::options::parser::Arg;
+        use crate::options::test::parse_for_test;
+        use crate::options::test::Strictnesses::*;
+
+        static TEST_ARGS: &[&Arg] = &[&flags::TAG];
+
+        fn tag(inputs: &[&str]) -> Vec<Result<Option<String>, OptionsError>> {
+            parse_for_test(inputs, TEST_ARGS, Both, FileFilter::deduce_tag)
+        }
+
+        #[test]
+        fn none() {
+            assert!(tag(&[]).into_iter().all(|r| r == Ok(None)));
+        }
+
+        #[test]
+        fn one() {
+            assert!(tag(&["--tag", "deprecated"])
+                .into_iter()
+                .all(|r| r == Ok(Some("deprecated".into()))));
+        }
+    }
+
+    mod zone_filters {
+        use super::*;
+        use crate::options::parser::Arg;
+        use crate::options::test::parse_for_test;
+        use crate::options::test::Strictnesses::*;
+        use std::ffi::OsString;
+
+        static TEST_ARGS: &[&Arg] = &[&flags::FILTER_ZONE];
+
+        fn filter(inputs: &[&str]) -> Vec<Result<Option<ZoneFilter>, OptionsError>> {
+            parse_for_test(inputs, TEST_ARGS, Both, FileFilter::deduce_zone_filter)
+        }
+
+        #[test]
+        fn none() {
+            assert!(filter(&[]).into_iter().all(|r| r == Ok(None)));
+        }
+
+        #[test]
+        fn conditions() {
+            let expected = ZoneFilter(vec![
+                ZoneCondition::Equals("owner".into(), "payments".into()),
+                ZoneCondition::NotEquals("type".into(), "app".into()),
+                ZoneCondition::Has("deprecated".into()),
+            ]);
+            let results = filter(&["--filter-zone", "owner=payments, type != app,deprecated"]);
+            assert!(results.into_iter().all(|r| r == Ok(Some(expected.clone()))));
+        }
+
+        #[test]
+        fn malformed() {
+            for expr in ["", "owner=", "=payments", "owner=payments,", "!=app"] {
+                let results = filter(&["--filter-zone", expr]);
+                assert!(
+                    results.into_iter().all(|r| r
+                        == Err(OptionsError::BadArgument(
+                            &flags::FILTER_ZONE,
+                            OsString::from(expr)
+                        ))),
+                    "{expr:?}"
+                );
+            }
+        }
+    }
 }
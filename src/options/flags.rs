@@ -6,12 +6,21 @@
 // SPDX-License-Identifier: MIT
 #![cfg_attr(rustfmt, rustfmt_skip)]
 use crate::options::parser::{Arg, Args, TakesValue, Values};
+use crate::options::view::COLUMN_NAMES;
 
 // exa options
 pub static VERSION: Arg = Arg { short: Some(b'v'), long: "version",  takes_value: TakesValue::Forbidden };
 pub static HELP:    Arg = Arg { short: Some(b'?'), long: "help",     takes_value: TakesValue::Forbidden };
+pub static JSON:    Arg = Arg { short: None,       long: "json",     takes_value: TakesValue::Forbidden };
+pub static COMPLETIONS: Arg = Arg { short: None,   long: "completions", takes_value: TakesValue::Necessary(Some(SHELLS)) };
+const SHELLS: Values = &["bash", "zsh", "fish"];
 
 // display options
+pub static JSON_LINES: Arg = Arg { short: None,       long: "json-lines",    takes_value: TakesValue::Forbidden };
+pub static CSV:          Arg = Arg { short: None,       long: "csv",             takes_value: TakesValue::Forbidden };
+pub static TSV:          Arg = Arg { short: None,       long: "tsv",             takes_value: TakesValue::Forbidden };
+pub static PRINT0:       Arg = Arg { short: Some(b'0'), long: "print0",          takes_value: TakesValue::Forbidden };
+pub static FORMAT:       Arg = Arg { short: None,       long: "format",          takes_value: TakesValue::Necessary(None) };
 pub static ONE_LINE:     Arg = Arg { short: Some(b'1'), long: "oneline",         takes_value: TakesValue::Forbidden };
 pub static LONG:         Arg = Arg { short: Some(b'l'), long: "long",            takes_value: TakesValue::Forbidden };
 pub static GRID:         Arg = Arg { short: Some(b'G'), long: "grid",            takes_value: TakesValue::Forbidden };
@@ -29,6 +38,10 @@ const ABSOLUTE_MODES: &[&str] = &["on", "follow", "off"];
 pub static COLOR:  Arg = Arg { short: None, long: "color",  takes_value: TakesValue::Optional(Some(WHEN), "auto") };
 pub static COLOUR: Arg = Arg { short: None, long: "colour", takes_value: TakesValue::Optional(Some(WHEN), "auto") };
 const WHEN: &[&str] = &["always", "auto", "never"];
+pub static STDERR_COLOR:  Arg = Arg { short: None, long: "stderr-color",  takes_value: TakesValue::Optional(Some(WHEN), "auto") };
+pub static STDERR_COLOUR: Arg = Arg { short: None, long: "stderr-colour", takes_value: TakesValue::Optional(Some(WHEN), "auto") };
+pub static ERRORS:        Arg = Arg { short: None, long: "errors",        takes_value: TakesValue::Necessary(Some(ERROR_FORMATS)) };
+const ERROR_FORMATS: Values = &["text", "json"];
 
 pub static COLOR_SCALE:  Arg = Arg { short: None, long: "color-scale",  takes_value: TakesValue::Optional(Some(SCALES), "all") };
 pub static COLOUR_SCALE: Arg = Arg { short: None, long: "colour-scale", takes_value: TakesValue::Optional(Some(SCALES), "all") };
@@ -36,6 +49,8 @@ pub static COLOR_SCALE_MODE:  Arg = Arg { short: None, long: "color-scale-mode",
 pub static COLOUR_SCALE_MODE: Arg = Arg { short: None, long: "colour-scale-mode", takes_value: TakesValue::Necessary(Some(COLOR_SCALE_MODES))};
 const SCALES: Values = &["all", "size", "age"];
 const COLOR_SCALE_MODES: Values = &["fixed", "gradient"];
+pub static PALETTE: Arg = Arg { short: None, long: "palette", takes_value: TakesValue::Necessary(Some(PALETTES)) };
+const PALETTES: Values = &["default", "deuteranopia", "protanopia", "tritanopia"];
 
 // filtering and sorting options
 pub static ALL:                 Arg = Arg { short: Some(b'a'), long: "all",         takes_value: TakesValue::Forbidden };
@@ -43,22 +58,36 @@ pub static ALMOST_ALL:          Arg = Arg { short: Some(b'A'), long: "almost-all
 pub static TREAT_DIRS_AS_FILES: Arg = Arg { short: Some(b'd'), long: "treat-dirs-as-files",   takes_value: TakesValue::Forbidden };
 pub static LIST_DIRS:           Arg = Arg { short: None, long: "list-dirs",   takes_value: TakesValue::Forbidden };
 pub static LEVEL:               Arg = Arg { short: Some(b'L'), long: "level",       takes_value: TakesValue::Necessary(None) };
+pub static GHOST_DEPTH:         Arg = Arg { short: None,       long: "ghost-depth", takes_value: TakesValue::Necessary(None) };
+pub static DEDUPE:              Arg = Arg { short: None,       long: "dedupe",      takes_value: TakesValue::Forbidden };
+pub static JOURNAL:             Arg = Arg { short: None,       long: "journal",     takes_value: TakesValue::Necessary(None) };
+pub static NO_CROSS_ZONES:      Arg = Arg { short: None,       long: "no-cross-zones", takes_value: TakesValue::Forbidden };
 pub static REVERSE:             Arg = Arg { short: Some(b'r'), long: "reverse",     takes_value: TakesValue::Forbidden };
 pub static SORT:                Arg = Arg { short: Some(b's'), long: "sort",        takes_value: TakesValue::Necessary(Some(SORTS)) };
 pub static IGNORE_GLOB:         Arg = Arg { short: Some(b'I'), long: "ignore-glob", takes_value: TakesValue::Necessary(None) };
+pub static GLOB:                Arg = Arg { short: None,       long: "glob",        takes_value: TakesValue::Necessary(None) };
 pub static GIT_IGNORE:          Arg = Arg { short: None, long: "git-ignore",           takes_value: TakesValue::Forbidden };
+pub static GHOST_RESPECT_GITIGNORE: Arg = Arg { short: None, long: "ghost-respect-gitignore", takes_value: TakesValue::Forbidden };
+pub static GIT_IGNORE_ZONES:    Arg = Arg { short: None, long: "git-ignore-zones",     takes_value: TakesValue::Forbidden };
 pub static DIRS_FIRST:          Arg = Arg { short: None, long: "group-directories-first",  takes_value: TakesValue::Forbidden };
 pub static DIRS_LAST:           Arg = Arg { short: None, long: "group-directories-last",  takes_value: TakesValue::Forbidden };
+pub static ZONES_FIRST:         Arg = Arg { short: None, long: "group-zones-first",  takes_value: TakesValue::Forbidden };
 pub static ONLY_DIRS:           Arg = Arg { short: Some(b'D'), long: "only-dirs", takes_value: TakesValue::Forbidden };
 pub static ONLY_FILES:          Arg = Arg { short: Some(b'f'), long: "only-files", takes_value: TakesValue::Forbidden };
 pub static NO_SYMLINKS:         Arg = Arg { short: None,       long: "no-symlinks", takes_value: TakesValue::Forbidden };
 pub static SHOW_SYMLINKS:       Arg = Arg { short: None,     long: "show-symlinks", takes_value: TakesValue::Forbidden };
+pub static GHOSTS:              Arg = Arg { short: None,     long: "ghosts",        takes_value: TakesValue::Forbidden };
 pub static NO_GHOSTS:           Arg = Arg { short: None,     long: "no-ghosts",     takes_value: TakesValue::Forbidden };
+pub static ALL_GHOSTS:          Arg = Arg { short: None,     long: "all-ghosts",    takes_value: TakesValue::Forbidden };
+pub static SPLIT_GHOSTS:        Arg = Arg { short: None,     long: "split-ghosts",  takes_value: TakesValue::Forbidden };
+pub static TAG:                 Arg = Arg { short: None,     long: "tag",           takes_value: TakesValue::Necessary(None) };
+pub static FILTER_ZONE:         Arg = Arg { short: None,     long: "filter-zone",   takes_value: TakesValue::Necessary(None) };
 pub static NO_SIGILS:           Arg = Arg { short: None,     long: "no-sigils",     takes_value: TakesValue::Forbidden };
 
 const SORTS: Values = &[ "name", "Name", "size", "extension",
                          "Extension", "modified", "changed", "accessed",
-                         "created", "inode", "type", "none" ];
+                         "created", "inode", "type", "zone", "none",
+                         "ghosts-first", "ghosts-last" ];
 
 // display options
 pub static BINARY:      Arg = Arg { short: Some(b'b'), long: "binary",      takes_value: TakesValue::Forbidden };
@@ -66,6 +95,8 @@ pub static BYTES:       Arg = Arg { short: Some(b'B'), long: "bytes",       take
 pub static GROUP:       Arg = Arg { short: Some(b'g'), long: "group",       takes_value: TakesValue::Forbidden };
 pub static NUMERIC:     Arg = Arg { short: Some(b'n'), long: "numeric",     takes_value: TakesValue::Forbidden };
 pub static HEADER:      Arg = Arg { short: Some(b'h'), long: "header",      takes_value: TakesValue::Forbidden };
+pub static ZONE_HEADERS: Arg = Arg { short: None,       long: "zone-headers", takes_value: TakesValue::Forbidden };
+pub static STREAM:      Arg = Arg { short: None,       long: "stream",      takes_value: TakesValue::Forbidden };
 pub static ICONS:       Arg = Arg { short: None,       long: "icons",       takes_value: TakesValue::Optional(Some(WHEN), "auto")};
 pub static INODE:       Arg = Arg { short: Some(b'i'), long: "inode",       takes_value: TakesValue::Forbidden };
 pub static LINKS:       Arg = Arg { short: Some(b'H'), long: "links",       takes_value: TakesValue::Forbidden };
@@ -73,13 +104,30 @@ pub static MODIFIED:    Arg = Arg { short: Some(b'm'), long: "modified",    take
 pub static CHANGED:     Arg = Arg { short: None,       long: "changed",     takes_value: TakesValue::Forbidden };
 pub static BLOCKSIZE:   Arg = Arg { short: Some(b'S'), long: "blocksize",   takes_value: TakesValue::Forbidden };
 pub static TOTAL_SIZE:  Arg = Arg { short: None,       long: "total-size",  takes_value: TakesValue::Forbidden };
+pub static TOTAL_SIZE_WITHIN_ZONE: Arg = Arg { short: None, long: "total-size-within-zone", takes_value: TakesValue::Forbidden };
 pub static TIME:        Arg = Arg { short: Some(b't'), long: "time",        takes_value: TakesValue::Necessary(Some(TIMES)) };
 pub static ACCESSED:    Arg = Arg { short: Some(b'u'), long: "accessed",    takes_value: TakesValue::Forbidden };
 pub static CREATED:     Arg = Arg { short: Some(b'U'), long: "created",     takes_value: TakesValue::Forbidden };
 pub static TIME_STYLE:  Arg = Arg { short: None,       long: "time-style",  takes_value: TakesValue::Necessary(Some(TIME_STYLES)) };
 pub static HYPERLINK:   Arg = Arg { short: None,       long: "hyperlink",   takes_value: TakesValue::Forbidden };
+pub static ZONE_URL:    Arg = Arg { short: None,       long: "zone-url",    takes_value: TakesValue::Necessary(None) };
 pub static MOUNTS:      Arg = Arg { short: Some(b'M'), long: "mounts",      takes_value: TakesValue::Forbidden };
 pub static SMART_GROUP: Arg = Arg { short: None,       long: "smart-group", takes_value: TakesValue::Forbidden };
+pub static SYNC_DETAILS: Arg = Arg { short: None,      long: "sync-details", takes_value: TakesValue::Forbidden };
+pub static MATERIALIZED: Arg = Arg { short: None,      long: "materialized", takes_value: TakesValue::Forbidden };
+pub static TAGS:         Arg = Arg { short: None,      long: "tags",         takes_value: TakesValue::Forbidden };
+pub static LAYER:        Arg = Arg { short: None,      long: "layer",        takes_value: TakesValue::Forbidden };
+pub static OWNERS:       Arg = Arg { short: None,      long: "owners",       takes_value: TakesValue::Forbidden };
+pub static FILESYSTEMS:  Arg = Arg { short: None,      long: "filesystems",  takes_value: TakesValue::Forbidden };
+pub static NESTED_REPOS: Arg = Arg { short: None,      long: "nested-repos", takes_value: TakesValue::Forbidden };
+pub static URL_TEMPLATE: Arg = Arg { short: None,      long: "url-template", takes_value: TakesValue::Necessary(None) };
+pub static COLUMNS:      Arg = Arg { short: None,      long: "columns",      takes_value: TakesValue::Necessary(Some(COLUMN_NAMES)) };
+pub static ZONE_COLORS: Arg = Arg { short: None,       long: "zone-colors", takes_value: TakesValue::Forbidden };
+pub static ZONE_BADGES: Arg = Arg { short: None,       long: "zone-badges", takes_value: TakesValue::Forbidden };
+pub static MATERIALIZE: Arg = Arg { short: None,       long: "materialize", takes_value: TakesValue::Forbidden };
+pub static ZONE_SUMMARY: Arg = Arg { short: None,      long: "zone-summary", takes_value: TakesValue::Forbidden };
+pub static FREE_SPACE:   Arg = Arg { short: None,      long: "free-space",   takes_value: TakesValue::Forbidden };
+pub static WATCH:        Arg = Arg { short: None,      long: "watch",        takes_value: TakesValue::Forbidden };
 const TIMES: Values = &["modified", "changed", "accessed", "created"];
 const TIME_STYLES: Values = &["default", "long-iso", "full-iso", "iso", "relative"];
 
@@ -94,26 +142,78 @@ pub static GIT:               Arg = Arg { short: None,       long: "git",
 pub static NO_GIT:            Arg = Arg { short: None,       long: "no-git",               takes_value: TakesValue::Forbidden };
 pub static GIT_REPOS:         Arg = Arg { short: None,       long: "git-repos",            takes_value: TakesValue::Forbidden };
 pub static GIT_REPOS_NO_STAT: Arg = Arg { short: None,       long: "git-repos-no-status",  takes_value: TakesValue::Forbidden };
+pub static GIT_BATCH:         Arg = Arg { short: None,       long: "git-batch",            takes_value: TakesValue::Forbidden };
+pub static ZONE_HEALTH:       Arg = Arg { short: None,       long: "zone-health",          takes_value: TakesValue::Forbidden };
 pub static EXTENDED:          Arg = Arg { short: Some(b'@'), long: "extended",             takes_value: TakesValue::Forbidden };
 pub static OCTAL:             Arg = Arg { short: Some(b'o'), long: "octal-permissions",    takes_value: TakesValue::Forbidden };
 pub static SECURITY_CONTEXT:  Arg = Arg { short: Some(b'Z'), long: "context",              takes_value: TakesValue::Forbidden };
 pub static STDIN:             Arg = Arg { short: None,       long: "stdin",                takes_value: TakesValue::Forbidden };
 pub static FILE_FLAGS:        Arg = Arg { short: Some(b'O'), long: "flags",                takes_value: TakesValue::Forbidden };
 
+// manifest options
+pub static MANIFEST:      Arg = Arg { short: None, long: "manifest",      takes_value: TakesValue::Necessary(None) };
+pub static TARGET_PREFIX: Arg = Arg { short: None, long: "target-prefix", takes_value: TakesValue::Necessary(None) };
+pub static IGNORE_MANIFEST: Arg = Arg { short: None, long: "ignore-manifest", takes_value: TakesValue::Forbidden };
+pub static SPARSE_CHECKOUT: Arg = Arg { short: None, long: "sparse-checkout", takes_value: TakesValue::Forbidden };
+pub static MANIFEST_ONLY: Arg = Arg { short: None, long: "manifest-only", takes_value: TakesValue::Forbidden };
+pub static MOVED_HINTS:   Arg = Arg { short: None, long: "moved-hints",   takes_value: TakesValue::Forbidden };
+pub static GHOST_DATES:   Arg = Arg { short: None, long: "ghost-dates",   takes_value: TakesValue::Forbidden };
+pub static DU:            Arg = Arg { short: None, long: "du",            takes_value: TakesValue::Forbidden };
+pub static ZONE_SYMLINKS: Arg = Arg { short: None, long: "zone-symlinks", takes_value: TakesValue::Necessary(Some(ZONE_SYMLINK_POLICIES)) };
+const ZONE_SYMLINK_POLICIES: Values = &["canonical", "logical", "both"];
+pub static ZONE_CASE: Arg = Arg { short: None, long: "zone-case", takes_value: TakesValue::Necessary(Some(ZONE_CASES)) };
+const ZONE_CASES: Values = &["exact", "fold"];
+pub static DAEMON:        Arg = Arg { short: None, long: "daemon",        takes_value: TakesValue::Forbidden };
+pub static NO_CACHE:      Arg = Arg { short: None, long: "no-cache",      takes_value: TakesValue::Forbidden };
+
+// diagnostics
+pub static TIMINGS: Arg = Arg { short: None, long: "timings", takes_value: TakesValue::Optional(Some(TIMINGS_MODES), "summary") };
+const TIMINGS_MODES: &[&str] = &["summary", "verbose"];
+
+// developer tools
+pub static CREATE_FIXTURE: Arg = Arg { short: None, long: "create-fixture", takes_value: TakesValue::Necessary(None) };
+
+// zone queries
+pub static ZONE_COMPLETE: Arg = Arg { short: None, long: "zone-complete", takes_value: TakesValue::Necessary(None) };
+pub static ZONE_OF:       Arg = Arg { short: None, long: "zone-of",       takes_value: TakesValue::Necessary(None) };
+pub static CHECK_MANIFEST: Arg = Arg { short: None, long: "check-manifest", takes_value: TakesValue::Forbidden };
+pub static ORPHANS:       Arg = Arg { short: None, long: "orphans",       takes_value: TakesValue::Forbidden };
+pub static ORPHAN_DEPTH:  Arg = Arg { short: None, long: "orphan-depth",  takes_value: TakesValue::Necessary(None) };
+pub static SHOW_DEPS:     Arg = Arg { short: None, long: "show-deps",     takes_value: TakesValue::Necessary(None) };
+pub static REVERSE_DEPS:  Arg = Arg { short: None, long: "reverse-deps",  takes_value: TakesValue::Forbidden };
+pub static DESCRIBE:      Arg = Arg { short: None, long: "describe",      takes_value: TakesValue::Necessary(None) };
+
 pub static ALL_ARGS: Args = Args(&[
-    &VERSION, &HELP,
+    &VERSION, &HELP, &JSON, &COMPLETIONS,
 
-    &ONE_LINE, &LONG, &GRID, &ACROSS, &RECURSE, &TREE, &CLASSIFY, &DEREF_LINKS, &FOLLOW_LINKS,
-    &COLOR, &COLOUR, &COLOR_SCALE, &COLOUR_SCALE, &COLOR_SCALE_MODE, &COLOUR_SCALE_MODE,
+    &ONE_LINE, &JSON_LINES, &CSV, &TSV, &PRINT0, &FORMAT, &LONG, &GRID, &ACROSS, &RECURSE, &TREE, &CLASSIFY, &DEREF_LINKS, &FOLLOW_LINKS,
+    &COLOR, &COLOUR, &STDERR_COLOR, &STDERR_COLOUR, &ERRORS, &COLOR_SCALE, &COLOUR_SCALE, &COLOR_SCALE_MODE, &COLOUR_SCALE_MODE, &PALETTE,
     &WIDTH, &NO_QUOTES, &ABSOLUTE,
 
-    &ALL, &ALMOST_ALL, &TREAT_DIRS_AS_FILES, &LIST_DIRS, &LEVEL, &REVERSE, &SORT, &DIRS_FIRST, &DIRS_LAST,
-    &IGNORE_GLOB, &GIT_IGNORE, &ONLY_DIRS, &ONLY_FILES,
+    &ALL, &ALMOST_ALL, &TREAT_DIRS_AS_FILES, &LIST_DIRS, &LEVEL, &GHOST_DEPTH, &DEDUPE, &JOURNAL, &NO_CROSS_ZONES, &REVERSE, &SORT, &DIRS_FIRST, &DIRS_LAST, &ZONES_FIRST,
+    &IGNORE_GLOB, &GLOB, &GIT_IGNORE, &GHOST_RESPECT_GITIGNORE, &GIT_IGNORE_ZONES, &ONLY_DIRS, &ONLY_FILES,
 
-    &BINARY, &BYTES, &GROUP, &NUMERIC, &HEADER, &ICONS, &INODE, &LINKS, &MODIFIED, &CHANGED,
-    &BLOCKSIZE, &TOTAL_SIZE, &TIME, &ACCESSED, &CREATED, &TIME_STYLE, &HYPERLINK, &MOUNTS,
-    &NO_PERMISSIONS, &NO_FILESIZE, &NO_USER, &NO_TIME, &SMART_GROUP, &NO_SYMLINKS, &SHOW_SYMLINKS, &NO_GHOSTS, &NO_SIGILS,
+    &BINARY, &BYTES, &GROUP, &NUMERIC, &HEADER, &ZONE_HEADERS, &STREAM, &ICONS, &INODE, &LINKS, &MODIFIED, &CHANGED,
+    &BLOCKSIZE, &TOTAL_SIZE, &TOTAL_SIZE_WITHIN_ZONE, &TIME, &ACCESSED, &CREATED, &TIME_STYLE, &HYPERLINK, &ZONE_URL, &MOUNTS,
+    &NO_PERMISSIONS, &NO_FILESIZE, &NO_USER, &NO_TIME, &SMART_GROUP, &NO_SYMLINKS, &SHOW_SYMLINKS, &GHOSTS, &NO_GHOSTS, &ALL_GHOSTS, &SPLIT_GHOSTS, &TAG, &FILTER_ZONE, &NO_SIGILS,
+    &ZONE_COLORS, &ZONE_BADGES, &ZONE_SUMMARY, &FREE_SPACE, &WATCH, &MATERIALIZE, &SYNC_DETAILS, &MATERIALIZED, &TAGS, &LAYER, &OWNERS, &NESTED_REPOS, &FILESYSTEMS, &URL_TEMPLATE, &COLUMNS,
 
-    &GIT, &NO_GIT, &GIT_REPOS, &GIT_REPOS_NO_STAT,
-    &EXTENDED, &OCTAL, &SECURITY_CONTEXT, &STDIN, &FILE_FLAGS
+    &GIT, &NO_GIT, &GIT_REPOS, &GIT_REPOS_NO_STAT, &GIT_BATCH, &ZONE_HEALTH,
+    &EXTENDED, &OCTAL, &SECURITY_CONTEXT, &STDIN, &FILE_FLAGS,
+
+    &MANIFEST, &TARGET_PREFIX, &IGNORE_MANIFEST, &SPARSE_CHECKOUT, &MANIFEST_ONLY, &DU, &MOVED_HINTS, &GHOST_DATES, &ZONE_SYMLINKS, &ZONE_CASE, &DAEMON, &NO_CACHE, &ZONE_COMPLETE, &ZONE_OF, &CHECK_MANIFEST, &ORPHANS, &ORPHAN_DEPTH, &SHOW_DEPS, &REVERSE_DEPS, &DESCRIBE,
+
+    &TIMINGS, &CREATE_FIXTURE,
 ]);
+
+// The flags the `wls.toml` next to a manifest can set for everyone listing
+// in its src root, which only change how the listing looks
+pub static REPO_ARGS: &[&Arg] = &[
+    &ONE_LINE, &LONG, &GRID, &ACROSS, &CLASSIFY, &NO_QUOTES,
+    &COLOR_SCALE, &COLOUR_SCALE, &COLOR_SCALE_MODE, &COLOUR_SCALE_MODE,
+    &REVERSE, &SORT, &DIRS_FIRST, &DIRS_LAST, &ZONES_FIRST,
+    &BINARY, &BYTES, &GROUP, &HEADER, &ZONE_HEADERS, &ICONS, &TIME_STYLE, &SMART_GROUP,
+    &NO_PERMISSIONS, &NO_FILESIZE, &NO_USER, &NO_TIME,
+    &GHOSTS, &NO_GHOSTS, &SPLIT_GHOSTS, &NO_SIGILS,
+    &ZONE_COLORS, &ZONE_BADGES, &SYNC_DETAILS, &MATERIALIZED, &TAGS, &LAYER, &OWNERS, &COLUMNS,
+];
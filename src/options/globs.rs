@@ -0,0 +1,64 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+use crate::options::parser::MatchedFlags;
+use crate::options::{flags, OptionsError};
+
+/// Glob patterns given with `--glob`, which get expanded into extra file
+/// arguments by wls itself, so that ghosts from the manifest can match as
+/// well as files on disk.
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct ArgGlobs(pub Vec<String>);
+
+impl ArgGlobs {
+    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+        let Some(inputs) = matches.get(&flags::GLOB)? else {
+            return Ok(Self::default());
+        };
+
+        let mut patterns = Vec::new();
+        for input in inputs.to_string_lossy().split('|') {
+            glob::Pattern::new(input)?;
+            patterns.push(input.to_string());
+        }
+
+        Ok(Self(patterns))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::test::parse_for_test;
+    use crate::options::test::Strictnesses::*;
+
+    static TEST_ARGS: &[&crate::options::parser::Arg] = &[&flags::GLOB];
+
+    #[test]
+    fn none() {
+        for result in parse_for_test(&[], TEST_ARGS, Both, ArgGlobs::deduce) {
+            assert_eq!(result, Ok(ArgGlobs::default()));
+        }
+    }
+
+    #[test]
+    fn pipe_separated() {
+        for result in parse_for_test(
+            &["--glob", "areas/pay*|areas/co?e"],
+            TEST_ARGS,
+            Both,
+            ArgGlobs::deduce,
+        ) {
+            assert_eq!(
+                result,
+                Ok(ArgGlobs(vec!["areas/pay*".into(), "areas/co?e".into()]))
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        for result in parse_for_test(&["--glob", "[areas"], TEST_ARGS, Both, ArgGlobs::deduce) {
+            assert!(matches!(result, Err(OptionsError::FailedGlobPattern(_))));
+        }
+    }
+}
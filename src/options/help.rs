@@ -18,9 +18,25 @@ static USAGE_PART1: &str = "Usage:
 META OPTIONS
   -?, --help                 show list of command-line options
   -v, --version              show version of wls
+  --json                     with --version, show the version and build
+                             capabilities as JSON
+  --completions SHELL        print a completion script for SHELL (bash, zsh, fish)
+                             that completes target paths from the manifest
 
 DISPLAY OPTIONS
   -1, --oneline              display one entry per line
+  --json-lines               stream one JSON object per entry, unsorted
+                             (warnings go to stderr as JSON too)
+  --csv, --tsv               write the long view's columns, with each entry's target
+                             and whether it's a zone or a ghost, as comma- or
+                             tab-separated records under a header row
+  -0, --print0               write each entry's bare path followed by a NUL, unsorted,
+                             for 'xargs -0'; ghosts are left out, unless --ghosts
+                             is given, when they're written as target paths
+  --format TEMPLATE          write a line per entry filled in from a template like
+                             '{name}\t{size}', unsorted (fields: name, path, type,
+                             size, modified, is_ghost, is_zone, zone_target, zone,
+                             target, owner, owners)
   -l, --long                 display extended file metadata as a table
   -G, --grid                 display entries as a grid (default)
   -x, --across               sort the grid across, rather than downwards
@@ -29,13 +45,28 @@ DISPLAY OPTIONS
   -X, --dereference          dereference symbolic links when displaying information
   -F, --classify=WHEN        display type indicator by file names (always, auto, never)
   --colo[u]r=WHEN            when to use terminal colours (always, auto, never)
+  --stderr-colo[u]r=WHEN     when to colour warnings on stderr (always, auto, never)
+  --errors=FORMAT            write warnings and errors on stderr as text or as JSON
+                             records (text, json); JSON is the default with --json-lines
   --colo[u]r-scale           highlight levels of 'field' distinctly(all, age, size)
   --colo[u]r-scale-mode      use gradient or fixed colors in --color-scale (fixed, gradient)
+  --palette=NAME             colorblind-safe built-in colours (default, deuteranopia,
+                             protanopia, tritanopia)
   --icons=WHEN               when to display icons (always, auto, never)
   --no-quotes                don't quote file names with spaces
   --hyperlink                display entries as hyperlinks
-  --absolute                 display entries with their absolute path (on, follow, off)
+  --zone-url TEMPLATE        with --hyperlink, link zones to this URL instead, filling
+                             in {target} and {path} (such as 'areas/tools/dev')
+  --zone-colors              colour each zone by a hash of its target path
+  --zone-badges              show each zone's type from the manifest after its
+                             name, such as [lib] or [svc]
+  --zone-summary             count the entries, zones and ghost zones after each listing
+  --free-space               show the free space on the disk after each listing
+  --materialize              print the command that checks out each ghost zone
+  --absolute                 display entries with their absolute path (on, follow, off),
+                             and ghosts with the path they would have on disk
   --follow-symlinks          drill down into symbolic links that point to directories
+  --watch                    list again whenever the directories, Git index or manifest change
   -w, --width COLS           set screen width in columns
 
 
@@ -48,20 +79,45 @@ FILTERING AND SORTING OPTIONS
   -f, --only-files           list only files
   --show-symlinks            explicitly show symbolic links (for use with --only-dirs | --only-files)
   --no-symlinks              do not show symbolic links
+  --ghosts, --no-ghosts      show or hide ghosts, whatever the src root's wls.toml says;
+                             with --tree, zones with hidden ghosts say how many there are
+  --all-ghosts               show every ghost when listing the src root, rather
+                             than only the first 100
+  --split-ghosts             list ghosts in their own section, below the files on disk
+  --tag NAME                 list only zones tagged NAME in the manifest, and the
+                             directories leading to them
+  --filter-zone EXPR         list only zones whose manifest entries match EXPR, such
+                             as 'owner=payments,type!=app', and the directories
+                             leading to them
   -L, --level DEPTH          limit the depth of recursion
+  --ghost-depth DEPTH        limit how deep ghost directories get expanded
+  --dedupe                   don't list a directory again when arguments overlap
+  --journal FILE             record finished directories in FILE, so an interrupted
+                             --recurse listing resumes where it stopped
+  --no-cross-zones           when recursing, list zones but don't descend into them
   -r, --reverse              reverse the sort order
   -s, --sort SORT_FIELD      which field to sort by
   --group-directories-first  list directories before other files
   --group-directories-last   list directories after other files
-  -I, --ignore-glob GLOBS    glob patterns (pipe-separated) of files to ignore";
+  --group-zones-first        list zones first, then other directories, then other files
+  -I, --ignore-glob GLOBS    glob patterns (pipe-separated) of files to ignore
+  --glob GLOBS               glob patterns (pipe-separated) of files to list,
+                             matching ghosts as well as files on disk";
 
 static GIT_FILTER_HELP: &str = "  \
-  --git-ignore               ignore files mentioned in '.gitignore'";
+  --git-ignore               ignore files mentioned in '.gitignore'
+  --ghost-respect-gitignore  don't list ghosts for targets that '.gitignore' ignores,
+                             such as generated output directories
+  --git-ignore-zones         keep listing zones that '.gitignore' ignores, with
+                             --git-ignore or --ghost-respect-gitignore";
 
 static USAGE_PART2: &str = "  \
   Valid sort fields:         name, Name, extension, Extension, size, type,
-                             created, modified, accessed, changed, inode, and none.
+                             created, modified, accessed, changed, inode, zone,
+                             and none.
                              date, time, old, and new all refer to modified.
+                             Add ,ghosts-first or ,ghosts-last to put ghosts
+                             before or after everything on disk.
 
 LONG VIEW OPTIONS
   -b, --binary               list file sizes with binary prefixes
@@ -69,6 +125,9 @@ LONG VIEW OPTIONS
   -g, --group                list each file's group
   --smart-group              only show group if it has a different name from owner
   -h, --header               add a header row to each column
+  --zone-headers             group entries by zone, with a heading before each
+  --stream                   print rows in chunks as they're read, each chunk
+                             sorted and aligned on its own
   -H, --links                list each file's number of hard links
   -i, --inode                list each file's inode number
   -M, --mounts               show mount details (Linux and Mac only)
@@ -85,13 +144,82 @@ LONG VIEW OPTIONS
                              like '+%Y-%m-%d %H:%M')
   --total-size               show the size of a directory as the size of all
                              files and directories inside (unix only)
+  --total-size-within-zone   like --total-size, but leave out the zones inside
+                             each directory (unix only)
   -o, --octal-permissions    list each file's permission in octal format
+  --sync-details             list who last synced each zone, and when
+  --materialized             list how much of the zones beneath each zone is on disk
+  --tags                     list the tags each zone has in the manifest
+  --filesystems              list the filesystem each file is on and where it's
+                             mounted, to show which directories are on network mounts
+  --layer                    list which overlayfs layer each file comes from (image,
+                             modified, or new), inside a container
+  --owners                   list who owns each file in the repository's CODEOWNERS
+  --nested-repos             mark directories that are submodules, worktrees, or
+                             other repositories inside the one they're in
+  --url-template TEMPLATE    add a column linking each entry to a web view, filling
+                             in {repo} and {rel_path} from Git
+  --columns NAMES            which columns to list, in order, such as 'name,size,zone,owner'
+                             (name, permissions, octal, links, inode, size,
+                             blocksize, user, group, flags, context, modified,
+                             changed, accessed, created, synced-by, synced-at,
+                             materialized, tags, zone, owner, owners, target, git,
+                             zone-health, git-repos, git-repos-no-status,
+                             nested-repos, filesystems, layer, url)
   --no-permissions           suppress the permissions field
   --no-filesize              suppress the filesize field
   --no-user                  suppress the user field
   --no-time                  suppress the time field
   --stdin                    read file names from stdin, one per line or other separator 
-                             specified in environment";
+                             specified in environment
+                             (absolute paths are shown relative to the directory
+                             they share, which is printed once at the top)
+  --timings=MODE             print how long loading the manifest, reading directories,
+                             stats, symlinks, Git, zone checks and rendering took to
+                             stderr; 'verbose' also lists the slowest entries (summary, verbose)";
+
+static ZONE_QUERY_HELP: &str = "
+ZONE OPTIONS
+  --manifest PATH            where to find the manifest (default: 'src/.meta/manifest.json');
+                             relative paths are looked for in every parent directory
+  --target-prefix PREFIX     what target paths start with instead of '//', such as '@world//'
+  --ignore-manifest          never look for the manifest, listing like plain eza
+  --sparse-checkout          mark ghosts that git's sparse checkout leaves out as [sparse],
+                             and ones it includes but that aren't on disk as [missing]
+  --manifest-only            list the children of targets from the manifest alone,
+                             without looking at the disk
+  --du                       list the zones beneath each directory in the long view,
+                             sized by the files in them, leaving out nested zones
+  --moved-hints              hint where directories the manifest doesn't know about
+                             were moved to, from the renames in recent Git history
+  --ghost-dates              date ghosts by the last commit that touched their targets,
+                             asking 'git log' once for each
+  --zone-symlinks POLICY     which path of a directory reached through a symlink to
+                             look up in the manifest (canonical, logical, or both,
+                             which tries canonical first)
+  --zone-case MODE           whether directories have to match the manifest's case
+                             (exact, or fold, the default on macOS and Windows)
+  --zone-complete PREFIX     print manifest entries matching a partial target,
+                             such as '//areas/to'
+  --zone-of PATH             print the target and src-relative path of the zone
+                             that PATH is in
+  --check-manifest           check the manifest for duplicate, unnormalized, and
+                             malformed entries, and exit with 1 if there are any
+  --orphans                  print directories the manifest doesn't know about,
+                             and exit with 1 if there are any
+  --orphan-depth DEPTH       limit how many directories deep --orphans looks
+  --show-deps TARGET         print the targets that TARGET's manifest entry depends on
+  --reverse-deps             with --show-deps, print the targets that depend on it instead
+  --describe TARGET          print a summary of a zone: its owner, type, description,
+                             how much of it is on disk, its size, and its Git status
+  --daemon                   keep manifests parsed in the background, for other
+                             invocations to ask for over a Unix socket
+  --no-cache                 parse the manifest instead of reading what was parsed
+                             from it last time from $XDG_CACHE_HOME/wls
+
+DEVELOPER OPTIONS
+  selftest --create-fixture DIR
+                             build a fixture src root in DIR and check how it's listed";
 
 static GIT_VIEW_HELP: &str = "  \
   --git                      list each file's Git status, if tracked or ignored
@@ -99,11 +227,15 @@ static GIT_VIEW_HELP: &str = "  \
                              --git-repos, --git-repos-no-status)
   --git-repos                list root of git-tree status
   --git-repos-no-status      list each git-repos branch name (much faster)
+  --zone-health              list whether each zone has uncommitted changes, and how
+                             far it's ahead of or behind its tracking branch
+  --git-batch                ask git for statuses once per listed directory,
+                             rather than walking the whole repository
     ";
 static EXTENDED_HELP: &str = "  \
   -@, --extended             list each file's extended attributes and sizes";
 static SECATTR_HELP: &str = "  \
-  -Z, --context              list each file's security context";
+  -Z, --context              list each file's SELinux or SMACK security context";
 
 /// All the information needed to display the help text, which depends
 /// on which features are enabled and whether the user only wants to
@@ -149,6 +281,8 @@ impl fmt::Display for HelpString {
             write!(f, "\n{SECATTR_HELP}")?;
         }
 
+        write!(f, "\n{ZONE_QUERY_HELP}")?;
+
         writeln!(f)
     }
 }
@@ -178,4 +312,20 @@ mod test {
         let opts = Options::parse(args, &None);
         assert!(!matches!(opts, OptionsResult::Help(_))); // no help when --help isn’t passed
     }
+
+    #[test]
+    fn lists_every_column_name() {
+        let listed = super::USAGE_PART2
+            .split_once("--columns NAMES")
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(names, _)| names.replace(char::is_whitespace, ""))
+            .unwrap();
+
+        for name in crate::options::view::COLUMN_NAMES {
+            assert!(
+                listed.split([',', '(']).any(|n| n == *name),
+                "{name} isn’t listed"
+            );
+        }
+    }
 }
@@ -0,0 +1,440 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::fs::timings::Timings;
+use crate::fs::{DirOptions, ManifestLocation, ZoneCase, ZoneSymlinks, DEFAULT_TARGET_PREFIX};
+use crate::options::parser::MatchedFlags;
+use crate::options::vars::{self, Vars};
+use crate::options::{flags, timings, OptionsError};
+
+/// The name of the file in the user’s config directory that lists the
+/// target paths whose ghosts are never shown.
+const IGNORED_GHOSTS_FILE_NAME: &str = "ignore-ghosts";
+
+impl DirOptions {
+    /// Determine how directories get matched against the manifest. Timing
+    /// starts here, if it was asked for.
+    pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+        Ok(Self {
+            manifest: ManifestLocation::deduce(matches, vars)?,
+            target_prefix: deduce_target_prefix(matches, vars)?,
+            zone_symlinks: ZoneSymlinks::deduce(matches)?,
+            zone_case: ZoneCase::deduce(matches, vars)?,
+            sparse_checkout: matches.has(&flags::SPARSE_CHECKOUT)?,
+            ghosts_respect_gitignore: matches.has(&flags::GHOST_RESPECT_GITIGNORE)?,
+            ignored_ghosts: deduce_ignored_ghosts(vars)?,
+            git_ignore_keeps_zones: deduce_git_ignore_zones(matches)?,
+            moved_hints: matches.has(&flags::MOVED_HINTS)?,
+            ghost_dates: matches.has(&flags::GHOST_DATES)?,
+            sizes_within_zones: matches.has(&flags::TOTAL_SIZE_WITHIN_ZONE)?,
+            // Which fields are needed depends on the view and the filter,
+            // so it’s decided along with them
+            entry_metadata: false,
+            manifest_cache: !matches.has(&flags::NO_CACHE)?,
+            timings: timings::deduce(matches)?.map(|mode| Arc::new(Timings::new(mode))),
+        })
+    }
+}
+
+/// Reads the target path patterns whose ghosts the user never wants to see,
+/// from `ignore-ghosts` in their config directory. Having no such file is
+/// the same as having an empty one.
+fn deduce_ignored_ghosts<V: Vars>(vars: &V) -> Result<Vec<glob::Pattern>, OptionsError> {
+    let Some(path) = vars
+        .config_dir()
+        .map(|dir| dir.join(IGNORED_GHOSTS_FILE_NAME))
+    else {
+        return Ok(Vec::new());
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(text) => parse_ignored_ghosts(&text).map_err(|e| OptionsError::Config(path, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(OptionsError::Config(path, e.to_string())),
+    }
+}
+
+/// Parses an `ignore-ghosts` file, which has a target path glob such as
+/// `//areas/legacy/**` on each line, and comments starting with `#`. A glob
+/// ending in `/**` also matches the directory it starts with, so that
+/// `legacy` goes along with everything in it.
+fn parse_ignored_ghosts(text: &str) -> Result<Vec<glob::Pattern>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .flat_map(|line| [Some(line), line.strip_suffix("/**")].into_iter().flatten())
+        .map(|line| glob::Pattern::new(line).map_err(|e| format!("bad pattern {line:?}: {e}")))
+        .collect()
+}
+
+/// Determine whether zones stay listed when `.gitignore` ignores them,
+/// which only matters when something is checking `.gitignore`.
+fn deduce_git_ignore_zones(matches: &MatchedFlags<'_>) -> Result<bool, OptionsError> {
+    let keeps_zones = matches.has(&flags::GIT_IGNORE_ZONES)?;
+    if keeps_zones
+        && matches.is_strict()
+        && !matches.has(&flags::GIT_IGNORE)?
+        && !matches.has(&flags::GHOST_RESPECT_GITIGNORE)?
+    {
+        return Err(OptionsError::Useless2(
+            &flags::GIT_IGNORE_ZONES,
+            &flags::GIT_IGNORE,
+            &flags::GHOST_RESPECT_GITIGNORE,
+        ));
+    }
+
+    Ok(keeps_zones)
+}
+
+impl ManifestLocation {
+    /// Determine where to look for the manifest, if anywhere. The flags take
+    /// precedence over the environment variables, and ignoring the manifest
+    /// over giving its path. Without any of them, the usual
+    /// `src/.meta/manifest.json` layout is assumed.
+    pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+        let path = matches.get(&flags::MANIFEST)?;
+        if matches.has(&flags::IGNORE_MANIFEST)? {
+            return match path {
+                Some(_) => Err(OptionsError::Conflict(
+                    &flags::IGNORE_MANIFEST,
+                    &flags::MANIFEST,
+                )),
+                None => Ok(Self::Ignored),
+            };
+        }
+
+        if let Some(path) = path {
+            return Ok(PathBuf::from(path).into());
+        }
+
+        if vars
+            .get(vars::WLS_IGNORE_MANIFEST)
+            .is_some_and(|v| !v.is_empty())
+        {
+            return Ok(Self::Ignored);
+        }
+
+        Ok(match vars.get(vars::WLS_MANIFEST_PATH) {
+            Some(path) if !path.is_empty() => PathBuf::from(path).into(),
+            _ => Self::Default,
+        })
+    }
+}
+
+impl ZoneSymlinks {
+    /// Determine which path of a directory reached through a symlink gets
+    /// matched against the manifest. Without the flag, the canonical path
+    /// is tried first, falling back to the logical one.
+    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+        let Some(word) = matches.get(&flags::ZONE_SYMLINKS)? else {
+            return Ok(Self::default());
+        };
+
+        match word.to_str() {
+            Some("canonical") => Ok(Self::Canonical),
+            Some("logical") => Ok(Self::Logical),
+            Some("both") => Ok(Self::Both),
+            _ => Err(OptionsError::BadArgument(
+                &flags::ZONE_SYMLINKS,
+                word.into(),
+            )),
+        }
+    }
+}
+
+impl ZoneCase {
+    /// Determine whether directories have to match the manifest’s case. The
+    /// flag takes precedence over the environment variable, and without
+    /// either, it depends on the platform.
+    pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+        let word = match matches.get(&flags::ZONE_CASE)? {
+            Some(word) => word.to_os_string(),
+            None => match vars.get(vars::WLS_ZONE_CASE) {
+                Some(word) if !word.is_empty() => word,
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        match word.to_str() {
+            Some("exact") => Ok(Self::Exact),
+            Some("fold") => Ok(Self::Fold),
+            _ => Err(OptionsError::BadArgument(&flags::ZONE_CASE, word)),
+        }
+    }
+}
+
+/// Determine what target paths start with, such as `@world//` for a
+/// toolchain that labels them that way. The `--target-prefix` flag takes
+/// precedence over the environment variable, and without either, it’s `//`.
+pub fn deduce_target_prefix<V: Vars>(
+    matches: &MatchedFlags<'_>,
+    vars: &V,
+) -> Result<String, OptionsError> {
+    if let Some(prefix) = matches.get(&flags::TARGET_PREFIX)? {
+        return match prefix.to_str() {
+            Some(prefix) => Ok(prefix.to_string()),
+            None => Err(OptionsError::BadArgument(
+                &flags::TARGET_PREFIX,
+                prefix.into(),
+            )),
+        };
+    }
+
+    Ok(
+        match vars
+            .get(vars::WLS_TARGET_PREFIX)
+            .and_then(|s| s.into_string().ok())
+        {
+            Some(prefix) if !prefix.is_empty() => prefix,
+            _ => DEFAULT_TARGET_PREFIX.to_string(),
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::test::parse_for_test;
+    use crate::options::test::Strictnesses::*;
+    use std::ffi::OsString;
+
+    static TEST_ARGS: &[&crate::options::parser::Arg] = &[
+        &flags::MANIFEST,
+        &flags::IGNORE_MANIFEST,
+        &flags::TARGET_PREFIX,
+        &flags::ZONE_SYMLINKS,
+        &flags::ZONE_CASE,
+        &flags::GIT_IGNORE,
+        &flags::GHOST_RESPECT_GITIGNORE,
+        &flags::GIT_IGNORE_ZONES,
+    ];
+
+    /// Environment variables where only the one given is set.
+    struct OneVar(&'static str, &'static str);
+
+    impl Vars for OneVar {
+        fn get(&self, name: &'static str) -> Option<OsString> {
+            (name == self.0).then(|| OsString::from(self.1))
+        }
+    }
+
+    #[test]
+    fn default() {
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| {
+            ManifestLocation::deduce(mf, &None)
+        }) {
+            assert_eq!(result, Ok(ManifestLocation::Default));
+        }
+    }
+
+    #[test]
+    fn from_flag() {
+        for result in parse_for_test(&["--manifest", "tools/zones.json"], TEST_ARGS, Both, |mf| {
+            ManifestLocation::deduce(mf, &None)
+        }) {
+            assert_eq!(
+                result,
+                Ok(ManifestLocation::Relative("tools/zones.json".into()))
+            );
+        }
+    }
+
+    #[test]
+    fn from_env() {
+        let env = OneVar(vars::WLS_MANIFEST_PATH, "/repo/tools/zones.json");
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| {
+            ManifestLocation::deduce(mf, &env)
+        }) {
+            assert_eq!(
+                result,
+                Ok(ManifestLocation::Absolute("/repo/tools/zones.json".into()))
+            );
+        }
+    }
+
+    #[test]
+    fn flag_beats_env() {
+        let env = Some(OsString::from("/repo/tools/zones.json"));
+        for result in parse_for_test(&["--manifest=zones.json"], TEST_ARGS, Both, |mf| {
+            ManifestLocation::deduce(mf, &env)
+        }) {
+            assert_eq!(result, Ok(ManifestLocation::Relative("zones.json".into())));
+        }
+    }
+
+    #[test]
+    fn ignored_from_flag() {
+        for result in parse_for_test(&["--ignore-manifest"], TEST_ARGS, Both, |mf| {
+            ManifestLocation::deduce(mf, &None)
+        }) {
+            assert_eq!(result, Ok(ManifestLocation::Ignored));
+        }
+    }
+
+    #[test]
+    fn ignored_from_env() {
+        let env = OneVar(vars::WLS_IGNORE_MANIFEST, "1");
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| {
+            ManifestLocation::deduce(mf, &env)
+        }) {
+            assert_eq!(result, Ok(ManifestLocation::Ignored));
+        }
+    }
+
+    #[test]
+    fn manifest_flag_beats_ignored_env() {
+        let env = OneVar(vars::WLS_IGNORE_MANIFEST, "1");
+        for result in parse_for_test(&["--manifest=zones.json"], TEST_ARGS, Both, |mf| {
+            ManifestLocation::deduce(mf, &env)
+        }) {
+            assert_eq!(result, Ok(ManifestLocation::Relative("zones.json".into())));
+        }
+    }
+
+    #[test]
+    fn ignored_conflicts_with_path() {
+        for result in parse_for_test(
+            &["--ignore-manifest", "--manifest=zones.json"],
+            TEST_ARGS,
+            Both,
+            |mf| ManifestLocation::deduce(mf, &None),
+        ) {
+            assert_eq!(
+                result,
+                Err(OptionsError::Conflict(
+                    &flags::IGNORE_MANIFEST,
+                    &flags::MANIFEST
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn default_target_prefix() {
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| deduce_target_prefix(mf, &None)) {
+            assert_eq!(result, Ok("//".to_string()));
+        }
+    }
+
+    #[test]
+    fn target_prefix_from_flag_beats_env() {
+        let env = Some(OsString::from("#"));
+        for result in parse_for_test(&["--target-prefix=@world//"], TEST_ARGS, Both, |mf| {
+            deduce_target_prefix(mf, &env)
+        }) {
+            assert_eq!(result, Ok("@world//".to_string()));
+        }
+    }
+
+    #[test]
+    fn target_prefix_from_env() {
+        let env = Some(OsString::from("@world//"));
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| deduce_target_prefix(mf, &env)) {
+            assert_eq!(result, Ok("@world//".to_string()));
+        }
+    }
+
+    #[test]
+    fn zone_symlinks_default() {
+        for result in parse_for_test(&[], TEST_ARGS, Both, ZoneSymlinks::deduce) {
+            assert_eq!(result, Ok(ZoneSymlinks::Both));
+        }
+    }
+
+    #[test]
+    fn zone_symlinks_from_flag() {
+        for result in parse_for_test(
+            &["--zone-symlinks=logical"],
+            TEST_ARGS,
+            Both,
+            ZoneSymlinks::deduce,
+        ) {
+            assert_eq!(result, Ok(ZoneSymlinks::Logical));
+        }
+    }
+
+    #[test]
+    fn zone_case_default() {
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| ZoneCase::deduce(mf, &None)) {
+            assert_eq!(result, Ok(ZoneCase::default()));
+        }
+    }
+
+    #[test]
+    fn zone_case_from_env() {
+        let env = OneVar(vars::WLS_ZONE_CASE, "fold");
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| ZoneCase::deduce(mf, &env)) {
+            assert_eq!(result, Ok(ZoneCase::Fold));
+        }
+    }
+
+    #[test]
+    fn zone_case_flag_beats_env() {
+        let env = OneVar(vars::WLS_ZONE_CASE, "fold");
+        for result in parse_for_test(&["--zone-case=exact"], TEST_ARGS, Both, |mf| {
+            ZoneCase::deduce(mf, &env)
+        }) {
+            assert_eq!(result, Ok(ZoneCase::Exact));
+        }
+    }
+
+    #[test]
+    fn zone_case_bad_env() {
+        let env = OneVar(vars::WLS_ZONE_CASE, "loud");
+        for result in parse_for_test(&[], TEST_ARGS, Both, |mf| ZoneCase::deduce(mf, &env)) {
+            assert_eq!(
+                result,
+                Err(OptionsError::BadArgument(&flags::ZONE_CASE, "loud".into()))
+            );
+        }
+    }
+
+    #[test]
+    fn git_ignore_zones() {
+        for result in parse_for_test(
+            &["--git-ignore", "--git-ignore-zones"],
+            TEST_ARGS,
+            Both,
+            deduce_git_ignore_zones,
+        ) {
+            assert_eq!(result, Ok(true));
+        }
+    }
+
+    #[test]
+    fn git_ignore_zones_alone() {
+        for result in parse_for_test(
+            &["--git-ignore-zones"],
+            TEST_ARGS,
+            Complain,
+            deduce_git_ignore_zones,
+        ) {
+            assert_eq!(
+                result,
+                Err(OptionsError::Useless2(
+                    &flags::GIT_IGNORE_ZONES,
+                    &flags::GIT_IGNORE,
+                    &flags::GHOST_RESPECT_GITIGNORE
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn ignored_ghosts() {
+        let patterns =
+            parse_ignored_ghosts("# Deprecated\n//areas/legacy/**\n\n  //areas/old-*  \n").unwrap();
+        let patterns: Vec<&str> = patterns.iter().map(glob::Pattern::as_str).collect();
+        assert_eq!(
+            patterns,
+            vec!["//areas/legacy/**", "//areas/legacy", "//areas/old-*"]
+        );
+    }
+
+    #[test]
+    fn ignored_ghosts_bad_pattern() {
+        assert!(parse_ignored_ghosts("//areas/[legacy\n").is_err());
+    }
+}
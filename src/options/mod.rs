@@ -74,22 +74,30 @@
 //! --grid --long` shouldn’t complain about `--long` being given twice when
 //! it’s clear what the user wants.
 
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 use crate::fs::dir_action::DirAction;
 use crate::fs::filter::{FileFilter, GitIgnore};
+use crate::fs::DirOptions;
+use crate::options::globs::ArgGlobs;
 use crate::options::stdin::FilesInput;
-use crate::output::{details, grid_details, Mode, View};
+use crate::output::template::Field;
+use crate::output::{delimited, details, grid_details, Mode, View};
 use crate::theme::Options as ThemeOptions;
+use crate::warnings::Format as ErrorFormat;
 
 mod dir_action;
 mod file_name;
 mod filter;
 
 mod error;
+mod manifest;
 #[rustfmt::skip]
 mod flags;
 mod theme;
+mod timings;
 mod view;
 
 pub use self::error::{NumberSource, OptionsError};
@@ -102,14 +110,25 @@ use self::parser::MatchedFlags;
 
 pub mod vars;
 pub use self::vars::Vars;
+pub mod completions;
 pub mod config;
+mod config_file;
+pub mod globs;
+mod repo_flags;
+pub mod selftest;
 pub mod stdin;
 mod version;
+pub mod zone_query;
 
+use self::completions::Completions;
+use self::config_file::ConfigFile;
+pub use self::repo_flags::RepoFlags;
 use self::version::VersionString;
+use self::zone_query::ZoneQuery;
 
 /// These **options** represent a parsed, error-checked versions of the
 /// user’s command-line options.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug)]
 pub struct Options {
     /// The action to perform when encountering a directory rather than a
@@ -130,6 +149,38 @@ pub struct Options {
 
     /// Whether to read file names from stdin instead of the command-line
     pub stdin: FilesInput,
+
+    /// Glob patterns to expand into extra file arguments.
+    pub globs: ArgGlobs,
+
+    /// How directories get matched against the manifest.
+    pub dirs: Arc<DirOptions>,
+
+    /// Whether to list targets from the manifest alone, without looking at
+    /// the disk.
+    pub manifest_only: bool,
+
+    /// Whether to list the zones beneath each directory, with how much
+    /// space the files in each take up, instead of the directory itself.
+    pub disk_usage: bool,
+
+    /// Whether to list everything again whenever it changes.
+    pub watch: bool,
+
+    /// Where to record the directories a recursive listing has finished, so
+    /// that an interrupted one can be resumed.
+    pub journal: Option<PathBuf>,
+
+    /// Whether to get Git statuses with one `git status` run scoped to the
+    /// listed directories, rather than from the whole repository.
+    pub git_batch: bool,
+
+    /// How warnings and errors get written to stderr.
+    pub errors: ErrorFormat,
+
+    /// The flags the src root set for everyone, which are already part of
+    /// these options, kept to warn about the ones that were left out.
+    pub repo_flags: RepoFlags,
 }
 
 impl Options {
@@ -160,12 +211,73 @@ impl Options {
             return OptionsResult::Help(help);
         }
 
-        if let Some(version) = VersionString::deduce(&flags) {
-            return OptionsResult::Version(version);
+        match VersionString::deduce(&flags) {
+            Ok(Some(version)) => return OptionsResult::Version(version),
+            Ok(None) => {}
+            Err(oe) => return OptionsResult::InvalidOptions(oe),
+        }
+
+        // Anything set in the config file only fills in for what the
+        // environment doesn’t set, and the flags win over both
+        let vars = &match ConfigFile::deduce(vars) {
+            Ok(config) => config.under(vars),
+            Err(oe) => return OptionsResult::InvalidOptions(oe),
+        };
+
+        match Completions::deduce(&flags) {
+            Ok(Some(completions)) => return OptionsResult::Completions(completions),
+            Ok(None) => {}
+            Err(oe) => return OptionsResult::InvalidOptions(oe),
+        }
+
+        match ZoneQuery::deduce(&flags) {
+            Ok(Some(query)) => {
+                let errors = match flags.has(&flags::JSON_LINES) {
+                    Ok(json_lines) => deduce_errors(&flags, json_lines),
+                    Err(oe) => Err(oe),
+                };
+                match (DirOptions::deduce(&flags, vars), errors) {
+                    (Ok(mut dirs), Ok(errors)) => {
+                        // The owner, type, and description are fields of the entry
+                        dirs.entry_metadata = matches!(query, ZoneQuery::Describe(_));
+                        return OptionsResult::ZoneQuery(query, dirs, errors);
+                    }
+                    (Err(oe), _) | (_, Err(oe)) => return OptionsResult::InvalidOptions(oe),
+                }
+            }
+            Ok(None) => {}
+            Err(oe) => return OptionsResult::InvalidOptions(oe),
+        }
+
+        match selftest::deduce(&flags, &frees) {
+            Ok(Some(dir)) => return OptionsResult::SelfTest(dir),
+            Ok(None) => {}
+            Err(oe) => return OptionsResult::InvalidOptions(oe),
         }
 
+        match flags.has(&flags::DAEMON) {
+            Ok(true) => return OptionsResult::Daemon,
+            Ok(false) => {}
+            Err(oe) => return OptionsResult::InvalidOptions(oe),
+        }
+
+        // Flags the src root sets for everyone go before the user’s own, so
+        // that theirs win
+        let repo_flags = RepoFlags::deduce(&flags, &frees, vars);
+        let repo_args = repo_flags.args.iter().map(OsString::as_os_str);
+        let flags = match flags::ALL_ARGS.parse(repo_args, strictness) {
+            Ok(repo) => repo.flags.followed_by(flags),
+            Err(pe) => return OptionsResult::InvalidOptions(OptionsError::Parse(pe)),
+        };
+
         match Self::deduce(&flags, vars) {
-            Ok(options) => OptionsResult::Ok(options, frees),
+            Ok(options) => OptionsResult::Ok(
+                Self {
+                    repo_flags,
+                    ..options
+                },
+                frees,
+            ),
             Err(oe) => OptionsResult::InvalidOptions(oe),
         }
     }
@@ -175,7 +287,8 @@ impl Options {
     /// results will end up being displayed.
     #[must_use]
     pub fn should_scan_for_git(&self) -> bool {
-        if self.filter.git_ignore == GitIgnore::CheckAndIgnore {
+        if self.filter.git_ignore == GitIgnore::CheckAndIgnore || self.dirs.ghosts_respect_gitignore
+        {
             return true;
         }
 
@@ -191,7 +304,7 @@ impl Options {
                         ..
                     },
                 ..
-            }) => table.columns.git,
+            }) => table.columns.git || table.columns.zone_health || table.url_template.is_some(),
             _ => false,
         }
     }
@@ -201,18 +314,33 @@ impl Options {
     fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         if cfg!(not(feature = "git"))
             && matches
-                .has_where_any(|f| f.matches(&flags::GIT) || f.matches(&flags::GIT_IGNORE))
+                .has_where_any(|f| {
+                    f.matches(&flags::GIT)
+                        || f.matches(&flags::GIT_IGNORE)
+                        || f.matches(&flags::GHOST_RESPECT_GITIGNORE)
+                })
                 .is_some()
         {
             return Err(OptionsError::Unsupported(String::from(
-                "Options --git and --git-ignore can't be used because `git` feature was disabled in this build of exa",
+                "Options --git, --git-ignore and --ghost-respect-gitignore can't be used because `git` feature was disabled in this build of exa",
             )));
         }
         let view = View::deduce(matches, vars)?;
         let dir_action = DirAction::deduce(matches, matches!(view.mode, Mode::Details(_)))?;
-        let filter = FileFilter::deduce(matches)?;
+        let filter = FileFilter::deduce(matches, vars)?;
         let theme = ThemeOptions::deduce(matches, vars)?;
         let stdin = FilesInput::deduce(matches, vars)?;
+        let globs = ArgGlobs::deduce(matches)?;
+        let mut dirs = DirOptions::deduce(matches, vars)?;
+        dirs.entry_metadata = keeps_entry_metadata(&filter, &view);
+        let dirs = Arc::new(dirs);
+        let manifest_only = matches.has(&flags::MANIFEST_ONLY)?;
+        let disk_usage = matches.has(&flags::DU)?;
+        let watch = matches.has(&flags::WATCH)?;
+        let journal = deduce_journal(matches, dir_action, watch)?;
+        let git_batch = matches.has(&flags::GIT_BATCH)?
+            || vars.get(vars::WLS_GIT_BATCH).is_some_and(|s| s == "1");
+        let errors = deduce_errors(matches, view.mode == Mode::JsonLines)?;
 
         Ok(Self {
             dir_action,
@@ -220,7 +348,124 @@ impl Options {
             view,
             theme,
             stdin,
+            globs,
+            dirs,
+            manifest_only,
+            disk_usage,
+            watch,
+            journal,
+            git_batch,
+            errors,
+            repo_flags: RepoFlags::default(),
+        })
+    }
+}
+
+/// Whether the fields of the manifest’s entries have to be kept, for
+/// `--filter-zone` to match against, for the owner column to show, or
+/// for `--zone-badges` to name the zones’ types.
+fn keeps_entry_metadata(filter: &FileFilter, view: &View) -> bool {
+    if filter.zone_filter.is_some() || view.file_style.zone_badges {
+        return true;
+    }
+
+    match view.mode {
+        Mode::Details(details::Options {
+            table: Some(ref table),
+            ..
+        })
+        | Mode::GridDetails(grid_details::Options {
+            details:
+                details::Options {
+                    table: Some(ref table),
+                    ..
+                },
+            ..
         })
+        | Mode::Delimited(delimited::Options { ref table, .. }) => table.columns.owner,
+        Mode::Template(ref template) => template.uses(Field::Owner),
+        _ => false,
+    }
+}
+
+/// Determines where the journal goes, if anywhere. It only means anything
+/// for a recursive listing that isn’t a tree, since a tree is drawn all at
+/// once, and a listing that runs forever never gets to the end.
+fn deduce_journal(
+    matches: &MatchedFlags<'_>,
+    dir_action: DirAction,
+    watch: bool,
+) -> Result<Option<PathBuf>, OptionsError> {
+    let Some(path) = matches.get(&flags::JOURNAL)? else {
+        return Ok(None);
+    };
+
+    if !dir_action.recurse_options().is_some_and(|r| !r.tree) {
+        return Err(OptionsError::Useless(
+            &flags::JOURNAL,
+            false,
+            &flags::RECURSE,
+        ));
+    }
+    if watch {
+        return Err(OptionsError::Conflict(&flags::JOURNAL, &flags::WATCH));
+    }
+
+    Ok(Some(PathBuf::from(path)))
+}
+
+/// How warnings and errors get written to stderr, judging by the arguments
+/// alone, for when they couldn’t be parsed and there are no options to ask.
+/// Like `deduce_errors`, this follows `--json-lines` unless `--errors` is
+/// given, but anything other than `json` counts as text, as there’s no way
+/// left to complain about it.
+pub fn errors_from_args<'a>(args: impl IntoIterator<Item = &'a OsStr>) -> ErrorFormat {
+    let mut given = None;
+    let mut json_lines = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let word = match arg.to_str() {
+            Some("--") | None => break,
+            Some("--json-lines") => {
+                json_lines = true;
+                continue;
+            }
+            Some("--errors") => args.next().and_then(OsStr::to_str),
+            Some(arg) => arg.strip_prefix("--errors="),
+        };
+        match word {
+            Some("json") => given = Some(ErrorFormat::Json),
+            Some(_) => given = Some(ErrorFormat::Text),
+            None => {}
+        }
+    }
+
+    given.unwrap_or(if json_lines {
+        ErrorFormat::Json
+    } else {
+        ErrorFormat::Text
+    })
+}
+
+/// Determines how warnings and errors get written to stderr. Unless the flag
+/// says otherwise, they’re JSON when the entries are.
+fn deduce_errors(
+    matches: &MatchedFlags<'_>,
+    json_lines: bool,
+) -> Result<ErrorFormat, OptionsError> {
+    let Some(word) = matches.get(&flags::ERRORS)? else {
+        return Ok(if json_lines {
+            ErrorFormat::Json
+        } else {
+            ErrorFormat::Text
+        });
+    };
+
+    match word.to_str() {
+        Some("text") => Ok(ErrorFormat::Text),
+        Some("json") => Ok(ErrorFormat::Json),
+        _ => Err(OptionsError::BadArgument(&flags::ERRORS, word.into())),
     }
 }
 
@@ -247,6 +492,23 @@ pub enum OptionsResult<'args> {
 
     /// One of the arguments was `--version`, so display the version number.
     Version(VersionString),
+
+    /// One of the arguments was `--completions`, so print a completion
+    /// script for that shell.
+    Completions(Completions),
+
+    /// One of the arguments asked a question about the manifest, such as
+    /// `--zone-complete`, so answer it instead of listing files, writing any
+    /// errors in the given format.
+    ZoneQuery(ZoneQuery, DirOptions, ErrorFormat),
+
+    /// The arguments were `selftest --create-fixture DIR`, so build a fixture
+    /// src root in that directory and check how it gets listed.
+    SelfTest(PathBuf),
+
+    /// One of the arguments was `--daemon`, so keep manifests parsed for
+    /// other invocations until killed.
+    Daemon,
 }
 
 #[cfg(test)]
@@ -294,4 +556,46 @@ pub mod test {
 
         result
     }
+
+    #[test]
+    fn errors_follow_the_entries_unless_told() {
+        use super::{deduce_errors, flags, ErrorFormat, OptionsError};
+        use std::ffi::OsString;
+
+        static TEST_ARGS: &[&Arg] = &[&flags::ERRORS];
+        let check =
+            |args: &[&str], json_lines: bool, expected: Result<ErrorFormat, OptionsError>| {
+                for result in parse_for_test(args, TEST_ARGS, Strictnesses::Both, |mf| {
+                    deduce_errors(mf, json_lines)
+                }) {
+                    assert_eq!(result, expected);
+                }
+            };
+
+        check(&[], false, Ok(ErrorFormat::Text));
+        check(&[], true, Ok(ErrorFormat::Json));
+        check(&["--errors=json"], false, Ok(ErrorFormat::Json));
+        check(&["--errors=text"], true, Ok(ErrorFormat::Text));
+        check(
+            &["--errors=xml"],
+            false,
+            Err(OptionsError::BadArgument(
+                &flags::ERRORS,
+                OsString::from("xml"),
+            )),
+        );
+    }
+
+    #[test]
+    fn errors_from_args_without_parsing() {
+        use super::{errors_from_args, ErrorFormat};
+
+        let check = |args: &[&str]| errors_from_args(args.iter().map(OsStr::new));
+        assert_eq!(check(&["--zone-of", "x"]), ErrorFormat::Text);
+        assert_eq!(check(&["--bogus", "--errors=json"]), ErrorFormat::Json);
+        assert_eq!(check(&["--errors", "json", "--bogus"]), ErrorFormat::Json);
+        assert_eq!(check(&["--json-lines", "--bogus"]), ErrorFormat::Json);
+        assert_eq!(check(&["--json-lines", "--errors=text"]), ErrorFormat::Text);
+        assert_eq!(check(&["--", "--errors=json"]), ErrorFormat::Text);
+    }
 }
@@ -509,6 +509,25 @@ impl MatchedFlags<'_> {
     pub fn is_strict(&self) -> bool {
         self.strictness == Strictness::ComplainAboutRedundantArguments
     }
+
+    /// Whether every flag, with or without a value, satisfies the predicate.
+    pub fn all<P>(&self, predicate: P) -> bool
+    where
+        P: Fn(&Flag) -> bool,
+    {
+        self.flags.iter().all(|tuple| predicate(&tuple.0))
+    }
+
+    /// These flags with the given ones after them, so that the given ones
+    /// win, checked as strictly as the given ones would be.
+    pub fn followed_by(self, later: Self) -> Self {
+        let mut flags = self.flags;
+        flags.extend(later.flags);
+        Self {
+            flags,
+            strictness: later.strictness,
+        }
+    }
 }
 
 /// A problem with the user’s input that meant it couldn’t be parsed into a
@@ -0,0 +1,156 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The flags a src root sets for everyone listing inside it, from the
+//! `flags` in the `wls.toml` next to its manifest.
+//!
+//! Each one has to be a single argument, such as `--sort=zone`, and one of
+//! the flags that only change how the listing looks. Anything else is left
+//! out with a warning naming the file, rather than stopping the listing
+//! with an error the user never typed.
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use crate::fs::{self, ManifestLocation};
+use crate::options::parser::{MatchedFlags, Strictness};
+use crate::options::vars::Vars;
+use crate::options::{flags, OptionsError};
+use crate::warnings::{self, Kind};
+
+/// The flags set for the src root of the first listed path, which go before
+/// the ones on the command line so that those can override them.
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct RepoFlags {
+    /// The `wls.toml` they came from.
+    pub path: PathBuf,
+
+    /// The flags that can be used.
+    pub args: Vec<OsString>,
+
+    /// Why each of the others was left out.
+    pub problems: Vec<String>,
+}
+
+impl RepoFlags {
+    /// Finds the flags set for the src root of the first listed path, or of
+    /// the current directory if none are, going by the manifest the command
+    /// line and the environment point to.
+    pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, frees: &[&OsStr], vars: &V) -> Self {
+        let Ok(manifest) = ManifestLocation::deduce(matches, vars) else {
+            return Self::default();
+        };
+
+        let start = frees.first().map_or(Path::new("."), Path::new);
+        let Some((path, config)) = fs::find_repo_config(start, &manifest) else {
+            return Self::default();
+        };
+
+        let mut repo_flags = Self {
+            path,
+            args: Vec::new(),
+            problems: Vec::new(),
+        };
+        for arg in config.flags {
+            match check(&arg) {
+                Ok(()) => repo_flags.args.push(arg.into()),
+                Err(problem) => repo_flags.problems.push(problem),
+            }
+        }
+
+        repo_flags
+    }
+
+    /// Warns about each flag that was left out.
+    pub fn warn(&self) {
+        for problem in &self.problems {
+            if !warnings::write_json(Kind::RepoConfig, self.path.display(), problem) {
+                warnings::write_text(format_args!("{}: {problem}", self.path.display()));
+            }
+        }
+    }
+}
+
+/// Checks that a flag from the file parses on its own, and can be set for
+/// everyone.
+fn check(arg: &str) -> Result<(), String> {
+    let matches = flags::ALL_ARGS
+        .parse([OsStr::new(arg)], Strictness::UseLastArguments)
+        .map_err(|e| format!("ignoring {arg:?}: {}", OptionsError::Parse(e)))?;
+
+    if !matches.frees.is_empty() {
+        return Err(format!("ignoring {arg:?}, which isn’t a flag"));
+    }
+
+    if !matches
+        .flags
+        .all(|flag| flags::REPO_ARGS.iter().any(|arg| flag.matches(arg)))
+    {
+        return Err(format!(
+            "ignoring {arg:?}, which only the command line can set"
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::filter::{FileFilterFlags, SortCase, SortField};
+    use crate::options::{Options, OptionsResult};
+
+    #[test]
+    fn display_flags() {
+        assert_eq!(check("--sort=zone"), Ok(()));
+        assert_eq!(check("-lG"), Ok(()));
+        assert_eq!(check("--zone-colors"), Ok(()));
+    }
+
+    #[test]
+    fn other_flags() {
+        assert!(check("--daemon").is_err());
+        assert!(check("--manifest=/elsewhere/zones.json").is_err());
+        assert!(check("-la").is_err());
+    }
+
+    #[test]
+    fn not_what_gets_listed() {
+        assert!(check("--tree").is_err());
+        assert!(check("-lT").is_err());
+        assert!(check("--recurse").is_err());
+    }
+
+    #[test]
+    fn not_on_their_own() {
+        assert!(check("--sort").is_err());
+        assert!(check("--sort zone").is_err());
+        assert!(check("areas").is_err());
+    }
+
+    #[test]
+    fn go_before_the_command_line() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let meta = dir.join("src").join(".meta");
+        std::fs::create_dir_all(&meta).unwrap();
+        std::fs::write(meta.join("manifest.json"), "{}").unwrap();
+        std::fs::write(
+            meta.join("wls.toml"),
+            "flags = ['--sort=zone', '--reverse', '--daemon']\n",
+        )
+        .unwrap();
+
+        let src = dir.join("src");
+        let args = [OsStr::new("--sort=name"), src.as_os_str()];
+        let OptionsResult::Ok(options, _) = Options::parse(args, &None) else {
+            panic!("the options should parse");
+        };
+        assert_eq!(options.filter.sort_field, SortField::Name(SortCase::AaBbCc));
+        assert!(options.filter.flags.contains(&FileFilterFlags::Reverse));
+        assert_eq!(
+            options.repo_flags.path,
+            meta.canonicalize().unwrap().join("wls.toml")
+        );
+        assert_eq!(options.repo_flags.problems.len(), 1);
+    }
+}
@@ -0,0 +1,81 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The `wls selftest` developer mode, which builds a fixture src root and
+//! checks how it gets listed instead of listing anything the user asked for.
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+
+use crate::options::parser::MatchedFlags;
+use crate::options::{flags, OptionsError};
+
+/// The word that turns the rest of the command line into a self-test.
+const SELFTEST: &str = "selftest";
+
+/// Determines whether to run the self-test, and where to create its fixture.
+/// A lone `selftest` argument without `--create-fixture` is still listed as
+/// a file, so a directory with that name can be looked at like any other.
+pub fn deduce(
+    matches: &MatchedFlags<'_>,
+    frees: &[&OsStr],
+) -> Result<Option<PathBuf>, OptionsError> {
+    let Some(dir) = matches.get(&flags::CREATE_FIXTURE)? else {
+        return Ok(None);
+    };
+
+    if frees != [OsStr::new(SELFTEST)] {
+        return Err(OptionsError::Unsupported(String::from(
+            "Option --create-fixture can only be used as `wls selftest --create-fixture DIR`",
+        )));
+    }
+
+    Ok(Some(PathBuf::from(dir)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::options::{Options, OptionsResult};
+    use std::ffi::OsStr;
+    use std::path::PathBuf;
+
+    #[test]
+    fn selftest() {
+        let args = vec![
+            OsStr::new("selftest"),
+            OsStr::new("--create-fixture"),
+            OsStr::new("/tmp/fixture"),
+        ];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::SelfTest(ref dir) if dir == &PathBuf::from("/tmp/fixture")
+        ));
+    }
+
+    #[test]
+    fn fixture_without_selftest() {
+        let args = vec![OsStr::new("--create-fixture"), OsStr::new("/tmp/fixture")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(opts, OptionsResult::InvalidOptions(_)));
+    }
+
+    #[test]
+    fn fixture_with_other_files() {
+        let args = vec![
+            OsStr::new("selftest"),
+            OsStr::new("src"),
+            OsStr::new("--create-fixture=/tmp/fixture"),
+        ];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(opts, OptionsResult::InvalidOptions(_)));
+    }
+
+    #[test]
+    fn directory_called_selftest() {
+        let args = vec![OsStr::new("selftest")];
+        let opts = Options::parse(args, &None);
+        assert!(
+            matches!(opts, OptionsResult::Ok(_, ref frees) if frees == &[OsStr::new("selftest")])
+        );
+    }
+}
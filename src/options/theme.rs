@@ -4,10 +4,11 @@
 // SPDX-FileCopyrightText: 2023-2024 Christina Sørensen, eza contributors
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
-use crate::options::parser::MatchedFlags;
+use crate::options::parser::{Arg, MatchedFlags};
 use crate::options::{flags, vars, OptionsError, Vars};
 use crate::output::color_scale::ColorScaleOptions;
-use crate::theme::{Definitions, Options, UseColours};
+use crate::theme::{Definitions, Options, Palette, UseColours};
+use log::warn;
 use std::path::PathBuf;
 
 use super::config::ThemeConfig;
@@ -15,8 +16,10 @@ use super::config::ThemeConfig;
 impl Options {
     pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         let use_colours = UseColours::deduce(matches, vars)?;
+        let stderr_colours = UseColours::deduce_stderr(matches, vars)?;
         let colour_scale = ColorScaleOptions::deduce(matches, vars)?;
         let theme_config = ThemeConfig::deduce(vars);
+        let palette = Palette::deduce(matches, vars)?;
 
         let definitions = if use_colours == UseColours::Never {
             Definitions::default()
@@ -26,16 +29,44 @@ impl Options {
 
         Ok(Self {
             use_colours,
+            stderr_colours,
             colour_scale,
             definitions,
             theme_config,
+            palette,
         })
     }
 }
 
+impl Palette {
+    /// Determines which built-in palette to use from `--palette`, falling
+    /// back to `WLS_PALETTE`. Returns `None` if neither is set, so that one
+    /// set in the theme file can be used instead.
+    fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Option<Self>, OptionsError> {
+        if let Some(word) = matches.get(&flags::PALETTE)? {
+            return match word.to_str().and_then(Self::from_name) {
+                Some(palette) => Ok(Some(palette)),
+                None => Err(OptionsError::BadArgument(&flags::PALETTE, word.into())),
+            };
+        }
+
+        let Some(name) = vars.get(vars::WLS_PALETTE) else {
+            return Ok(None);
+        };
+        let palette = name.to_str().and_then(Self::from_name);
+        if palette.is_none() {
+            warn!("Ignoring unknown palette {name:?} in {}", vars::WLS_PALETTE);
+        }
+        Ok(palette)
+    }
+}
+
 impl ThemeConfig {
     fn deduce<V: Vars>(vars: &V) -> Option<Self> {
-        if let Some(path) = vars.get("WLS_CONFIG_DIR").or_else(|| vars.get("EZA_CONFIG_DIR")) {
+        if let Some(path) = vars
+            .get("WLS_CONFIG_DIR")
+            .or_else(|| vars.get("EZA_CONFIG_DIR"))
+        {
             let path = PathBuf::from(path);
             let theme = path.join("theme.yml");
             if theme.exists() {
@@ -74,15 +105,23 @@ impl ThemeConfig {
 
 impl UseColours {
     fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
-        let default_value = match vars.get(vars::NO_COLOR) {
-            Some(_) => Self::Never,
-            None => Self::Automatic,
-        };
+        Self::deduce_from(matches, vars, &flags::COLOR, &flags::COLOUR)
+    }
+
+    /// Determines when to colour stderr, which only follows its own flags
+    /// and the environment, not `--color`.
+    fn deduce_stderr<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
+        Self::deduce_from(matches, vars, &flags::STDERR_COLOR, &flags::STDERR_COLOUR)
+    }
 
-        let Some(word) =
-            matches.get_where(|f| f.matches(&flags::COLOR) || f.matches(&flags::COLOUR))?
-        else {
-            return Ok(default_value);
+    fn deduce_from<V: Vars>(
+        matches: &MatchedFlags<'_>,
+        vars: &V,
+        flag: &'static Arg,
+        british_flag: &'static Arg,
+    ) -> Result<Self, OptionsError> {
+        let Some(word) = matches.get_where(|f| f.matches(flag) || f.matches(british_flag))? else {
+            return Ok(Self::from_env(vars));
         };
 
         if word == "always" {
@@ -92,12 +131,28 @@ impl UseColours {
         } else if word == "never" {
             Ok(Self::Never)
         } else {
-            Err(OptionsError::BadArgument(&flags::COLOR, word.into()))
+            Err(OptionsError::BadArgument(flag, word.into()))
+        }
+    }
+
+    /// What to do when no flag says: `NO_COLOR` turns colours off, then
+    /// `CLICOLOR_FORCE` turns them on and `CLICOLOR=0` turns them off.
+    fn from_env<V: Vars>(vars: &V) -> Self {
+        let is_zero = |name| vars.get(name).is_some_and(|value| value == "0");
+
+        if vars.get(vars::NO_COLOR).is_some() {
+            Self::Never
+        } else if vars.get(vars::CLICOLOR_FORCE).is_some() && !is_zero(vars::CLICOLOR_FORCE) {
+            Self::Always
+        } else if is_zero(vars::CLICOLOR) {
+            Self::Never
+        } else {
+            Self::Automatic
         }
     }
 }
 
-/// Bundled LS_COLORS compiled from dircolors source at build time.
+/// Bundled `LS_COLORS` compiled from dircolors source at build time.
 const BUNDLED_LS_COLORS: &str = include_str!(concat!(env!("OUT_DIR"), "/ls_colors.txt"));
 
 impl Definitions {
@@ -127,8 +182,11 @@ mod terminal_test {
     static TEST_ARGS: &[&Arg] = &[
         &flags::COLOR,
         &flags::COLOUR,
+        &flags::STDERR_COLOR,
+        &flags::STDERR_COLOUR,
         &flags::COLOR_SCALE,
         &flags::COLOUR_SCALE,
+        &flags::PALETTE,
     ];
 
     #[allow(unused_macro_rules)]
@@ -219,6 +277,37 @@ mod terminal_test {
         }
     }
 
+    struct EnvVars(&'static [(&'static str, &'static str)]);
+
+    impl Vars for EnvVars {
+        fn get(&self, name: &'static str) -> Option<OsString> {
+            self.0
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| OsString::from(v))
+        }
+    }
+
+    /// Lets the `test!` macro call `UseColours::deduce_stderr`.
+    struct StderrColours;
+
+    impl StderrColours {
+        fn deduce<V: Vars>(
+            matches: &MatchedFlags<'_>,
+            vars: &V,
+        ) -> Result<UseColours, OptionsError> {
+            UseColours::deduce_stderr(matches, vars)
+        }
+    }
+
+    struct PaletteVar(&'static str);
+
+    impl Vars for PaletteVar {
+        fn get(&self, name: &'static str) -> Option<OsString> {
+            (name == vars::WLS_PALETTE).then(|| OsString::from(self.0))
+        }
+    }
+
     // Default
     test!(empty:         UseColours <- [], MockVars::empty();                     Both => Ok(UseColours::Automatic));
     test!(empty_with_no_color: UseColours <- [], MockVars::with_no_color();             Both => Ok(UseColours::Never));
@@ -247,6 +336,29 @@ mod terminal_test {
     test!(overridden_6:  UseColours <- ["--color=auto",  "--colour=never"], MockVars::empty();  Complain => err OptionsError::Duplicate(Flag::Long("color"),  Flag::Long("colour")));
     test!(overridden_7:  UseColours <- ["--colour=auto", "--color=never"], MockVars::empty();   Complain => err OptionsError::Duplicate(Flag::Long("colour"), Flag::Long("color")));
     test!(overridden_8:  UseColours <- ["--color=auto",  "--color=never"], MockVars::empty();   Complain => err OptionsError::Duplicate(Flag::Long("color"),  Flag::Long("color")));
+
+    // CLICOLOR and CLICOLOR_FORCE
+    test!(clicolor_off:       UseColours <- [], EnvVars(&[("CLICOLOR", "0")]);                     Both => Ok(UseColours::Never));
+    test!(clicolor_on:        UseColours <- [], EnvVars(&[("CLICOLOR", "1")]);                     Both => Ok(UseColours::Automatic));
+    test!(clicolor_force:     UseColours <- [], EnvVars(&[("CLICOLOR", "0"), ("CLICOLOR_FORCE", "1")]); Both => Ok(UseColours::Always));
+    test!(clicolor_force_off: UseColours <- [], EnvVars(&[("CLICOLOR_FORCE", "0")]);               Both => Ok(UseColours::Automatic));
+    test!(no_color_wins:      UseColours <- [], EnvVars(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]); Both => Ok(UseColours::Never));
+    test!(flag_beats_env:     UseColours <- ["--color=always"], EnvVars(&[("CLICOLOR", "0")]);     Both => Ok(UseColours::Always));
+
+    // --stderr-colour
+    test!(stderr_default:     StderrColours <- [], MockVars::empty();                          Both => Ok(UseColours::Automatic));
+    test!(stderr_own_flag:    StderrColours <- ["--stderr-color=never"], MockVars::empty();    Both => Ok(UseColours::Never));
+    test!(stderr_not_stdout:  StderrColours <- ["--color=always"], MockVars::empty();          Both => Ok(UseColours::Automatic));
+    test!(stderr_env:         StderrColours <- [], EnvVars(&[("CLICOLOR_FORCE", "1")]);        Both => Ok(UseColours::Always));
+    test!(stderr_error:       StderrColours <- ["--stderr-colour=loud"], MockVars::empty();    Both => err OptionsError::BadArgument(&flags::STDERR_COLOR, OsString::from("loud")));
+
+    // --palette
+    test!(palette_none:     Palette <- [], MockVars::empty();                            Both => Ok(None));
+    test!(palette_flag:     Palette <- ["--palette=deuteranopia"], MockVars::empty();    Both => Ok(Some(Palette::Deuteranopia)));
+    test!(palette_env:      Palette <- [], PaletteVar("tritanopia");                     Both => Ok(Some(Palette::Tritanopia)));
+    test!(palette_beats_env: Palette <- ["--palette", "protanopia"], PaletteVar("default"); Both => Ok(Some(Palette::Protanopia)));
+    test!(palette_bad_env:  Palette <- [], PaletteVar("sepia");                          Both => Ok(None));
+    test!(palette_error:    Palette <- ["--palette=sepia"], MockVars::empty();           Both => err OptionsError::BadArgument(&flags::PALETTE, OsString::from("sepia")));
 }
 
 #[cfg(test)]
@@ -276,6 +388,7 @@ mod bundled_ls_colors_test {
     }
 
     #[test]
+    #[allow(clippy::const_is_empty)]
     fn bundled_ls_colors_is_non_empty() {
         assert!(!BUNDLED_LS_COLORS.is_empty());
     }
@@ -0,0 +1,67 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+use crate::fs::timings::TimingsMode;
+use crate::options::parser::MatchedFlags;
+use crate::options::{flags, OptionsError};
+
+/// Determine whether to report timings, and in how much detail. A bare
+/// `--timings` only reports the totals for each phase.
+pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Option<TimingsMode>, OptionsError> {
+    let Some(word) = matches.get(&flags::TIMINGS)? else {
+        return Ok(None);
+    };
+
+    match word.to_str() {
+        Some("summary") => Ok(Some(TimingsMode::Summary)),
+        Some("verbose") => Ok(Some(TimingsMode::Verbose)),
+        _ => Err(OptionsError::BadArgument(&flags::TIMINGS, word.into())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::test::parse_for_test;
+    use crate::options::test::Strictnesses::*;
+    use std::ffi::OsString;
+
+    static TEST_ARGS: &[&crate::options::parser::Arg] = &[&flags::TIMINGS];
+
+    fn deduce_from(args: &[&str]) -> Vec<Result<Option<TimingsMode>, OptionsError>> {
+        parse_for_test(args, TEST_ARGS, Both, deduce)
+    }
+
+    #[test]
+    fn absent() {
+        for result in deduce_from(&[]) {
+            assert_eq!(result, Ok(None));
+        }
+    }
+
+    #[test]
+    fn bare_flag_is_a_summary() {
+        for result in deduce_from(&["--timings"]) {
+            assert_eq!(result, Ok(Some(TimingsMode::Summary)));
+        }
+    }
+
+    #[test]
+    fn verbose() {
+        for result in deduce_from(&["--timings=verbose"]) {
+            assert_eq!(result, Ok(Some(TimingsMode::Verbose)));
+        }
+    }
+
+    #[test]
+    fn bad_mode() {
+        for result in deduce_from(&["--timings=loud"]) {
+            assert_eq!(
+                result,
+                Err(OptionsError::BadArgument(
+                    &flags::TIMINGS,
+                    OsString::from("loud")
+                ))
+            );
+        }
+    }
+}
@@ -5,6 +5,7 @@
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
 use std::ffi::OsString;
+use std::path::PathBuf;
 
 // General variables
 
@@ -24,6 +25,14 @@ pub static TIME_STYLE: &str = "TIME_STYLE";
 /// See: <https://no-color.org/>
 pub static NO_COLOR: &str = "NO_COLOR";
 
+/// Environment variable used to disable colors when set to `0`.
+/// See: <https://bixense.com/clicolors/>
+pub static CLICOLOR: &str = "CLICOLOR";
+
+/// Environment variable used to enable colors even when not writing to a
+/// terminal, when set to anything but `0`.
+pub static CLICOLOR_FORCE: &str = "CLICOLOR_FORCE";
+
 // exa-specific variables
 
 /// Environment variable used to colour exa’s interface when colours are
@@ -85,6 +94,79 @@ pub static WLS_STDIN_SEPARATOR: &str = "WLS_STDIN_SEPARATOR";
 /// Any non-empty value will disable sigils.
 pub static WLS_NO_SIGILS: &str = "WLS_NO_SIGILS";
 
+/// Environment variable used to colour each zone by a hash of its target
+/// path, the same as `--zone-colors`. Any non-empty value will turn it on.
+pub static WLS_ZONE_COLORS: &str = "WLS_ZONE_COLORS";
+
+/// Environment variable used to set the command `--materialize` prints for
+/// each ghost zone. `{target}` is replaced by the zone’s target path, such
+/// as `//areas/tools/dev`, and `{path}` by the same path without the leading
+/// slashes.
+pub static WLS_MATERIALIZE_COMMAND: &str = "WLS_MATERIALIZE_COMMAND";
+
+/// Environment variable used to set where `--hyperlink` links zones to, the
+/// same as `--zone-url`. `{target}` is replaced by the zone’s target path,
+/// and `{path}` by the same path without the leading slashes.
+pub static WLS_ZONE_URL: &str = "WLS_ZONE_URL";
+
+/// Environment variable used to set the glyph put after ghost names in the
+/// grid view, for ghosts that aren’t zones.
+pub static WLS_GHOST_MARKER: &str = "WLS_GHOST_MARKER";
+
+/// Environment variable used to set the glyph put after ghost zone names in
+/// the grid view.
+pub static WLS_GHOST_ZONE_MARKER: &str = "WLS_GHOST_ZONE_MARKER";
+
+/// Environment variable used to hide ghosts by default when set to `0`, the
+/// same as `--no-ghosts`, or to show them when set to `1`, whatever the src
+/// root’s `wls.toml` says.
+pub static WLS_GHOSTS: &str = "WLS_GHOSTS";
+
+/// Environment variable used to set how many ghosts are shown when listing
+/// the src root itself, where there can be thousands of them. Set it to `0`
+/// to show all of them, the same as `--all-ghosts`.
+pub static WLS_GHOST_LIMIT: &str = "WLS_GHOST_LIMIT";
+
+/// Environment variable used to set where the manifest is, the same as
+/// `--manifest`. A relative path is looked for in every ancestor of the
+/// listed directory; an absolute path is used as-is.
+pub static WLS_MANIFEST_PATH: &str = "WLS_MANIFEST_PATH";
+
+/// Environment variable used to stop the manifest being looked for at all,
+/// the same as `--ignore-manifest`. Any non-empty value will turn it on.
+pub static WLS_IGNORE_MANIFEST: &str = "WLS_IGNORE_MANIFEST";
+
+/// Environment variable used to set what target paths start with, the same
+/// as `--target-prefix`.
+pub static WLS_TARGET_PREFIX: &str = "WLS_TARGET_PREFIX";
+
+/// Environment variable used to set whether directories have to match the
+/// manifest’s case, the same as `--zone-case`.
+pub static WLS_ZONE_CASE: &str = "WLS_ZONE_CASE";
+
+/// Environment variable used to choose a built-in palette, the same as
+/// `--palette`, such as `deuteranopia`.
+pub static WLS_PALETTE: &str = "WLS_PALETTE";
+
+/// Environment variable used to get Git statuses the same way as
+/// `--git-batch` when set to `1`.
+pub static WLS_GIT_BATCH: &str = "WLS_GIT_BATCH";
+
+/// Environment variable used to set how many threads fetch file metadata
+/// concurrently, which hides the latency of network filesystems. Set it to
+/// `0` to fetch metadata one file at a time instead.
+pub static WLS_STAT_THREADS: &str = "WLS_STAT_THREADS";
+
+/// Environment variable used to set the Unix socket that `--daemon` listens
+/// on, and that every other invocation asks for manifests over. Without it,
+/// it’s `wls.sock` in `$XDG_RUNTIME_DIR`.
+pub static WLS_DAEMON_SOCKET: &str = "WLS_DAEMON_SOCKET";
+
+/// Environment variable used to set the directory the user’s own files for
+/// wls are in, such as `theme.yml`, `config.toml`, and `ignore-ghosts`.
+pub static EZA_CONFIG_DIR: &str = "EZA_CONFIG_DIR";
+pub static WLS_CONFIG_DIR: &str = "WLS_CONFIG_DIR";
+
 /// Environment variable used to choose how windows attributes are displayed.
 /// Short will display a single character for each set attribute, long will
 /// display a comma separated list of descriptions.
@@ -109,6 +191,15 @@ pub trait Vars {
             None => self.get(fallback).and(Some(fallback)),
         }
     }
+
+    /// The directory the user’s own files for wls are in, if there is one.
+    /// Only the environment can say where it is here; the real environment
+    /// falls back to the platform’s config directory, so that nothing else
+    /// reads the user’s own files by accident.
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.get_with_fallback(WLS_CONFIG_DIR, EZA_CONFIG_DIR)
+            .map(PathBuf::from)
+    }
 }
 
 // Test impl that just returns the value it has.
@@ -117,6 +208,10 @@ impl Vars for Option<OsString> {
     fn get(&self, _name: &'static str) -> Option<OsString> {
         self.clone()
     }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -142,8 +237,12 @@ impl Vars for MockVars {
             "EZA_COLORS" | "LS_COLORS" | "EXA_COLORS" | "WLS_COLORS" => Some(self.colors.clone()),
             "EXA_DEBUG" | "EZA_DEBUG" | "WLS_DEBUG" => Some(self.debug.clone()),
             "EXA_GRID_ROWS" | "EZA_GRID_ROWS" | "WLS_GRID_ROWS" => Some(self.grid_rows.clone()),
-            "EXA_ICON_SPACING" | "EZA_ICON_SPACING" | "WLS_ICON_SPACING" => Some(self.icon_spacing.clone()),
-            "EXA_MIN_LUMINANCE" | "EZA_MIN_LUMINANCE" | "WLS_MIN_LUMINANCE" => Some(self.luminance.clone()),
+            "EXA_ICON_SPACING" | "EZA_ICON_SPACING" | "WLS_ICON_SPACING" => {
+                Some(self.icon_spacing.clone())
+            }
+            "EXA_MIN_LUMINANCE" | "EZA_MIN_LUMINANCE" | "WLS_MIN_LUMINANCE" => {
+                Some(self.luminance.clone())
+            }
             "EZA_ICONS_AUTO" | "WLS_ICONS_AUTO" => Some(self.icons.clone()),
             "COLUMNS" => Some(self.columns.clone()),
             "NO_COLOR" => Some(self.no_colors.clone()),
@@ -161,8 +260,12 @@ impl MockVars {
             "EZA_COLORS" | "LS_COLORS" | "EXA_COLORS" | "WLS_COLORS" => self.colors = value.clone(),
             "EXA_DEBUG" | "EZA_DEBUG" | "WLS_DEBUG" => self.debug = value.clone(),
             "EXA_GRID_ROWS" | "EZA_GRID_ROWS" | "WLS_GRID_ROWS" => self.grid_rows = value.clone(),
-            "EXA_ICON_SPACING" | "EZA_ICON_SPACING" | "WLS_ICON_SPACING" => self.icon_spacing = value.clone(),
-            "EXA_MIN_LUMINANCE" | "EZA_MIN_LUMINANCE" | "WLS_MIN_LUMINANCE" => self.luminance = value.clone(),
+            "EXA_ICON_SPACING" | "EZA_ICON_SPACING" | "WLS_ICON_SPACING" => {
+                self.icon_spacing = value.clone();
+            }
+            "EXA_MIN_LUMINANCE" | "EZA_MIN_LUMINANCE" | "WLS_MIN_LUMINANCE" => {
+                self.luminance = value.clone();
+            }
             "EZA_ICONS_AUTO" | "WLS_ICONS_AUTO" => self.icons = value.clone(),
             "COLUMNS" => self.columns = value.clone(),
             "NO_COLOR" => self.no_colors = value.clone(),
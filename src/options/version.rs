@@ -10,30 +10,84 @@
 
 use std::fmt;
 
+use serde::Serialize;
+
 use crate::options::flags;
 use crate::options::parser::MatchedFlags;
+use crate::options::OptionsError;
+use crate::output::Mode;
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
-pub struct VersionString;
-// There were options here once, but there aren’t anymore!
+pub struct VersionString {
+    /// Whether to print what this build can do as JSON, for wrapper tooling
+    /// to feature-detect with, rather than the human-readable banner.
+    json: bool,
+}
+
+/// The Cargo features that can be turned on when building wls.
+const FEATURES: &[(&str, bool)] = &[
+    ("git", cfg!(feature = "git")),
+    ("vendored-openssl", cfg!(feature = "vendored-openssl")),
+    ("vendored-libgit2", cfg!(feature = "vendored-libgit2")),
+    ("nix", cfg!(feature = "nix")),
+    ("nix-local", cfg!(feature = "nix-local")),
+    ("powertest", cfg!(feature = "powertest")),
+    ("nix-generated", cfg!(feature = "nix-generated")),
+];
+
+/// Everything printed by `--version --json`.
+#[derive(Serialize)]
+struct Capabilities {
+    version: &'static str,
+    git_hash: Option<&'static str>,
+    features: Vec<&'static str>,
+    output_formats: &'static [&'static str],
+}
+
+impl Capabilities {
+    fn of_this_build() -> Self {
+        let git_hash = Some(env!("WLS_GIT_HASH")).filter(|hash| !hash.is_empty());
+        let features = FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash,
+            features,
+            output_formats: Mode::FORMATS,
+        }
+    }
+}
 
 impl VersionString {
     /// Determines how to show the version, if at all, based on the user’s
-    /// command-line arguments. This one works backwards from the other
-    /// ‘deduce’ functions, returning Err if help needs to be shown.
+    /// command-line arguments.
     ///
-    /// Like --help, this doesn’t check for errors.
-    pub fn deduce(matches: &MatchedFlags<'_>) -> Option<Self> {
+    /// Like --help, this doesn’t check for errors once the version is asked
+    /// for, but `--json` on its own doesn’t do anything, so it’s an error.
+    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Option<Self>, OptionsError> {
         if matches.count(&flags::VERSION) > 0 {
-            Some(Self)
+            let json = matches.has(&flags::JSON).unwrap_or(false);
+            Ok(Some(Self { json }))
+        } else if matches.has(&flags::JSON)? {
+            Err(OptionsError::Useless(&flags::JSON, false, &flags::VERSION))
         } else {
-            None
+            Ok(None)
         }
     }
 }
 
 impl fmt::Display for VersionString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        if self.json {
+            let json =
+                serde_json::to_string(&Capabilities::of_this_build()).map_err(|_| fmt::Error)?;
+            return writeln!(f, "{json}");
+        }
+
         write!(
             f,
             "{}",
@@ -44,7 +98,10 @@ impl fmt::Display for VersionString {
 
 #[cfg(test)]
 mod test {
-    use crate::options::{Options, OptionsResult};
+    use super::VersionString;
+    use crate::options::{flags, Options, OptionsError, OptionsResult};
+    use crate::output::Mode;
+    use std::collections::BTreeSet;
     use std::ffi::OsStr;
 
     #[test]
@@ -60,4 +117,65 @@ mod test {
         let opts = Options::parse(args, &None);
         assert!(matches!(opts, OptionsResult::Version(_)));
     }
+
+    #[test]
+    fn version_json() {
+        let args = vec![OsStr::new("--version"), OsStr::new("--json")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::Version(VersionString { json: true })
+        ));
+    }
+
+    #[test]
+    fn capabilities_are_valid_json() {
+        let text = VersionString { json: true }.to_string();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            value["output_formats"].as_array().unwrap().len(),
+            Mode::FORMATS.len()
+        );
+    }
+
+    #[test]
+    fn json_needs_version() {
+        let args = vec![OsStr::new("--json"), OsStr::new(".")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::InvalidOptions(OptionsError::Useless(a, false, b))
+                if *a == flags::JSON && *b == flags::VERSION
+        ));
+    }
+
+    #[test]
+    fn output_formats_are_every_mode() {
+        // The grid is only the default when there’s a terminal to fit it to
+        let modes = [
+            &["--width", "80"][..],
+            &["--long"],
+            &["--long", "--grid"],
+            &["--oneline"],
+            &["--tree"],
+            &["--json-lines"],
+            &["--csv"],
+            &["--tsv"],
+            &["--print0"],
+            &["--format", "{name}"],
+        ];
+
+        let formats: BTreeSet<_> = modes
+            .iter()
+            .map(
+                |args| match Options::parse(args.iter().map(OsStr::new), &None) {
+                    OptionsResult::Ok(options, _) => options.view.mode.format(),
+                    result => panic!("{args:?} didn’t parse: {result:?}"),
+                },
+            )
+            .collect();
+        assert_eq!(formats, Mode::FORMATS.iter().copied().collect());
+        assert_eq!(formats.len(), Mode::FORMATS.len());
+    }
 }
@@ -10,12 +10,18 @@ use crate::fs::feature::xattr;
 use crate::options::parser::MatchedFlags;
 use crate::options::{flags, vars, NumberSource, OptionsError, Vars};
 use crate::output::color_scale::{ColorScaleMode, ColorScaleOptions};
-use crate::output::file_name::Options as FileStyle;
+use crate::output::delimited::{self, Separator};
+use crate::output::file_name::{GhostMarkers, Options as FileStyle};
 use crate::output::grid_details::{self, RowThreshold};
+use crate::output::materialize::MaterializeHint;
+use crate::output::print0;
 use crate::output::table::{
-    Columns, FlagsFormat, GroupFormat, Options as TableOptions, SizeFormat, TimeTypes, UserFormat,
+    Column, Columns, FlagsFormat, GroupFormat, Options as TableOptions, SizeFormat, TimeType,
+    TimeTypes, UserFormat,
 };
+use crate::output::template::Template;
 use crate::output::time::TimeFormat;
+use crate::output::url_template::UrlTemplate;
 use crate::output::{details, grid, Mode, TerminalWidth, View};
 
 impl View {
@@ -25,7 +31,19 @@ impl View {
         let mode = Mode::deduce(matches, vars, is_tty)?;
         let deref_links = matches.has(&flags::DEREF_LINKS)?;
         let follow_links = matches.has(&flags::FOLLOW_LINKS)?;
-        let total_size = matches.has(&flags::TOTAL_SIZE)?;
+        let total_size =
+            matches.has(&flags::TOTAL_SIZE_WITHIN_ZONE)? || matches.has(&flags::TOTAL_SIZE)?;
+        let zone_summary = matches.has(&flags::ZONE_SUMMARY)?;
+        let free_space = matches.has(&flags::FREE_SPACE)?;
+        let split_ghosts = matches.has(&flags::SPLIT_GHOSTS)?;
+        if split_ghosts && matches.is_strict() && matches.has(&flags::NO_GHOSTS)? {
+            return Err(OptionsError::Useless(
+                &flags::SPLIT_GHOSTS,
+                true,
+                &flags::NO_GHOSTS,
+            ));
+        }
+        let materialize = MaterializeHint::deduce(matches, vars)?;
         let file_style = FileStyle::deduce(matches, vars, is_tty)?;
         Ok(Self {
             mode,
@@ -34,10 +52,32 @@ impl View {
             deref_links,
             follow_links,
             total_size,
+            zone_summary,
+            free_space,
+            split_ghosts,
+            materialize,
         })
     }
 }
 
+impl MaterializeHint {
+    /// Determines whether to print materialize hints, and which command to
+    /// print, which comes from the environment so teams can point it at
+    /// their own tooling.
+    fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Option<Self>, OptionsError> {
+        if !matches.has(&flags::MATERIALIZE)? {
+            return Ok(None);
+        }
+
+        let template = vars
+            .get(vars::WLS_MATERIALIZE_COMMAND)
+            .and_then(|s| s.into_string().ok())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| Self::DEFAULT_TEMPLATE.into());
+        Ok(Some(Self { template }))
+    }
+}
+
 impl Mode {
     /// Determine which viewing mode to use based on the user’s options.
     ///
@@ -52,6 +92,41 @@ impl Mode {
         vars: &V,
         is_tty: bool,
     ) -> Result<Self, OptionsError> {
+        if matches.has(&flags::JSON_LINES)? {
+            return Ok(Self::JsonLines);
+        }
+
+        if let Some(flag) =
+            matches.has_where_any(|f| f.matches(&flags::CSV) || f.matches(&flags::TSV))
+        {
+            let separator = if flag.matches(&flags::CSV) {
+                Separator::Comma
+            } else {
+                Separator::Tab
+            };
+            let table = TableOptions::deduce(matches, vars)?;
+            return Ok(Self::Delimited(delimited::Options { separator, table }));
+        }
+
+        if matches.has(&flags::PRINT0)? {
+            let ghosts =
+                matches.has_where(|f| f.matches(&flags::GHOSTS) || f.matches(&flags::NO_GHOSTS))?;
+            let ghosts = ghosts.is_some_and(|flag| flag.matches(&flags::GHOSTS));
+            return Ok(Self::Print0(print0::Options { ghosts }));
+        }
+
+        if let Some(word) = matches.get(&flags::FORMAT)? {
+            let Some(text) = word.to_str() else {
+                return Err(OptionsError::BadArgument(&flags::FORMAT, word.into()));
+            };
+            return match Template::parse(text) {
+                Ok(template) => Ok(Self::Template(template)),
+                Err(e) => Err(OptionsError::Unsupported(format!(
+                    "Bad --format template: {e}"
+                ))),
+            };
+        }
+
         let flag = matches.has_where_any(|f| {
             f.matches(&flags::LONG)
                 || f.matches(&flags::ONE_LINE)
@@ -59,10 +134,16 @@ impl Mode {
                 || f.matches(&flags::TREE)
         });
 
+        // Zone usage is only worth listing with the sizes
+        if flag.is_none() && matches.has(&flags::DU)? {
+            let details = details::Options::deduce_long(matches, vars)?;
+            return Ok(Self::Details(details));
+        }
+
         let Some(flag) = flag else {
             Self::strict_check_long_flags(matches)?;
             if is_tty {
-                let grid = grid::Options::deduce(matches)?;
+                let grid = grid::Options::deduce(matches, vars)?;
                 return Ok(Self::Grid(grid));
             }
             return Ok(Self::Lines);
@@ -105,7 +186,7 @@ impl Mode {
             return Ok(Self::Lines);
         }
 
-        let grid = grid::Options::deduce(matches)?;
+        let grid = grid::Options::deduce(matches, vars)?;
         Ok(Self::Grid(grid))
     }
 
@@ -124,6 +205,17 @@ impl Mode {
                 &flags::GROUP,
                 &flags::NUMERIC,
                 &flags::MOUNTS,
+                &flags::SYNC_DETAILS,
+                &flags::MATERIALIZED,
+                &flags::TAGS,
+                &flags::LAYER,
+                &flags::OWNERS,
+                &flags::NESTED_REPOS,
+                &flags::FILESYSTEMS,
+                &flags::URL_TEMPLATE,
+                &flags::ZONE_HEADERS,
+                &flags::STREAM,
+                &flags::ZONE_HEALTH,
             ] {
                 if matches.has(option)? {
                     return Err(OptionsError::Useless(option, false, &flags::LONG));
@@ -149,15 +241,33 @@ impl Mode {
 }
 
 impl grid::Options {
-    fn deduce(matches: &MatchedFlags<'_>) -> Result<Self, OptionsError> {
+    fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         let grid = grid::Options {
             across: matches.has(&flags::ACROSS)?,
+            ghost_markers: GhostMarkers::deduce(vars),
         };
 
         Ok(grid)
     }
 }
 
+impl GhostMarkers {
+    /// Determines which glyphs to put after ghost names, if the user has
+    /// chosen their own. Only the first character of each variable is used.
+    fn deduce<V: Vars>(vars: &V) -> Self {
+        let glyph = |name| {
+            vars.get(name)
+                .and_then(|s| s.into_string().ok())
+                .and_then(|s| s.chars().next())
+        };
+
+        Self {
+            zone: glyph(vars::WLS_GHOST_ZONE_MARKER),
+            plain: glyph(vars::WLS_GHOST_MARKER),
+        }
+    }
+}
+
 impl details::Options {
     fn deduce_tree<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
         let details = details::Options {
@@ -168,6 +278,8 @@ impl details::Options {
             mounts: matches.has(&flags::MOUNTS)?,
             color_scale: ColorScaleOptions::deduce(matches, vars)?,
             follow_links: matches.has(&flags::FOLLOW_LINKS)?,
+            zone_headers: false,
+            stream: false,
         };
 
         Ok(details)
@@ -179,6 +291,8 @@ impl details::Options {
                 return Err(OptionsError::Useless(&flags::ACROSS, true, &flags::LONG));
             } else if matches.has(&flags::ONE_LINE)? {
                 return Err(OptionsError::Useless(&flags::ONE_LINE, true, &flags::LONG));
+            } else if matches.has(&flags::STREAM)? && matches.has(&flags::TREE)? {
+                return Err(OptionsError::Conflict(&flags::STREAM, &flags::TREE));
             }
         }
 
@@ -190,6 +304,8 @@ impl details::Options {
             mounts: matches.has(&flags::MOUNTS)?,
             color_scale: ColorScaleOptions::deduce(matches, vars)?,
             follow_links: matches.has(&flags::FOLLOW_LINKS)?,
+            zone_headers: matches.has(&flags::ZONE_HEADERS)?,
+            stream: matches.has(&flags::STREAM)?,
         })
     }
 }
@@ -227,7 +343,7 @@ impl TerminalWidth {
 
 impl RowThreshold {
     fn deduce<V: Vars>(vars: &V) -> Result<Self, OptionsError> {
-                if let Some(columns) = vars
+        if let Some(columns) = vars
             .get(vars::WLS_GRID_ROWS)
             .or_else(|| vars.get_with_fallback(vars::EZA_GRID_ROWS, vars::EXA_GRID_ROWS))
             .and_then(|s| s.into_string().ok())
@@ -255,7 +371,13 @@ impl TableOptions {
         let user_format = UserFormat::deduce(matches)?;
         let group_format = GroupFormat::deduce(matches)?;
         let flags_format = FlagsFormat::deduce(vars);
-        let columns = Columns::deduce(matches, vars)?;
+        let url_template = matches
+            .get(&flags::URL_TEMPLATE)?
+            .map(|t| UrlTemplate(t.to_string_lossy().into_owned()));
+        let mut columns = Columns::deduce(matches, vars)?;
+        if columns.selected.is_none() {
+            columns.url = url_template.is_some();
+        }
         Ok(Self {
             size_format,
             time_format,
@@ -263,26 +385,30 @@ impl TableOptions {
             group_format,
             flags_format,
             columns,
+            url_template,
         })
     }
 }
 
 impl Columns {
     fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
-        let time_types = TimeTypes::deduce(matches)?;
-
         let no_git_env = vars
             .get(vars::WLS_OVERRIDE_GIT)
             .or_else(|| vars.get_with_fallback(vars::EXA_OVERRIDE_GIT, vars::EZA_OVERRIDE_GIT))
             .is_some();
+        let no_git = matches.has(&flags::NO_GIT)? || no_git_env;
+
+        if let Some(selected) = Self::deduce_selection(matches)? {
+            return Ok(Self::from_selection(selected, no_git));
+        }
+
+        let time_types = TimeTypes::deduce(matches)?;
 
-        let git = matches.has(&flags::GIT)? && !matches.has(&flags::NO_GIT)? && !no_git_env;
-        let subdir_git_repos =
-            matches.has(&flags::GIT_REPOS)? && !matches.has(&flags::NO_GIT)? && !no_git_env;
-        let subdir_git_repos_no_stat = !subdir_git_repos
-            && matches.has(&flags::GIT_REPOS_NO_STAT)?
-            && !matches.has(&flags::NO_GIT)?
-            && !no_git_env;
+        let git = matches.has(&flags::GIT)? && !no_git;
+        let subdir_git_repos = matches.has(&flags::GIT_REPOS)? && !no_git;
+        let zone_health = matches.has(&flags::ZONE_HEALTH)? && !no_git;
+        let subdir_git_repos_no_stat =
+            !subdir_git_repos && matches.has(&flags::GIT_REPOS_NO_STAT)? && !no_git;
 
         let blocksize = matches.has(&flags::BLOCKSIZE)?;
         let group = matches.has(&flags::GROUP)?;
@@ -291,6 +417,13 @@ impl Columns {
         let octal = matches.has(&flags::OCTAL)?;
         let security_context = xattr::ENABLED && matches.has(&flags::SECURITY_CONTEXT)?;
         let file_flags = matches.has(&flags::FILE_FLAGS)?;
+        let sync_details = matches.has(&flags::SYNC_DETAILS)?;
+        let layer = matches.has(&flags::LAYER)?;
+        let filesystems = matches.has(&flags::FILESYSTEMS)?;
+        let materialized = matches.has(&flags::MATERIALIZED)?;
+        let tags = matches.has(&flags::TAGS)?;
+        let owners = matches.has(&flags::OWNERS)?;
+        let nested_repos = matches.has(&flags::NESTED_REPOS)?;
 
         let permissions = !matches.has(&flags::NO_PERMISSIONS)?;
         let filesize = !matches.has(&flags::NO_FILESIZE)?;
@@ -305,14 +438,186 @@ impl Columns {
             git,
             subdir_git_repos,
             subdir_git_repos_no_stat,
+            zone_health,
+            nested_repos,
             octal,
             security_context,
             file_flags,
+            sync_details,
+            filesystems,
+            layer,
+            materialized,
+            tags,
+            url: false,
+            zone: false,
+            owner: false,
+            owners,
+            target: false,
             permissions,
             filesize,
             user,
+            selected: None,
         })
     }
+
+    /// Determines which columns `--columns` picks, in the order they’re
+    /// given, such as `name,size,zone,owner`. Returns `None` if it isn’t
+    /// given, or `Err` if a name isn’t one we know or is given twice.
+    fn deduce_selection(matches: &MatchedFlags<'_>) -> Result<Option<Vec<Column>>, OptionsError> {
+        let Some(word) = matches.get(&flags::COLUMNS)? else {
+            return Ok(None);
+        };
+
+        let Some(names) = word.to_str() else {
+            return Err(OptionsError::BadArgument(&flags::COLUMNS, word.into()));
+        };
+
+        let mut selected = Vec::new();
+        for name in names.split(',').map(str::trim) {
+            match column_named(name) {
+                Some(column) if selected.contains(&column) => {
+                    return Err(OptionsError::Unsupported(format!(
+                        "Column {name:?} was given twice to --columns"
+                    )));
+                }
+                Some(column) => selected.push(column),
+                None => return Err(OptionsError::BadArgument(&flags::COLUMNS, name.into())),
+            }
+        }
+
+        Ok(Some(selected))
+    }
+
+    /// The columns `--columns` picks, with each one turned on, so that
+    /// everything that checks whether a column is shown sees it. The
+    /// Git columns stay off when Git is turned off.
+    fn from_selection(selected: Vec<Column>, no_git: bool) -> Self {
+        let has = |column| selected.contains(&column);
+
+        Self {
+            time_types: TimeTypes {
+                modified: has(Column::Timestamp(TimeType::Modified)),
+                changed: has(Column::Timestamp(TimeType::Changed)),
+                accessed: has(Column::Timestamp(TimeType::Accessed)),
+                created: has(Column::Timestamp(TimeType::Created)),
+            },
+            #[cfg(unix)]
+            inode: has(Column::Inode),
+            #[cfg(unix)]
+            links: has(Column::HardLinks),
+            #[cfg(unix)]
+            blocksize: has(Column::Blocksize),
+            #[cfg(unix)]
+            group: has(Column::Group),
+            #[cfg(unix)]
+            octal: has(Column::Octal),
+            #[cfg(unix)]
+            security_context: xattr::ENABLED && has(Column::SecurityContext),
+            #[cfg(unix)]
+            user: has(Column::User),
+            #[cfg(windows)]
+            inode: false,
+            #[cfg(windows)]
+            links: false,
+            #[cfg(windows)]
+            blocksize: false,
+            #[cfg(windows)]
+            group: false,
+            #[cfg(windows)]
+            octal: false,
+            #[cfg(windows)]
+            security_context: false,
+            #[cfg(windows)]
+            user: false,
+            git: has(Column::GitStatus) && !no_git,
+            subdir_git_repos: has(Column::SubdirGitRepo(true)) && !no_git,
+            subdir_git_repos_no_stat: has(Column::SubdirGitRepo(false)) && !no_git,
+            zone_health: has(Column::ZoneHealth) && !no_git,
+            nested_repos: has(Column::NestedRepo),
+            file_flags: has(Column::FileFlags),
+            sync_details: has(Column::SyncUser) || has(Column::SyncTime),
+            filesystems: has(Column::Filesystem),
+            #[cfg(target_os = "linux")]
+            layer: has(Column::Layer),
+            #[cfg(not(target_os = "linux"))]
+            layer: false,
+            materialized: has(Column::Materialized),
+            tags: has(Column::Tags),
+            url: has(Column::Url),
+            zone: has(Column::Zone),
+            owner: has(Column::Owner),
+            owners: has(Column::Owners),
+            target: has(Column::Target),
+            permissions: has(Column::Permissions),
+            filesize: has(Column::FileSize),
+            selected: Some(selected),
+        }
+    }
+}
+
+/// Every column `--columns` can pick, by the name it’s given there, which
+/// is the name of the flag that turns it on where there is one.
+const NAMED_COLUMNS: &[(&str, Column)] = &[
+    ("name", Column::Name),
+    ("permissions", Column::Permissions),
+    ("size", Column::FileSize),
+    ("modified", Column::Timestamp(TimeType::Modified)),
+    ("changed", Column::Timestamp(TimeType::Changed)),
+    ("accessed", Column::Timestamp(TimeType::Accessed)),
+    ("created", Column::Timestamp(TimeType::Created)),
+    #[cfg(unix)]
+    ("blocksize", Column::Blocksize),
+    #[cfg(unix)]
+    ("user", Column::User),
+    #[cfg(unix)]
+    ("group", Column::Group),
+    #[cfg(unix)]
+    ("links", Column::HardLinks),
+    #[cfg(unix)]
+    ("inode", Column::Inode),
+    #[cfg(unix)]
+    ("octal", Column::Octal),
+    #[cfg(unix)]
+    ("context", Column::SecurityContext),
+    ("flags", Column::FileFlags),
+    ("git", Column::GitStatus),
+    ("git-repos", Column::SubdirGitRepo(true)),
+    ("git-repos-no-status", Column::SubdirGitRepo(false)),
+    ("nested-repos", Column::NestedRepo),
+    ("zone-health", Column::ZoneHealth),
+    ("synced-by", Column::SyncUser),
+    ("synced-at", Column::SyncTime),
+    ("materialized", Column::Materialized),
+    ("tags", Column::Tags),
+    ("zone", Column::Zone),
+    ("owner", Column::Owner),
+    ("owners", Column::Owners),
+    ("target", Column::Target),
+    ("filesystems", Column::Filesystem),
+    #[cfg(target_os = "linux")]
+    ("layer", Column::Layer),
+    ("url", Column::Url),
+];
+
+/// The names in [`NAMED_COLUMNS`], which `--columns` accepts.
+pub(super) const COLUMN_NAMES: &[&str] = &column_names();
+
+const fn column_names() -> [&'static str; NAMED_COLUMNS.len()] {
+    let mut names = [""; NAMED_COLUMNS.len()];
+    let mut i = 0;
+    while i < names.len() {
+        names[i] = NAMED_COLUMNS[i].0;
+        i += 1;
+    }
+    names
+}
+
+/// The column with the given name in `--columns`.
+fn column_named(name: &str) -> Option<Column> {
+    NAMED_COLUMNS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, column)| column)
 }
 
 impl SizeFormat {
@@ -470,17 +775,16 @@ impl TimeTypes {
 
 impl ColorScaleOptions {
     pub fn deduce<V: Vars>(matches: &MatchedFlags<'_>, vars: &V) -> Result<Self, OptionsError> {
-        let min_luminance =
-            match vars
-                .get(vars::WLS_MIN_LUMINANCE)
-                .or_else(|| vars.get_with_fallback(vars::EZA_MIN_LUMINANCE, vars::EXA_MIN_LUMINANCE))
-            {
-                Some(var) => match var.to_string_lossy().parse() {
-                    Ok(luminance) if (-100..=100).contains(&luminance) => luminance,
-                    _ => 40,
-                },
-                None => 40,
-            };
+        let min_luminance = match vars
+            .get(vars::WLS_MIN_LUMINANCE)
+            .or_else(|| vars.get_with_fallback(vars::EZA_MIN_LUMINANCE, vars::EXA_MIN_LUMINANCE))
+        {
+            Some(var) => match var.to_string_lossy().parse() {
+                Ok(luminance) if (-100..=100).contains(&luminance) => luminance,
+                _ => 40,
+            },
+            None => 40,
+        };
 
         let mode = if let Some(w) = matches
             .get(&flags::COLOR_SCALE_MODE)?
@@ -553,6 +857,8 @@ mod test {
         &flags::CREATED,
         &flags::ACCESSED,
         &flags::HEADER,
+        &flags::ZONE_HEADERS,
+        &flags::STREAM,
         &flags::GROUP,
         &flags::INODE,
         &flags::GIT,
@@ -563,8 +869,17 @@ mod test {
         &flags::GRID,
         &flags::ACROSS,
         &flags::ONE_LINE,
+        &flags::JSON_LINES,
+        &flags::CSV,
+        &flags::TSV,
+        &flags::PRINT0,
+        &flags::FORMAT,
+        &flags::GHOSTS,
+        &flags::NO_GHOSTS,
         &flags::TREE,
         &flags::NUMERIC,
+        &flags::COLUMNS,
+        &flags::DU,
     ];
 
     #[allow(unused_macro_rules)]
@@ -773,6 +1088,114 @@ mod test {
         test!(overridden_2: TimeTypes <- ["-tcr", "-tmod"];    Complain => err OptionsError::Duplicate(Flag::Short(b't'), Flag::Short(b't')));
     }
 
+    mod column_selections {
+        use super::*;
+
+        fn selection(inputs: &[&str]) -> Vec<Result<Option<Vec<Column>>, OptionsError>> {
+            parse_for_test(inputs, TEST_ARGS, Both, Columns::deduce_selection)
+        }
+
+        #[test]
+        fn not_given() {
+            for result in selection(&[]) {
+                assert_eq!(result, Ok(None));
+            }
+        }
+
+        #[test]
+        fn in_the_order_given() {
+            for result in selection(&["--columns", "name,size,zone,owner"]) {
+                assert_eq!(
+                    result,
+                    Ok(Some(vec![
+                        Column::Name,
+                        Column::FileSize,
+                        Column::Zone,
+                        Column::Owner
+                    ]))
+                );
+            }
+        }
+
+        #[test]
+        fn owners_from_codeowners() {
+            for result in selection(&["--columns", "name,owner,owners"]) {
+                assert_eq!(
+                    result,
+                    Ok(Some(vec![Column::Name, Column::Owner, Column::Owners]))
+                );
+            }
+        }
+
+        #[test]
+        fn nested_repos() {
+            for result in selection(&["--columns", "name,git,nested-repos"]) {
+                assert_eq!(
+                    result,
+                    Ok(Some(vec![
+                        Column::Name,
+                        Column::GitStatus,
+                        Column::NestedRepo
+                    ]))
+                );
+            }
+        }
+
+        #[test]
+        fn filesystems() {
+            for result in selection(&["--columns", "filesystems,name"]) {
+                assert_eq!(result, Ok(Some(vec![Column::Filesystem, Column::Name])));
+            }
+        }
+
+        #[test]
+        fn unknown_names() {
+            for result in selection(&["--columns=size,colour"]) {
+                assert_eq!(
+                    result,
+                    Err(OptionsError::BadArgument(
+                        &flags::COLUMNS,
+                        OsString::from("colour")
+                    ))
+                );
+            }
+        }
+
+        #[test]
+        fn names_given_twice() {
+            for result in selection(&["--columns=size,name,size"]) {
+                assert!(matches!(result, Err(OptionsError::Unsupported(_))));
+            }
+        }
+
+        #[test]
+        fn selection_turns_columns_on() {
+            let columns = Columns::from_selection(
+                vec![Column::Target, Column::Name, Column::FileSize],
+                false,
+            );
+            assert!(columns.filesize && columns.target);
+            assert!(!columns.permissions && !columns.time_types.modified);
+            assert_eq!(
+                columns.collect(false, false),
+                [Column::Target, Column::Name, Column::FileSize]
+            );
+        }
+
+        #[test]
+        fn git_columns_need_git() {
+            let columns = Columns::from_selection(vec![Column::Name, Column::GitStatus], false);
+            assert_eq!(columns.collect(false, false), [Column::Name]);
+            assert_eq!(
+                columns.collect(true, false),
+                [Column::Name, Column::GitStatus]
+            );
+
+            let columns = Columns::from_selection(vec![Column::Name, Column::GitStatus], true);
+            assert_eq!(columns.collect(true, false), [Column::Name]);
+        }
+    }
+
     mod views {
         use super::*;
 
@@ -796,13 +1219,37 @@ mod test {
         test_mode!(across:     <- ["--across"], None;  Both => like Ok(Mode::Grid(GridOptions { across: true,  .. })));
         test_mode!(gracross:   <- ["-xG"], None;       Both => like Ok(Mode::Grid(GridOptions { across: true,  .. })));
 
+        // Ghost markers
+        test_mode!(ghost_default: <- ["--grid"], None;               Both => like Ok(Mode::Grid(GridOptions { ghost_markers: GhostMarkers { zone: None, plain: None }, .. })));
+        test_mode!(ghost_marker:  <- ["--grid"], Some("~!".into());  Both => like Ok(Mode::Grid(GridOptions { ghost_markers: GhostMarkers { zone: Some('~'), plain: Some('~') }, .. })));
+
         // Lines views
         test_mode!(lines:      <- ["--oneline"], None;     Both => like Ok(Mode::Lines));
         test_mode!(prima:      <- ["-1"], None;            Both => like Ok(Mode::Lines));
 
+        // JSON lines view
+        test_mode!(json_lines: <- ["--json-lines"], None;           Both => like Ok(Mode::JsonLines));
+        test_mode!(json_long:  <- ["--long", "--json-lines"], None; Both => like Ok(Mode::JsonLines));
+
+        // Delimited views
+        test_mode!(csv:        <- ["--csv"], None;                  Both => like Ok(Mode::Delimited(delimited::Options { separator: Separator::Comma, .. })));
+        test_mode!(csv_tsv:    <- ["--csv", "--tsv"], None;         Last => like Ok(Mode::Delimited(delimited::Options { separator: Separator::Tab, .. })));
+
+        // NUL-separated view
+        test_mode!(print0:        <- ["--print0"], None;                     Both => like Ok(Mode::Print0(print0::Options { ghosts: false })));
+        test_mode!(print0_short:  <- ["-l0"], None;                          Both => like Ok(Mode::Print0(_)));
+        test_mode!(print0_ghosts: <- ["-0", "--ghosts"], None;               Both => like Ok(Mode::Print0(print0::Options { ghosts: true })));
+        test_mode!(print0_no:     <- ["-0", "--ghosts", "--no-ghosts"], None; Last => like Ok(Mode::Print0(print0::Options { ghosts: false })));
+        test_mode!(format:        <- ["--format", "{name}\\t{size}"], None; Both => like Ok(Mode::Template(_)));
+        test_mode!(format_long:   <- ["-l", "--format={zone}"], None;      Both => like Ok(Mode::Template(_)));
+        test_mode!(format_bad:    <- ["--format", "{colour}"], None;        Both => like Err(OptionsError::Unsupported(_)));
+
         // Details views
         test_mode!(long:       <- ["--long"], None;    Both => like Ok(Mode::Details(_)));
         test_mode!(ell:        <- ["-l"], None;        Both => like Ok(Mode::Details(_)));
+        test_mode!(du:         <- ["--du"], None;      Both => like Ok(Mode::Details(_)));
+        test_mode!(du_grid:    <- ["--du", "-lG"], None; Both => like Ok(Mode::GridDetails(_)));
+        test_mode!(du_lines:   <- ["--du", "-1"], None;  Both => like Ok(Mode::Lines));
 
         // Grid-details views
         test_mode!(lid:        <- ["--long", "--grid"], None;  Both => like Ok(Mode::GridDetails(_)));
@@ -832,6 +1279,8 @@ mod test {
         test_mode!(just_binary_2: <- ["--binary"],    None;  Complain => err OptionsError::Useless(&flags::BINARY,  false, &flags::LONG));
         test_mode!(just_bytes_2:  <- ["--bytes"],     None;  Complain => err OptionsError::Useless(&flags::BYTES,   false, &flags::LONG));
         test_mode!(just_numeric2: <- ["--numeric"],   None;  Complain => err OptionsError::Useless(&flags::NUMERIC, false, &flags::LONG));
+        test_mode!(just_zones_2:  <- ["--zone-headers"], None; Complain => err OptionsError::Useless(&flags::ZONE_HEADERS, false, &flags::LONG));
+        test_mode!(just_stream_2: <- ["--stream"],    None;  Complain => err OptionsError::Useless(&flags::STREAM,  false, &flags::LONG));
 
         #[cfg(feature = "git")]
         test_mode!(just_git_2:    <- ["--git"],    None;  Complain => err OptionsError::Useless(&flags::GIT,    false, &flags::LONG));
@@ -844,5 +1293,7 @@ mod test {
         test_mode!(ot:            <- ["--oneline", "--tree"],           None;  Both => like Ok(Mode::Details(_)));
         test_mode!(og:            <- ["--oneline", "--grid"],           None;  Both => like Ok(Mode::Grid(_)));
         test_mode!(tg:            <- ["--tree", "--grid"],              None;  Both => like Ok(Mode::Grid(_)));
+        test_mode!(ls:            <- ["--long", "--stream"],            None;  Both => like Ok(Mode::Details(details::Options { stream: true, .. })));
+        test_mode!(lst:           <- ["--long", "--stream", "--tree"],  None;  Complain => err OptionsError::Conflict(&flags::STREAM, &flags::TREE));
     }
 }
@@ -0,0 +1,262 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Machine-oriented queries about zones, which answer a question about the
+//! manifest instead of listing any files.
+
+use std::path::PathBuf;
+
+use crate::options::parser::MatchedFlags;
+use crate::options::{flags, NumberSource, OptionsError};
+
+/// A question about the manifest that the user wants answered, instead of
+/// getting a file listing.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ZoneQuery {
+    /// Print every manifest entry matching a partial target path, such as
+    /// `//areas/to`, one per line.
+    Complete(String),
+
+    /// Print the target path of the nearest zone enclosing a path, and the
+    /// zone’s path relative to the src root.
+    Of(PathBuf),
+
+    /// Check the manifest above the current directory for mistakes, and
+    /// print what was found.
+    CheckManifest,
+
+    /// Print the directories in the src root that the manifest knows
+    /// nothing about, looking at most this many directories deep if given.
+    Orphans(Option<usize>),
+
+    /// Print the target paths that a zone depends on, from the `deps` of
+    /// its manifest entry, or the ones that depend on it if `reverse`.
+    Deps { target: String, reverse: bool },
+
+    /// Print a summary of everything known about the zone with a target
+    /// path: its manifest entry, whether it’s on disk, and its Git status.
+    Describe(String),
+}
+
+impl ZoneQuery {
+    /// Determines which query to run, if any, based on the user’s
+    /// command-line arguments. Like `--help` and `--version`, a query takes
+    /// over the whole invocation, so any file arguments are ignored.
+    pub fn deduce(matches: &MatchedFlags<'_>) -> Result<Option<Self>, OptionsError> {
+        if let Some(prefix) = matches.get(&flags::ZONE_COMPLETE)? {
+            return Ok(Some(Self::Complete(prefix.to_string_lossy().into_owned())));
+        }
+
+        if let Some(path) = matches.get(&flags::ZONE_OF)? {
+            return Ok(Some(Self::Of(PathBuf::from(path))));
+        }
+
+        if matches.has(&flags::CHECK_MANIFEST)? {
+            return Ok(Some(Self::CheckManifest));
+        }
+
+        let depth = match matches.get(&flags::ORPHAN_DEPTH)? {
+            Some(depth) => {
+                let depth = depth.to_string_lossy();
+                match depth.parse() {
+                    Ok(d) => Some(d),
+                    Err(e) => {
+                        let source = NumberSource::Arg(&flags::ORPHAN_DEPTH);
+                        return Err(OptionsError::FailedParse(depth.to_string(), source, e));
+                    }
+                }
+            }
+            None => None,
+        };
+
+        if matches.has(&flags::ORPHANS)? {
+            return Ok(Some(Self::Orphans(depth)));
+        } else if depth.is_some() {
+            return Err(OptionsError::Useless(
+                &flags::ORPHAN_DEPTH,
+                false,
+                &flags::ORPHANS,
+            ));
+        }
+
+        let reverse = matches.has(&flags::REVERSE_DEPS)?;
+        if let Some(target) = matches.get(&flags::SHOW_DEPS)? {
+            let target = target.to_string_lossy().into_owned();
+            return Ok(Some(Self::Deps { target, reverse }));
+        } else if reverse {
+            return Err(OptionsError::Useless(
+                &flags::REVERSE_DEPS,
+                false,
+                &flags::SHOW_DEPS,
+            ));
+        }
+
+        if let Some(target) = matches.get(&flags::DESCRIBE)? {
+            return Ok(Some(Self::Describe(target.to_string_lossy().into_owned())));
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::{Options, OptionsResult};
+    use crate::warnings::Format;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn zone_complete() {
+        let args = vec![OsStr::new("--zone-complete"), OsStr::new("//areas/to")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Complete(ref p), ..) if p == "//areas/to"
+        ));
+    }
+
+    #[test]
+    fn zone_complete_with_file() {
+        let args = vec![OsStr::new("--zone-complete=//a"), OsStr::new("me")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Complete(_), ..)
+        ));
+    }
+
+    #[test]
+    fn zone_of() {
+        let args = vec![
+            OsStr::new("--zone-of"),
+            OsStr::new("areas/tools/dev/README.md"),
+        ];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Of(ref p), ..) if p == &PathBuf::from("areas/tools/dev/README.md")
+        ));
+    }
+
+    #[test]
+    fn check_manifest() {
+        let args = vec![OsStr::new("--check-manifest")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::CheckManifest, ..)
+        ));
+    }
+
+    #[test]
+    fn orphans() {
+        let args = vec![OsStr::new("--orphans")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Orphans(None), ..)
+        ));
+    }
+
+    #[test]
+    fn orphans_with_depth() {
+        let args = vec![OsStr::new("--orphans"), OsStr::new("--orphan-depth=2")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Orphans(Some(2)), ..)
+        ));
+    }
+
+    #[test]
+    fn orphan_depth_without_orphans() {
+        let args = vec![OsStr::new("--orphan-depth=2")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::InvalidOptions(OptionsError::Useless(_, false, _))
+        ));
+    }
+
+    #[test]
+    fn show_deps() {
+        let args = vec![OsStr::new("--show-deps"), OsStr::new("//areas/apps/flow")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Deps { ref target, reverse: false }, ..) if target == "//areas/apps/flow"
+        ));
+    }
+
+    #[test]
+    fn reverse_deps() {
+        let args = vec![
+            OsStr::new("--show-deps=//areas/lib"),
+            OsStr::new("--reverse-deps"),
+        ];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Deps { reverse: true, .. }, ..)
+        ));
+    }
+
+    #[test]
+    fn reverse_deps_without_show_deps() {
+        let args = vec![OsStr::new("--reverse-deps")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::InvalidOptions(OptionsError::Useless(_, false, _))
+        ));
+    }
+
+    #[test]
+    fn describe() {
+        let args = vec![OsStr::new("--describe"), OsStr::new("//areas/tools/dev")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::ZoneQuery(ZoneQuery::Describe(ref target), ..) if target == "//areas/tools/dev"
+        ));
+    }
+
+    #[test]
+    fn errors_as_json() {
+        let args = vec![
+            OsStr::new("--zone-of"),
+            OsStr::new("README.md"),
+            OsStr::new("--errors=json"),
+        ];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(opts, OptionsResult::ZoneQuery(_, _, Format::Json)));
+    }
+
+    #[test]
+    fn errors_that_are_a_value() {
+        let args = vec![OsStr::new("--zone-of"), OsStr::new("--errors=json")];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(opts, OptionsResult::ZoneQuery(_, _, Format::Text)));
+    }
+
+    #[test]
+    fn bad_errors() {
+        let args = vec![
+            OsStr::new("--zone-of"),
+            OsStr::new("README.md"),
+            OsStr::new("--errors=bogus"),
+        ];
+        let opts = Options::parse(args, &None);
+        assert!(matches!(
+            opts,
+            OptionsResult::InvalidOptions(OptionsError::BadArgument(..))
+        ));
+    }
+
+    #[test]
+    fn no_query() {
+        let args = vec![OsStr::new("-l")];
+        let opts = Options::parse(args, &None);
+        assert!(!matches!(opts, OptionsResult::ZoneQuery(..)));
+    }
+}
@@ -0,0 +1,84 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Leaving out the start that every path read from `--stdin` has in common.
+//!
+//! Piping in a list of absolute paths, such as the zones a query found,
+//! makes every name in the listing start with the same long path to the src
+//! root, which pushes the rest of a wide table off the screen. Instead, that
+//! shared start is printed once as a heading, and the paths are shown
+//! relative to it.
+
+use std::path::{Path, PathBuf};
+
+/// The given path with the given prefix left out, or the path itself if
+/// there’s no prefix or it isn’t inside it.
+pub fn strip<'a>(path: &'a Path, prefix: Option<&Path>) -> &'a Path {
+    prefix
+        .and_then(|prefix| path.strip_prefix(prefix).ok())
+        .unwrap_or(path)
+}
+
+/// The deepest directory that the parents of all of the given paths are
+/// inside, if they’re all absolute and it’s deeper than the root. Taking it
+/// from the parents means that every path still has at least its name left
+/// once it’s been stripped.
+pub fn find<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<PathBuf> {
+    let mut prefix: Option<PathBuf> = None;
+    let mut count = 0;
+
+    for path in paths {
+        if !path.is_absolute() {
+            return None;
+        }
+
+        let parent = path.parent()?;
+        prefix = Some(match prefix {
+            None => parent.to_path_buf(),
+            Some(prefix) => prefix
+                .components()
+                .zip(parent.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+        count += 1;
+    }
+
+    prefix.filter(|prefix| count > 1 && prefix.parent().is_some())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn find_in(paths: &[&str]) -> Option<PathBuf> {
+        find(paths.iter().map(Path::new))
+    }
+
+    #[test]
+    fn shared_directory() {
+        let prefix = find_in(&["/home/dev/src/areas/core", "/home/dev/src/areas/tools/dev"]);
+        assert_eq!(prefix, Some(PathBuf::from("/home/dev/src/areas")));
+    }
+
+    #[test]
+    fn one_path_inside_another() {
+        let prefix = find_in(&["/home/dev/src/areas", "/home/dev/src/areas/core"]);
+        assert_eq!(prefix, Some(PathBuf::from("/home/dev/src")));
+    }
+
+    #[test]
+    fn nothing_shared_but_the_root() {
+        assert_eq!(find_in(&["/home/dev", "/tmp/x"]), None);
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(find_in(&["/home/dev/a", "home/dev/b"]), None);
+    }
+
+    #[test]
+    fn a_single_path() {
+        assert_eq!(find_in(&["/home/dev/src/areas/core"]), None);
+    }
+}
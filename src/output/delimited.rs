@@ -0,0 +1,157 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The `--csv` and `--tsv` views, which write the long view’s columns as one
+//! record per entry, under a header row, for pasting into spreadsheets.
+//!
+//! Each record ends with the entry’s name and path, followed by its zone’s
+//! target path and whether it’s a zone and a ghost, which the long view only
+//! shows through colours. Cells are written without any colours or padding,
+//! and blank ones are left empty rather than holding a dash.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::fs::feature::git::GitCache;
+use crate::fs::File;
+use crate::output::table::{Options as TableOptions, Table};
+use crate::theme::Theme;
+
+/// The headers of the fields that come after the long view’s columns.
+const TRAILING_HEADERS: [&str; 5] = ["Name", "Path", "Target", "Is Zone", "Is Ghost"];
+
+/// What goes between the fields of a record.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Separator {
+    /// Commas, with fields quoted as RFC 4180 describes.
+    Comma,
+
+    /// Tabs, with tabs, newlines, and backslashes in fields escaped.
+    Tab,
+}
+
+impl Separator {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Comma => ",",
+            Self::Tab => "\t",
+        }
+    }
+
+    /// The field as it gets written, quoted or escaped if it has to be.
+    fn field(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::Comma if text.contains([',', '"', '\n', '\r']) => {
+                Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+            }
+            Self::Tab if text.contains(['\t', '\n', '\r', '\\']) => Cow::Owned(
+                text.replace('\\', "\\\\")
+                    .replace('\t', "\\t")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r"),
+            ),
+            _ => Cow::Borrowed(text),
+        }
+    }
+}
+
+/// Options for the delimited views.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Options {
+    pub separator: Separator,
+
+    /// Which of the long view’s columns to write, and how.
+    pub table: TableOptions,
+}
+
+/// Writes entries as records, with the same columns every time.
+pub struct Writer<'a> {
+    separator: Separator,
+    table: Table<'a>,
+}
+
+impl<'a> Writer<'a> {
+    #[must_use]
+    pub fn new(
+        opts: &'a Options,
+        git: Option<&'a GitCache>,
+        theme: &'a Theme,
+        git_repos: bool,
+    ) -> Self {
+        Self {
+            separator: opts.separator,
+            table: Table::new(&opts.table, git, theme, git_repos),
+        }
+    }
+
+    /// Writes the header row, which goes once at the top of the output.
+    pub fn write_header<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let row = self.table.header_row();
+        let headers = row
+            .plain_cells()
+            .chain(TRAILING_HEADERS.iter().map(|h| (*h).to_string()));
+        self.write_record(w, headers)
+    }
+
+    /// Writes the record for a single file.
+    pub fn write_entry<W: Write>(&self, w: &mut W, file: &File<'_>) -> io::Result<()> {
+        let row = self.table.row_for_file(file, false, None);
+        let cells = row
+            .plain_cells()
+            .map(|cell| if cell == "-" { String::new() } else { cell });
+        let trailing = [
+            file.name.clone(),
+            file.path.to_string_lossy().into_owned(),
+            file.zone_target().unwrap_or_default().to_string(),
+            file.is_zone().to_string(),
+            file.is_ghost.to_string(),
+        ];
+        self.write_record(w, cells.chain(trailing))
+    }
+
+    fn write_record<W: Write>(
+        &self,
+        w: &mut W,
+        fields: impl Iterator<Item = String>,
+    ) -> io::Result<()> {
+        for (i, field) in fields.enumerate() {
+            if i > 0 {
+                write!(w, "{}", self.separator.as_str())?;
+            }
+            write!(w, "{}", self.separator.field(&field))?;
+        }
+        writeln!(w)
+    }
+}
+
+pub struct Render<'a> {
+    pub files: Vec<File<'a>>,
+    pub writer: Writer<'a>,
+}
+
+impl Render<'_> {
+    pub fn render<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for file in &self.files {
+            self.writer.write_entry(w, file)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn csv_fields_are_quoted_when_they_have_to_be() {
+        assert_eq!(Separator::Comma.field("plain"), "plain");
+        assert_eq!(Separator::Comma.field("a,b"), "\"a,b\"");
+        assert_eq!(Separator::Comma.field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn tsv_fields_are_escaped() {
+        assert_eq!(Separator::Tab.field("a,b"), "a,b");
+        assert_eq!(Separator::Tab.field("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+    }
+}
@@ -0,0 +1,260 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The summary of a single zone printed by `--describe`, which gathers what
+//! the manifest, the disk, and Git know about it, so nobody has to dig
+//! through the manifest by hand.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::fs::disk_usage::Usage;
+use crate::fs::fields::{Materialized, ZoneHealth};
+use crate::fs::{Estimate, Zone};
+use crate::output::render::size_in_words;
+
+/// Everything known about a zone, such as:
+///
+/// ```text
+/// //areas/tools/dev
+///   path          /src/areas/tools/dev
+///   owner         payments
+///   type          library
+///   materialized  yes, with 2 of 3 zones beneath it
+///   size          1.2 MiB, of which 840 KiB is outside the zones inside it
+///   git           3 modified, 1 untracked
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ZoneDescription {
+    pub target: String,
+    pub path: PathBuf,
+
+    /// The fields of the zone’s manifest entry that get a line of their own.
+    pub owner: Option<String>,
+    pub kind: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+
+    /// Whether the zone is on disk, and how many of the zones beneath it are.
+    pub materialized: bool,
+    pub beneath: Option<Materialized>,
+
+    /// How much space the zone takes up on disk, all of it and just the part
+    /// outside the zones inside it, if it’s materialized.
+    pub usage: Option<(Usage, Usage)>,
+
+    /// How big the manifest says the zone is, for ghosts.
+    pub estimate: Option<Estimate>,
+
+    /// The zone’s uncommitted changes, if it’s in a Git repository.
+    pub git: Option<ZoneHealth>,
+}
+
+impl ZoneDescription {
+    /// Starts a description of the given zone from its manifest entry,
+    /// leaving the disk and Git to be filled in.
+    pub fn of(zone: &Zone) -> Self {
+        let field = |name: &str| {
+            let values = zone.metadata.as_ref()?.get(name)?;
+            Some(values.join(", "))
+        };
+
+        Self {
+            target: zone.target.clone(),
+            path: zone.path.clone(),
+            owner: field("owner"),
+            kind: field("type"),
+            description: field("description"),
+            tags: zone.tags.clone(),
+            materialized: zone.materialized,
+            beneath: None,
+            usage: None,
+            estimate: zone.estimate,
+            git: None,
+        }
+    }
+}
+
+impl fmt::Display for ZoneDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.target)?;
+        line(f, "path", &self.path.display())?;
+
+        for (name, value) in [
+            ("owner", &self.owner),
+            ("type", &self.kind),
+            ("description", &self.description),
+        ] {
+            if let Some(value) = value {
+                line(f, name, value)?;
+            }
+        }
+        if !self.tags.is_empty() {
+            line(f, "tags", &self.tags.join(", "))?;
+        }
+
+        let on_disk = if self.materialized {
+            "yes"
+        } else {
+            "no, it’s a ghost"
+        };
+        match self.beneath {
+            Some(beneath) => line(
+                f,
+                "materialized",
+                &format!(
+                    "{on_disk}, with {} of {} zones beneath it",
+                    beneath.present, beneath.total
+                ),
+            )?,
+            None => line(f, "materialized", &on_disk)?,
+        }
+
+        match (
+            self.usage,
+            self.estimate.and_then(|estimate| estimate.bytes),
+        ) {
+            (Some((total, own)), _) if total == own => {
+                line(f, "size", &size_in_words(total.bytes))?;
+            }
+            (Some((total, own)), _) => line(
+                f,
+                "size",
+                &format!(
+                    "{}, of which {} is outside the zones inside it",
+                    size_in_words(total.bytes),
+                    size_in_words(own.bytes)
+                ),
+            )?,
+            (None, Some(estimate)) => line(
+                f,
+                "size",
+                &format!("about {}, says the manifest", size_in_words(estimate)),
+            )?,
+            (None, None) => {}
+        }
+
+        if let Some(health) = self.git {
+            line(f, "git", &git(health))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn line(f: &mut fmt::Formatter<'_>, name: &str, value: &dyn fmt::Display) -> fmt::Result {
+    writeln!(f, "  {name:<13} {value}")
+}
+
+fn git(health: ZoneHealth) -> String {
+    let mut parts = Vec::new();
+    if health.counts.modified > 0 {
+        parts.push(format!("{} modified", health.counts.modified));
+    }
+    if health.counts.untracked > 0 {
+        parts.push(format!("{} untracked", health.counts.untracked));
+    }
+    if health.ahead > 0 {
+        parts.push(format!("{} ahead", health.ahead));
+    }
+    if health.behind > 0 {
+        parts.push(format!("{} behind", health.behind));
+    }
+
+    if parts.is_empty() {
+        String::from("clean")
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::fields::GitCounts;
+
+    fn description() -> ZoneDescription {
+        ZoneDescription {
+            target: "//areas/tools/dev".into(),
+            path: PathBuf::from("/src/areas/tools/dev"),
+            owner: Some("payments".into()),
+            kind: None,
+            description: None,
+            tags: Vec::new(),
+            materialized: true,
+            beneath: None,
+            usage: None,
+            estimate: None,
+            git: None,
+        }
+    }
+
+    #[test]
+    fn materialized() {
+        let mut description = description();
+        description.beneath = Some(Materialized {
+            present: 2,
+            total: 3,
+        });
+        description.usage = Some((
+            Usage {
+                bytes: 2048,
+                blocks: 8,
+            },
+            Usage {
+                bytes: 1024,
+                blocks: 4,
+            },
+        ));
+        description.git = Some(ZoneHealth {
+            counts: GitCounts {
+                modified: 3,
+                untracked: 1,
+            },
+            ahead: 0,
+            behind: 2,
+        });
+
+        assert_eq!(
+            description.to_string(),
+            "//areas/tools/dev\n\
+            \x20 path          /src/areas/tools/dev\n\
+            \x20 owner         payments\n\
+            \x20 materialized  yes, with 2 of 3 zones beneath it\n\
+            \x20 size          2.0 KiB, of which 1.0 KiB is outside the zones inside it\n\
+            \x20 git           3 modified, 1 untracked, 2 behind\n"
+        );
+    }
+
+    #[test]
+    fn ghost() {
+        let mut description = description();
+        description.materialized = false;
+        description.owner = None;
+        description.kind = Some("library".into());
+        description.tags = vec!["core".into(), "rust".into()];
+        description.estimate = Some(Estimate {
+            bytes: Some(512),
+            files: None,
+        });
+
+        assert_eq!(
+            description.to_string(),
+            "//areas/tools/dev\n\
+            \x20 path          /src/areas/tools/dev\n\
+            \x20 type          library\n\
+            \x20 tags          core, rust\n\
+            \x20 materialized  no, it’s a ghost\n\
+            \x20 size          about 512 B, says the manifest\n"
+        );
+    }
+
+    #[test]
+    fn clean() {
+        let health = ZoneHealth {
+            counts: GitCounts::default(),
+            ahead: 0,
+            behind: 0,
+        };
+        assert_eq!(git(health), "clean");
+    }
+}
@@ -70,7 +70,7 @@ use std::path::PathBuf;
 use std::vec::IntoIter as VecIntoIter;
 
 use nu_ansi_term::Style;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
 use log::{debug, trace};
 
@@ -79,6 +79,7 @@ use crate::fs::feature::git::GitCache;
 use crate::fs::feature::xattr::Attribute;
 use crate::fs::fields::SecurityContextType;
 use crate::fs::filter::FileFilter;
+use crate::fs::prefetch;
 use crate::fs::{Dir, File};
 use crate::output::cell::TextCell;
 use crate::output::color_scale::{ColorScaleInformation, ColorScaleOptions};
@@ -86,6 +87,7 @@ use crate::output::file_name::Options as FileStyle;
 use crate::output::table::{Options as TableOptions, Row as TableRow, Table};
 use crate::output::tree::{TreeDepth, TreeParams, TreeTrunk};
 use crate::theme::Theme;
+use crate::warnings::{self, Kind};
 
 /// With the **Details** view, the output gets formatted into columns, with
 /// each `Column` object showing some piece of information about the file,
@@ -124,8 +126,19 @@ pub struct Options {
 
     /// Whether to drill down into symbolic links that point to directories
     pub follow_links: bool,
+
+    /// Whether to group the listed entries by the zone they’re in, with a
+    /// heading line before each group.
+    pub zone_headers: bool,
+
+    /// Whether to print the rows in chunks as they’re read, rather than
+    /// waiting for every entry so they can all be sorted and lined up.
+    pub stream: bool,
 }
 
+/// How many entries go in each chunk printed by `--stream`.
+pub const STREAM_CHUNK_SIZE: usize = 1024;
+
 pub struct Render<'a> {
     pub dir: Option<&'a Dir>,
     pub files: Vec<File<'a>>,
@@ -156,6 +169,7 @@ struct Egg<'a> {
     errors:    Vec<(io::Error, Option<PathBuf>)>,
     dir:       Option<Dir>,
     file:      &'a File<'a>,
+    zone:      Option<String>,
 }
 
 impl<'a> AsRef<File<'a>> for Egg<'a> {
@@ -165,7 +179,14 @@ impl<'a> AsRef<File<'a>> for Egg<'a> {
 }
 
 impl<'a> Render<'a> {
-    pub fn render<W: Write>(mut self, w: &mut W) -> io::Result<()> {
+    pub fn render<W: Write>(self, w: &mut W) -> io::Result<()> {
+        let header = self.opts.header;
+        self.render_chunk(w, header)
+    }
+
+    /// Renders the files as one chunk of a longer listing, sorted and lined
+    /// up on their own, with the header line only if it’s asked for.
+    pub fn render_chunk<W: Write>(mut self, w: &mut W, header: bool) -> io::Result<()> {
         let mut rows = Vec::new();
 
         let color_scale_info = ColorScaleInformation::from_color_scale(
@@ -177,6 +198,10 @@ impl<'a> Render<'a> {
             self.recurse,
         );
 
+        // Listing a ghost directory means its ghosts are already beneath
+        // anything that’s really there
+        let root_ghost_level = self.dir.map_or(0, Dir::ghost_level);
+
         if let Some(ref table) = self.opts.table {
             match (self.git, self.dir) {
                 (Some(g), Some(d)) => {
@@ -194,7 +219,7 @@ impl<'a> Render<'a> {
 
             let mut table = Table::new(table, self.git, self.theme, self.git_repos);
 
-            if self.opts.header {
+            if header {
                 let header = table.header_row();
                 table.add_widths(&header);
                 rows.push(self.render_header(header));
@@ -208,6 +233,7 @@ impl<'a> Render<'a> {
                 &mut rows,
                 &self.files,
                 TreeDepth::root(),
+                root_ghost_level,
                 color_scale_info,
             );
 
@@ -220,6 +246,7 @@ impl<'a> Render<'a> {
                 &mut rows,
                 &self.files,
                 TreeDepth::root(),
+                root_ghost_level,
                 color_scale_info,
             );
 
@@ -238,7 +265,7 @@ impl<'a> Render<'a> {
         let xattr_count = file.extended_attributes().len();
         let selinux_ctx_shown = self.opts.secattr
             && match file.security_context().context {
-                SecurityContextType::SELinux(_) => true,
+                SecurityContextType::SELinux(_) | SecurityContextType::Smack(_) => true,
                 SecurityContextType::None => false,
             };
         xattr_count > 1 || (xattr_count == 1 && !selinux_ctx_shown)
@@ -252,10 +279,15 @@ impl<'a> Render<'a> {
         rows: &mut Vec<Row>,
         src: &[File<'dir>],
         depth: TreeDepth,
+        ghost_level: usize,
         color_scale_info: Option<ColorScaleInformation>,
     ) {
         use crate::fs::feature::xattr;
 
+        // Only the entries at the top get grouped, as anything further down
+        // the tree has to stay beneath its directory
+        let group_by_zone = self.opts.zone_headers && depth.0 == 0;
+
         let mut file_eggs: Vec<_> = src
             .par_iter()
             .map(|file| {
@@ -302,26 +334,51 @@ impl<'a> Render<'a> {
                         file.is_directory()
                     }) && r.tree
                         && !r.is_too_deep(depth.0)
-                        && !file.is_ghost
+                        && !(depth.0 > 0 && r.stops_at(file))
                     {
-                        trace!("matching on read_dir");
-                        match file.read_dir() {
-                            Ok(d) => {
-                                dir = Some(d);
+                        if file.is_ghost {
+                            // Ghosts have nothing on disk to read, so their
+                            // children come from the manifest alone
+                            if r.expands_ghost(ghost_level) {
+                                dir = file.to_ghost_dir();
                             }
-                            Err(e) => {
-                                errors.push((e, None));
+                        } else if let Some(ancestor) =
+                            follow_links.then(|| file.linked_ancestor()).flatten()
+                        {
+                            warnings::link_cycle(&file.path, &ancestor);
+                        } else {
+                            trace!("matching on read_dir");
+                            match file.read_dir() {
+                                Ok(d) => {
+                                    dir = Some(d);
+                                }
+                                Err(e) => {
+                                    if !warnings::write_json(
+                                        Kind::of_io_error(&e),
+                                        file.path.display(),
+                                        &e,
+                                    ) {
+                                        errors.push((e, None));
+                                    }
+                                }
                             }
                         }
                     }
                 }
 
+                let zone = if group_by_zone {
+                    file.enclosing_zone_target()
+                } else {
+                    None
+                };
+
                 Egg {
                     table_row,
                     xattrs,
                     errors,
                     dir,
                     file,
+                    zone,
                 }
             })
             .collect();
@@ -329,10 +386,36 @@ impl<'a> Render<'a> {
         // this is safe because all entries have been initialized above
         self.filter.sort_files(&mut file_eggs);
 
-        for (tree_params, egg) in depth.iterate_over(file_eggs.into_iter()) {
-            let mut files = Vec::new();
+        // Outside of any src root there’s nothing to group by, so there’s no
+        // point in a heading saying so
+        let group_by_zone = group_by_zone && file_eggs.iter().any(|egg| egg.zone.is_some());
+        if group_by_zone {
+            file_eggs.sort_by(|a, b| a.zone.cmp(&b.zone));
+        }
+
+        // Reading each directory’s entries and working out its ghosts from
+        // the manifest happens for the whole level at once, rather than one
+        // directory at a time as the rows get written. The results come back
+        // in the same order as the directories, so the output doesn’t change.
+        let dirs: Vec<Option<Dir>> = file_eggs.iter_mut().map(|egg| egg.dir.take()).collect();
+        let children: Vec<Option<Vec<File<'_>>>> = dirs
+            .par_iter()
+            .zip(file_eggs.par_iter())
+            .map(|(dir, egg)| dir.as_ref().map(|dir| self.child_files(dir, egg.file)))
+            .collect();
+
+        let mut last_zone = None;
+        let levels = dirs.iter().zip(children);
+        for ((tree_params, egg), (dir, files)) in
+            depth.iterate_over(file_eggs.into_iter()).zip(levels)
+        {
             let errors = egg.errors;
 
+            if group_by_zone && last_zone.as_ref() != Some(&egg.zone) {
+                rows.push(self.render_zone_heading(egg.zone.as_deref()));
+                last_zone = Some(egg.zone);
+            }
+
             if let (Some(ref mut t), Some(row)) = (table.as_mut(), egg.table_row.as_ref()) {
                 t.add_widths(row);
             }
@@ -351,26 +434,27 @@ impl<'a> Render<'a> {
                 tree: tree_params,
                 cells: egg.table_row,
                 name: file_name,
+                heading: false,
             };
 
             rows.push(row);
 
-            if let Some(ref dir) = egg.dir {
-                for file_to_add in dir.files(
-                    self.filter.dot_filter,
-                    self.git,
-                    self.git_ignoring,
-                    egg.file.deref_links,
-                    egg.file.is_recursive_size(),
-                    self.filter.no_ghosts,
-                ) {
-                    files.push(file_to_add);
-                }
-
-                self.filter
-                    .filter_child_files(self.recurse.is_some(), &mut files);
+            // A zone on disk whose ghosts aren’t being listed says how many
+            // there would have been, before anything else inside it
+            let unmaterialized = dir
+                .as_ref()
+                .filter(|dir| !egg.file.is_ghost && !dir.lists_ghosts(self.filter.ghosts))
+                .map_or(0, |_| egg.file.unmaterialized_children());
 
+            if let Some(files) = files {
                 if !files.is_empty() {
+                    if unmaterialized > 0 {
+                        rows.push(self.render_unmaterialized(
+                            unmaterialized,
+                            TreeParams::new(depth.deeper(), false),
+                        ));
+                    }
+
                     for xattr in egg.xattrs {
                         rows.push(self.render_xattr(xattr, TreeParams::new(depth.deeper(), false)));
                     }
@@ -383,11 +467,29 @@ impl<'a> Render<'a> {
                         ));
                     }
 
-                    self.add_files_to_table(table, rows, &files, depth.deeper(), color_scale_info);
+                    let ghost_level = if egg.file.is_ghost {
+                        ghost_level + 1
+                    } else {
+                        0
+                    };
+                    self.add_files_to_table(
+                        table,
+                        rows,
+                        &files,
+                        depth.deeper(),
+                        ghost_level,
+                        color_scale_info,
+                    );
                     continue;
                 }
             }
 
+            if unmaterialized > 0 {
+                let params =
+                    TreeParams::new(depth.deeper(), egg.xattrs.is_empty() && errors.is_empty());
+                rows.push(self.render_unmaterialized(unmaterialized, params));
+            }
+
             let count = egg.xattrs.len();
             for (index, xattr) in egg.xattrs.iter().enumerate() {
                 let params =
@@ -405,12 +507,67 @@ impl<'a> Render<'a> {
         }
     }
 
+    /// The files inside a directory being recursed into, ghosts included,
+    /// filtered the same way as the ones above them.
+    fn child_files<'dir>(&self, dir: &'dir Dir, file: &File<'_>) -> Vec<File<'dir>> {
+        let mut files: Vec<_> = dir
+            .files(
+                self.filter.dot_filter,
+                self.git,
+                self.git_ignoring,
+                file.deref_links,
+                file.is_recursive_size(),
+                self.filter.ghosts,
+            )
+            .collect();
+
+        prefetch::metadata(&files);
+        self.filter
+            .filter_child_files(self.recurse.is_some(), &mut files);
+        self.filter.sort_files(&mut files);
+        self.filter.ghost_limit(dir).apply(&mut files);
+        files
+    }
+
     #[must_use]
     pub fn render_header(&self, header: TableRow) -> Row {
         Row {
             tree: TreeParams::new(TreeDepth::root(), false),
             cells: Some(header),
             name: TextCell::paint_str(self.theme.ui.header.unwrap_or_default(), "Name"),
+            heading: false,
+        }
+    }
+
+    /// The line that goes before the entries in a zone, which names it, or
+    /// says that they aren’t in one.
+    fn render_zone_heading(&self, zone: Option<&str>) -> Row {
+        let style = self.theme.ui.header.unwrap_or_default();
+        Row {
+            tree: TreeParams::new(TreeDepth::root(), false),
+            cells: None,
+            name: match zone {
+                Some(zone) => TextCell::paint(style, zone.to_string()),
+                None => TextCell::paint_str(style, "(no zone)"),
+            },
+            heading: true,
+        }
+    }
+
+    /// The line that stands in for the ghosts inside a zone when they
+    /// aren’t being listed.
+    fn render_unmaterialized(&self, count: usize, tree: TreeParams) -> Row {
+        use crate::output::file_name::Colours;
+
+        let plural = if count == 1 { "zone" } else { "zones" };
+        Row {
+            cells: None,
+            name: TextCell::paint(
+                self.theme.ghost(),
+                format!("… {count} unmaterialized {plural}"),
+            ),
+            tree,
+            heading: false,
         }
     }
 
@@ -430,6 +587,7 @@ impl<'a> Render<'a> {
             cells: None,
             name,
             tree,
+            heading: false,
         }
     }
 
@@ -442,14 +600,27 @@ impl<'a> Render<'a> {
             cells: None,
             name,
             tree,
+            heading: false,
         }
     }
 
     #[must_use]
     pub fn iterate_with_table(&'a self, table: Table<'a>, rows: Vec<Row>) -> TableIter<'a> {
+        // The names only need lining up when there are cells after them
+        let name_width = if table.has_cells_after_name() {
+            rows.iter()
+                .filter(|row| row.cells.is_some())
+                .map(|row| row.tree.width() + *row.name.width)
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
         TableIter {
             tree_trunk: TreeTrunk::default(),
-            total_width: table.widths().total(),
+            total_width: table.width_before_name(),
+            name_width,
             table,
             inner: rows.into_iter(),
             tree_style: self.theme.ui.punctuation.unwrap_or_default(),
@@ -481,6 +652,10 @@ pub struct Row {
 
     /// Information used to determine which symbols to display in a tree.
     pub tree: TreeParams,
+
+    /// Whether this row is a heading, which starts at the left edge rather
+    /// than lining up with the names.
+    pub heading: bool,
 }
 
 #[rustfmt::skip]
@@ -489,6 +664,7 @@ pub struct TableIter<'a> {
     table: Table<'a>,
 
     total_width: usize,
+    name_width:  usize,
     tree_style:  Style,
     tree_trunk:  TreeTrunk,
 }
@@ -498,19 +674,29 @@ impl Iterator for TableIter<'_> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next().map(|row| {
-            let mut cell = if let Some(cells) = row.cells {
-                self.table.render(cells)
+            let (mut cell, after) = if let Some(cells) = row.cells {
+                let (before, after) = self.table.render(cells);
+                (before, Some(after))
+            } else if row.heading {
+                (TextCell::default(), None)
             } else {
                 let mut cell = TextCell::default();
                 cell.add_spaces(self.total_width);
-                cell
+                (cell, None)
             };
 
             for tree_part in self.tree_trunk.new_row(row.tree) {
                 cell.push(self.tree_style.paint(tree_part.ascii_art()), 4);
             }
 
+            let name_width = row.tree.width() + *row.name.width;
             cell.append(row.name);
+
+            if let Some(after) = after.filter(|after| *after.width > 0) {
+                cell.add_spaces(self.name_width.saturating_sub(name_width));
+                cell.append(after);
+            }
+
             cell
         })
     }
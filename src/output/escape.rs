@@ -58,7 +58,12 @@ pub fn get_hyperlink_start_tag(abs_path: &str) -> String {
     #[cfg(target_os = "windows")]
     let abs_path = abs_path.strip_prefix("\\\\?\\").unwrap_or(&abs_path);
 
-    format!("{HYPERLINK_OPENING_START}file://{abs_path}{HYPERLINK_OPENING_END}")
+    get_url_hyperlink_start_tag(&format!("file://{abs_path}"))
+}
+
+/// The start of a hyperlink to a URL that’s already been escaped.
+pub fn get_url_hyperlink_start_tag(url: &str) -> String {
+    format!("{HYPERLINK_OPENING_START}{url}{HYPERLINK_OPENING_END}")
 }
 
 #[cfg(test)]
@@ -5,21 +5,25 @@
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
 use std::fmt::Debug;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use nu_ansi_term::{AnsiString as ANSIString, Style};
+use nu_ansi_term::{AnsiString as ANSIString, Color, Style};
 use path_clean;
 use unicode_width::UnicodeWidthStr;
 
-use crate::fs::{File, FileTarget};
+use crate::fs::sparse::GhostReason;
+use crate::fs::{File, FileTarget, Zone};
 use crate::output::cell::TextCellContents;
+use crate::output::common_prefix;
 use crate::output::escape;
-use crate::output::icons::{icon_for_file, iconify_style};
+use crate::output::icons::{ghost_marker_icon, icon_for_file, iconify_style};
 use crate::output::render::FiletypeColours;
+use crate::output::url_template::UrlTemplate;
 use crate::theme::FileNameStyle;
 
 /// Basically a file name factory.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Options {
     /// Whether to append file class characters to file names.
     pub classify: Classify,
@@ -33,6 +37,10 @@ pub struct Options {
     /// Whether to make file names hyperlinks.
     pub embed_hyperlinks: EmbedHyperlinks,
 
+    /// Where hyperlinks for zones go instead of their directories, if
+    /// anywhere.
+    pub zone_url: Option<UrlTemplate>,
+
     /// Whether to display files with their absolute path.
     pub absolute: Absolute,
 
@@ -41,13 +49,23 @@ pub struct Options {
 
     /// Whether to suppress zone sigils on directories.
     pub no_sigils: bool,
+
+    /// Whether to colour each zone by a hash of its target path.
+    pub hashed_zone_colours: bool,
+
+    /// Whether to follow each zone’s name with a badge for its type.
+    pub zone_badges: bool,
+
+    /// The directory that every path being listed is inside, which gets left
+    /// out of them, if there is one.
+    pub common_prefix: Option<PathBuf>,
 }
 
 impl Options {
     /// Create a new `FileName` that prints the given file’s name, painting it
     /// with the remaining arguments.
     pub fn for_file<'a, 'dir, C>(
-        self,
+        &'a self,
         file: &'a File<'dir>,
         colours: &'a C,
     ) -> FileName<'a, 'dir, C> {
@@ -62,6 +80,7 @@ impl Options {
                 None
             },
             mount_style: MountStyle::JustDirectoryNames,
+            ghost_markers: None,
         }
     }
 }
@@ -162,10 +181,43 @@ pub struct FileName<'a, 'dir, C> {
     /// How to handle displaying links.
     link_style: LinkStyle,
 
-    pub options: Options,
+    pub options: &'a Options,
 
     /// How to handle displaying a mounted filesystem.
     mount_style: MountStyle,
+
+    /// What to put after the names of ghosts, if anything.
+    ghost_markers: Option<GhostMarkers>,
+}
+
+/// The glyphs put after ghost names in views that don’t otherwise show that
+/// an entry is a ghost, such as the grid view. Either can be overridden;
+/// otherwise, they default to an icon when icons are shown, and to a dagger
+/// when they aren’t.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct GhostMarkers {
+    /// The glyph for ghosts that are zones.
+    pub zone: Option<char>,
+
+    /// The glyph for every other ghost.
+    pub plain: Option<char>,
+}
+
+impl GhostMarkers {
+    /// Picks the glyph for the given ghost.
+    fn glyph(self, file: &File<'_>, icons: bool) -> char {
+        let custom = if file.is_zone() {
+            self.zone
+        } else {
+            self.plain
+        };
+        match custom {
+            Some(glyph) => glyph,
+            None if icons => ghost_marker_icon(file),
+            None if file.is_zone() => '‡',
+            None => '†',
+        }
+    }
 }
 
 impl<C> FileName<'_, '_, C> {
@@ -190,6 +242,13 @@ impl<C> FileName<'_, '_, C> {
         };
         self
     }
+
+    /// Sets the glyphs to put after this file’s name if it’s a ghost.
+    #[must_use]
+    pub fn with_ghost_markers(mut self, markers: GhostMarkers) -> Self {
+        self.ghost_markers = Some(markers);
+        self
+    }
 }
 
 impl<C: Colours> FileName<'_, '_, C> {
@@ -245,7 +304,10 @@ impl<C: Colours> FileName<'_, '_, C> {
 
         if self.file.parent_dir.is_none() && self.options.absolute == Absolute::Off {
             if let Some(parent) = self.file.path.parent() {
-                self.add_parent_bits(&mut bits, parent);
+                self.add_parent_bits(
+                    &mut bits,
+                    common_prefix::strip(parent, self.options.common_prefix.as_deref()),
+                );
             }
         }
 
@@ -278,9 +340,13 @@ impl<C: Colours> FileName<'_, '_, C> {
                             quote_style: QuoteStyle::QuoteSpaces,
                             show_icons: ShowIcons::Never,
                             embed_hyperlinks: EmbedHyperlinks::Off,
+                            zone_url: None,
                             is_a_tty: self.options.is_a_tty,
                             absolute: Absolute::Off,
                             no_sigils: self.options.no_sigils,
+                            hashed_zone_colours: self.options.hashed_zone_colours,
+                            zone_badges: false,
+                            common_prefix: None,
                         };
 
                         let target_name = FileName {
@@ -288,8 +354,9 @@ impl<C: Colours> FileName<'_, '_, C> {
                             colours: self.colours,
                             target: None,
                             link_style: LinkStyle::FullLinkPaths,
-                            options: target_options,
+                            options: &target_options,
                             mount_style: MountStyle::JustDirectoryNames,
+                            ghost_markers: None,
                         };
 
                         for bit in target_name.escaped_file_name(filename_style_override) {
@@ -328,6 +395,34 @@ impl<C: Colours> FileName<'_, '_, C> {
             }
         }
 
+        if let (true, Some(markers)) = (self.file.is_ghost, self.ghost_markers) {
+            let glyph = markers.glyph(self.file, spaces_count_opt.is_some());
+            bits.push(self.colours.ghost().paint(glyph.to_string()));
+        }
+
+        if self.options.zone_badges {
+            if let Some(kind) = self.file.zone_type() {
+                let style = if self.file.is_ghost {
+                    self.colours.zone_sigil_ghost()
+                } else {
+                    self.colours.zone_sigil()
+                };
+                bits.push(style.paint(format!(" [{}]", zone_badge(&kind))));
+            }
+        }
+
+        if let Some(reason) = self.file.ghost_reason {
+            let label = match reason {
+                GhostReason::Excluded => " [sparse]",
+                GhostReason::Missing => " [missing]",
+            };
+            bits.push(self.colours.ghost().paint(label));
+        }
+
+        if let Some(target) = self.file.moved_to() {
+            bits.push(self.colours.ghost().paint(format!(" [moved to {target}?]")));
+        }
+
         if self.mount_style == MountStyle::MountInfo {
             if let Some(mount_details) = self.file.mount_point_info() {
                 // This is a filesystem mounted on the directory, output its details
@@ -402,7 +497,8 @@ impl<C: Colours> FileName<'_, '_, C> {
     /// Returns at least one ANSI-highlighted string representing this file’s
     /// name using the given set of colours.
     ///
-    /// If --hyperlink flag is provided, it will escape the filename accordingly.
+    /// If --hyperlink flag is provided, it will escape the filename accordingly,
+    /// linking zones to their `--zone-url` if one was given.
     ///
     /// Ordinarily, this will be just one string: the file’s complete name,
     /// coloured according to its file type. If the name contains control
@@ -420,7 +516,11 @@ impl<C: Colours> FileName<'_, '_, C> {
 
         let mut display_hyperlink = false;
         if self.options.embed_hyperlinks == EmbedHyperlinks::On {
-            if let Some(abs_path) = self
+            if let Some(url) = self.zone_url() {
+                bits.push(ANSIString::from(escape::get_url_hyperlink_start_tag(&url)));
+
+                display_hyperlink = true;
+            } else if let Some(abs_path) = self
                 .file
                 .absolute_path()
                 .and_then(|p| p.as_os_str().to_str())
@@ -446,9 +546,25 @@ impl<C: Colours> FileName<'_, '_, C> {
         bits
     }
 
+    /// The URL to link this file to if it’s a zone and there’s a template
+    /// for zones, whether or not it’s on disk.
+    fn zone_url(&self) -> Option<String> {
+        let template = self.options.zone_url.as_ref()?;
+        self.file
+            .zone_target()
+            .map(|target| template.render_zone(target))
+    }
+
     /// Returns the string that should be displayed as the file's name.
     fn display_name(&self) -> String {
         match self.options.absolute {
+            // Ghosts have no symlinks to follow, or not, so either way they
+            // get the path they would have on disk
+            Absolute::On | Absolute::Follow if self.file.is_ghost => self
+                .file
+                .absolute_path()
+                .and_then(|p| p.to_str())
+                .map(std::borrow::ToOwned::to_owned),
             Absolute::On => std::env::current_dir().ok().and_then(|p| {
                 path_clean::clean(p.join(&self.file.path))
                     .to_str()
@@ -480,10 +596,10 @@ impl<C: Colours> FileName<'_, '_, C> {
 
         #[rustfmt::skip]
         return match self.file {
-            f if f.is_ghost && f.is_zone => self.colours.ghost().bold(),
+            f if f.is_ghost && f.is_zone() => self.zone_style(self.colours.ghost()).bold(),
             f if f.is_ghost              => self.colours.ghost(),
             f if f.is_mount_point()      => self.colours.mount_point(),
-            f if f.is_directory() && f.is_zone => self.colours.directory().bold(),
+            f if f.is_directory() && f.is_zone() => self.zone_style(self.colours.directory()).bold(),
             f if f.is_directory()        => self.colours.directory(),
             #[cfg(unix)]
             f if f.is_executable_file()  => self.colours.executable_file(),
@@ -501,6 +617,24 @@ impl<C: Colours> FileName<'_, '_, C> {
         };
     }
 
+    /// Recolours the given style with the colour the src root’s zone rules
+    /// give the zone, or its hashed colour when that option is on, as long
+    /// as colours are enabled.
+    fn zone_style(&self, base: Style) -> Style {
+        let hashed = self.options.hashed_zone_colours;
+        match self
+            .file
+            .zone()
+            .and_then(|zone| self.colours.zone_colour(zone, hashed))
+        {
+            Some(colour) => Style {
+                foreground: Some(colour),
+                ..base
+            },
+            None => base,
+        }
+    }
+
     /// For grid's use, to cover the case of hyperlink escape sequences
     #[must_use]
     pub fn bare_utf8_width(&self) -> usize {
@@ -509,6 +643,34 @@ impl<C: Colours> FileName<'_, '_, C> {
 }
 
 /// The set of colours that are needed to paint a file name.
+/// The short names badges give the zone types that are used the most, with
+/// the types they stand for.
+const ZONE_BADGES: &[(&str, &str)] = &[
+    ("library", "lib"),
+    ("application", "app"),
+    ("service", "svc"),
+    ("binary", "bin"),
+    ("tool", "tool"),
+    ("test", "test"),
+    ("documentation", "doc"),
+    ("config", "cfg"),
+];
+
+/// The text of the badge for a zone of the given type, which is its short
+/// name if it has one, or the type itself cut down to its first three
+/// characters if not.
+fn zone_badge(kind: &str) -> String {
+    let kind = kind.trim().to_lowercase();
+    if let Some((_, badge)) = ZONE_BADGES
+        .iter()
+        .find(|(name, badge)| *name == kind || *badge == kind)
+    {
+        return (*badge).to_string();
+    }
+
+    kind.chars().take(3).collect()
+}
+
 pub trait Colours: FiletypeColours {
     /// The style to paint the path of a symlink’s target, up to but not
     /// including the file’s name.
@@ -547,7 +709,29 @@ pub trait Colours: FiletypeColours {
     /// The style to paint the zone sigil for ghost directories.
     fn zone_sigil_ghost(&self) -> Style;
 
+    /// The colour derived from a zone’s target path, or `None` if colours
+    /// are turned off.
+    fn zone_colour(&self, zone: &Zone, hashed: bool) -> Option<Color>;
+
     fn colour_file(&self, file: &File<'_>) -> Style;
 
     fn style_override(&self, file: &File<'_>) -> Option<FileNameStyle>;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn badges_for_common_types() {
+        assert_eq!(zone_badge("library"), "lib");
+        assert_eq!(zone_badge("Service"), "svc");
+        assert_eq!(zone_badge("app"), "app");
+    }
+
+    #[test]
+    fn badges_for_other_types() {
+        assert_eq!(zone_badge("pipeline"), "pip");
+        assert_eq!(zone_badge("ui"), "ui");
+    }
+}
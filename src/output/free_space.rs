@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The footer printed by `--free-space`, which shows how full the disk under
+//! a listing is, because giant checkouts fill disks up quickly.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::output::render::size_in_words;
+
+/// The free and total space of a filesystem, in bytes, such as
+/// `87 GiB free of 932 GiB (9%)`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct FreeSpace {
+    /// The space available to unprivileged users.
+    pub free: u64,
+
+    /// The size of the whole filesystem.
+    pub total: u64,
+}
+
+impl FreeSpace {
+    /// Asks the filesystem containing the given path how full it is.
+    #[cfg(unix)]
+    pub fn of(path: &Path) -> Option<Self> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+        if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        let stat = unsafe { stat.assume_init() };
+
+        // The field types vary between platforms
+        #[allow(trivial_numeric_casts, clippy::unnecessary_cast)]
+        let (block_size, available, blocks) = (
+            stat.f_frsize as u64,
+            stat.f_bavail as u64,
+            stat.f_blocks as u64,
+        );
+
+        Some(Self {
+            free: available * block_size,
+            total: blocks * block_size,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(_path: &Path) -> Option<Self> {
+        None
+    }
+}
+
+impl fmt::Display for FreeSpace {
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = if self.total == 0 {
+            0
+        } else {
+            (self.free as f64 / self.total as f64 * 100.0).round() as u64
+        };
+
+        write!(
+            f,
+            "{} free of {} ({percent}%)",
+            size_in_words(self.free),
+            size_in_words(self.total)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let space = FreeSpace {
+            free: 87 * 1024 * 1024 * 1024,
+            total: 1024 * 1024 * 1024 * 1024,
+        };
+        assert_eq!(space.to_string(), "87 GiB free of 1.0 TiB (8%)");
+    }
+
+    #[test]
+    fn small() {
+        let space = FreeSpace {
+            free: 512,
+            total: 2048,
+        };
+        assert_eq!(space.to_string(), "512 B free of 2.0 KiB (25%)");
+    }
+
+    #[test]
+    fn empty_filesystem() {
+        let space = FreeSpace { free: 0, total: 0 };
+        assert_eq!(space.to_string(), "0 B free of 0 B (0%)");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn root_has_a_size() {
+        assert!(FreeSpace::of(Path::new("/")).is_some_and(|s| s.total > 0));
+    }
+}
@@ -10,12 +10,13 @@ use term_grid::{Direction, Filling, Grid, GridOptions};
 
 use crate::fs::filter::FileFilter;
 use crate::fs::File;
-use crate::output::file_name::Options as FileStyle;
+use crate::output::file_name::{GhostMarkers, Options as FileStyle};
 use crate::theme::Theme;
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub struct Options {
     pub across: bool,
+    pub ghost_markers: GhostMarkers,
 }
 
 impl Options {
@@ -48,6 +49,7 @@ impl Render<'_> {
             .map(|file| {
                 self.file_style
                     .for_file(file, self.theme)
+                    .with_ghost_markers(self.opts.ghost_markers)
                     .paint()
                     .strings()
                     .to_string()
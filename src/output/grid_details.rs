@@ -8,9 +8,7 @@
 
 use std::io::{self, Write};
 
-use ansi_width;
-use grid::{Direction, Filling, Grid, GridOptions};
-use term_grid as grid;
+use term_grid::{Direction, Filling, Grid, GridOptions};
 
 use crate::fs::feature::git::GitCache;
 use crate::fs::filter::FileFilter;
@@ -149,31 +147,47 @@ impl<'a> Render<'a> {
             })
             .collect();
 
-        let cells = rows
-            .into_iter()
-            .zip(&self.files)
-            .map(|(row, file)| {
-                let filename = self
-                    .file_style
+        let filenames: Vec<String> = self
+            .files
+            .iter()
+            .map(|file| {
+                self.file_style
                     .for_file(file, self.theme)
                     .paint()
                     .strings()
-                    .to_string();
-                let details = table.render(row).strings().to_string();
-
-                // This bit fixes a strange corner case. If there is a header,
-                // then "Name" will be added to the header row. That means that
-                // the filename column, should be at least 4 characters wide.
-                // Therefore we pad the filenames with some spaces. We have to
-                // use ansi_width here, because the filename might contain some
-                // styling.
-                let padding = " ".repeat(if self.details.header {
-                    4usize.saturating_sub(ansi_width::ansi_width(&filename))
-                } else {
-                    0
-                });
-
-                format!("{details} {filename}{padding}")
+                    .to_string()
+            })
+            .collect();
+
+        // This bit fixes a strange corner case. If there is a header, then
+        // "Name" will be added to the header row. That means that the
+        // filename column, should be at least 4 characters wide. Therefore
+        // we pad the filenames with some spaces. When there are cells after
+        // the names, the names get padded to line them up as well. We have
+        // to use ansi_width here, because the filename might contain some
+        // styling.
+        let mut name_width = if self.details.header { 4 } else { 0 };
+        if table.has_cells_after_name() {
+            let widest = filenames
+                .iter()
+                .map(|filename| ansi_width::ansi_width(filename))
+                .max();
+            name_width = name_width.max(widest.unwrap_or(0));
+        }
+
+        let cells = rows
+            .into_iter()
+            .zip(filenames)
+            .map(|(row, filename)| {
+                let (before, after) = table.render(row);
+                let padding =
+                    " ".repeat(name_width.saturating_sub(ansi_width::ansi_width(&filename)));
+                format!(
+                    "{}{}{filename}{padding}{}",
+                    before.strings(),
+                    gap(&before),
+                    after.strings()
+                )
             })
             .collect();
 
@@ -226,8 +240,14 @@ impl<'a> Render<'a> {
             let name = TextCell::paint_str(self.theme.ui.header.unwrap_or_default(), "Name")
                 .strings()
                 .to_string();
-            let s = table.render(row).strings().to_string();
-            let combined_header = format!("{s} {name}");
+            let (before, after) = table.render(row);
+            let padding = " ".repeat(name_width.saturating_sub(4));
+            let combined_header = format!(
+                "{}{}{name}{padding}{}",
+                before.strings(),
+                gap(&before),
+                after.strings()
+            );
             let header_width = ansi_width::ansi_width(&combined_header);
             for column_width in grid.column_widths() {
                 let padding = " ".repeat((column_width + 4).saturating_sub(header_width));
@@ -268,3 +288,13 @@ impl<'a> Render<'a> {
         table
     }
 }
+
+/// What goes between the cells before a file’s name and the name, which is
+/// nothing when the name comes first.
+fn gap(before: &TextCell) -> &'static str {
+    if *before.width == 0 {
+        ""
+    } else {
+        " "
+    }
+}
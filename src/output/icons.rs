@@ -7,13 +7,14 @@
 use nu_ansi_term::Style;
 use phf::{phf_map, Map};
 
-use crate::fs::File;
+use crate::fs::{File, ZoneKind};
 
 #[non_exhaustive]
 struct Icons;
 
 #[rustfmt::skip]
 impl Icons {
+    const APPLICATION: char     = '\u{f08c6}'; // 󰣆
     const AUDIO: char           = '\u{f001}';  // 
     const BINARY: char          = '\u{eae8}';  // 
     const BOOK: char            = '\u{e28b}';  // 
@@ -21,6 +22,7 @@ impl Icons {
     const CACHE: char           = '\u{f49b}';  // 
     const CAD: char             = '\u{f0eeb}'; // 󰻫
     const CLOCK: char           = '\u{f43a}';  // 
+    const CLOUD_OUTLINE: char   = '\u{f0163}'; // 󰅣
     const COMPRESSED: char      = '\u{f410}';  // 
     const CONFIG: char          = '\u{f107b}'; // 󱁻
     const CSS3: char            = '\u{e749}';  // 
@@ -50,6 +52,7 @@ impl Icons {
     const FILE_UNKNOW: char     = '\u{f086f}'; // 󰡯
     const FONT: char            = '\u{f031}';  // 
     const FREECAD: char         = '\u{f336}';  // 
+    const GHOST: char           = '\u{f02a0}'; // 󰊠
     const GIMP: char            = '\u{f338}';  // 
     const GIST_SECRET: char     = '\u{eafa}';  // 
     const GIT: char             = '\u{f02a2}'; // 󰊢
@@ -1123,10 +1126,34 @@ pub fn iconify_style(style: Style) -> Style {
         .unwrap_or_default()
 }
 
-/// Lookup the icon for a file based on the file's name, if the entry is a
+/// The icon put after a ghost’s name in the grid view, which differs for
+/// ghosts that are zones themselves.
+pub fn ghost_marker_icon(file: &File<'_>) -> char {
+    if file.is_zone() {
+        Icons::CLOUD_OUTLINE // 󰅣
+    } else {
+        Icons::GHOST // 󰊠
+    }
+}
+
+/// The icon for a zone whose manifest entry says what it holds.
+fn zone_icon(kind: ZoneKind) -> char {
+    match kind {
+        ZoneKind::Library => Icons::LIBRARY, // 
+        ZoneKind::App => Icons::APPLICATION, // 󰣆
+        ZoneKind::Tool => Icons::WRENCH,     // 
+    }
+}
+
+/// Lookup the icon for a file based on the src root's zone rules or what
+/// the manifest says a zone holds, the file's name, if the entry is a
 /// directory, or by the lowercase file extension.
 pub fn icon_for_file(file: &File<'_>) -> char {
-    if file.points_to_directory() {
+    if let Some(icon) = file.zone().and_then(|zone| zone.icon) {
+        icon
+    } else if let Some(kind) = file.zone().and_then(|zone| zone.kind) {
+        zone_icon(kind)
+    } else if file.points_to_directory() {
         *DIRECTORY_ICONS.get(file.name.as_str()).unwrap_or_else(|| {
             if file.is_empty_dir() {
                 &Icons::FOLDER_OPEN // 
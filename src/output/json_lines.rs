@@ -0,0 +1,103 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The `--json-lines` view, which writes one JSON object per entry so that
+//! huge directories can be streamed into other programs as they are read.
+
+use std::io::{self, Write};
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use crate::fs::File;
+
+/// One line of output.
+#[derive(Serialize)]
+struct Entry<'a> {
+    path: String,
+    name: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: Option<u64>,
+    modified: Option<u64>,
+    ghost: bool,
+    zone: Option<&'a str>,
+}
+
+/// Writes a single file as a line of JSON.
+pub fn write_entry<W: Write>(w: &mut W, file: &File<'_>) -> io::Result<()> {
+    let entry = Entry {
+        path: file.path.to_string_lossy().into_owned(),
+        name: &file.name,
+        kind: kind(file),
+        size: file.is_file().then(|| file.length()),
+        modified: (!file.is_ghost)
+            .then(|| file.metadata().ok())
+            .flatten()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+        ghost: file.is_ghost,
+        zone: file.zone_target(),
+    };
+
+    serde_json::to_writer(&mut *w, &entry)?;
+    writeln!(w)
+}
+
+/// The name for the type of file, as used in the `type` field.
+pub fn kind(file: &File<'_>) -> &'static str {
+    if file.is_directory() {
+        "directory"
+    } else if file.is_link() {
+        "symlink"
+    } else if file.is_file() {
+        "file"
+    } else if file.is_pipe() {
+        "pipe"
+    } else if file.is_socket() {
+        "socket"
+    } else if file.is_char_device() {
+        "char-device"
+    } else if file.is_block_device() {
+        "block-device"
+    } else {
+        "other"
+    }
+}
+
+pub struct Render<'a> {
+    pub files: Vec<File<'a>>,
+}
+
+impl Render<'_> {
+    pub fn render<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for file in &self.files {
+            write_entry(w, file)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::{Dir, DirOptions};
+    use std::sync::Arc;
+
+    #[test]
+    fn ghosts_are_not_statted() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = Dir::read_dir(temp.path().into(), Arc::new(DirOptions::default())).unwrap();
+        let path = temp.path().join("tools");
+        let file = File::new_ghost(path, &dir, "tools".into(), None, false, None);
+
+        let mut out = Vec::new();
+        write_entry(&mut out, &file).unwrap();
+        assert!(file.metadata.get().is_none());
+
+        let entry: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(entry["ghost"], true);
+        assert!(entry["modified"].is_null());
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The `--manifest-only` view, which lists a target’s children from the
+//! manifest alone, so it works before any of the repository is on disk.
+//!
+//! The only thing looked at on disk is whether each child’s directory
+//! exists, so that the ones that don’t can be drawn as ghosts. Zones are
+//! coloured by their kind, so the list is as easy to scan as a listing.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use nu_ansi_term::Style;
+use unicode_width::UnicodeWidthStr;
+
+use crate::fs::{ManifestChild, ZoneKind};
+
+/// The styles to paint the children in.
+pub trait Colours {
+    /// Children that aren’t zones, or whose kind isn’t known.
+    fn directory(&self) -> Style;
+
+    /// Children that aren’t on disk, on top of their usual colour.
+    fn ghost(&self) -> Style;
+
+    /// Zones of the given kind.
+    fn zone_kind(&self, kind: ZoneKind) -> Style;
+}
+
+pub struct Render<'a> {
+    pub children: &'a [ManifestChild],
+
+    /// The directory the children would be in on disk, if the target has
+    /// one, to tell which of them are ghosts.
+    pub dir: Option<&'a Path>,
+
+    pub colours: &'a dyn Colours,
+}
+
+impl Render<'_> {
+    /// Writes one child per line, followed by how many zones are at or
+    /// beneath it, such as `tools  3 zones`.
+    pub fn render<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let width = self
+            .children
+            .iter()
+            .map(|c| c.name.width())
+            .max()
+            .unwrap_or(0);
+        let count_width = self
+            .children
+            .iter()
+            .map(|c| c.zones.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        for child in self.children {
+            let style = self.style(child);
+            let zones = if child.zones == 1 { "zone" } else { "zones" };
+            let padding = " ".repeat(width - child.name.width());
+            writeln!(
+                w,
+                "{}{padding}  {:>count_width$} {zones}",
+                style.paint(&child.name),
+                child.zones
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Zones get the colour of their kind, and ghosts get the ghost style
+    /// on top, keeping that colour unless the ghost style has its own.
+    fn style(&self, child: &ManifestChild) -> Style {
+        let style = child.kind.map_or_else(
+            || self.colours.directory(),
+            |kind| self.colours.zone_kind(kind),
+        );
+        let on_disk = self.dir.is_some_and(|dir| dir.join(&child.name).is_dir());
+
+        let style = if on_disk {
+            style
+        } else {
+            let ghost = self.colours.ghost();
+            Style {
+                foreground: ghost.foreground.or(style.foreground),
+                ..ghost
+            }
+        };
+
+        if child.is_zone {
+            style.bold()
+        } else {
+            style
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use nu_ansi_term::Color::{Blue, Fixed};
+
+    struct Plain;
+
+    impl Colours for Plain {
+        fn directory(&self) -> Style {
+            Style::default()
+        }
+
+        fn ghost(&self) -> Style {
+            Style::default()
+        }
+
+        fn zone_kind(&self, _kind: ZoneKind) -> Style {
+            Style::default()
+        }
+    }
+
+    struct TestColours;
+
+    impl Colours for TestColours {
+        fn directory(&self) -> Style {
+            Blue.normal()
+        }
+
+        fn ghost(&self) -> Style {
+            Style::default().italic()
+        }
+
+        fn zone_kind(&self, _kind: ZoneKind) -> Style {
+            Fixed(90).normal()
+        }
+    }
+
+    #[test]
+    fn counts_are_aligned() {
+        let children = [
+            ManifestChild {
+                name: "areas".into(),
+                is_zone: false,
+                zones: 12,
+                kind: None,
+            },
+            ManifestChild {
+                name: "ci".into(),
+                is_zone: false,
+                zones: 1,
+                kind: None,
+            },
+        ];
+
+        let mut out = Vec::new();
+        Render {
+            children: &children,
+            dir: None,
+            colours: &Plain,
+        }
+        .render(&mut out)
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "areas  12 zones\nci      1 zone\n"
+        );
+    }
+
+    #[test]
+    fn ghosts_keep_the_colour_of_their_kind() {
+        let render = Render {
+            children: &[],
+            dir: None,
+            colours: &TestColours,
+        };
+        let zone = ManifestChild {
+            name: "dev".into(),
+            is_zone: true,
+            zones: 1,
+            kind: Some(ZoneKind::Tool),
+        };
+        assert_eq!(render.style(&zone), Fixed(90).bold().italic());
+
+        let dir = ManifestChild {
+            name: "areas".into(),
+            is_zone: false,
+            zones: 3,
+            kind: None,
+        };
+        assert_eq!(render.style(&dir), Blue.italic());
+    }
+
+    #[test]
+    fn directories_on_disk_are_not_ghosts() {
+        let dir = std::env::temp_dir();
+        let render = Render {
+            children: &[],
+            dir: Some(&dir),
+            colours: &TestColours,
+        };
+        let child = ManifestChild {
+            name: ".".into(),
+            is_zone: false,
+            zones: 1,
+            kind: None,
+        };
+        assert_eq!(render.style(&child), Blue.normal());
+    }
+}
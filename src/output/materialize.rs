@@ -0,0 +1,65 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The hints printed by `--materialize`, which tell the user exactly what to
+//! run to check out each ghost zone in a listing.
+
+use crate::fs::File;
+
+/// How to turn a zone’s target path into the command that materializes it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct MaterializeHint {
+    /// The command to print, with `{target}` replaced by the zone’s target
+    /// path, such as `//areas/tools/dev`, and `{path}` replaced by the same
+    /// path relative to the src root, such as `areas/tools/dev`.
+    pub template: String,
+}
+
+impl MaterializeHint {
+    /// The command used when `WLS_MATERIALIZE_COMMAND` isn’t set.
+    pub const DEFAULT_TEMPLATE: &'static str = "git sparse-checkout add {path}";
+
+    /// Fills in the template for one zone.
+    pub fn command(&self, target: &str) -> String {
+        let path = target.trim_start_matches('/');
+        self.template
+            .replace("{target}", target)
+            .replace("{path}", path)
+    }
+
+    /// The commands for every ghost zone among the files about to be listed.
+    pub fn commands(&self, files: &[File<'_>]) -> Vec<String> {
+        files
+            .iter()
+            .filter(|f| f.is_ghost)
+            .filter_map(File::zone_target)
+            .map(|target| self.command(target))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_template() {
+        let hint = MaterializeHint {
+            template: MaterializeHint::DEFAULT_TEMPLATE.into(),
+        };
+        assert_eq!(
+            hint.command("//areas/tools/dev"),
+            "git sparse-checkout add areas/tools/dev"
+        );
+    }
+
+    #[test]
+    fn target_placeholder() {
+        let hint = MaterializeHint {
+            template: "spin materialize {target}".into(),
+        };
+        assert_eq!(
+            hint.command("//areas/tools/dev"),
+            "spin materialize //areas/tools/dev"
+        );
+    }
+}
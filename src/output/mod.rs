@@ -9,15 +9,26 @@ pub use self::cell::TextCell;
 pub use self::escape::escape;
 
 pub mod color_scale;
+pub mod common_prefix;
+pub mod delimited;
+pub mod describe;
 pub mod details;
 pub mod file_name;
+pub mod free_space;
 pub mod grid;
 pub mod grid_details;
 pub mod icons;
+pub mod json_lines;
 pub mod lines;
+pub mod manifest_only;
+pub mod materialize;
+pub mod print0;
 pub mod render;
 pub mod table;
+pub mod template;
 pub mod time;
+pub mod url_template;
+pub mod zone_summary;
 
 mod cell;
 mod escape;
@@ -25,6 +36,7 @@ mod tree;
 
 /// The **view** contains all information about how to format output.
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct View {
     pub mode: Mode,
     pub width: TerminalWidth,
@@ -32,6 +44,13 @@ pub struct View {
     pub deref_links: bool,
     pub follow_links: bool,
     pub total_size: bool,
+    pub zone_summary: bool,
+    pub free_space: bool,
+
+    /// Whether ghosts are listed in a section of their own, below the files
+    /// that are on disk, rather than mixed in with them.
+    pub split_ghosts: bool,
+    pub materialize: Option<materialize::MaterializeHint>,
 }
 
 /// The **mode** is the “type” of output.
@@ -42,6 +61,57 @@ pub enum Mode {
     Details(details::Options),
     GridDetails(grid_details::Options),
     Lines,
+    JsonLines,
+    Delimited(delimited::Options),
+    Print0(print0::Options),
+    Template(template::Template),
+}
+
+impl Mode {
+    /// Every name `format` gives, which `--version --json` reports for
+    /// wrapper tooling to feature-detect with.
+    pub const FORMATS: &'static [&'static str] = &[
+        "grid",
+        "long",
+        "grid-details",
+        "oneline",
+        "tree",
+        "json-lines",
+        "csv",
+        "tsv",
+        "print0",
+        "format",
+    ];
+
+    /// The name of the format this mode writes entries in.
+    #[must_use]
+    pub fn format(&self) -> &'static str {
+        match self {
+            Self::Grid(_) => "grid",
+            Self::Details(details::Options { table: None, .. }) => "tree",
+            Self::Details(_) => "long",
+            Self::GridDetails(_) => "grid-details",
+            Self::Lines => "oneline",
+            Self::JsonLines => "json-lines",
+            Self::Delimited(opts) => match opts.separator {
+                delimited::Separator::Comma => "csv",
+                delimited::Separator::Tab => "tsv",
+            },
+            Self::Print0(_) => "print0",
+            Self::Template(_) => "format",
+        }
+    }
+
+    /// Whether entries are written as records for other programs as they’re
+    /// read, with recursive listings flattened into one stream, rather than
+    /// laid out for people.
+    #[must_use]
+    pub fn is_streamed(&self) -> bool {
+        matches!(
+            self,
+            Self::JsonLines | Self::Delimited(_) | Self::Print0(_) | Self::Template(_)
+        )
+    }
 }
 
 /// The width of the terminal requested by the user.
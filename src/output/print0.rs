@@ -0,0 +1,113 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The `--print0` view, which writes each entry’s bare path followed by a
+//! NUL byte, for `xargs -0` and `fzf --read0`.
+//!
+//! Paths are written as their bytes, without any escaping or quoting, so
+//! names with newlines or invalid UTF-8 in them get through intact. Ghosts
+//! aren’t on disk, so a program given their paths couldn’t do anything
+//! with them: they’re left out, unless asked for, in which case they’re
+//! written as their target paths, which start with the target prefix
+//! rather than looking like somewhere on disk.
+
+use std::io::{self, Write};
+
+use crate::fs::File;
+
+/// Options for the `--print0` view.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct Options {
+    /// Whether ghosts are written, as their target paths.
+    pub ghosts: bool,
+}
+
+/// Writes a single file’s path, and the NUL after it.
+pub fn write_entry<W: Write>(w: &mut W, file: &File<'_>, opts: Options) -> io::Result<()> {
+    if file.is_ghost {
+        match file.target_path() {
+            Some(target) if opts.ghosts => w.write_all(target.as_bytes())?,
+            _ => return Ok(()),
+        }
+    } else {
+        w.write_all(&path_bytes(file))?;
+    }
+
+    w.write_all(b"\0")
+}
+
+#[cfg(unix)]
+fn path_bytes<'a>(file: &'a File<'_>) -> std::borrow::Cow<'a, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    std::borrow::Cow::Borrowed(file.path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_bytes<'a>(file: &'a File<'_>) -> std::borrow::Cow<'a, [u8]> {
+    match file.path.to_string_lossy() {
+        std::borrow::Cow::Borrowed(path) => std::borrow::Cow::Borrowed(path.as_bytes()),
+        std::borrow::Cow::Owned(path) => std::borrow::Cow::Owned(path.into_bytes()),
+    }
+}
+
+pub struct Render<'a> {
+    pub files: Vec<File<'a>>,
+    pub opts: Options,
+}
+
+impl Render<'_> {
+    pub fn render<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for file in &self.files {
+            write_entry(w, file, self.opts)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use crate::fs::{Dir, DirOptions};
+
+    fn written(file: &File<'_>, ghosts: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_entry(&mut bytes, file, Options { ghosts }).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn paths_are_written_as_they_are() {
+        let options = Arc::new(DirOptions::default());
+        let file = File::from_args(
+            PathBuf::from("dir/new\nline name"),
+            None,
+            None,
+            false,
+            false,
+            None,
+            &options,
+        );
+        assert_eq!(written(&file, false), b"dir/new\nline name\0");
+    }
+
+    #[test]
+    fn ghosts_outside_a_src_root_are_left_out() {
+        let dir = Dir::new(
+            PathBuf::from("/test/src/areas"),
+            Arc::new(DirOptions::default()),
+        );
+        let ghost = File::new_ghost(
+            dir.join(Path::new("apps")),
+            &dir,
+            "apps".into(),
+            None,
+            false,
+            None,
+        );
+        assert_eq!(written(&ghost, true), b"");
+        assert_eq!(written(&ghost, false), b"");
+    }
+}
@@ -0,0 +1,83 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+use nu_ansi_term::Style;
+
+use crate::fs::fields as f;
+use crate::output::cell::{DisplayWidth, TextCell};
+
+impl f::Filesystem {
+    pub fn render(&self, colours: &dyn Colours) -> TextCell {
+        let style = match self.kind {
+            f::FilesystemKind::Local => colours.local(),
+            f::FilesystemKind::Overlay => colours.overlay(),
+            f::FilesystemKind::Network => colours.network(),
+        };
+        let mount_point = self.mount_point.to_string_lossy().into_owned();
+
+        TextCell {
+            width: DisplayWidth::from(self.fstype.as_str())
+                + DisplayWidth::from(1)
+                + DisplayWidth::from(mount_point.as_str()),
+            contents: vec![
+                style.paint(self.fstype.clone()),
+                Style::default().paint(" "),
+                colours.mount_point().paint(mount_point),
+            ]
+            .into(),
+        }
+    }
+}
+
+pub trait Colours {
+    fn local(&self) -> Style;
+    fn overlay(&self) -> Style;
+    fn network(&self) -> Style;
+    fn mount_point(&self) -> Style;
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Colours;
+    use crate::fs::fields as f;
+    use crate::output::cell::{DisplayWidth, TextCell};
+
+    use nu_ansi_term::Color::*;
+    use nu_ansi_term::Style;
+
+    struct TestColours;
+
+    impl Colours for TestColours {
+        fn local(&self) -> Style {
+            Fixed(90).normal()
+        }
+        fn overlay(&self) -> Style {
+            Fixed(91).normal()
+        }
+        fn network(&self) -> Style {
+            Fixed(92).normal()
+        }
+        fn mount_point(&self) -> Style {
+            Fixed(93).normal()
+        }
+    }
+
+    #[test]
+    fn network() {
+        let filesystem = f::Filesystem {
+            kind: f::FilesystemKind::Network,
+            fstype: "nfs4".into(),
+            mount_point: "/src".into(),
+        };
+
+        let expected = TextCell {
+            width: DisplayWidth::from(9),
+            contents: vec![
+                Fixed(92).paint("nfs4"),
+                Style::default().paint(" "),
+                Fixed(93).paint("/src"),
+            ]
+            .into(),
+        };
+        assert_eq!(expected, filesystem.render(&TestColours));
+    }
+}
@@ -18,6 +18,55 @@ impl f::Git {
     }
 }
 
+impl f::GitCounts {
+    /// Renders the counts as, say, `3M 1N`, leaving out any that are zero.
+    pub fn render(self, colours: &dyn Colours) -> TextCell {
+        let mut cell = TextCell::default();
+
+        for (count, letter, style) in [
+            (self.modified, "M", colours.modified()),
+            (self.untracked, "N", colours.new()),
+        ] {
+            if count == 0 {
+                continue;
+            }
+
+            if *cell.width > 0 {
+                cell.add_spaces(1);
+            }
+            let text = format!("{count}{letter}");
+            let width = text.len();
+            cell.push(style.paint(text), width);
+        }
+
+        cell
+    }
+}
+
+impl f::ZoneHealth {
+    /// Renders whether the zone is clean or dirty, the same way as the Git
+    /// repos column, followed by how far ahead or behind it is, say `+ ↑2`.
+    pub fn render(self, colours: &dyn RepoColours) -> TextCell {
+        let mut cell = TextCell::default();
+        if self.counts.is_empty() {
+            cell.push(colours.git_clean().paint("|"), 1);
+        } else {
+            cell.push(colours.git_dirty().paint("+"), 1);
+        }
+
+        for (count, arrow) in [(self.ahead, "↑"), (self.behind, "↓")] {
+            if count > 0 {
+                let text = format!("{arrow}{count}");
+                let width = DisplayWidth::from(text.as_str());
+                cell.add_spaces(1);
+                cell.push(colours.branch_other().paint(text), *width);
+            }
+        }
+
+        cell
+    }
+}
+
 impl f::GitStatus {
     fn render(self, colours: &dyn Colours) -> ANSIString<'static> {
         #[rustfmt::skip]
@@ -87,6 +136,17 @@ impl f::SubdirGitRepoStatus {
     }
 }
 
+impl f::NestedRepo {
+    pub fn render(self, colours: &dyn RepoColours) -> TextCell {
+        let name = match self {
+            Self::Submodule => "submodule",
+            Self::Worktree => "worktree",
+            Self::Repository => "repo",
+        };
+        TextCell::paint_str(colours.branch_other(), name)
+    }
+}
+
 pub trait RepoColours {
     fn branch_main(&self) -> Style;
     fn branch_other(&self) -> Style;
@@ -97,7 +157,7 @@ pub trait RepoColours {
 
 #[cfg(test)]
 pub mod test {
-    use super::Colours;
+    use super::{Colours, RepoColours};
     use crate::fs::fields as f;
     use crate::output::cell::{DisplayWidth, TextCell};
 
@@ -133,6 +193,24 @@ pub mod test {
         }
     }
 
+    impl RepoColours for TestColours {
+        fn branch_main(&self) -> Style {
+            Fixed(80).normal()
+        }
+        fn branch_other(&self) -> Style {
+            Fixed(81).normal()
+        }
+        fn no_repo(&self) -> Style {
+            Fixed(82).normal()
+        }
+        fn git_clean(&self) -> Style {
+            Fixed(83).normal()
+        }
+        fn git_dirty(&self) -> Style {
+            Fixed(84).normal()
+        }
+    }
+
     #[test]
     fn git_blank() {
         let stati = f::Git {
@@ -162,4 +240,100 @@ pub mod test {
 
         assert_eq!(expected, stati.render(&TestColours));
     }
+
+    #[test]
+    fn git_counts_both() {
+        let counts = f::GitCounts {
+            modified: 3,
+            untracked: 12,
+        };
+
+        let expected = TextCell {
+            width: DisplayWidth::from(6),
+            contents: vec![
+                Fixed(92).paint("3M"),
+                Style::default().paint(" "),
+                Fixed(91).paint("12N"),
+            ]
+            .into(),
+        };
+
+        assert_eq!(expected, counts.render(&TestColours));
+    }
+
+    #[test]
+    fn git_counts_untracked_only() {
+        let counts = f::GitCounts {
+            modified: 0,
+            untracked: 1,
+        };
+
+        let expected = TextCell {
+            width: DisplayWidth::from(2),
+            contents: vec![Fixed(91).paint("1N")].into(),
+        };
+
+        assert_eq!(expected, counts.render(&TestColours));
+    }
+
+    #[test]
+    fn zone_health_clean() {
+        let health = f::ZoneHealth::default();
+
+        let expected = TextCell {
+            width: DisplayWidth::from(1),
+            contents: vec![Fixed(83).paint("|")].into(),
+        };
+
+        assert_eq!(expected, health.render(&TestColours));
+    }
+
+    #[test]
+    fn zone_health_dirty_and_diverged() {
+        let health = f::ZoneHealth {
+            counts: f::GitCounts {
+                modified: 1,
+                untracked: 0,
+            },
+            ahead: 2,
+            behind: 10,
+        };
+
+        let expected = TextCell {
+            width: DisplayWidth::from(8),
+            contents: vec![
+                Fixed(84).paint("+"),
+                Style::default().paint(" "),
+                Fixed(81).paint("↑2"),
+                Style::default().paint(" "),
+                Fixed(81).paint("↓10"),
+            ]
+            .into(),
+        };
+
+        assert_eq!(expected, health.render(&TestColours));
+    }
+
+    #[test]
+    fn nested_submodule() {
+        let expected = TextCell::paint_str(Fixed(81).normal(), "submodule");
+        assert_eq!(expected, f::NestedRepo::Submodule.render(&TestColours));
+    }
+
+    #[test]
+    fn gitlinks() {
+        assert_eq!(
+            f::NestedRepo::from_gitlink("gitdir: ../../.git/modules/vendor/zlib\n"),
+            Some(f::NestedRepo::Submodule)
+        );
+        assert_eq!(
+            f::NestedRepo::from_gitlink("gitdir: /src/.git/worktrees/hotfix\n"),
+            Some(f::NestedRepo::Worktree)
+        );
+        assert_eq!(
+            f::NestedRepo::from_gitlink("gitdir: /elsewhere/repo.git\n"),
+            Some(f::NestedRepo::Repository)
+        );
+        assert_eq!(f::NestedRepo::from_gitlink("not a gitlink"), None);
+    }
 }
@@ -0,0 +1,58 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+use nu_ansi_term::Style;
+
+use crate::fs::fields as f;
+use crate::output::cell::TextCell;
+
+impl f::Layer {
+    pub fn render(self, colours: &dyn Colours) -> TextCell {
+        match self {
+            Self::Lower => TextCell::paint_str(colours.lower(), "image"),
+            Self::CopiedUp => TextCell::paint_str(colours.copied_up(), "modified"),
+            Self::Upper => TextCell::paint_str(colours.upper(), "new"),
+        }
+    }
+}
+
+pub trait Colours {
+    fn lower(&self) -> Style;
+    fn copied_up(&self) -> Style;
+    fn upper(&self) -> Style;
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Colours;
+    use crate::fs::fields as f;
+    use crate::output::cell::TextCell;
+
+    use nu_ansi_term::Color::*;
+    use nu_ansi_term::Style;
+
+    struct TestColours;
+
+    impl Colours for TestColours {
+        fn lower(&self) -> Style {
+            Fixed(90).normal()
+        }
+        fn copied_up(&self) -> Style {
+            Fixed(91).normal()
+        }
+        fn upper(&self) -> Style {
+            Fixed(92).normal()
+        }
+    }
+
+    #[test]
+    fn lower() {
+        let expected = TextCell::paint_str(Fixed(90).normal(), "image");
+        assert_eq!(expected, f::Layer::Lower.render(&TestColours));
+    }
+
+    #[test]
+    fn copied_up() {
+        let expected = TextCell::paint_str(Fixed(91).normal(), "modified");
+        assert_eq!(expected, f::Layer::CopiedUp.render(&TestColours));
+    }
+}
@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+use nu_ansi_term::Style;
+
+use crate::fs::fields as f;
+use crate::output::cell::TextCell;
+
+impl f::Materialized {
+    pub fn render(self, colours: &dyn Colours) -> TextCell {
+        let style = if self.present == self.total {
+            colours.complete()
+        } else if self.present == 0 {
+            colours.empty()
+        } else {
+            colours.partial()
+        };
+
+        TextCell::paint(style, format!("{}%", self.percent()))
+    }
+}
+
+pub trait Colours {
+    fn complete(&self) -> Style;
+    fn partial(&self) -> Style;
+    fn empty(&self) -> Style;
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Colours;
+    use crate::fs::fields as f;
+    use crate::output::cell::TextCell;
+
+    use nu_ansi_term::Color::*;
+    use nu_ansi_term::Style;
+
+    struct TestColours;
+
+    impl Colours for TestColours {
+        fn complete(&self) -> Style {
+            Fixed(90).normal()
+        }
+        fn partial(&self) -> Style {
+            Fixed(91).normal()
+        }
+        fn empty(&self) -> Style {
+            Fixed(92).normal()
+        }
+    }
+
+    #[test]
+    fn complete() {
+        let materialized = f::Materialized {
+            present: 3,
+            total: 3,
+        };
+        let expected = TextCell::paint_str(Fixed(90).normal(), "100%");
+        assert_eq!(expected, materialized.render(&TestColours));
+    }
+
+    #[test]
+    fn partial() {
+        let materialized = f::Materialized {
+            present: 1,
+            total: 3,
+        };
+        let expected = TextCell::paint_str(Fixed(91).normal(), "33%");
+        assert_eq!(expected, materialized.render(&TestColours));
+    }
+
+    #[test]
+    fn empty() {
+        let materialized = f::Materialized {
+            present: 0,
+            total: 3,
+        };
+        let expected = TextCell::paint_str(Fixed(92).normal(), "0%");
+        assert_eq!(expected, materialized.render(&TestColours));
+    }
+}
@@ -9,6 +9,9 @@ mod blocks;
 #[cfg(unix)]
 pub use self::blocks::Colours as BlocksColours;
 
+mod filesystem;
+pub use self::filesystem::Colours as FilesystemColours;
+
 mod filetype;
 pub use self::filetype::Colours as FiletypeColours;
 
@@ -25,14 +28,20 @@ pub use self::groups::{Colours as GroupColours, Render as GroupRender};
 mod inode;
 // inode uses just one colour
 
+mod layer;
+pub use self::layer::Colours as LayerColours;
+
 mod links;
 pub use self::links::Colours as LinksColours;
 
+mod materialized;
+pub use self::materialized::Colours as MaterializedColours;
+
 mod permissions;
 pub use self::permissions::{Colours as PermissionsColours, PermissionsPlusRender};
 
 mod size;
-pub use self::size::Colours as SizeColours;
+pub use self::size::{size_in_words, Colours as SizeColours};
 
 mod times;
 pub use self::times::Render as TimeRender;
@@ -34,6 +34,9 @@ impl f::SecurityContext<'_> {
                     width: DisplayWidth::from(context.len()),
                 }
             }
+            f::SecurityContextType::Smack(label) => {
+                TextCell::paint(colours.smack(), String::from(*label))
+            }
         }
     }
 }
@@ -46,4 +49,71 @@ pub trait Colours {
     fn selinux_role(&self)  -> Style;
     fn selinux_type(&self)  -> Style;
     fn selinux_range(&self) -> Style;
+    fn smack(&self)         -> Style;
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::Colours;
+    use crate::fs::fields as f;
+    use crate::output::cell::{DisplayWidth, TextCell};
+
+    use nu_ansi_term::Color::*;
+    use nu_ansi_term::Style;
+
+    struct TestColours;
+
+    #[rustfmt::skip]
+    impl Colours for TestColours {
+        fn none(&self)          -> Style { Fixed(90).normal() }
+        fn selinux_colon(&self) -> Style { Fixed(91).normal() }
+        fn selinux_user(&self)  -> Style { Fixed(92).normal() }
+        fn selinux_role(&self)  -> Style { Fixed(93).normal() }
+        fn selinux_type(&self)  -> Style { Fixed(94).normal() }
+        fn selinux_range(&self) -> Style { Fixed(95).normal() }
+        fn smack(&self)         -> Style { Fixed(96).normal() }
+    }
+
+    #[test]
+    fn no_context() {
+        let context = f::SecurityContext {
+            context: f::SecurityContextType::None,
+        };
+        assert_eq!(
+            TextCell::paint_str(Fixed(90).normal(), "?"),
+            context.render(&TestColours)
+        );
+    }
+
+    #[test]
+    fn selinux_parts() {
+        let context = f::SecurityContext {
+            context: f::SecurityContextType::SELinux("u:r:t:s0"),
+        };
+        let expected = TextCell {
+            width: DisplayWidth::from(8),
+            contents: vec![
+                Fixed(92).paint("u"),
+                Fixed(91).paint(":"),
+                Fixed(93).paint("r"),
+                Fixed(91).paint(":"),
+                Fixed(94).paint("t"),
+                Fixed(91).paint(":"),
+                Fixed(95).paint("s0"),
+            ]
+            .into(),
+        };
+        assert_eq!(expected, context.render(&TestColours));
+    }
+
+    #[test]
+    fn smack_labels() {
+        let context = f::SecurityContext {
+            context: f::SecurityContextType::Smack("System::Shared"),
+        };
+        assert_eq!(
+            TextCell::paint_str(Fixed(96).normal(), "System::Shared"),
+            context.render(&TestColours)
+        );
+    }
 }
@@ -27,6 +27,11 @@ impl f::Size {
             Self::Some(s) => s,
             Self::None => return TextCell::blank(colours.no_size()),
             Self::DeviceIDs(ref ids) => return ids.render(colours),
+            Self::Estimate(bytes) => return render_estimate(colours, bytes, size_format, numerics),
+            Self::EstimatedFiles(files) => {
+                let files = numerics.format_int(files);
+                return TextCell::paint(colours.estimate(), format!("~{files} files"));
+            }
         };
 
         let is_gradient_mode =
@@ -103,6 +108,55 @@ impl f::Size {
     }
 }
 
+/// A size that the manifest estimated, rather than one read from the disk,
+/// which gets a `~` in front and is all in the one style.
+fn render_estimate<C: Colours>(
+    colours: &C,
+    size: u64,
+    size_format: SizeFormat,
+    numerics: &NumericLocale,
+) -> TextCell {
+    let (number, prefix) = size_number(size, size_format, numerics);
+    let symbol = prefix.map_or("", Prefix::symbol);
+    TextCell::paint(colours.estimate(), format!("~{number}{symbol}"))
+}
+
+/// A size written out in words, such as `840 KiB`, for the places that
+/// describe a size in a sentence rather than in a column.
+pub fn size_in_words(size: u64) -> String {
+    match size_number(size, SizeFormat::BinaryBytes, &NumericLocale::english()) {
+        (number, Some(prefix)) => format!("{number} {}B", prefix.symbol()),
+        (number, None) => format!("{number} B"),
+    }
+}
+
+/// A size as the number the size column would show, and the prefix of the
+/// unit it’s in, if any: one decimal place below ten, and whole numbers
+/// above it.
+fn size_number(
+    size: u64,
+    size_format: SizeFormat,
+    numerics: &NumericLocale,
+) -> (String, Option<Prefix>) {
+    use number_prefix::NumberPrefix;
+
+    let result = match size_format {
+        SizeFormat::DecimalBytes => NumberPrefix::decimal(size as f64),
+        SizeFormat::BinaryBytes => NumberPrefix::binary(size as f64),
+        SizeFormat::JustBytes => NumberPrefix::Standalone(size as f64),
+    };
+
+    match result {
+        NumberPrefix::Standalone(_) => (numerics.format_int(size), None),
+        NumberPrefix::Prefixed(prefix, n) if n < 10_f64 => {
+            (numerics.format_float(n, 1), Some(prefix))
+        }
+        NumberPrefix::Prefixed(prefix, n) => {
+            (numerics.format_int(n.round() as isize), Some(prefix))
+        }
+    }
+}
+
 impl f::DeviceIDs {
     fn render<C: Colours>(self, colours: &C) -> TextCell {
         let major = self.major.to_string();
@@ -124,6 +178,7 @@ pub trait Colours {
     fn size(&self, prefix: Option<Prefix>) -> Style;
     fn unit(&self, prefix: Option<Prefix>) -> Style;
     fn no_size(&self) -> Style;
+    fn estimate(&self) -> Style;
 
     fn major(&self) -> Style;
     fn comma(&self) -> Style;
@@ -149,6 +204,7 @@ pub mod test {
         fn size(&self, _prefix: Option<Prefix>) -> Style { Fixed(66).normal() }
         fn unit(&self, _prefix: Option<Prefix>) -> Style { Fixed(77).bold() }
         fn no_size(&self)                       -> Style { Black.italic() }
+        fn estimate(&self)                      -> Style { Fixed(88).dimmed() }
 
         fn major(&self) -> Style { Blue.on(Red) }
         fn comma(&self) -> Style { Green.italic() }
@@ -227,6 +283,54 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn estimate_decimal() {
+        let estimate = f::Size::Estimate(1_200_000_000);
+        let expected = TextCell::paint(Fixed(88).dimmed(), "~1.2G".into());
+
+        assert_eq!(
+            expected,
+            estimate.render(
+                &TestColours,
+                SizeFormat::DecimalBytes,
+                &NumericLocale::english(),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn estimate_bytes() {
+        let estimate = f::Size::Estimate(1_048_576);
+        let expected = TextCell::paint(Fixed(88).dimmed(), "~1,048,576".into());
+
+        assert_eq!(
+            expected,
+            estimate.render(
+                &TestColours,
+                SizeFormat::JustBytes,
+                &NumericLocale::english(),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn estimated_files() {
+        let estimate = f::Size::EstimatedFiles(4200);
+        let expected = TextCell::paint(Fixed(88).dimmed(), "~4,200 files".into());
+
+        assert_eq!(
+            expected,
+            estimate.render(
+                &TestColours,
+                SizeFormat::DecimalBytes,
+                &NumericLocale::english(),
+                None
+            )
+        );
+    }
+
     #[test]
     fn device_ids() {
         let directory = f::Size::DeviceIDs(f::DeviceIDs {
@@ -17,7 +17,7 @@ use std::sync::LazyLock;
 use uzers::UsersCache;
 
 use crate::fs::feature::git::GitCache;
-use crate::fs::{fields as f, File};
+use crate::fs::{fields as f, File, SyncState};
 use crate::options::vars::{EZA_WINDOWS_ATTRIBUTES, WLS_WINDOWS_ATTRIBUTES};
 use crate::options::Vars;
 use crate::output::cell::TextCell;
@@ -26,7 +26,9 @@ use crate::output::color_scale::ColorScaleInformation;
 use crate::output::render::{GroupRender, OctalPermissionsRender, UserRender};
 use crate::output::render::{PermissionsPlusRender, TimeRender};
 use crate::output::time::TimeFormat;
+use crate::output::url_template::UrlTemplate;
 use crate::theme::Theme;
+use nu_ansi_term::Style;
 
 use super::color_scale::ColorScaleMode;
 
@@ -39,11 +41,12 @@ pub struct Options {
     pub group_format: GroupFormat,
     pub flags_format: FlagsFormat,
     pub columns: Columns,
+    pub url_template: Option<UrlTemplate>,
 }
 
 /// Extra columns to display in the table.
 #[allow(clippy::struct_excessive_bools)]
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Columns {
     /// At least one of these timestamps will be shown.
     pub time_types: TimeTypes,
@@ -56,19 +59,51 @@ pub struct Columns {
     pub git: bool,
     pub subdir_git_repos: bool,
     pub subdir_git_repos_no_stat: bool,
+    pub zone_health: bool,
+    pub nested_repos: bool,
     pub octal: bool,
     pub security_context: bool,
     pub file_flags: bool,
+    pub sync_details: bool,
+    pub filesystems: bool,
+    pub layer: bool,
+    pub materialized: bool,
+    pub tags: bool,
+    pub url: bool,
+    pub zone: bool,
+    pub owner: bool,
+    pub owners: bool,
+    pub target: bool,
 
     // Defaults to true:
     pub permissions: bool,
     pub filesize: bool,
     pub user: bool,
+
+    /// The columns picked with `--columns`, in the order they go in, which
+    /// can include where the name goes. When this is `None`, the columns
+    /// turned on above go in their usual order, with the name last.
+    pub selected: Option<Vec<Column>>,
 }
 
 impl Columns {
     #[must_use]
     pub fn collect(&self, actually_enable_git: bool, git_repos: bool) -> Vec<Column> {
+        let columns = self.collect_turned_on(actually_enable_git, git_repos);
+
+        match self.selected {
+            Some(ref selected) => selected
+                .iter()
+                .copied()
+                .filter(|column| *column == Column::Name || columns.contains(column))
+                .collect(),
+            None => columns,
+        }
+    }
+
+    /// The columns that are turned on and can be shown, in their usual
+    /// order.
+    fn collect_turned_on(&self, actually_enable_git: bool, git_repos: bool) -> Vec<Column> {
         let mut columns = Vec::with_capacity(4);
 
         if self.inode {
@@ -134,10 +169,43 @@ impl Columns {
             columns.push(Column::Timestamp(TimeType::Accessed));
         }
 
+        if self.sync_details {
+            columns.push(Column::SyncUser);
+            columns.push(Column::SyncTime);
+        }
+
+        if self.materialized {
+            columns.push(Column::Materialized);
+        }
+
+        if self.tags {
+            columns.push(Column::Tags);
+        }
+
+        if self.zone {
+            columns.push(Column::Zone);
+        }
+
+        if self.owner {
+            columns.push(Column::Owner);
+        }
+
+        if self.owners {
+            columns.push(Column::Owners);
+        }
+
+        if self.target {
+            columns.push(Column::Target);
+        }
+
         if self.git && actually_enable_git {
             columns.push(Column::GitStatus);
         }
 
+        if self.zone_health && actually_enable_git {
+            columns.push(Column::ZoneHealth);
+        }
+
         if self.subdir_git_repos && git_repos {
             columns.push(Column::SubdirGitRepo(true));
         }
@@ -146,13 +214,33 @@ impl Columns {
             columns.push(Column::SubdirGitRepo(false));
         }
 
+        if self.nested_repos {
+            columns.push(Column::NestedRepo);
+        }
+
+        if self.filesystems {
+            columns.push(Column::Filesystem);
+        }
+
+        #[cfg(target_os = "linux")]
+        if self.layer {
+            columns.push(Column::Layer);
+        }
+
+        if self.url {
+            columns.push(Column::Url);
+        }
+
         columns
     }
 }
 
 /// A table contains these.
-#[derive(Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub enum Column {
+    /// Where the file’s name goes, which isn’t one of the table’s own
+    /// cells, as it’s drawn after the tree.
+    Name,
     Permissions,
     FileSize,
     Timestamp(TimeType),
@@ -168,11 +256,25 @@ pub enum Column {
     Inode,
     GitStatus,
     SubdirGitRepo(bool),
+    NestedRepo,
+    ZoneHealth,
     #[cfg(unix)]
     Octal,
     #[cfg(unix)]
     SecurityContext,
     FileFlags,
+    SyncUser,
+    SyncTime,
+    Materialized,
+    Tags,
+    Zone,
+    Owner,
+    Owners,
+    Target,
+    Filesystem,
+    #[cfg(target_os = "linux")]
+    Layer,
+    Url,
 }
 
 /// Each column can pick its own **Alignment**. Usually, numbers are
@@ -190,9 +292,12 @@ impl Column {
     pub fn alignment(self) -> Alignment {
         #[allow(clippy::wildcard_in_or_patterns)]
         match self {
-            Self::FileSize | Self::HardLinks | Self::Inode | Self::Blocksize | Self::GitStatus => {
-                Alignment::Right
-            }
+            Self::FileSize
+            | Self::HardLinks
+            | Self::Inode
+            | Self::Blocksize
+            | Self::GitStatus
+            | Self::Materialized => Alignment::Right,
             Self::Timestamp(_) | _ => Alignment::Left,
         }
     }
@@ -200,7 +305,7 @@ impl Column {
     #[cfg(windows)]
     pub fn alignment(self) -> Alignment {
         match self {
-            Self::FileSize | Self::GitStatus => Alignment::Right,
+            Self::FileSize | Self::GitStatus | Self::Materialized => Alignment::Right,
             _ => Alignment::Left,
         }
     }
@@ -210,6 +315,7 @@ impl Column {
     #[must_use]
     pub fn header(self) -> &'static str {
         match self {
+            Self::Name => "Name",
             #[cfg(unix)]
             Self::Permissions => "Permissions",
             #[cfg(windows)]
@@ -228,11 +334,25 @@ impl Column {
             Self::Inode => "inode",
             Self::GitStatus => "Git",
             Self::SubdirGitRepo(_) => "Git Repo",
+            Self::NestedRepo => "Nested",
+            Self::ZoneHealth => "Health",
             #[cfg(unix)]
             Self::Octal => "Octal",
             #[cfg(unix)]
             Self::SecurityContext => "Security Context",
             Self::FileFlags => "Flags",
+            Self::SyncUser => "Synced By",
+            Self::SyncTime => "Date Synced",
+            Self::Materialized => "Materialized",
+            Self::Tags => "Tags",
+            Self::Zone => "Zone",
+            Self::Owner => "Owner",
+            Self::Owners => "Owners",
+            Self::Target => "Target",
+            Self::Filesystem => "Filesystem",
+            #[cfg(target_os = "linux")]
+            Self::Layer => "Layer",
+            Self::Url => "URL",
         }
     }
 }
@@ -407,6 +527,7 @@ static ENVIRONMENT: LazyLock<Environment> = LazyLock::new(Environment::load_all)
 
 pub struct Table<'a> {
     columns: Vec<Column>,
+    name_index: usize,
     theme: &'a Theme,
     env: &'a Environment,
     widths: TableWidths,
@@ -418,6 +539,7 @@ pub struct Table<'a> {
     group_format: GroupFormat,
     flags_format: FlagsFormat,
     git: Option<&'a GitCache>,
+    url_template: Option<&'a UrlTemplate>,
 }
 
 #[derive(Clone)]
@@ -425,6 +547,18 @@ pub struct Row {
     cells: Vec<TextCell>,
 }
 
+impl Row {
+    /// The text of each cell, without any colours.
+    pub fn plain_cells(&self) -> impl Iterator<Item = String> + '_ {
+        self.cells.iter().map(|cell| {
+            cell.contents
+                .iter()
+                .map(nu_ansi_term::AnsiGenericString::as_str)
+                .collect()
+        })
+    }
+}
+
 impl<'a> Table<'a> {
     #[must_use]
     pub fn new(
@@ -433,16 +567,22 @@ impl<'a> Table<'a> {
         theme: &'a Theme,
         git_repos: bool,
     ) -> Table<'a> {
-        let columns = options.columns.collect(git.is_some(), git_repos);
+        let mut columns = options.columns.collect(git.is_some(), git_repos);
+        let name_index = columns
+            .iter()
+            .position(|c| *c == Column::Name)
+            .unwrap_or(columns.len());
+        columns.retain(|c| *c != Column::Name);
         let widths = TableWidths::zero(columns.len());
         let env = &*ENVIRONMENT;
 
-        debug!("Creating table with columns: {columns:?}");
+        debug!("Creating table with columns: {columns:?}, with the name after {name_index}");
 
         Table {
             theme,
             widths,
             columns,
+            name_index,
             git,
             env,
             time_format: options.time_format.clone(),
@@ -452,12 +592,25 @@ impl<'a> Table<'a> {
             #[cfg(unix)]
             group_format: options.group_format,
             flags_format: options.flags_format,
+            url_template: options.url_template.as_ref(),
         }
     }
 
+    /// How wide the cells that go before the file’s name are, with the
+    /// space after each one.
     #[must_use]
-    pub fn widths(&self) -> &TableWidths {
-        &self.widths
+    pub fn width_before_name(&self) -> usize {
+        self.widths[..self.name_index]
+            .iter()
+            .map(|width| width + 1)
+            .sum()
+    }
+
+    /// Whether any cells go after the file’s name, which means the names
+    /// have to be padded to line them up.
+    #[must_use]
+    pub fn has_cells_after_name(&self) -> bool {
+        self.name_index < self.columns.len()
     }
 
     #[must_use]
@@ -510,6 +663,18 @@ impl<'a> Table<'a> {
         })
     }
 
+    /// The URL for this file from the `--url-template`, or `None` if it’s
+    /// not in a Git repository.
+    fn url(&self, file: &File<'_>) -> Option<String> {
+        let template = self.url_template?;
+        let path = match file.absolute_path() {
+            Some(path) => std::path::absolute(path).ok()?,
+            None => std::path::absolute(&file.path).ok()?,
+        };
+        let (repo, rel_path) = self.git?.repo_relative_path(&path)?;
+        Some(template.render(&repo, &rel_path))
+    }
+
     #[cfg(unix)]
     fn octal_permissions(&self, file: &File<'_>) -> Option<f::OctalPermissions> {
         file.permissions()
@@ -524,6 +689,8 @@ impl<'a> Table<'a> {
         color_scale_info: Option<ColorScaleInformation>,
     ) -> TextCell {
         match column {
+            // The name is drawn by the view, after the tree
+            Column::Name => TextCell::default(),
             Column::Permissions => self.permissions_plus(file, xattrs).render(self.theme),
             Column::FileSize => file.size().render(
                 self.theme,
@@ -558,13 +725,75 @@ impl<'a> Table<'a> {
             Column::FileFlags => file
                 .flags()
                 .render(self.theme.ui.flags.unwrap_or_default(), self.flags_format),
-            Column::GitStatus => self.git_status(file).render(self.theme),
+            Column::GitStatus => match self.zone_git_counts(file) {
+                Some(counts) => counts.render(self.theme),
+                None => self.git_status(file).render(self.theme),
+            },
             Column::SubdirGitRepo(status) => self.subdir_git_repo(file, status).render(self.theme),
+            Column::NestedRepo => match file.nested_repo() {
+                Some(nested) => nested.render(self.theme),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            Column::ZoneHealth => match self.zone_health(file) {
+                Some(health) => health.render(self.theme),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
             #[cfg(unix)]
             Column::Octal => self
                 .octal_permissions(file)
                 .render(self.theme.ui.octal.unwrap_or_default()),
 
+            Column::SyncUser => match file.zone_sync() {
+                Some(sync) => TextCell::paint(
+                    self.theme.ui.users.unwrap_or_default().user_other(),
+                    sync.user.clone(),
+                ),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            Column::SyncTime => file.zone_sync().and_then(SyncState::synced_at).render(
+                self.theme.ui.date.unwrap_or_default(),
+                self.env.time_offset,
+                self.time_format.clone(),
+            ),
+            Column::Materialized => match file.materialized() {
+                Some(materialized) => materialized.render(self.theme),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            Column::Tags => match file.zone_tags() {
+                [] => TextCell::blank(self.theme.ui.punctuation()),
+                tags => TextCell::paint(Style::default(), tags.join(",")),
+            },
+            Column::Zone => match file.enclosing_zone_target() {
+                Some(target) => TextCell::paint(Style::default(), target),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            Column::Owner => match file.zone_owner() {
+                Some(owner) => TextCell::paint(Style::default(), owner),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            Column::Owners => match file.code_owners() {
+                Some(owners) => TextCell::paint(Style::default(), owners),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            Column::Target => match file.target_path() {
+                Some(target) => TextCell::paint(Style::default(), target),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            Column::Filesystem => match file.filesystem() {
+                Some(filesystem) => filesystem.render(self.theme),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+            #[cfg(target_os = "linux")]
+            Column::Layer => match file.layer() {
+                Some(layer) => layer.render(self.theme),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+
+            Column::Url => match self.url(file) {
+                Some(url) => TextCell::paint(Style::default(), url),
+                None => TextCell::blank(self.theme.ui.punctuation()),
+            },
+
             Column::Timestamp(time_type) => time_type.get_corresponding_time(file).render(
                 if color_scale_info.is_some_and(|csi| csi.options.mode == ColorScaleMode::Gradient)
                 {
@@ -590,6 +819,26 @@ impl<'a> Table<'a> {
             .unwrap_or_default()
     }
 
+    /// For a zone with uncommitted work, how many files inside it have
+    /// changes, which says more than the directory’s own status does.
+    fn zone_git_counts(&self, file: &File<'_>) -> Option<f::GitCounts> {
+        if !file.is_zone() || file.is_ghost || !file.is_directory() {
+            return None;
+        }
+
+        let counts = self.git?.counts(&file.path);
+        (!counts.is_empty()).then_some(counts)
+    }
+
+    /// For a zone on disk, whether it has uncommitted or unpushed work.
+    fn zone_health(&self, file: &File<'_>) -> Option<f::ZoneHealth> {
+        if !file.is_zone() || file.is_ghost || !file.is_directory() {
+            return None;
+        }
+
+        self.git?.zone_health(&file.path)
+    }
+
     fn subdir_git_repo(&self, file: &File<'_>, status: bool) -> f::SubdirGitRepo {
         debug!("Getting subdir repo status for path {:?}", file.path);
 
@@ -599,30 +848,56 @@ impl<'a> Table<'a> {
         f::SubdirGitRepo::default()
     }
 
+    /// Renders the cells that go before the file’s name, each padded to
+    /// its column’s width and followed by a space, and the cells that go
+    /// after it, each with a space before it.
     #[must_use]
-    pub fn render(&self, row: Row) -> TextCell {
-        let mut cell = TextCell::default();
+    pub fn render(&self, row: Row) -> (TextCell, TextCell) {
+        let mut before = TextCell::default();
+        let mut after = TextCell::default();
 
+        let last = row.cells.len().saturating_sub(1);
         let iter = row.cells.into_iter().zip(self.widths.iter()).enumerate();
 
         for (n, (this_cell, width)) in iter {
             let padding = width - *this_cell.width;
+            let alignment = self.columns[n].alignment();
 
-            match self.columns[n].alignment() {
-                Alignment::Left => {
-                    cell.append(this_cell);
-                    cell.add_spaces(padding);
-                }
-                Alignment::Right => {
-                    cell.add_spaces(padding);
-                    cell.append(this_cell);
-                }
-            }
+            if n < self.name_index {
+                Self::append_aligned(&mut before, this_cell, padding, alignment);
+                before.add_spaces(1);
+            } else {
+                after.add_spaces(1);
 
-            cell.add_spaces(1);
+                // Leave out the spaces that would only trail at the end
+                let padding = if n == last && matches!(alignment, Alignment::Left) {
+                    0
+                } else {
+                    padding
+                };
+                Self::append_aligned(&mut after, this_cell, padding, alignment);
+            }
         }
 
-        cell
+        (before, after)
+    }
+
+    fn append_aligned(
+        cell: &mut TextCell,
+        this_cell: TextCell,
+        padding: usize,
+        alignment: Alignment,
+    ) {
+        match alignment {
+            Alignment::Left => {
+                cell.append(this_cell);
+                cell.add_spaces(padding);
+            }
+            Alignment::Right => {
+                cell.add_spaces(padding);
+                cell.append(this_cell);
+            }
+        }
     }
 }
 
@@ -647,9 +922,4 @@ impl TableWidths {
             *old_width = max(*old_width, *cell.width);
         }
     }
-
-    #[must_use]
-    pub fn total(&self) -> usize {
-        self.0.len() + self.0.iter().sum::<usize>()
-    }
 }
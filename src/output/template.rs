@@ -0,0 +1,308 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The `--format` view, which writes a line per entry filled in from a
+//! template such as `{name}\t{zone_target}\t{size}`, so scripts can pick
+//! exactly the fields they want.
+//!
+//! The field names are part of the interface scripts depend on, so they
+//! only ever get added to. A field with nothing to say about an entry,
+//! such as the size of a directory or the owner of something that isn’t a
+//! zone, is left empty. The escapes `\t`, `\n`, `\0`, and `\\` work as
+//! they do in `printf`, because the shell won’t have expanded them, and
+//! braces are written `{{` and `}}`.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::time::UNIX_EPOCH;
+
+use crate::fs::File;
+use crate::output::json_lines;
+
+/// A parsed `--format` template.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Template(Vec<Part>);
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+/// Something about an entry that a template can include.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Field {
+    /// The file’s name.
+    Name,
+
+    /// The file’s path, as it was listed.
+    Path,
+
+    /// The kind of file, as in the `type` field of `--json-lines`.
+    Type,
+
+    /// The size in bytes, for files.
+    Size,
+
+    /// When the file was modified, in seconds since the Unix epoch.
+    Modified,
+
+    /// `true` for ghosts, and `false` for everything on disk.
+    IsGhost,
+
+    /// `true` for zones, and `false` for everything else.
+    IsZone,
+
+    /// The target path of the zone, for zones.
+    ZoneTarget,
+
+    /// The target path of the zone the file is in.
+    Zone,
+
+    /// The file’s own target path, for anything beneath a src root.
+    Target,
+
+    /// The `owner` field of the zone’s manifest entry, for zones.
+    Owner,
+
+    /// Whoever the repository’s CODEOWNERS file says owns the file.
+    Owners,
+}
+
+impl Field {
+    const ALL: &'static [(&'static str, Self)] = &[
+        ("name", Self::Name),
+        ("path", Self::Path),
+        ("type", Self::Type),
+        ("size", Self::Size),
+        ("modified", Self::Modified),
+        ("is_ghost", Self::IsGhost),
+        ("is_zone", Self::IsZone),
+        ("zone_target", Self::ZoneTarget),
+        ("zone", Self::Zone),
+        ("target", Self::Target),
+        ("owner", Self::Owner),
+        ("owners", Self::Owners),
+    ];
+
+    fn named(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, field)| *field)
+    }
+
+    /// The value of this field for a file, which is empty if it has none.
+    fn value(self, file: &File<'_>) -> String {
+        match self {
+            Self::Name => file.name.clone(),
+            Self::Path => file.path.to_string_lossy().into_owned(),
+            Self::Type => json_lines::kind(file).to_string(),
+            Self::Size if file.is_file() => file.length().to_string(),
+            Self::Modified if !file.is_ghost => file
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs().to_string())
+                .unwrap_or_default(),
+            Self::Size | Self::Modified => String::new(),
+            Self::IsGhost => file.is_ghost.to_string(),
+            Self::IsZone => file.is_zone().to_string(),
+            Self::ZoneTarget => file.zone_target().map(String::from).unwrap_or_default(),
+            Self::Zone => file.enclosing_zone_target().unwrap_or_default(),
+            Self::Target => file.target_path().unwrap_or_default(),
+            Self::Owner => file.zone_owner().unwrap_or_default(),
+            Self::Owners => file.code_owners().unwrap_or_default(),
+        }
+    }
+}
+
+/// Why a template couldn’t be parsed.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum TemplateError {
+    /// A `{` without a `}` after it.
+    Unclosed,
+
+    /// A `}` without a `{` before it.
+    Unopened,
+
+    /// A field name that isn’t one we know.
+    UnknownField(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unclosed => write!(f, "a '{{' is never closed (write '{{{{' for a brace)"),
+            Self::Unopened => write!(f, "a '}}' is never opened (write '}}}}' for a brace)"),
+            Self::UnknownField(name) => {
+                let names = Field::ALL
+                    .iter()
+                    .map(|(n, _)| *n)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "unknown field {{{name}}} (choices: {names})")
+            }
+        }
+    }
+}
+
+impl Template {
+    /// Parses a template, turning its escapes into the characters they
+    /// stand for.
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    let _ = chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    let _ = chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(TemplateError::Unclosed),
+                        }
+                    }
+
+                    let field =
+                        Field::named(name.trim()).ok_or(TemplateError::UnknownField(name))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => return Err(TemplateError::Unopened),
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('0') => text.push('\0'),
+                    Some('\\') | None => text.push('\\'),
+                    Some(other) => {
+                        text.push('\\');
+                        text.push(other);
+                    }
+                },
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self(parts))
+    }
+
+    /// Whether the template includes the given field.
+    pub fn uses(&self, field: Field) -> bool {
+        self.0.contains(&Part::Field(field))
+    }
+
+    /// Fills in the template for a single file.
+    pub fn fill(&self, file: &File<'_>) -> String {
+        let mut line = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field(field) => line.push_str(&field.value(file)),
+            }
+        }
+        line
+    }
+}
+
+/// Writes a single file’s line.
+pub fn write_entry<W: Write>(w: &mut W, file: &File<'_>, template: &Template) -> io::Result<()> {
+    writeln!(w, "{}", template.fill(file))
+}
+
+pub struct Render<'a> {
+    pub files: Vec<File<'a>>,
+    pub template: &'a Template,
+}
+
+impl Render<'_> {
+    pub fn render<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for file in &self.files {
+            write_entry(w, file, self.template)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use crate::fs::{Dir, DirOptions};
+
+    #[test]
+    fn fields_and_text() {
+        let template = Template::parse("{name}\\t{ is_ghost }!").unwrap();
+        assert_eq!(
+            template,
+            Template(vec![
+                Part::Field(Field::Name),
+                Part::Text("\t".into()),
+                Part::Field(Field::IsGhost),
+                Part::Text("!".into()),
+            ])
+        );
+        assert!(template.uses(Field::IsGhost));
+        assert!(!template.uses(Field::Owner));
+    }
+
+    #[test]
+    fn escapes() {
+        let template = Template::parse("{{{name}}}\\n\\0\\\\\\q").unwrap();
+        assert_eq!(
+            template,
+            Template(vec![
+                Part::Text("{".into()),
+                Part::Field(Field::Name),
+                Part::Text("}\n\0\\\\q".into())
+            ])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Template::parse("{name"), Err(TemplateError::Unclosed));
+        assert_eq!(Template::parse("name}"), Err(TemplateError::Unopened));
+        assert_eq!(
+            Template::parse("{colour}"),
+            Err(TemplateError::UnknownField("colour".into()))
+        );
+    }
+
+    #[test]
+    fn ghosts_have_empty_sizes() {
+        let dir = Dir::new(
+            PathBuf::from("/test/src/areas"),
+            Arc::new(DirOptions::default()),
+        );
+        let ghost = File::new_ghost(
+            dir.join(Path::new("apps")),
+            &dir,
+            "apps".into(),
+            None,
+            false,
+            None,
+        );
+        let template = Template::parse("{name},{type},{size},{modified},{is_ghost}").unwrap();
+        assert_eq!(template.fill(&ghost), "apps,directory,,,true");
+    }
+}
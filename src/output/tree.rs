@@ -149,6 +149,11 @@ impl TreeParams {
     pub fn new(depth: TreeDepth, last: bool) -> Self {
         Self { depth, last }
     }
+
+    /// How wide the tree parts that go before this entry are.
+    pub fn width(&self) -> usize {
+        self.depth.0 * 4
+    }
 }
 
 impl TreeDepth {
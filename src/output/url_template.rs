@@ -0,0 +1,100 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The URL column added by `--url-template`, which links each entry to the
+//! same file in a web-based code search or browser, and the zone links
+//! `--hyperlink` makes with `--zone-url`.
+
+use std::path::Path;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// Characters that can’t appear as they are in the path part of a URL.
+/// Slashes are left alone so the relative path keeps its structure.
+const PATH_ESCAPE_CHARS: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// A URL with placeholders, such as
+/// `https://sourcegraph.example.com/{repo}/-/blob/{rel_path}`.
+///
+/// `{repo}` is replaced by the name of the Git repository the entry is in,
+/// and `{rel_path}` by the entry’s path relative to the repository root.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UrlTemplate(pub String);
+
+impl UrlTemplate {
+    /// Fills in the template for one entry.
+    #[must_use]
+    pub fn render(&self, repo: &str, rel_path: &Path) -> String {
+        let rel_path = rel_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.fill(&[("{repo}", repo), ("{rel_path}", &rel_path)])
+    }
+
+    /// Fills in the template for one zone, as used by `--zone-url`.
+    ///
+    /// `{target}` is replaced by the zone’s target path, such as
+    /// `//areas/tools/dev`, and `{path}` by the same path without the
+    /// leading slashes, such as `areas/tools/dev`.
+    #[must_use]
+    pub fn render_zone(&self, target: &str) -> String {
+        let path = target.trim_start_matches('/');
+        self.fill(&[("{target}", target), ("{path}", path)])
+    }
+
+    /// Replaces each placeholder with its percent-encoded value.
+    fn fill(&self, placeholders: &[(&str, &str)]) -> String {
+        placeholders
+            .iter()
+            .fold(self.0.clone(), |url, (placeholder, value)| {
+                url.replace(
+                    placeholder,
+                    &utf8_percent_encode(value, PATH_ESCAPE_CHARS).to_string(),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fills_in_placeholders() {
+        let template = UrlTemplate("https://cs.example.com/{repo}/-/blob/{rel_path}".into());
+        assert_eq!(
+            template.render("world", Path::new("areas/tools/dev/README.md")),
+            "https://cs.example.com/world/-/blob/areas/tools/dev/README.md"
+        );
+    }
+
+    #[test]
+    fn escapes_path() {
+        let template = UrlTemplate("https://cs.example.com/{repo}/{rel_path}".into());
+        assert_eq!(
+            template.render("world", Path::new("a dir/#1.txt")),
+            "https://cs.example.com/world/a%20dir/%231.txt"
+        );
+    }
+
+    #[test]
+    fn fills_in_zone_placeholders() {
+        let template = UrlTemplate("https://code.example.com/zones/{path}?target={target}".into());
+        assert_eq!(
+            template.render_zone("//areas/tools/dev"),
+            "https://code.example.com/zones/areas/tools/dev?target=//areas/tools/dev"
+        );
+    }
+}
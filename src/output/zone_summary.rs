@@ -0,0 +1,107 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! The footer printed by `--zone-summary`, which shows how much of a listing
+//! is actually materialized.
+
+use std::fmt;
+use std::ops::AddAssign;
+
+use crate::fs::File;
+
+/// Counts of what was in a listing, such as `42 entries, 7 zones (3 ghost)`.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct ZoneSummary {
+    pub entries: usize,
+    pub zones: usize,
+    pub ghost_zones: usize,
+}
+
+impl ZoneSummary {
+    /// Counts the files that are about to be listed.
+    pub fn of(files: &[File<'_>]) -> Self {
+        let mut summary = Self {
+            entries: files.len(),
+            ..Self::default()
+        };
+
+        for file in files.iter().filter(|f| f.is_zone()) {
+            summary.zones += 1;
+            if file.is_ghost {
+                summary.ghost_zones += 1;
+            }
+        }
+
+        summary
+    }
+}
+
+impl AddAssign for ZoneSummary {
+    fn add_assign(&mut self, other: Self) {
+        self.entries += other.entries;
+        self.zones += other.zones;
+        self.ghost_zones += other.ghost_zones;
+    }
+}
+
+impl fmt::Display for ZoneSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = if self.entries == 1 {
+            "entry"
+        } else {
+            "entries"
+        };
+        let zones = if self.zones == 1 { "zone" } else { "zones" };
+        write!(
+            f,
+            "{} {entries}, {} {zones} ({} ghost)",
+            self.entries, self.zones, self.ghost_zones
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plural() {
+        let summary = ZoneSummary {
+            entries: 42,
+            zones: 7,
+            ghost_zones: 3,
+        };
+        assert_eq!(summary.to_string(), "42 entries, 7 zones (3 ghost)");
+    }
+
+    #[test]
+    fn singular() {
+        let summary = ZoneSummary {
+            entries: 1,
+            zones: 1,
+            ghost_zones: 0,
+        };
+        assert_eq!(summary.to_string(), "1 entry, 1 zone (0 ghost)");
+    }
+
+    #[test]
+    fn chunks_add_up() {
+        let mut summary = ZoneSummary {
+            entries: 1024,
+            zones: 2,
+            ghost_zones: 1,
+        };
+        summary += ZoneSummary {
+            entries: 10,
+            zones: 1,
+            ghost_zones: 1,
+        };
+        assert_eq!(
+            summary,
+            ZoneSummary {
+                entries: 1034,
+                zones: 3,
+                ghost_zones: 2
+            }
+        );
+    }
+}
@@ -0,0 +1,235 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! `wls selftest --create-fixture DIR`, which builds a small src root with
+//! every kind of entry that wls treats specially, lists it, and checks that
+//! each one comes out the way it should.
+//!
+//! This is for packagers trying wls out on a new platform, where the ways
+//! that paths get canonicalized and symlinks get followed can differ. The
+//! listings are done by running this same executable with `--json-lines`,
+//! so they go through exactly what a user’s invocation would.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+
+use crate::options::vars;
+
+/// The manifest of the fixture src root. `//areas/tools/dev` is on disk,
+/// the others are ghosts, and `areas/legacy` is an orphan: it’s on disk,
+/// but nothing in the manifest mentions it.
+const MANIFEST: &str = r#"{
+  "//areas/tools/dev": {},
+  "//areas/tools/cli": {},
+  "//areas/apps/flow": {},
+  "//areas/other": {}
+}
+"#;
+
+/// Environment variables that would change what the listings show, so the
+/// user’s own settings are kept out of them.
+static CLEARED_VARS: &[&str] = &[
+    vars::WLS_MANIFEST_PATH,
+    vars::WLS_IGNORE_MANIFEST,
+    vars::WLS_TARGET_PREFIX,
+    vars::WLS_GHOSTS,
+    vars::WLS_GHOST_LIMIT,
+    vars::WLS_ZONE_CASE,
+    vars::WLS_ZONE_URL,
+    vars::WLS_GIT_BATCH,
+    vars::WLS_STAT_THREADS,
+    vars::WLS_DAEMON_SOCKET,
+    vars::WLS_STRICT,
+    vars::EZA_STRICT,
+    vars::EXA_STRICT,
+    vars::EZA_CONFIG_DIR,
+];
+
+/// The directory in the fixture that the listings use as their config
+/// directory, which is left empty so the user’s own `config.toml` and
+/// `ignore-ghosts` don’t apply.
+const CONFIG_DIR: &str = "config";
+
+/// An entry that a listing should contain.
+struct Expected {
+    name: &'static str,
+    ghost: bool,
+    zone: Option<&'static str>,
+}
+
+const fn on_disk(name: &'static str, zone: Option<&'static str>) -> Expected {
+    Expected {
+        name,
+        ghost: false,
+        zone,
+    }
+}
+
+const fn ghost(name: &'static str, zone: Option<&'static str>) -> Expected {
+    Expected {
+        name,
+        ghost: true,
+        zone,
+    }
+}
+
+/// Each directory to list, relative to the fixture, and what it should hold.
+fn checks() -> Vec<(&'static str, Vec<Expected>)> {
+    let tools = || {
+        vec![
+            on_disk("dev", Some("//areas/tools/dev")),
+            ghost("cli", Some("//areas/tools/cli")),
+        ]
+    };
+
+    let mut checks = vec![
+        (
+            "src/areas",
+            vec![
+                on_disk("tools", None),
+                on_disk("legacy", None),
+                ghost("apps", None),
+                ghost("other", Some("//areas/other")),
+            ],
+        ),
+        ("src/areas/tools", tools()),
+        (
+            "src/areas/apps",
+            vec![ghost("flow", Some("//areas/apps/flow"))],
+        ),
+    ];
+
+    if cfg!(unix) {
+        checks.push(("linked-src/areas/tools", tools()));
+    }
+
+    checks
+}
+
+/// Creates the fixture in the given directory, lists it, and reports each
+/// check to stdout. Returns the exit code: zero if every check passed.
+pub fn run(dir: &Path) -> io::Result<i32> {
+    create_fixture(dir)?;
+
+    let exe = env::current_exe()?;
+    let mut stdout = io::stdout().lock();
+    let mut failures = 0;
+
+    for (rel_path, expected) in checks() {
+        let problems = check(&exe, dir, &dir.join(rel_path), &expected)?;
+        if problems.is_empty() {
+            writeln!(stdout, "ok     {rel_path}")?;
+        } else {
+            failures += 1;
+            writeln!(stdout, "FAILED {rel_path}")?;
+            for problem in problems {
+                writeln!(stdout, "         {problem}")?;
+            }
+        }
+    }
+
+    if failures == 0 {
+        writeln!(stdout, "selftest passed, fixture left in {}", dir.display())?;
+        Ok(0)
+    } else {
+        writeln!(
+            stdout,
+            "selftest failed: {failures} listings were wrong, fixture left in {}",
+            dir.display()
+        )?;
+        Ok(1)
+    }
+}
+
+/// Builds the fixture src root, refusing to touch one that’s already there.
+fn create_fixture(dir: &Path) -> io::Result<()> {
+    let src = dir.join("src");
+    if src.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", src.display()),
+        ));
+    }
+
+    fs::create_dir_all(src.join(".meta"))?;
+    fs::write(src.join(".meta").join("manifest.json"), MANIFEST)?;
+    fs::create_dir_all(dir.join(CONFIG_DIR))?;
+
+    fs::create_dir_all(src.join("areas/tools/dev"))?;
+    fs::write(
+        src.join("areas/tools/dev/README.md"),
+        "A zone that’s on disk.\n",
+    )?;
+
+    fs::create_dir_all(src.join("areas/legacy"))?;
+    fs::write(src.join("areas/legacy/notes.txt"), "Not in the manifest.\n")?;
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("src", dir.join("linked-src"))?;
+
+    Ok(())
+}
+
+/// Lists one directory of the fixture and compares it with what it should
+/// hold, returning a description of everything that’s different.
+fn check(
+    exe: &Path,
+    fixture: &Path,
+    path: &Path,
+    expected: &[Expected],
+) -> io::Result<Vec<String>> {
+    let mut command = Command::new(exe);
+    command.arg("--json-lines").arg(path);
+    for var in CLEARED_VARS {
+        command.env_remove(var);
+    }
+    command.env(vars::WLS_CONFIG_DIR, fixture.join(CONFIG_DIR));
+
+    let output = command.output()?;
+    let mut problems = Vec::new();
+    if !output.status.success() {
+        problems.push(format!(
+            "listing exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let entries: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    for want in expected {
+        let Some(entry) = entries.iter().find(|e| e["name"] == want.name) else {
+            problems.push(format!("{} is missing", want.name));
+            continue;
+        };
+
+        if entry["ghost"] != want.ghost {
+            let what = if want.ghost { "a ghost" } else { "on disk" };
+            problems.push(format!("{} should be {what}", want.name));
+        }
+
+        let zone = entry["zone"].as_str();
+        if zone != want.zone {
+            problems.push(format!(
+                "{} should have zone {}, not {}",
+                want.name,
+                want.zone.unwrap_or("none"),
+                zone.unwrap_or("none")
+            ));
+        }
+    }
+
+    for entry in &entries {
+        let name = entry["name"].as_str().unwrap_or_default();
+        if !expected.iter().any(|want| want.name == name) {
+            problems.push(format!("{name} shouldn’t be listed"));
+        }
+    }
+
+    Ok(problems)
+}
@@ -0,0 +1,379 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Building src roots on disk for tests, with a manifest and whatever files
+//! they need, and listing them through the same views that `wls` uses.
+//!
+//! The unit tests in `dir.rs` make up their manifests in memory and never
+//! get as far as printing anything. A `Fixture` goes from a manifest file on
+//! disk all the way to the rendered text, so what ghosts and zones look
+//! like can be checked end to end:
+//!
+//! ```text
+//! let fixture = Fixture::new()
+//!     .zone("//areas/tools/dev", "{}")
+//!     .zone("//areas/tools/cli", "{}")
+//!     .file("areas/tools/dev/README.md", "hello");
+//! assert_eq!(fixture.render(&["-1"], "areas/tools"), "cli\ndev\n");
+//! ```
+//!
+//! Listings are always done without colours, the manifest cache, or the
+//! user’s own config file, so nothing outside the fixture changes them.
+//! Zones are still made bold, which `render_styled` keeps and `render`
+//! takes out.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::fs::feature::git::GitCache;
+use crate::fs::filter::GitIgnore;
+use crate::fs::{is_valid_ghost_dir, Dir};
+use crate::options::{vars, Options, OptionsResult, Vars};
+use crate::output::{details, grid, json_lines, lines, Mode};
+
+/// Flags that every listing gets, before the test’s own.
+const FIXED_ARGS: &[&str] = &["--no-cache", "--color=never"];
+
+/// A src root in a temporary directory, which is deleted when it’s dropped.
+pub struct Fixture {
+    root: tempfile::TempDir,
+
+    /// Each zone in the manifest, with the JSON object of its fields.
+    zones: Vec<(String, String)>,
+
+    /// The environment variables the listings see.
+    vars: Vec<(&'static str, String)>,
+}
+
+impl Fixture {
+    /// Makes a src root with an empty manifest, in a temporary directory.
+    pub fn new() -> Self {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("src").join(".meta")).unwrap();
+
+        let fixture = Self {
+            root,
+            zones: Vec::new(),
+            vars: Vec::new(),
+        };
+        fixture.write_manifest();
+        fixture
+    }
+
+    /// Adds a zone to the manifest, with its fields as a JSON object.
+    pub fn zone(mut self, target: &str, fields: &str) -> Self {
+        self.zones.push((target.to_string(), fields.to_string()));
+        self.write_manifest();
+        self
+    }
+
+    /// Sets an environment variable for the listings.
+    pub fn var(mut self, name: &'static str, value: &str) -> Self {
+        self.vars.push((name, value.to_string()));
+        self
+    }
+
+    /// Makes a directory, and any above it, relative to the src root.
+    pub fn dir(self, rel_path: &str) -> Self {
+        fs::create_dir_all(self.path(rel_path)).unwrap();
+        self
+    }
+
+    /// Writes a file, making the directories above it, relative to the src
+    /// root.
+    pub fn file(self, rel_path: &str, contents: &str) -> Self {
+        let path = self.path(rel_path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        self
+    }
+
+    /// Makes the src root a Git repository, so that `.gitignore` files in
+    /// it are checked by `--git-ignore`.
+    #[cfg(feature = "git")]
+    pub fn git(self) -> Self {
+        let _ = git2::Repository::init(self.path("")).unwrap();
+        self
+    }
+
+    /// The path of something relative to the src root.
+    pub fn path(&self, rel_path: &str) -> PathBuf {
+        self.root.path().join("src").join(rel_path)
+    }
+
+    /// Lists the directory at the given path, relative to the src root, with
+    /// the given flags, and returns what got printed, without any escape
+    /// sequences.
+    pub fn render(&self, args: &[&str], rel_path: &str) -> String {
+        strip_escapes(&self.render_styled(args, rel_path))
+    }
+
+    /// Lists the directory at the given path, relative to the src root, with
+    /// the given flags, and returns exactly what got printed. Only the views
+    /// that lay out a single directory are supported: lines, grid, details,
+    /// and JSON lines.
+    pub fn render_styled(&self, args: &[&str], rel_path: &str) -> String {
+        let args: Vec<OsString> = FIXED_ARGS.iter().chain(args).map(OsString::from).collect();
+        let vars = FixtureVars {
+            config_dir: self.root.path().join("config"),
+            vars: &self.vars,
+        };
+        let OptionsResult::Ok(options, _) =
+            Options::parse(args.iter().map(OsString::as_os_str), &vars)
+        else {
+            panic!("Bad arguments: {args:?}");
+        };
+
+        let theme = options.theme.to_theme(false);
+        let dir = self.read_dir(rel_path, &options);
+        let git_ignoring = options.filter.git_ignore == GitIgnore::CheckAndIgnore;
+        let git: Option<GitCache> = git_ignoring.then(|| std::iter::once(self.path("")).collect());
+        let mut files: Vec<_> = dir
+            .files(
+                options.filter.dot_filter,
+                git.as_ref(),
+                git_ignoring,
+                options.view.deref_links,
+                false,
+                options.filter.ghosts,
+            )
+            .collect();
+        options.filter.filter_child_files(false, &mut files);
+        options.filter.sort_files(&mut files);
+        options.filter.ghost_limit(&dir).apply(&mut files);
+
+        let mut output = Vec::new();
+        let file_style = &options.view.file_style;
+        let filter = &options.filter;
+        let theme = &theme;
+        match options.view.mode {
+            Mode::Details(ref opts) => details::Render {
+                dir: Some(&dir),
+                files,
+                theme,
+                file_style,
+                opts,
+                recurse: options.dir_action.recurse_options(),
+                filter,
+                git_ignoring,
+                git: git.as_ref(),
+                git_repos: false,
+            }
+            .render(&mut output),
+            Mode::Grid(ref opts) => grid::Render {
+                files,
+                theme,
+                file_style,
+                opts,
+                console_width: 80,
+                filter,
+            }
+            .render(&mut output),
+            Mode::JsonLines => json_lines::Render { files }.render(&mut output),
+            Mode::Lines => lines::Render {
+                files,
+                theme,
+                file_style,
+                filter,
+            }
+            .render(&mut output),
+            ref mode => panic!("Fixtures can’t render {mode:?}"),
+        }
+        .unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    /// Reads the directory at the given path, or makes up a ghost one from
+    /// the manifest if it isn’t on disk.
+    fn read_dir(&self, rel_path: &str, options: &Options) -> Dir {
+        let path = self.path(rel_path);
+        if path.is_dir() {
+            return Dir::read_dir(path, Arc::clone(&options.dirs)).unwrap();
+        }
+
+        let (manifest_info, canonical_path) = is_valid_ghost_dir(&path, &options.dirs)
+            .unwrap_or_else(|| panic!("{rel_path} is neither on disk nor in the manifest"));
+        Dir::new_ghost(
+            path,
+            manifest_info,
+            canonical_path,
+            Arc::clone(&options.dirs),
+        )
+    }
+
+    fn write_manifest(&self) {
+        let entries: Vec<String> = self
+            .zones
+            .iter()
+            .map(|(target, fields)| format!("  {target:?}: {fields}"))
+            .collect();
+        let manifest = format!("{{\n{}\n}}\n", entries.join(",\n"));
+        fs::write(self.path(".meta/manifest.json"), manifest).unwrap();
+    }
+}
+
+/// The text without the control sequences that style it.
+fn strip_escapes(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Control sequences end with a byte from `@` to `~`
+            if chars.next() == Some('[') {
+                while !matches!(chars.next(), Some('\x40'..='\x7e') | None) {}
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// The environment a fixture is listed in, which has nothing set but its
+/// own variables and where to look for the config file, so the user’s own
+/// can’t get in the way.
+struct FixtureVars<'a> {
+    config_dir: PathBuf,
+    vars: &'a [(&'static str, String)],
+}
+
+impl Vars for FixtureVars<'_> {
+    fn get(&self, name: &'static str) -> Option<OsString> {
+        if name == vars::WLS_CONFIG_DIR {
+            return Some(self.config_dir.clone().into_os_string());
+        }
+
+        self.vars
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::vars;
+
+    fn fixture() -> Fixture {
+        Fixture::new()
+            .zone("//areas/tools/dev", r#"{"type": "library"}"#)
+            .zone("//areas/tools/cli", r#"{"type": "service"}"#)
+            .zone("//areas/apps/flow", "{}")
+            .file("areas/tools/dev/README.md", "hello")
+            .dir("areas/legacy")
+    }
+
+    #[test]
+    fn ghosts_are_listed_with_whats_on_disk() {
+        let fixture = fixture();
+        assert_eq!(fixture.render(&["-1"], "areas"), "apps\nlegacy\ntools\n");
+        assert_eq!(fixture.render(&["-1"], "areas/tools"), "cli\ndev\n");
+    }
+
+    #[test]
+    fn zones_are_bold() {
+        let fixture = fixture();
+        assert_eq!(
+            fixture.render_styled(&["-1", "--no-ghosts"], "areas/tools"),
+            "\x1b[1mdev\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn ghost_directories_list_the_manifest() {
+        let fixture = fixture();
+        assert_eq!(fixture.render(&["-1"], "areas/apps"), "flow\n");
+    }
+
+    #[test]
+    fn ghosts_can_be_left_out() {
+        let fixture = fixture();
+        assert_eq!(
+            fixture.render(&["-1", "--no-ghosts"], "areas/tools"),
+            "dev\n"
+        );
+    }
+
+    #[test]
+    fn zones_get_badges() {
+        let fixture = fixture();
+        assert_eq!(
+            fixture.render(&["-1", "--zone-badges"], "areas/tools"),
+            "cli [svc]\ndev [lib]\n"
+        );
+    }
+
+    #[test]
+    fn zones_say_how_much_of_them_is_missing() {
+        let fixture = Fixture::new()
+            .zone("//areas", "{}")
+            .zone("//areas/tools/dev", "{}")
+            .zone("//areas/tools/cli", "{}")
+            .zone("//areas/apps/flow", "{}")
+            .zone("//areas/lib", "{}")
+            .dir("areas/tools");
+        assert_eq!(
+            fixture.render(&["--tree", "--no-ghosts"], ""),
+            "areas\n├── … 2 unmaterialized zones\n└── tools\n"
+        );
+    }
+
+    fn src_root() -> Fixture {
+        Fixture::new()
+            .zone("//a", "{}")
+            .zone("//b", "{}")
+            .zone("//c", "{}")
+            .zone("//d", "{}")
+            .dir("x")
+            .var(vars::WLS_GHOST_LIMIT, "2")
+    }
+
+    #[test]
+    fn the_ghost_limit_keeps_the_first_ghosts_listed() {
+        let fixture = src_root();
+        assert_eq!(fixture.render(&["-1"], ""), "a\nb\nx\n");
+        assert_eq!(fixture.render(&["-1", "--reverse"], ""), "x\nd\nc\n");
+    }
+
+    #[test]
+    fn the_ghost_limit_counts_what_gets_through_the_filters() {
+        let fixture = src_root();
+        assert_eq!(fixture.render(&["-1", "--ignore-glob=a"], ""), "b\nc\nx\n");
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn ignored_zones_are_kept_if_asked() {
+        let fixture = Fixture::new()
+            .zone("//out", "{}")
+            .zone("//tools", "{}")
+            .dir("out")
+            .dir("build")
+            .dir("tools")
+            .file(".gitignore", "out/\nbuild/\n")
+            .git();
+        assert_eq!(fixture.render(&["-1", "--git-ignore"], ""), "tools\n");
+        assert_eq!(
+            fixture.render(&["-1", "--git-ignore", "--git-ignore-zones"], ""),
+            "out\ntools\n"
+        );
+    }
+
+    #[test]
+    fn json_lines_say_which_entries_are_ghosts() {
+        let fixture = fixture();
+        let output = fixture.render(&["--json-lines"], "areas/tools");
+        let ghosts: Vec<bool> = output
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["ghost"]
+                    .as_bool()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(ghosts, vec![true, false]);
+    }
+}
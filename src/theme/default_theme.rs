@@ -27,6 +27,7 @@ impl Default for UiStyles {
     fn default() -> Self {
         Self {
             colourful: Some(true),
+            palette: None,
 
             #[rustfmt::skip]
             filekinds: Some(FileKinds {
@@ -110,6 +111,7 @@ impl Default for UiStyles {
                     typ:   Some(Yellow.normal()),
                     range: Some(Cyan.normal()),
                 }),
+                smack: Some(Purple.normal()),
             }),
 
             #[rustfmt::skip]
@@ -142,6 +144,12 @@ impl Default for UiStyles {
 
             zone_sigil: Some(White.normal()),
             zone_sigil_ghost: Some(DarkGray.normal()),
+            zone_library: Some(Cyan.normal()),
+            zone_app: Some(Purple.normal()),
+            zone_tool: Some(Yellow.normal()),
+
+            filesystem_overlay: Some(Blue.normal()),
+            filesystem_network: Some(Red.normal()),
 
             filenames: None,
             extensions: None,
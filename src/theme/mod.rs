@@ -8,11 +8,12 @@ use nu_ansi_term::Style;
 
 use std::collections::HashMap;
 
-use crate::fs::File;
+use crate::fs::{File, Zone, ZoneKind};
 use crate::info::filetype::FileType;
 use crate::options::config::ThemeConfig;
 use crate::output::color_scale::ColorScaleOptions;
 use crate::output::file_name::Colours as FileNameColours;
+use crate::output::manifest_only;
 use crate::output::render;
 
 mod ui_styles;
@@ -25,15 +26,29 @@ pub use self::lsc::LSColors;
 
 mod default_theme;
 
+mod palette;
+pub use self::palette::Palette;
+
+mod zone_colours;
+pub use self::zone_colours::zone_colour;
+
 #[derive(PartialEq, Eq, Debug)]
 pub struct Options {
     pub use_colours: UseColours,
 
+    /// When to colour the warnings written to stderr, which is decided
+    /// separately, because stdout and stderr often go to different places.
+    pub stderr_colours: UseColours,
+
     pub colour_scale: ColorScaleOptions,
 
     pub definitions: Definitions,
 
     pub theme_config: Option<ThemeConfig>,
+
+    /// The built-in palette asked for with `--palette` or `WLS_PALETTE`,
+    /// which takes precedence over one set in the theme file.
+    pub palette: Option<Palette>,
 }
 
 /// Under what circumstances we should display coloured, rather than plain,
@@ -55,6 +70,18 @@ pub enum UseColours {
     Never,
 }
 
+impl UseColours {
+    /// Whether to colour a stream, given whether it’s a terminal.
+    #[must_use]
+    pub fn enabled(self, isatty: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Automatic => isatty,
+            Self::Never => false,
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Default)]
 pub struct Definitions {
     pub ls: Option<String>,
@@ -69,9 +96,7 @@ pub struct Theme {
 impl Options {
     #[must_use]
     pub fn to_theme(&self, isatty: bool) -> Theme {
-        if self.use_colours == UseColours::Never
-            || (self.use_colours == UseColours::Automatic && !isatty)
-        {
+        if !self.use_colours.enabled(isatty) {
             let ui = UiStyles::plain();
             let exts = Box::new(NoFileStyle);
             return Theme { ui, exts };
@@ -91,7 +116,7 @@ impl Options {
 
         match self.theme_config {
             Some(ref theme) => {
-                if let Some(mut ui) = theme.to_theme() {
+                if let Some(mut ui) = theme.to_theme(self.palette) {
                     let (exts, use_default_filetypes) = self.definitions.parse_color_vars(&mut ui);
                     let exts: Box<dyn FileStyle> =
                         match (exts.is_non_empty(), use_default_filetypes) {
@@ -110,6 +135,9 @@ impl Options {
 
     fn default_theme(&self) -> Theme {
         let mut ui = UiStyles::default_theme(self.colour_scale);
+        if let Some(palette) = self.palette {
+            ui.set_palette(palette);
+        }
         let (exts, use_default_filetypes) = self.definitions.parse_color_vars(&mut ui);
         let exts: Box<dyn FileStyle> = match (exts.is_non_empty(), use_default_filetypes) {
             (false, false) => Box::new(NoFileStyle),
@@ -373,6 +401,46 @@ impl render::GitColours for Theme {
     fn conflicted(&self)    -> Style { self.ui.git.unwrap_or_default().conflicted() }
 }
 
+#[rustfmt::skip]
+impl render::FilesystemColours for Theme {
+    fn local(&self)       -> Style { Style::default() }
+    fn overlay(&self)     -> Style { self.ui.filesystem_overlay() }
+    fn network(&self)     -> Style { self.ui.filesystem_network() }
+    fn mount_point(&self) -> Style { self.ui.punctuation() }
+}
+
+#[rustfmt::skip]
+impl render::LayerColours for Theme {
+    fn lower(&self)     -> Style { self.ui.punctuation() }
+    fn copied_up(&self) -> Style { self.ui.git.unwrap_or_default().modified() }
+    fn upper(&self)     -> Style { self.ui.git.unwrap_or_default().new() }
+}
+
+#[rustfmt::skip]
+impl render::MaterializedColours for Theme {
+    fn complete(&self) -> Style { self.ui.git_repo.unwrap_or_default().git_clean() }
+    fn partial(&self)  -> Style { self.ui.git.unwrap_or_default().modified() }
+    fn empty(&self)    -> Style { self.ui.filekinds.unwrap_or_default().ghost() }
+}
+
+impl manifest_only::Colours for Theme {
+    fn directory(&self) -> Style {
+        self.ui.filekinds.unwrap_or_default().directory()
+    }
+
+    fn ghost(&self) -> Style {
+        self.ui.filekinds.unwrap_or_default().ghost()
+    }
+
+    fn zone_kind(&self, kind: ZoneKind) -> Style {
+        match kind {
+            ZoneKind::Library => self.ui.zone_library(),
+            ZoneKind::App => self.ui.zone_app(),
+            ZoneKind::Tool => self.ui.zone_tool(),
+        }
+    }
+}
+
 #[rustfmt::skip]
 impl render::GitRepoColours for Theme {
     fn branch_main(&self)  -> Style { self.ui.git_repo.unwrap_or_default().branch_main() }
@@ -445,6 +513,8 @@ impl render::SizeColours for Theme {
     #[rustfmt::skip]
     fn no_size(&self) -> Style { self.ui.punctuation() }
     #[rustfmt::skip]
+    fn estimate(&self) -> Style { self.ui.filekinds.unwrap_or_default().ghost() }
+    #[rustfmt::skip]
     fn major(&self)   -> Style { self.ui.size.unwrap_or_default().major() }
     #[rustfmt::skip]
     fn comma(&self)   -> Style { self.ui.punctuation() }
@@ -475,6 +545,16 @@ impl FileNameColours for Theme {
     fn zone_sigil(&self)          -> Style { self.ui.zone_sigil() }
     fn zone_sigil_ghost(&self)    -> Style { self.ui.zone_sigil_ghost() }
 
+    fn zone_colour(&self, zone: &Zone, hashed: bool) -> Option<nu_ansi_term::Color> {
+        if self.ui.colourful == Some(false) {
+            None
+        } else if zone.colour.is_some() {
+            zone.colour
+        } else {
+            hashed.then(|| zone_colour(&zone.target, self.ui.palette.unwrap_or_default()))
+        }
+    }
+
     fn colour_file(&self, file: &File<'_>) -> Style {
         self.exts
             .get_style(file, self)
@@ -508,6 +588,7 @@ impl render::SecurityCtxColours for Theme {
     fn selinux_role(&self)  -> Style { self.ui.security_context.unwrap_or_default().selinux().role() }
     fn selinux_type(&self)  -> Style { self.ui.security_context.unwrap_or_default().selinux().typ() }
     fn selinux_range(&self) -> Style { self.ui.security_context.unwrap_or_default().selinux().range() }
+    fn smack(&self)         -> Style { self.ui.security_context.unwrap_or_default().smack() }
 }
 
 /// Some of the styles are **overlays**: although they have the same attribute
@@ -751,6 +832,9 @@ mod customs_test {
     test!(exa_Sr:  ls "", exa "Sr=38;5;130"  =>  colours c -> { c.security_context().selinux().role         = Some(Fixed(130).normal()); });
     test!(exa_St:  ls "", exa "St=38;5;131"  =>  colours c -> { c.security_context().selinux().typ          = Some(Fixed(131).normal()); });
     test!(exa_Sl:  ls "", exa "Sl=38;5;132"  =>  colours c -> { c.security_context().selinux().range        = Some(Fixed(132).normal()); });
+    test!(exa_Sm:  ls "", exa "Sm=38;5;133"  =>  colours c -> { c.security_context().smack                  = Some(Fixed(133).normal()); });
+    test!(exa_Fo:  ls "", exa "Fo=38;5;134"  =>  colours c -> { c.filesystem_overlay                         = Some(Fixed(134).normal()); });
+    test!(exa_Fn:  ls "", exa "Fn=38;5;135"  =>  colours c -> { c.filesystem_network                         = Some(Fixed(135).normal()); });
 
     // All the while, LS_COLORS treats them as filenames:
     test!(ls_uu:   ls "uu=38;5;117", exa ""  =>  exts [ ("uu", Fixed(117).normal()) ]);
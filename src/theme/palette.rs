@@ -0,0 +1,254 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Built-in palettes for people who can’t tell some colours apart.
+//!
+//! The default theme leans on red and green to tell permissions and Git
+//! statuses apart, and its hashed zone colours are spread around the whole
+//! colour wheel, so with a colour vision deficiency, write and execute bits
+//! or new and deleted files can look the same. Each palette here swaps those
+//! colours for ones that stay distinct for a particular deficiency. Ghosts
+//! are already told apart by being dimmed and italic, rather than by hue, so
+//! they look the same in every palette.
+
+use nu_ansi_term::Color::{self, Blue, Cyan, DarkGray, Fixed, Purple, Red, White, Yellow};
+use serde::{Deserialize, Serialize};
+
+use crate::theme::ui_styles::{Git, GitRepo, Permissions, UiStyles};
+
+/// Which set of built-in colours to use.
+#[derive(PartialEq, Eq, Debug, Default, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// The usual colours.
+    #[default]
+    Default,
+
+    /// Safe for red-green colour blindness where green is weak, the most
+    /// common kind: reds and greens become oranges and blues.
+    Deuteranopia,
+
+    /// Safe for red-green colour blindness where red is weak. Reds look dark
+    /// as well as greenish, so these use brighter oranges than deuteranopia.
+    Protanopia,
+
+    /// Safe for blue-yellow colour blindness: blues and yellows become
+    /// teals and pinks.
+    Tritanopia,
+}
+
+/// Colours from the 256-colour palette used to hash zones with, which are
+/// readable on both light and dark backgrounds, and distinct enough from
+/// their neighbours that two zones listed next to each other can be told
+/// apart.
+const ZONES: &[u8] = &[
+    33, 37, 41, 43, 69, 71, 99, 105, 111, 131, 135, 141, 166, 168, 172, 173, 176, 178, 184, 203,
+    208, 209, 214, 220,
+];
+
+/// Zone colours without reds or greens: blues, yellows, and oranges, told
+/// apart by lightness as well as hue.
+const ZONES_RED_GREEN: &[u8] = &[
+    25, 27, 33, 39, 63, 69, 75, 111, 117, 136, 142, 172, 178, 184, 208, 214, 220, 228,
+];
+
+/// Zone colours without blues or yellows: reds, pinks, and teals.
+const ZONES_BLUE_YELLOW: &[u8] = &[
+    30, 37, 43, 73, 80, 124, 125, 160, 161, 167, 168, 174, 196, 203, 204, 210, 211, 217,
+];
+
+/// The colours the “good” and “bad” ends of a palette are drawn from.
+struct Swatch {
+    /// Reading, new files, clean repositories, and the main branch.
+    good: Color,
+
+    /// Writing, deleted and conflicted files, and dirty repositories.
+    bad: Color,
+
+    /// Executing, and modified files.
+    changed: Color,
+
+    /// Renamed files and other branches.
+    other: Color,
+}
+
+impl Palette {
+    /// All the palettes, in the order they’re listed in the help text.
+    pub const ALL: &'static [Self] = &[
+        Self::Default,
+        Self::Deuteranopia,
+        Self::Protanopia,
+        Self::Tritanopia,
+    ];
+
+    /// The name used for this palette on the command line and in the theme
+    /// file.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Deuteranopia => "deuteranopia",
+            Self::Protanopia => "protanopia",
+            Self::Tritanopia => "tritanopia",
+        }
+    }
+
+    /// The palette with the given name, if there is one.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|palette| palette.name() == name)
+    }
+
+    /// The colours that zones get hashed into with `--zone-colors`.
+    pub(crate) fn zone_colours(self) -> &'static [u8] {
+        match self {
+            Self::Default => ZONES,
+            Self::Deuteranopia | Self::Protanopia => ZONES_RED_GREEN,
+            Self::Tritanopia => ZONES_BLUE_YELLOW,
+        }
+    }
+
+    fn swatch(self) -> Option<Swatch> {
+        match self {
+            Self::Default => None,
+            Self::Deuteranopia => Some(Swatch {
+                good: Blue,
+                bad: Fixed(208),
+                changed: Yellow,
+                other: Cyan,
+            }),
+            Self::Protanopia => Some(Swatch {
+                good: Blue,
+                bad: Fixed(214),
+                changed: Fixed(228),
+                other: Cyan,
+            }),
+            Self::Tritanopia => Some(Swatch {
+                good: Fixed(37),
+                bad: Red,
+                changed: Fixed(211),
+                other: White,
+            }),
+        }
+    }
+}
+
+impl UiStyles {
+    /// Recolours the zone, Git, and permission styles with the given
+    /// palette’s colours, and remembers it for hashing zone colours.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = Some(palette);
+        let Some(swatch) = palette.swatch() else {
+            return;
+        };
+
+        #[rustfmt::skip]
+        let perms = Permissions {
+            user_read:           Some(swatch.good.bold()),
+            user_write:          Some(swatch.bad.bold()),
+            user_execute_file:   Some(swatch.changed.bold().underline()),
+            user_execute_other:  Some(swatch.changed.bold()),
+
+            group_read:          Some(swatch.good.normal()),
+            group_write:         Some(swatch.bad.normal()),
+            group_execute:       Some(swatch.changed.normal()),
+
+            other_read:          Some(swatch.good.normal()),
+            other_write:         Some(swatch.bad.normal()),
+            other_execute:       Some(swatch.changed.normal()),
+
+            special_user_file:   Some(Purple.normal()),
+            special_other:       Some(Purple.normal()),
+
+            attribute:           self.perms.and_then(|p| p.attribute),
+        };
+        self.perms = Some(perms);
+
+        #[rustfmt::skip]
+        let git = Git {
+            new:         Some(swatch.good.normal()),
+            modified:    Some(swatch.changed.normal()),
+            deleted:     Some(swatch.bad.normal()),
+            renamed:     Some(swatch.other.normal()),
+            typechange:  Some(Purple.normal()),
+            ignored:     self.git.and_then(|g| g.ignored),
+            conflicted:  Some(swatch.bad.bold()),
+        };
+        self.git = Some(git);
+
+        self.git_repo = Some(GitRepo {
+            branch_main: Some(swatch.good.normal()),
+            branch_other: Some(swatch.other.normal()),
+            git_clean: Some(swatch.good.normal()),
+            git_dirty: Some(swatch.bad.bold()),
+        });
+
+        self.zone_sigil = Some(White.normal());
+        self.zone_sigil_ghost = Some(DarkGray.normal());
+        self.zone_library = Some(swatch.good.normal());
+        self.zone_app = Some(Purple.normal());
+        self.zone_tool = Some(swatch.changed.normal());
+
+        self.filesystem_overlay = Some(swatch.changed.normal());
+        self.filesystem_network = Some(swatch.bad.normal());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for palette in Palette::ALL {
+            assert_eq!(Palette::from_name(palette.name()), Some(*palette));
+        }
+        assert_eq!(Palette::from_name("sepia"), None);
+    }
+
+    #[test]
+    fn default_changes_nothing() {
+        let mut ui = UiStyles::default();
+        ui.set_palette(Palette::Default);
+        assert_eq!(ui.git, UiStyles::default().git);
+        assert_eq!(ui.perms, UiStyles::default().perms);
+    }
+
+    #[test]
+    fn red_green_palettes_have_no_red_or_green() {
+        for palette in [Palette::Deuteranopia, Palette::Protanopia] {
+            let mut ui = UiStyles::default();
+            ui.set_palette(palette);
+
+            let git = ui.git.unwrap();
+            let perms = ui.perms.unwrap();
+            for style in [
+                git.new,
+                git.deleted,
+                perms.user_write,
+                perms.user_execute_other,
+            ] {
+                let colour = style.unwrap().foreground;
+                assert!(
+                    !matches!(colour, Some(Color::Red | Color::Green)),
+                    "{palette:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn statuses_stay_distinct() {
+        for palette in Palette::ALL {
+            let mut ui = UiStyles::default();
+            ui.set_palette(*palette);
+
+            let git = ui.git.unwrap();
+            assert_ne!(git.new, git.deleted, "{palette:?}");
+            assert_ne!(git.new, git.modified, "{palette:?}");
+            assert_ne!(git.modified, git.deleted, "{palette:?}");
+        }
+    }
+}
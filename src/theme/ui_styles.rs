@@ -5,6 +5,7 @@
 // SPDX-FileCopyrightText: 2014 Benjamin Sago
 // SPDX-License-Identifier: MIT
 use crate::theme::lsc::Pair;
+use crate::theme::Palette;
 use nu_ansi_term::{
     Color::{Blue, Cyan, Green, Purple, Red, Yellow},
     Style,
@@ -29,6 +30,7 @@ pub struct FileNameStyle {
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct UiStyles {
     pub colourful: Option<bool>,
+    pub palette:   Option<Palette>,
 
     pub filekinds:        Option<FileKinds>,
     pub perms:            Option<Permissions>,
@@ -55,6 +57,12 @@ pub struct UiStyles {
 
     pub zone_sigil:           Option<Style>,  // zs - zone sigil for directories
     pub zone_sigil_ghost:     Option<Style>,  // zg - zone sigil for ghost directories
+    pub zone_library:         Option<Style>,  // zl - library zones in --manifest-only
+    pub zone_app:             Option<Style>,  // za - app zones in --manifest-only
+    pub zone_tool:            Option<Style>,  // zt - tool zones in --manifest-only
+
+    pub filesystem_overlay:   Option<Style>,  // Fo - overlay filesystems in --filesystems
+    pub filesystem_network:   Option<Style>,  // Fn - network filesystems in --filesystems
 
     pub filenames: Option<HashMap<String, FileNameStyle>>,
     pub extensions: Option<HashMap<String, FileNameStyle>>,
@@ -117,7 +125,12 @@ field_accessors!(
     broken_symlink: Option<Style>,
     broken_path_overlay: Option<Style>,
     zone_sigil: Option<Style>,
-    zone_sigil_ghost: Option<Style>
+    zone_sigil_ghost: Option<Style>,
+    zone_library: Option<Style>,
+    zone_app: Option<Style>,
+    zone_tool: Option<Style>,
+    filesystem_overlay: Option<Style>,
+    filesystem_network: Option<Style>
 );
 
 #[rustfmt::skip]
@@ -353,11 +366,13 @@ field_accessors!(
 pub struct SecurityContext {
     pub none:    Option<Style>, // Sn
     pub selinux: Option<SELinuxContext>,
+    pub smack:   Option<Style>, // Sm
 }
 field_accessors!(
     SecurityContext,
     none: Option<Style>,
-    selinux: Option<SELinuxContext>
+    selinux: Option<SELinuxContext>,
+    smack: Option<Style>
 );
 
 impl Default for SecurityContext {
@@ -371,6 +386,7 @@ impl Default for SecurityContext {
                 typ: Some(Yellow.normal()),
                 range: Some(Cyan.normal()),
             }),
+            smack: Some(Purple.normal()),
         }
     }
 }
@@ -397,6 +413,7 @@ impl UiStyles {
     pub fn plain() -> Self {
         Self {
             colourful: Some(false),
+            palette: None,
 
             #[rustfmt::skip]
             filekinds: Some(FileKinds {
@@ -480,6 +497,7 @@ impl UiStyles {
                     typ:   Some(Style::default()),
                     range: Some(Style::default()),
                 }),
+                smack: Some(Style::default()),
             }),
 
             #[rustfmt::skip]
@@ -512,6 +530,12 @@ impl UiStyles {
 
             zone_sigil: Some(Style::default()),
             zone_sigil_ghost: Some(Style::default()),
+            zone_library: Some(Style::default()),
+            zone_app: Some(Style::default()),
+            zone_tool: Some(Style::default()),
+
+            filesystem_overlay: Some(Style::default()),
+            filesystem_network: Some(Style::default()),
 
             filenames: None,
             extensions: None,
@@ -633,9 +657,16 @@ impl UiStyles {
             "Sr" => self.security_context().selinux().role  = Some(pair.to_style()),
             "St" => self.security_context().selinux().typ   = Some(pair.to_style()),
             "Sl" => self.security_context().selinux().range = Some(pair.to_style()),
+            "Sm" => self.security_context().smack           = Some(pair.to_style()),
 
             "zs" => self.zone_sigil                        = Some(pair.to_style()),
             "zg" => self.zone_sigil_ghost                  = Some(pair.to_style()),
+            "zl" => self.zone_library                      = Some(pair.to_style()),
+            "za" => self.zone_app                          = Some(pair.to_style()),
+            "zt" => self.zone_tool                         = Some(pair.to_style()),
+
+            "Fo" => self.filesystem_overlay                = Some(pair.to_style()),
+            "Fn" => self.filesystem_network                = Some(pair.to_style()),
 
              _   => return false,
         }
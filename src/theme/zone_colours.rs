@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+use nu_ansi_term::Color;
+
+use crate::theme::Palette;
+
+/// Picks the colour for the zone with the given target path, such as
+/// `//areas/tools/dev`, out of the given palette’s zone colours.
+///
+/// The same target always gets the same colour, across runs and across
+/// machines, which is why this uses FNV-1a rather than the standard
+/// library’s randomly-seeded hasher.
+#[must_use]
+pub fn zone_colour(target: &str, palette: Palette) -> Color {
+    let colours = palette.zone_colours();
+    let index = fnv1a(target.as_bytes()) % colours.len() as u64;
+    Color::Fixed(colours[index as usize])
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_target_same_colour() {
+        assert_eq!(
+            zone_colour("//areas/tools/dev", Palette::Default),
+            zone_colour("//areas/tools/dev", Palette::Default)
+        );
+    }
+
+    #[test]
+    fn colour_is_stable() {
+        // Guards against accidental changes to the hash, which
+        // would recolour every zone for every user.
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn colours_come_from_palette() {
+        for palette in Palette::ALL {
+            for target in ["//a", "//areas/apps/flow", "//areas/core/shopify"] {
+                match zone_colour(target, *palette) {
+                    Color::Fixed(n) => assert!(palette.zone_colours().contains(&n)),
+                    other => panic!("unexpected colour {other:?}"),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! Warnings about things that went wrong without stopping the listing.
+//!
+//! Normally these are written to stderr as text, or only logged, depending
+//! on how much they matter. With machine output, or `--errors=json`, every
+//! one of them is written to stderr as a line of JSON instead, so that
+//! whatever is wrapping wls can react to them without parsing free text.
+//!
+//! Whether the text gets coloured is decided for stderr on its own, rather
+//! than following stdout, so that `wls > listing.txt` still has coloured
+//! warnings in the terminal, and `wls 2> wls.log` keeps escape codes out of
+//! the log.
+
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use log::warn;
+use nu_ansi_term::Color::Yellow;
+use serde::Serialize;
+
+/// What a warning is about, as given in its `kind` field.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// The manifest couldn’t be found or parsed, so no zones or ghosts are
+    /// shown.
+    Manifest,
+
+    /// The sync-state file next to the manifest couldn’t be parsed.
+    SyncState,
+
+    /// The `wls.toml` next to a manifest couldn’t be parsed.
+    RepoConfig,
+
+    /// The arguments couldn’t be parsed, so nothing was listed.
+    InvalidOptions,
+
+    /// An argument couldn’t be read.
+    Io,
+
+    /// A directory couldn’t be read because of its permissions.
+    PermissionDenied,
+
+    /// A glob pattern matched nothing, on disk or in the manifest.
+    NoMatches,
+
+    /// A target given to `--manifest-only` isn’t in the manifest.
+    NotInManifest,
+
+    /// A manifest entry wasn’t shown as a ghost because a directory on disk
+    /// has the same name in a different case.
+    CaseCollision,
+
+    /// A symlink that was going to be followed while recursing leads back
+    /// to a directory above it, so it wasn’t.
+    LinkCycle,
+
+    /// Something that would have made the listing faster or more complete,
+    /// such as Git statuses, stopped working, and the listing went on
+    /// without it.
+    #[cfg(feature = "git")]
+    Degraded,
+}
+
+impl Kind {
+    /// The kind of warning for something that couldn’t be read from disk.
+    pub fn of_io_error(error: &io::Error) -> Self {
+        if error.kind() == io::ErrorKind::PermissionDenied {
+            Self::PermissionDenied
+        } else {
+            Self::Io
+        }
+    }
+}
+
+/// How warnings get written to stderr.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Format {
+    /// As text for people to read, after `wls: warning:`.
+    Text,
+
+    /// As a line of JSON each, for the program wrapping wls to read.
+    Json,
+}
+
+/// One line of output.
+#[derive(Serialize)]
+struct Warning {
+    #[serde(rename = "warning")]
+    kind: Kind,
+    path: String,
+    message: String,
+}
+
+static JSON: OnceLock<bool> = OnceLock::new();
+
+static COLOURS: OnceLock<bool> = OnceLock::new();
+
+/// Sets whether text written to stderr should be coloured for the rest of
+/// the program.
+pub fn set_colours(enabled: bool) {
+    if COLOURS.set(enabled).is_err() {
+        warn!("Stderr colours were set twice");
+    }
+}
+
+/// Whether text written to stderr should be coloured. Until the options
+/// have decided, such as while they’re being parsed, that’s whether stderr
+/// is a terminal.
+pub fn colours() -> bool {
+    COLOURS
+        .get()
+        .copied()
+        .unwrap_or_else(|| io::stderr().is_terminal())
+}
+
+/// Writes a warning to stderr as text, after `wls: warning:`.
+pub fn write_text(message: impl Display) {
+    if colours() {
+        eprintln!("wls: {} {message}", Yellow.bold().paint("warning:"));
+    } else {
+        eprintln!("wls: warning: {message}");
+    }
+}
+
+/// Sets whether warnings should be written as JSON for the rest of the
+/// program. This has to happen before any directories are listed.
+pub fn set_json(enabled: bool) {
+    if JSON.set(enabled).is_err() {
+        warn!("JSON warnings were set twice");
+    }
+}
+
+/// Writes a warning to stderr as a line of JSON, if machine output was asked
+/// for, and returns whether it did. When it didn’t, the caller writes or
+/// logs the warning as text in whichever way it usually would.
+pub fn write_json(kind: Kind, path: impl Display, message: impl Display) -> bool {
+    if !JSON.get().copied().unwrap_or(false) {
+        return false;
+    }
+
+    let warning = Warning {
+        kind,
+        path: path.to_string(),
+        message: message.to_string(),
+    };
+
+    let mut stderr = io::stderr().lock();
+    let written = serde_json::to_writer(&mut stderr, &warning)
+        .map_err(io::Error::from)
+        .and_then(|()| writeln!(stderr));
+    if let Err(e) = written {
+        warn!("Failed to write a JSON warning: {e}");
+    }
+    true
+}
+
+/// Warns that the symlink at the given path wasn’t followed while
+/// recursing, because it leads back to a directory it’s already inside, so
+/// whatever’s beneath it got left out.
+pub fn link_cycle(link: &Path, ancestor: &Path) {
+    let message = format!("links back to {}", ancestor.display());
+    if !write_json(Kind::LinkCycle, link.display(), &message) {
+        write_text(format_args!(
+            "{} {message}, so it wasn’t followed",
+            link.display()
+        ));
+    }
+}
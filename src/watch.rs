@@ -0,0 +1,168 @@
+// SPDX-FileCopyrightText: 2024 Christina Sørensen
+// SPDX-License-Identifier: EUPL-1.2
+//! `--watch`, which lists everything again whenever something that would
+//! change the listing changes: the contents of a listed directory, the Git
+//! index of the repository it’s in, or the manifest of its src root.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use log::*;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::fs::{manifest_path, DirOptions};
+
+/// How long to wait for things to settle down after a change, so that a
+/// checkout touching thousands of files only causes one listing.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// The files in a `.git` directory whose changes affect the Git column.
+/// Everything else in there changes far too often to be worth watching.
+const GIT_FILES: &[&str] = &["index", "HEAD"];
+
+/// Watches the paths that a listing depends on.
+pub struct Watch {
+    // Never read, but the watching stops when it’s dropped
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl Watch {
+    /// Starts watching the given paths, along with the Git directories and
+    /// manifests that apply to them. Listed directories are watched all the
+    /// way down if the listing recurses. The manifests are looked for the
+    /// same way as the listing looks for them.
+    pub fn new(paths: &[PathBuf], recursive: bool, options: &DirOptions) -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        for path in paths {
+            // Ghosts aren’t on disk, so the nearest directory that is gets
+            // watched instead, to notice them being materialized
+            let Some(existing) = path.ancestors().find(|p| p.exists()) else {
+                continue;
+            };
+            watch(&mut watcher, existing, mode);
+
+            if let Some(git_dir) = existing
+                .ancestors()
+                .map(|p| p.join(".git"))
+                .find(|p| p.is_dir())
+            {
+                watch(&mut watcher, &git_dir, RecursiveMode::NonRecursive);
+            }
+
+            // Manifests tend to be replaced rather than written to, so it’s
+            // the directory they’re in that gets watched
+            if let Some(meta_dir) = manifest_path(existing, options)
+                .as_deref()
+                .and_then(Path::parent)
+            {
+                watch(&mut watcher, meta_dir, RecursiveMode::NonRecursive);
+            }
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Blocks until something changes, and then until things have settled
+    /// down. Returns `false` if watching has stopped working.
+    pub fn wait(&self) -> bool {
+        loop {
+            match self.events.recv() {
+                Ok(Ok(event)) if is_relevant(&event) => break,
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => warn!("Error watching for changes: {e}"),
+                Err(_) => return false,
+            }
+        }
+
+        loop {
+            match self.events.recv_timeout(SETTLE_TIME) {
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => return true,
+                Err(RecvTimeoutError::Disconnected) => return false,
+            }
+        }
+    }
+}
+
+fn watch(watcher: &mut RecommendedWatcher, path: &Path, mode: RecursiveMode) {
+    debug!("Watching {path:?} ({mode:?})");
+    if let Err(e) = watcher.watch(path, mode) {
+        warn!("Failed to watch {path:?}: {e}");
+    }
+}
+
+/// Whether an event could change the listing. Only a few of the files in a
+/// `.git` directory matter, and reading files doesn’t change anything.
+fn is_relevant(event: &Event) -> bool {
+    if event.kind.is_access() {
+        return false;
+    }
+
+    event.paths.iter().any(|path| {
+        let in_git_dir = path
+            .parent()
+            .is_some_and(|p| p.components().any(|c| c.as_os_str() == ".git"));
+        !in_git_dir
+            || path
+                .file_name()
+                .is_some_and(|name| GIT_FILES.iter().any(|f| name == *f))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, EventKind, ModifyKind};
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn new_file() {
+        assert!(is_relevant(&event(
+            EventKind::Create(CreateKind::File),
+            "/src/areas/tools/dev/new.rs"
+        )));
+    }
+
+    #[test]
+    fn reading_a_file() {
+        assert!(!is_relevant(&event(
+            EventKind::Access(AccessKind::Read),
+            "/src/areas/tools/dev/main.rs"
+        )));
+    }
+
+    #[test]
+    fn git_index() {
+        assert!(is_relevant(&event(
+            EventKind::Modify(ModifyKind::Any),
+            "/repo/.git/index"
+        )));
+    }
+
+    #[test]
+    fn git_objects() {
+        assert!(!is_relevant(&event(
+            EventKind::Create(CreateKind::File),
+            "/repo/.git/objects/ab/cdef"
+        )));
+        assert!(!is_relevant(&event(
+            EventKind::Create(CreateKind::File),
+            "/repo/.git/index.lock"
+        )));
+    }
+}